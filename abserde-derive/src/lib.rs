@@ -0,0 +1,282 @@
+//! Derive macro generating config methods for the [abserde](https://docs.rs/abserde) crate.
+//!
+//! Use it through abserde, with its derive feature enabled, rather than depending on this crate directly.
+
+#![deny(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+	parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields, Ident, Lit,
+	LitStr, Member, Meta, Result,
+};
+
+/// Generate `load`, `save` and `delete` methods for a config type, bound to an `Abserde` described by the
+/// `#[abserde(...)]` attribute of the type.
+///
+/// The attribute takes the `app` name, along with an optional `qualifier`, `organization`, config file `name`,
+/// config file `extension` and `format`, such as `#[abserde(app = "MyApp", format = "toml")]`. Formats are
+/// given by the extension of their config files, and default to JSON. The config type must implement `Serialize` and `Deserialize`.
+///
+/// Fields of structs can be overridden from environment variables after loading, with an attribute such as
+/// `#[abserde(env = "MYAPP_PORT")]`. Values are parsed as JSON, such as numbers and booleans, falling back to
+/// the raw string for fields holding strings, and must suit the type of the field.
+///
+/// Fields can be kept in section files of their own, next to the config file, with an attribute such as
+/// `#[abserde(file = "keybindings")]`, so users can share them without sharing the rest of the config.
+///
+/// The doc comments of the type and its fields are captured to implement `DocumentedConfig`, which generates
+/// sample config documents commented with them.
+#[proc_macro_derive(AbserdeConfig, attributes(abserde))]
+pub fn derive_abserde_config(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+
+	expand(&input)
+		.unwrap_or_else(Error::into_compile_error)
+		.into()
+}
+
+// Options given by the `#[abserde(...)]` attribute of a config type.
+#[derive(Default)]
+struct Options {
+	app: Option<LitStr>,
+	qualifier: Option<LitStr>,
+	organization: Option<LitStr>,
+	name: Option<LitStr>,
+	extension: Option<LitStr>,
+	format: Option<LitStr>,
+}
+
+impl Options {
+	// Parse the options from the attributes of a config type.
+	fn parse(input: &DeriveInput) -> Result<Self> {
+		let mut options = Options::default();
+
+		for attr in input
+			.attrs
+			.iter()
+			.filter(|attr| attr.path().is_ident("abserde"))
+		{
+			attr.parse_nested_meta(|meta| {
+				let option = match meta.path.get_ident().map(Ident::to_string).as_deref() {
+					Some("app") => &mut options.app,
+					Some("qualifier") => &mut options.qualifier,
+					Some("organization") => &mut options.organization,
+					Some("name") => &mut options.name,
+					Some("extension") => &mut options.extension,
+					Some("format") => &mut options.format,
+					_ => return Err(meta.error("unknown abserde option")),
+				};
+
+				*option = Some(meta.value()?.parse()?);
+
+				Ok(())
+			})?;
+		}
+
+		Ok(options)
+	}
+}
+
+// Options given by the `#[abserde(...)]` attributes of the fields of a config type.
+#[derive(Default)]
+struct FieldOptions {
+	// Fields overridden by environment variables, along with the names of their variables.
+	env_overrides: Vec<(Member, LitStr)>,
+	// Keys of fields kept in section files of their own, along with the names of their files.
+	section_files: Vec<(String, LitStr)>,
+}
+
+// Parse the options of the fields of a config type.
+fn field_options(input: &DeriveInput) -> Result<FieldOptions> {
+	let fields = match &input.data {
+		Data::Struct(data) => &data.fields,
+		_ => &Fields::Unit,
+	};
+	let mut options = FieldOptions::default();
+
+	for (index, field) in fields.iter().enumerate() {
+		for attr in field
+			.attrs
+			.iter()
+			.filter(|attr| attr.path().is_ident("abserde"))
+		{
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("file") {
+					let key = field_key(field).ok_or_else(|| {
+						meta.error("only named fields can be kept in section files")
+					})?;
+
+					options.section_files.push((key, meta.value()?.parse()?));
+
+					return Ok(());
+				}
+
+				if !meta.path.is_ident("env") {
+					return Err(meta.error("unknown abserde field option"));
+				}
+
+				let member = match &field.ident {
+					Some(ident) => Member::Named(ident.clone()),
+					None => Member::Unnamed(index.into()),
+				};
+
+				options.env_overrides.push((member, meta.value()?.parse()?));
+
+				Ok(())
+			})?;
+		}
+	}
+
+	Ok(options)
+}
+
+// Doc comment of a type or field, from its `#[doc = "..."]` attributes, without the space following `///`.
+fn docs(attrs: &[Attribute]) -> String {
+	attrs
+		.iter()
+		.filter_map(|attr| match &attr.meta {
+			Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+				Expr::Lit(ExprLit {
+					lit: Lit::Str(doc), ..
+				}) => Some(doc.value()),
+				_ => None,
+			},
+			_ => None,
+		})
+		.map(|line| line.strip_prefix(' ').map(str::to_string).unwrap_or(line))
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+// Key of a field in config documents, which is its name unless renamed with `#[serde(rename = "...")]`.
+fn field_key(field: &syn::Field) -> Option<String> {
+	let mut key = field.ident.as_ref()?.to_string();
+
+	for attr in field
+		.attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("serde"))
+	{
+		// Other serde options are none of our business, so they're skipped rather than rejected.
+		_ = attr.parse_nested_meta(|meta| {
+			match meta.path.is_ident("rename") {
+				true => key = meta.value()?.parse::<LitStr>()?.value(),
+				false => _ = meta.value().and_then(|value| value.parse::<Expr>()),
+			}
+
+			Ok(())
+		});
+	}
+
+	Some(key)
+}
+
+// Generate the methods of a config type.
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+	let options = Options::parse(input)?;
+	let field_options = field_options(input)?;
+	let (override_members, override_vars): (Vec<_>, Vec<_>) =
+		field_options.env_overrides.into_iter().unzip();
+	let (section_keys, section_files): (Vec<_>, Vec<_>) =
+		field_options.section_files.into_iter().unzip();
+	let ident = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let app = options.app.ok_or_else(|| {
+		Error::new(
+			Span::call_site(),
+			"missing app name, given with #[abserde(app = \"MyApp\")]",
+		)
+	})?;
+	let optional = |option: Option<LitStr>| match option {
+		Some(value) => {
+			quote!(::std::option::Option::Some(::std::string::ToString::to_string(#value)))
+		}
+		None => quote!(::std::option::Option::None),
+	};
+	let type_docs = docs(&input.attrs);
+	let (field_keys, field_docs): (Vec<_>, Vec<_>) = match &input.data {
+		Data::Struct(data) => data
+			.fields
+			.iter()
+			.filter_map(|field| Some((field_key(field)?, docs(&field.attrs))))
+			.unzip(),
+		_ => Default::default(),
+	};
+	let qualifier = optional(options.qualifier);
+	let organization = optional(options.organization);
+	let name = optional(options.name);
+	let extension = optional(options.extension);
+	let format = match options.format {
+		Some(format) => format_variant(&format)?,
+		None => quote!(::std::default::Default::default()),
+	};
+
+	Ok(quote! {
+		impl #impl_generics #ident #ty_generics #where_clause {
+			/// The `Abserde` which configs of this type are loaded with, saved with and deleted with.
+			pub fn abserde() -> &'static ::abserde::Abserde {
+				static ABSERDE: ::std::sync::OnceLock<::abserde::Abserde> = ::std::sync::OnceLock::new();
+
+				ABSERDE.get_or_init(|| ::abserde::Abserde {
+					app: ::std::string::ToString::to_string(#app),
+					qualifier: #qualifier,
+					organization: #organization,
+					name: #name,
+					extension: #extension,
+					format: #format,
+					section_files: ::std::iter::Iterator::collect(::std::iter::IntoIterator::into_iter([#(
+						(::std::string::ToString::to_string(#section_keys), ::std::string::ToString::to_string(#section_files)),
+					)*])),
+					..::std::default::Default::default()
+				})
+			}
+
+			/// Load the config from disk, then override any fields set by environment variables.
+			pub fn load() -> ::abserde::Result<Self> {
+				#[allow(unused_mut)]
+				let mut config = <Self as ::abserde::Config>::load_config(Self::abserde())?;
+
+				#(
+					if let ::std::option::Option::Some(value) = ::abserde::derive::env_override(#override_vars)? {
+						config.#override_members = value;
+					}
+				)*
+
+				::std::result::Result::Ok(config)
+			}
+
+			/// Save the config to disk.
+			pub fn save(&self) -> ::abserde::Result<()> {
+				<Self as ::abserde::Config>::save_config(self, Self::abserde())
+			}
+
+			/// Delete the config file.
+			pub fn delete() -> ::abserde::Result<()> {
+				Self::abserde().delete()
+			}
+		}
+
+		impl #impl_generics ::abserde::DocumentedConfig for #ident #ty_generics #where_clause {
+			const DOCS: &'static str = #type_docs;
+			const FIELD_DOCS: &'static [(&'static str, &'static str)] = &[#((#field_keys, #field_docs)),*];
+		}
+	})
+}
+
+// Variant of abserde's `Format` for the extension of its config files, such as `toml`.
+fn format_variant(format: &LitStr) -> Result<TokenStream2> {
+	let variant = match format.value().to_lowercase().as_str() {
+		"json" => "Json",
+		"yaml" | "yml" => "Yaml",
+		"pickle" | "pkl" => "Pickle",
+		"ini" => "Ini",
+		"toml" => "Toml",
+		"plist" => "Plist",
+		_ => return Err(Error::new(format.span(), "unknown config format")),
+	};
+	let variant = Ident::new(variant, format.span());
+
+	Ok(quote!(::abserde::Format::#variant))
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use abserde::*;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+// Number of entries in the benchmarked config, which is a few megabytes in each format.
+const ENTRIES: usize = 20_000;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+	name: String,
+	path: String,
+	size: u64,
+	ratio: f64,
+	enabled: bool,
+	tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LargeConfig {
+	version: u32,
+	entries: Vec<Entry>,
+	settings: BTreeMap<String, String>,
+}
+
+impl LargeConfig {
+	fn new() -> Self {
+		Self {
+			version: 1,
+			entries: (0..ENTRIES)
+				.map(|index| Entry {
+					name: format!("entry-{}", index),
+					path: format!("/home/user/projects/project-{}/src/main.rs", index),
+					size: index as u64 * 4096,
+					ratio: index as f64 / 7.0,
+					enabled: index % 2 == 0,
+					tags: vec!["recent".to_string(), format!("group-{}", index % 16)],
+				})
+				.collect(),
+			settings: (0..ENTRIES / 10)
+				.map(|index| (format!("setting.{}", index), format!("value {}", index)))
+				.collect(),
+		}
+	}
+}
+
+fn bench_save(c: &mut Criterion) {
+	let config = LargeConfig::new();
+	let mut group = c.benchmark_group("save");
+
+	for format in [
+		Format::Json,
+		Format::PrettyJson(PrettyJsonIndent::Spaces(2)),
+	] {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: "abserde-bench".to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: format.clone(),
+			..Default::default()
+		};
+		let bytes = config.to_config_string(&format).unwrap().len();
+
+		group.throughput(Throughput::Bytes(bytes as u64));
+		group.bench_function(format!("{:?}", format), |b| {
+			b.iter(|| config.save_config(&abserde).unwrap())
+		});
+	}
+
+	group.finish();
+}
+
+fn bench_to_string(c: &mut Criterion) {
+	let config = LargeConfig::new();
+	let mut group = c.benchmark_group("to_config_string");
+
+	for format in [
+		Format::Json,
+		Format::PrettyJson(PrettyJsonIndent::Spaces(2)),
+	] {
+		let bytes = config.to_config_string(&format).unwrap().len();
+
+		group.throughput(Throughput::Bytes(bytes as u64));
+		group.bench_function(format!("{:?}", format), |b| {
+			b.iter(|| config.to_config_string(&format).unwrap())
+		});
+	}
+
+	group.finish();
+}
+
+fn bench_load(c: &mut Criterion) {
+	let config = LargeConfig::new();
+	let tmp_dir = TempDir::new().unwrap();
+	let abserde = Abserde {
+		app: "abserde-bench".to_string(),
+		location: Location::Dir(tmp_dir.path().into()),
+		format: Format::Json,
+		..Default::default()
+	};
+	let mut group = c.benchmark_group("load");
+
+	config.save_config(&abserde).unwrap();
+
+	group.throughput(Throughput::Bytes(
+		config.to_config_string(&Format::Json).unwrap().len() as u64,
+	));
+	group.bench_function("Json", |b| {
+		b.iter(|| LargeConfig::load_config(&abserde).unwrap())
+	});
+
+	group.finish();
+}
+
+criterion_group!(benches, bench_save, bench_to_string, bench_load);
+criterion_main!(benches);
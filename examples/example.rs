@@ -20,6 +20,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 		app: "MyApp".to_string(),
 		location: Location::Auto,
 		format: Format::Json,
+		..Default::default()
 	};
 
 	let mut my_config = MyConfig {
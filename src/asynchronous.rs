@@ -0,0 +1,207 @@
+//! Runtime-agnostic async support.
+//!
+//! Config files are still read and written using blocking file operations, which are handed to a pluggable
+//! [Spawn]er so they never block the async runtime. Results are sent back over a channel, so any executor
+//! can await them. In the browser, where nothing may block, configs kept in localStorage are loaded and saved
+//! straight away, and those kept in the private file system are accessed asynchronously.
+
+use std::future::Future;
+use std::path::PathBuf;
+#[cfg(feature = "notify")]
+use std::pin::Pin;
+use std::sync::{Arc, PoisonError, RwLock};
+#[cfg(feature = "notify")]
+use std::task::{Context, Poll};
+use std::thread;
+
+#[cfg(feature = "notify")]
+use futures::channel::mpsc;
+use futures::channel::oneshot;
+#[cfg(feature = "notify")]
+use futures::Stream;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(target_arch = "wasm32")]
+use crate::opfs;
+#[cfg(feature = "notify")]
+use crate::ConfigWatcher;
+#[cfg(target_arch = "wasm32")]
+use crate::Storage;
+use crate::{Abserde, Config, Error, Result};
+
+/// Runs blocking work on behalf of async config operations.
+///
+/// Implement this to run blocking work on your async runtime's blocking thread pool, then install it with
+/// [set_spawner]. For example, with tokio:
+///
+/// ```ignore
+/// struct TokioSpawner;
+///
+/// impl Spawn for TokioSpawner {
+/// 	fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+/// 		tokio::task::spawn_blocking(task);
+/// 	}
+/// }
+///
+/// set_spawner(TokioSpawner);
+/// ```
+///
+/// With async-std, use `async_std::task::spawn_blocking(task)`, and with smol, `smol::unblock(task).detach()`.
+pub trait Spawn: Send + Sync {
+	/// Run a blocking task to completion, without blocking the calling async task.
+	fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>);
+}
+
+/// Spawner which runs each blocking task on a new thread, used until another spawner is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadSpawner;
+
+impl Spawn for ThreadSpawner {
+	fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+		thread::spawn(task);
+	}
+}
+
+static SPAWNER: RwLock<Option<Arc<dyn Spawn>>> = RwLock::new(None);
+
+/// Set the spawner used to run blocking work for all async config operations.
+pub fn set_spawner<S: Spawn + 'static>(spawner: S) {
+	*SPAWNER.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(spawner));
+}
+
+// Run a blocking function using the current spawner, and wait for its result.
+async fn unblock<R, F>(f: F) -> Result<R>
+where
+	R: Send + 'static,
+	F: FnOnce() -> Result<R> + Send + 'static,
+{
+	// Browsers don't allow blocking, and there are no threads to block, so wasm32 runs the work straight away.
+	if cfg!(target_arch = "wasm32") {
+		return f();
+	}
+
+	let (sender, receiver) = oneshot::channel();
+	let task = Box::new(move || {
+		// The receiver is gone if the future was dropped, in which case the result is unwanted.
+		let _ = sender.send(f());
+	});
+
+	match SPAWNER
+		.read()
+		.unwrap_or_else(PoisonError::into_inner)
+		.clone()
+	{
+		Some(spawner) => spawner.spawn_blocking(task),
+		None => ThreadSpawner.spawn_blocking(task),
+	}
+
+	receiver.await.map_err(|_| Error::Cancelled)?
+}
+
+/// Async versions of the [Config] methods, usable with any async runtime.
+///
+/// Blocking work is run by the spawner set with [set_spawner], or on a new thread by default. These are the only
+/// methods able to load and save configs stored with [Storage::Opfs](crate::Storage::Opfs) in the browser.
+pub trait AsyncConfig: Config {
+	/// Load config from disk.
+	fn load_config_async(abserde: &Abserde) -> impl Future<Output = Result<Self::T>> + Send;
+
+	/// Load config from disk, falling back to the most recent readable backup. See
+	/// [Config::load_config_with_recovery].
+	fn load_config_with_recovery_async(
+		abserde: &Abserde,
+	) -> impl Future<Output = Result<(Self::T, PathBuf)>> + Send;
+
+	/// Save config to disk.
+	fn save_config_async(&self, abserde: &Abserde) -> impl Future<Output = Result<()>> + Send;
+
+	/// Watch the config file for changes on disk, as a stream of reloaded configs. See [Config::watch_config].
+	///
+	/// Watching stops when the stream is dropped. Enabled with the notify feature.
+	#[cfg(feature = "notify")]
+	fn watch_config_stream(abserde: &Abserde) -> Result<ConfigStream<Self::T>>;
+}
+
+impl<T> AsyncConfig for T
+where
+	T: Serialize,
+	T: DeserializeOwned,
+	T: Clone + Send + 'static,
+{
+	fn load_config_async(abserde: &Abserde) -> impl Future<Output = Result<Self::T>> + Send {
+		let abserde = abserde.clone();
+
+		async move {
+			#[cfg(target_arch = "wasm32")]
+			if abserde.storage == Storage::Opfs {
+				return opfs::load_config(abserde).await;
+			}
+
+			unblock(move || T::load_config(&abserde)).await
+		}
+	}
+
+	fn load_config_with_recovery_async(
+		abserde: &Abserde,
+	) -> impl Future<Output = Result<(Self::T, PathBuf)>> + Send {
+		let abserde = abserde.clone();
+
+		async move {
+			// Backups aren't kept in the private file system, so there is nothing to recover from.
+			#[cfg(target_arch = "wasm32")]
+			if abserde.storage == Storage::Opfs {
+				let config_path = abserde.load_path()?;
+
+				return Ok((opfs::load_config(abserde).await?, config_path));
+			}
+
+			unblock(move || T::load_config_with_recovery(&abserde)).await
+		}
+	}
+
+	fn save_config_async(&self, abserde: &Abserde) -> impl Future<Output = Result<()>> + Send {
+		let config = self.clone();
+		let abserde = abserde.clone();
+
+		async move {
+			#[cfg(target_arch = "wasm32")]
+			if abserde.storage == Storage::Opfs {
+				return opfs::save_config(abserde, config).await;
+			}
+
+			unblock(move || config.save_config(&abserde)).await
+		}
+	}
+
+	#[cfg(feature = "notify")]
+	fn watch_config_stream(abserde: &Abserde) -> Result<ConfigStream<Self::T>> {
+		let (sender, receiver) = mpsc::unbounded();
+		let watcher = T::watch_config(abserde, move |config| {
+			// The receiver is only gone once the stream, and so the watcher, is being dropped.
+			let _ = sender.unbounded_send(config);
+		})?;
+
+		Ok(ConfigStream {
+			_watcher: watcher,
+			receiver,
+		})
+	}
+}
+
+/// Stream of configs reloaded after changes on disk, created by [AsyncConfig::watch_config_stream].
+#[cfg(feature = "notify")]
+#[derive(Debug)]
+pub struct ConfigStream<T> {
+	_watcher: ConfigWatcher,
+	receiver: mpsc::UnboundedReceiver<Result<T>>,
+}
+
+#[cfg(feature = "notify")]
+impl<T> Stream for ConfigStream<T> {
+	type Item = Result<T>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		Pin::new(&mut self.receiver).poll_next(cx)
+	}
+}
@@ -0,0 +1,236 @@
+//! Append-only audit log of the changes made to configs by saving and deleting them, enabled with
+//! [Abserde::audit](crate::Abserde::audit).
+//!
+//! The audit log is kept in the config directory, shared by the configs of the app, as JSON lines, each holding
+//! an [AuditEntry]. Configs are compared through [serde_json::Value], so changes are recorded key by key.
+
+use std::collections::BTreeSet;
+use std::env::{current_exe, var};
+use std::fs::read_to_string;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Abserde, DeleteMode, Error, Operation, Result, Storage};
+
+// File name of the audit log, within the config directory.
+const AUDIT_LOG_NAME: &str = "audit.jsonl";
+
+/// Save or delete of a config, recorded in the audit log, as returned by
+/// [Abserde::audit_log](crate::Abserde::audit_log).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+	/// Time the config was saved or deleted.
+	pub time: SystemTime,
+
+	/// Whether the config was saved or deleted.
+	pub operation: Operation,
+
+	/// Path of the config file.
+	pub config_path: PathBuf,
+
+	/// ID of the process which saved or deleted the config.
+	pub pid: u32,
+
+	/// Path of the executable of the process, if known.
+	pub executable: Option<PathBuf>,
+
+	/// Name of the user running the process, if known.
+	pub user: Option<String>,
+
+	/// Keys of the config which changed, in alphabetical order.
+	pub changes: Vec<AuditChange>,
+}
+
+/// Change to a key of a config, recorded in an [AuditEntry].
+///
+/// Values are left out for config files which are encrypted, so the audit log doesn't reveal them.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AuditChange {
+	/// Key which changed, such as `window.width`, where nested keys are separated by `.`.
+	pub key: String,
+
+	/// Value of the key before the change, which is none if it was added.
+	pub old: Option<Value>,
+
+	/// Value of the key after the change, which is none if it was removed.
+	pub new: Option<Value>,
+}
+
+impl Abserde {
+	/// Return the entries of the audit log recording changes to the config, oldest first. See [Abserde::audit].
+	///
+	/// ```no_run
+	/// # use abserde::*;
+	/// #
+	/// let my_abserde = Abserde {
+	/// 	app: "MyApp".to_string(),
+	/// 	audit: true,
+	/// 	..Default::default()
+	/// };
+	///
+	/// for entry in my_abserde.audit_log()? {
+	/// 	for change in entry.changes {
+	/// 		println!("{:?} by {:?}: {}", entry.time, entry.user, change.key);
+	/// 	}
+	/// }
+	/// #
+	/// # Ok::<(), Error>(())
+	/// ```
+	pub fn audit_log(&self) -> Result<Vec<AuditEntry>> {
+		let config_path = self.config_path()?;
+		let audit_path = audit_log_path(&config_path);
+		let contents = match read_to_string(&audit_path) {
+			Ok(contents) => contents,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(err) => return Err(Error::file(&audit_path)(err)),
+		};
+
+		// A line cut short by a crash while it was appended is skipped.
+		Ok(contents
+			.lines()
+			.filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+			.filter(|entry| entry.config_path == config_path)
+			.collect())
+	}
+
+	// Write a config, recording the keys which changed in the audit log.
+	pub(crate) fn write_audited<T: Serialize>(&self, config: &T) -> Result<()> {
+		let abserde = self.without_audit()?;
+		let old = abserde.read_audited()?;
+
+		abserde.write_config(config)?;
+
+		// The config is read back as it was written, so values are compared as the config format holds them,
+		// such as floats which it rounds.
+		let new = abserde.read_audited()?;
+
+		self.append_audit(Operation::Save, &old, &new)
+	}
+
+	// Delete a config, recording the keys it held in the audit log.
+	pub(crate) fn delete_audited(&self, mode: DeleteMode) -> Result<()> {
+		let abserde = self.without_audit()?;
+		let old = abserde.with_context(Operation::Delete, || abserde.read_audited())?;
+
+		abserde.delete_with(mode)?;
+
+		self.with_context(Operation::Delete, || {
+			self.append_audit(Operation::Delete, &old, &Value::Null)
+		})
+	}
+
+	// Read the config as it is before being changed, which is null if there's no config, or it can't be parsed.
+	fn read_audited(&self) -> Result<Value> {
+		match self.read_config_file(&self.config_path()?) {
+			Ok(config) => Ok(config),
+			Err(Error::NotFound(_) | Error::Deserialize(_)) => Ok(Value::Null),
+			Err(err) => Err(err),
+		}
+	}
+
+	// Append an entry recording the keys which changed to the audit log.
+	fn append_audit(&self, operation: Operation, old: &Value, new: &Value) -> Result<()> {
+		let config_path = self.config_path()?;
+		let audit_path = audit_log_path(&config_path);
+		let mut changes = Vec::new();
+
+		diff(old, new, "", &mut changes);
+
+		if self.encryption.encrypts_files() {
+			for change in &mut changes {
+				change.old = None;
+				change.new = None;
+			}
+		}
+
+		let entry = AuditEntry {
+			time: SystemTime::now(),
+			operation,
+			config_path,
+			pid: process::id(),
+			executable: current_exe().ok(),
+			user: var("USER").or_else(|_| var("USERNAME")).ok(),
+			changes,
+		};
+		let mut line = serde_json::to_vec(&entry).map_err(|err| Error::Serialize(err.into()))?;
+
+		line.push(b'\n');
+
+		let audit_dir = audit_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+		self.permissions
+			.create_dir_all(audit_dir)
+			.map_err(Error::file(audit_dir))?;
+
+		// Each entry is appended in a single write, so entries of processes saving at once aren't interleaved.
+		self.permissions
+			.append_file(&audit_path)
+			.and_then(|mut file| file.write_all(&line))
+			.map_err(Error::file(&audit_path))
+	}
+
+	// Copy of this Abserde which doesn't audit changes, for the config itself.
+	fn without_audit(&self) -> Result<Abserde> {
+		if self.storage != Storage::File {
+			return Err(Error::Unsupported(
+				"only changes to config files can be audited".to_string(),
+			));
+		}
+
+		Ok(Abserde {
+			audit: false,
+			..self.clone()
+		})
+	}
+}
+
+// Return the path of the audit log of the given config file, in its directory.
+fn audit_log_path(config_path: &Path) -> PathBuf {
+	config_path.with_file_name(AUDIT_LOG_NAME)
+}
+
+// Collect the changes between two values of a config, key by key at any depth, where null values and missing
+// keys are the same.
+fn diff(old: &Value, new: &Value, prefix: &str, changes: &mut Vec<AuditChange>) {
+	let empty = Map::new();
+
+	match (as_map(old, &empty), as_map(new, &empty)) {
+		(Some(old), Some(new)) => {
+			let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+
+			for key in keys {
+				let key_path = match prefix {
+					"" => key.to_string(),
+					prefix => format!("{}.{}", prefix, key),
+				};
+
+				diff(
+					old.get(key).unwrap_or(&Value::Null),
+					new.get(key).unwrap_or(&Value::Null),
+					&key_path,
+					changes,
+				);
+			}
+		}
+		_ if old != new => changes.push(AuditChange {
+			key: prefix.to_string(),
+			old: Some(old.clone()).filter(|old| !old.is_null()),
+			new: Some(new.clone()).filter(|new| !new.is_null()),
+		}),
+		_ => {}
+	}
+}
+
+// Return the map of a value whose keys are compared one by one, where null is the given empty map.
+fn as_map<'a>(value: &'a Value, empty: &'a Map<String, Value>) -> Option<&'a Map<String, Value>> {
+	match value {
+		Value::Object(map) => Some(map),
+		Value::Null => Some(empty),
+		_ => None,
+	}
+}
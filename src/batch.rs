@@ -0,0 +1,282 @@
+//! Saving of several configs together, so they stay consistent with each other, with [Batch].
+
+use std::fs::{copy, hard_link, remove_file, rename};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::{sync_dir, temp_path, Abserde, Capture, Error, Operation, Result, Storage};
+
+/// Configs saved together, so that either all of them are saved or none are, such as configs which refer to
+/// each other.
+///
+/// Configs are serialised as they're added, as they would be saved, such as merged into a shared config file
+/// under their [Abserde::namespace] or checked against the [Abserde::policy]. When the batch is saved, each
+/// config is written to a temporary file next to its config file, and only once every one has been written are
+/// they renamed into place. If any of them can't be renamed, the config files already replaced are restored.
+/// Backups are rotated as usual. Only configs stored in config files, without a journal, capture, audit log,
+/// section files or verification, can be saved in a batch, and others fail with an [Error::Unsupported], as do
+/// batches holding several configs sharing a config file.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct UiConfig {
+/// # 	theme: String,
+/// # }
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct Keybindings {
+/// # 	quit: String,
+/// # }
+/// #
+/// let my_abserde = Abserde::default();
+///
+/// Batch::new()
+/// 	.add(&UiConfig::default(), &my_abserde.clone().named("ui"))
+/// 	.add(&Keybindings::default(), &my_abserde.clone().named("keybindings"))
+/// 	.save()?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Batch {
+	configs: Vec<Staged>,
+	error: Option<Error>,
+}
+
+// Encoded contents of a config file in a batch, along with the Abserde it is saved with.
+#[derive(Debug)]
+struct Staged {
+	abserde: Abserde,
+	data: Vec<u8>,
+}
+
+// Config file replaced by a batch, which can be restored until the batch is saved.
+struct Replaced {
+	write_path: PathBuf,
+	original: Option<PathBuf>,
+}
+
+impl Batch {
+	/// Create an empty batch.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a config to the batch, to be saved with the given [Abserde].
+	///
+	/// The config is serialised straight away. If that fails, the error is returned when the batch is saved,
+	/// and none of its configs are saved.
+	pub fn add<T: Serialize>(mut self, config: &T, abserde: &Abserde) -> Self {
+		if self.error.is_some() {
+			return self;
+		}
+
+		let data = abserde.with_context(Operation::Save, || {
+			if abserde.storage != Storage::File
+				|| abserde.journal > 0
+				|| abserde.capture.is_some()
+				|| abserde.audit
+				|| abserde.verify_saves
+				|| !abserde.section_files.is_empty()
+			{
+				return Err(Error::Unsupported(
+					"only configs stored in config files, without a journal, capture, audit log, section files or verification, can be saved in a batch"
+						.to_string(),
+				));
+			}
+
+			// The config is saved as usual, but captured, so it's only written along with the rest of the batch.
+			let capture = Capture::new();
+
+			Abserde {
+				capture: Some(capture.clone()),
+				..abserde.clone()
+			}
+			.write_config(config)?;
+
+			Ok(capture.last().unwrap_or_default())
+		});
+
+		match data {
+			Ok(data) => self.configs.push(Staged {
+				abserde: abserde.clone(),
+				data,
+			}),
+			Err(err) => self.error = Some(err),
+		}
+
+		self
+	}
+
+	/// Save every config in the batch, or none of them if any can't be saved.
+	pub fn save(self) -> Result<()> {
+		if let Some(err) = self.error {
+			return Err(err);
+		}
+
+		// Locks are taken in order of path, so batches of the same configs in other processes can't deadlock.
+		let mut locked = self.configs.iter().collect::<Vec<_>>();
+
+		locked.sort_by_cached_key(|staged| staged.abserde.config_path().ok());
+
+		// Configs sharing a config file would overwrite each other, and the config file can only be locked once.
+		if locked
+			.windows(2)
+			.any(|pair| pair[0].abserde.config_path().ok() == pair[1].abserde.config_path().ok())
+		{
+			return Err(Error::Unsupported(
+				"configs sharing a config file can't be saved in the same batch".to_string(),
+			));
+		}
+
+		let _locks = locked
+			.into_iter()
+			.map(|staged| staged.abserde.lock_config(Operation::Save))
+			.collect::<Result<Vec<_>>>()?;
+
+		let mut temp_paths = Vec::with_capacity(self.configs.len());
+		let result: Result<()> = self
+			.configs
+			.iter()
+			.try_for_each(|staged| {
+				temp_paths.push(staged.write_temp()?);
+
+				Ok(())
+			})
+			.and_then(|_| self.replace(&temp_paths));
+
+		for temp_path in &temp_paths {
+			// Ignore any errors here, as temporary files are only left behind when the batch failed.
+			_ = remove_file(temp_path);
+		}
+
+		result
+	}
+
+	// Rename each written temporary file into place, restoring the config files already replaced if any fails.
+	fn replace(&self, temp_paths: &[PathBuf]) -> Result<()> {
+		let mut replaced = Vec::with_capacity(self.configs.len());
+		let result: Result<()> =
+			self.configs
+				.iter()
+				.zip(temp_paths)
+				.try_for_each(|(staged, temp_path)| {
+					replaced.push(staged.replace(temp_path)?);
+
+					Ok(())
+				});
+
+		for replaced in replaced.into_iter().rev() {
+			replaced.finish(result.is_err());
+		}
+
+		result?;
+
+		for staged in &self.configs {
+			staged.abserde.with_context(Operation::Save, || {
+				if staged.abserde.sync {
+					let (_, write_path) = staged.abserde.write_paths(true)?;
+
+					sync_dir(write_path.parent().ok_or(Error::NoSystemConfigDir)?)?;
+				}
+
+				#[cfg(feature = "git")]
+				if staged.abserde.git {
+					staged.abserde.commit(Operation::Save)?;
+				}
+
+				Ok(())
+			})?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Staged {
+	// Write the config to a temporary file next to the file it replaces.
+	fn write_temp(&self) -> Result<PathBuf> {
+		self.abserde.with_context(Operation::Save, || {
+			let (config_path, write_path) = self.abserde.write_paths(true)?;
+			let temp_path = temp_path(&write_path);
+			let result = self
+				.abserde
+				.permissions
+				.create_file(&temp_path)
+				.and_then(|mut file| {
+					file.write_all(&self.data)?;
+
+					if self.abserde.sync {
+						file.sync_all()?;
+					}
+
+					Ok(())
+				})
+				.map_err(Error::file(&config_path));
+
+			match result {
+				Ok(()) => Ok(temp_path),
+				Err(err) => {
+					// Ignore any errors here, as the original error is more useful.
+					_ = remove_file(&temp_path);
+
+					Err(err)
+				}
+			}
+		})
+	}
+
+	// Rename a temporary file into place, keeping the config file it replaces until the batch is saved.
+	fn replace(&self, temp_path: &Path) -> Result<Replaced> {
+		self.abserde.with_context(Operation::Save, || {
+			let (config_path, write_path) = self.abserde.write_paths(true)?;
+			let original = if write_path.exists() {
+				let original_path = temp_path.with_extension("orig");
+
+				// A hard link keeps the original without copying it, where the file system supports them.
+				hard_link(&write_path, &original_path)
+					.or_else(|_| copy(&write_path, &original_path).map(drop))
+					.map_err(Error::file(&config_path))?;
+
+				Some(original_path)
+			} else {
+				None
+			};
+			let replaced = Replaced {
+				write_path,
+				original,
+			};
+
+			let result = self.abserde.rotate_backups(&config_path).and_then(|_| {
+				rename(temp_path, &replaced.write_path).map_err(Error::file(&config_path))
+			});
+
+			match result {
+				Ok(()) => Ok(replaced),
+				Err(err) => {
+					replaced.finish(false);
+
+					Err(err)
+				}
+			}
+		})
+	}
+}
+
+impl Replaced {
+	// Restore the original config file if the batch failed, or otherwise remove the copy kept of it.
+	fn finish(self, restore: bool) {
+		// Ignore any errors here, as the original error of a failed batch is more useful.
+		match (restore, self.original) {
+			(false, Some(original)) => _ = remove_file(original),
+			(false, None) => {}
+			(true, Some(original)) => _ = rename(original, &self.write_path),
+			(true, None) => _ = remove_file(&self.write_path),
+		}
+	}
+}
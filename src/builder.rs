@@ -0,0 +1,336 @@
+//! Builder for [Abserde], validating its options when built.
+
+use std::time::Duration;
+
+use crate::{
+	Abserde, Capture, Compression, Encryption, Error, FilePermissions, Format, LineEnding,
+	Location, PolicyConflict, Result, Signing, Storage, SymlinkPolicy,
+};
+
+/// Builder for an [Abserde], created with [Abserde::builder].
+///
+/// Options which aren't set keep their default values, as in [Abserde::default]. Unlike creating an [Abserde]
+/// directly, the combination of options is checked when it is built.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde::builder()
+/// 	.app("MyApp")
+/// 	.format(Format::Json)
+/// 	.location(Location::Auto)
+/// 	.build()?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct AbserdeBuilder {
+	abserde: Abserde,
+}
+
+impl AbserdeBuilder {
+	/// Set the app name under which app settings are stored. See [Abserde::app].
+	pub fn app<S: Into<String>>(mut self, app: S) -> Self {
+		self.abserde.app = app.into();
+
+		self
+	}
+
+	/// Set the reverse domain name of the organisation behind the app. See [Abserde::qualifier].
+	pub fn qualifier<S: Into<String>>(mut self, qualifier: S) -> Self {
+		self.abserde.qualifier = Some(qualifier.into());
+
+		self
+	}
+
+	/// Set the name of the organisation behind the app. See [Abserde::organization].
+	pub fn organization<S: Into<String>>(mut self, organization: S) -> Self {
+		self.abserde.organization = Some(organization.into());
+
+		self
+	}
+
+	/// Set the name of the config, used in its default file name. See [Abserde::name].
+	pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+		self.abserde.name = Some(name.into());
+
+		self
+	}
+
+	/// Set the extension of the default config file name. See [Abserde::extension].
+	pub fn extension<S: Into<String>>(mut self, extension: S) -> Self {
+		self.abserde.extension = Some(extension.into());
+
+		self
+	}
+
+	/// Keep a top-level key of the config in a section file of its own, with the given name. See
+	/// [Abserde::section_files].
+	pub fn section_file<K: Into<String>, S: Into<String>>(mut self, key: K, name: S) -> Self {
+		self.abserde.section_files.insert(key.into(), name.into());
+
+		self
+	}
+
+	/// Keep the config under a top-level key of the config file, sharing the file with other configs. See
+	/// [Abserde::namespace].
+	pub fn namespace<S: Into<String>>(mut self, key: S) -> Self {
+		self.abserde.namespace = Some(key.into());
+
+		self
+	}
+
+	/// Lock the keys of the config set by the policy file at the given location. See [Abserde::policy].
+	pub fn policy(mut self, location: Location) -> Self {
+		self.abserde.policy = Some(location);
+
+		self
+	}
+
+	/// Set what happens when a config being saved conflicts with the policy. See [Abserde::policy_conflicts].
+	pub fn policy_conflicts(mut self, policy_conflicts: PolicyConflict) -> Self {
+		self.abserde.policy_conflicts = policy_conflicts;
+
+		self
+	}
+
+	/// Set the profile of the config, which is kept in a directory of its own. See [Abserde::profile].
+	pub fn profile<S: Into<String>>(mut self, profile: S) -> Self {
+		self.abserde.profile = Some(profile.into());
+
+		self
+	}
+
+	/// Set the location of the config file. See [Abserde::location].
+	pub fn location(mut self, location: Location) -> Self {
+		self.abserde.location = location;
+
+		self
+	}
+
+	/// Set whether to prefer the system directories of the host over those of a sandbox. See
+	/// [Abserde::host_paths].
+	pub fn host_paths(mut self, host_paths: bool) -> Self {
+		self.abserde.host_paths = host_paths;
+
+		self
+	}
+
+	/// Set the format of the config file. See [Abserde::format].
+	pub fn format(mut self, format: Format) -> Self {
+		self.abserde.format = format;
+
+		self
+	}
+
+	/// Set the line endings of config files in text formats. See [Abserde::line_ending].
+	pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+		self.abserde.line_ending = line_ending;
+
+		self
+	}
+
+	/// Set whether keys are sorted when saving config files in text formats. See [Abserde::sort_keys].
+	pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+		self.abserde.sort_keys = sort_keys;
+
+		self
+	}
+
+	/// Set whether to keep comments in config files when saving. See [Abserde::preserve_comments].
+	pub fn preserve_comments(mut self, preserve_comments: bool) -> Self {
+		self.abserde.preserve_comments = preserve_comments;
+
+		self
+	}
+
+	/// Set the storage backend holding the config. See [Abserde::storage].
+	pub fn storage(mut self, storage: Storage) -> Self {
+		self.abserde.storage = storage;
+
+		self
+	}
+
+	/// Set whether config files are written atomically. See [Abserde::atomic].
+	pub fn atomic(mut self, atomic: bool) -> Self {
+		self.abserde.atomic = atomic;
+
+		self
+	}
+
+	/// Set whether config files are flushed to disk before a save returns. See [Abserde::sync].
+	pub fn sync(mut self, sync: bool) -> Self {
+		self.abserde.sync = sync;
+
+		self
+	}
+
+	/// Set whether configs are read back as soon as they're saved, to check they load as saved. See
+	/// [Abserde::verify_saves].
+	pub fn verify_saves(mut self, verify_saves: bool) -> Self {
+		self.abserde.verify_saves = verify_saves;
+
+		self
+	}
+
+	/// Set how config files which are symbolic links are saved and deleted. See [Abserde::symlinks].
+	pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+		self.abserde.symlinks = symlinks;
+
+		self
+	}
+
+	/// Set the permissions given to config files and their directory. See [Abserde::permissions].
+	pub fn permissions(mut self, permissions: FilePermissions) -> Self {
+		self.abserde.permissions = permissions;
+
+		self
+	}
+
+	/// Set whether config files with insecure permissions are refused when loading. See
+	/// [Abserde::audit_permissions].
+	pub fn audit_permissions(mut self, audit_permissions: bool) -> Self {
+		self.abserde.audit_permissions = audit_permissions;
+
+		self
+	}
+
+	/// Set the number of previous config files kept as backups. See [Abserde::backups].
+	pub fn backups(mut self, backups: usize) -> Self {
+		self.abserde.backups = backups;
+
+		self
+	}
+
+	/// Set the number of saves appended to the journal before it is compacted. See [Abserde::journal].
+	pub fn journal(mut self, journal: usize) -> Self {
+		self.abserde.journal = journal;
+
+		self
+	}
+
+	/// Set whether the config file is locked while it is loaded, saved or deleted. See [Abserde::lock].
+	pub fn lock(mut self, lock: bool) -> Self {
+		self.abserde.lock = lock;
+
+		self
+	}
+
+	/// Set the longest time to wait for the lock on the config file. See [Abserde::lock_timeout].
+	pub fn lock_timeout(mut self, lock_timeout: Duration) -> Self {
+		self.abserde.lock_timeout = Some(lock_timeout);
+
+		self
+	}
+
+	/// Set the encryption applied to config files. See [Abserde::encryption].
+	pub fn encryption(mut self, encryption: Encryption) -> Self {
+		self.abserde.encryption = encryption;
+
+		self
+	}
+
+	/// Set the compression applied to config files. See [Abserde::compression].
+	pub fn compression(mut self, compression: Compression) -> Self {
+		self.abserde.compression = compression;
+
+		self
+	}
+
+	/// Set the signing of config files. See [Abserde::signing].
+	pub fn signing(mut self, signing: Signing) -> Self {
+		self.abserde.signing = signing;
+
+		self
+	}
+
+	/// Save configs in an envelope recording the given schema version. See [Abserde::schema_version].
+	pub fn schema_version(mut self, schema_version: u64) -> Self {
+		self.abserde.schema_version = Some(schema_version);
+
+		self
+	}
+
+	/// Set the default config document. See [Abserde::embedded_defaults].
+	pub fn embedded_defaults(mut self, defaults: &'static str) -> Self {
+		self.abserde.embedded_defaults = Some(defaults);
+
+		self
+	}
+
+	/// Set the key of configs listing other config files they include. See [Abserde::include_key].
+	pub fn include_key<S: Into<String>>(mut self, key: S) -> Self {
+		self.abserde.include_key = Some(key.into());
+
+		self
+	}
+
+	/// Set whether to expand environment variables in string values of configs. See [Abserde::interpolate].
+	pub fn interpolate(mut self, interpolate: bool) -> Self {
+		self.abserde.interpolate = interpolate;
+
+		self
+	}
+
+	/// Capture saved configs in the given sink, in place of storing them. See [Abserde::capture].
+	pub fn capture(mut self, capture: Capture) -> Self {
+		self.abserde.capture = Some(capture);
+
+		self
+	}
+
+	/// Set whether saves and deletes of the config are recorded in an audit log. See [Abserde::audit].
+	pub fn audit(mut self, audit: bool) -> Self {
+		self.abserde.audit = audit;
+
+		self
+	}
+
+	/// Set whether the config file is committed to a git repository whenever it changes. See [Abserde::git].
+	#[cfg(feature = "git")]
+	pub fn git(mut self, git: bool) -> Self {
+		self.abserde.git = git;
+
+		self
+	}
+
+	/// Check the combination of options, returning the [Abserde] if they are valid.
+	///
+	/// Fails with an [Error::Unsupported] if the app name is needed to locate the config file but is empty,
+	/// if the format must be inferred from a config file name which isn't given, or if the extension of the
+	/// config file name is that of a different format.
+	pub fn build(self) -> Result<Abserde> {
+		let abserde = self.abserde;
+
+		if abserde.app.is_empty()
+			&& !matches!(
+				abserde.platform_location(),
+				Location::Path(_) | Location::Dir(_)
+			) {
+			return Err(Error::Unsupported(
+				"app name must be given to locate the config file".to_string(),
+			));
+		}
+
+		let path = match abserde.platform_location() {
+			Location::Path(path) | Location::File(path) => path.clone(),
+			_ => abserde.default_name()?.into(),
+		};
+
+		match (&abserde.format, abserde.format_of_name(&path)) {
+			(Format::FromExtension, None) => Err(Error::Unsupported(format!(
+				"can't infer config format from file name {}",
+				path.display()
+			))),
+			(Format::FromExtension, Some(_)) => Ok(abserde),
+			(format, Some(inferred)) if format.default_name() != inferred.default_name() => {
+				Err(Error::Unsupported(format!(
+					"config file name {} doesn't match format {:?}",
+					path.display(),
+					format
+				)))
+			}
+			_ => Ok(abserde),
+		}
+	}
+}
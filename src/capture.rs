@@ -0,0 +1,82 @@
+//! Capture of saved configs in memory, in place of storing them, enabled with [Abserde::capture].
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[cfg(doc)]
+use crate::Abserde;
+
+// Configs as they would have been saved, along with the paths of their config files.
+type Writes = Vec<(PathBuf, Vec<u8>)>;
+
+/// Sink holding configs as they would have been saved, used with [Abserde::capture].
+///
+/// Captures are shared by their clones, so a clone can be given to the [Abserde] while the original is kept to
+/// retrieve what was saved, such as to preview changes or to assert on the exact output in tests.
+///
+/// ```
+/// # use abserde::*;
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct MyConfig {
+/// # 	window_width: usize,
+/// # }
+/// #
+/// let capture = Capture::new();
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	capture: Some(capture.clone()),
+/// 	..Default::default()
+/// };
+///
+/// MyConfig::default().save_config(&my_abserde)?;
+///
+/// let preview = String::from_utf8(capture.last().unwrap()).unwrap();
+/// #
+/// # assert!(preview.contains("window_width"));
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Capture {
+	writes: Arc<Mutex<Writes>>,
+}
+
+impl Capture {
+	/// Create an empty capture.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The contents of the config file as last saved, after serialisation and any compression and encryption.
+	pub fn last(&self) -> Option<Vec<u8>> {
+		self.lock().last().map(|(_, data)| data.clone())
+	}
+
+	/// Every config saved so far, in order, along with the path of the config file it would have been saved to.
+	pub fn writes(&self) -> Vec<(PathBuf, Vec<u8>)> {
+		self.lock().clone()
+	}
+
+	/// Remove and return every config saved so far, leaving the capture empty.
+	pub fn take(&self) -> Vec<(PathBuf, Vec<u8>)> {
+		std::mem::take(&mut *self.lock())
+	}
+
+	// Record a config as it would have been saved to the given config file.
+	pub(crate) fn record(&self, config_path: PathBuf, data: &[u8]) {
+		self.lock().push((config_path, data.to_owned()));
+	}
+
+	// Lock the captured configs, which are left consistent even if a thread panicked holding the lock.
+	fn lock(&self) -> MutexGuard<'_, Writes> {
+		self.writes.lock().unwrap_or_else(|err| err.into_inner())
+	}
+}
+
+impl PartialEq for Capture {
+	// Captures are equal when they share the same sink.
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.writes, &other.writes)
+	}
+}
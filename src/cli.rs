@@ -0,0 +1,57 @@
+//! Command-line overrides of config fields, parsed with clap.
+
+use crate::Layers;
+
+/// Command-line arguments overriding config fields, to be flattened into the app's own arguments.
+///
+/// Each `--set KEY=VALUE` argument overrides a config field, with `.` separating nested fields, such as
+/// `--set window.width=1920`. Add them as the last of the [Layers] with [Layers::args], so that they take
+/// precedence over config files and environment variables.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use clap::Parser;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct MyConfig {
+/// # 	theme: String,
+/// # }
+/// #
+/// #[derive(Parser)]
+/// struct Cli {
+/// 	#[command(flatten)]
+/// 	config: ConfigArgs,
+/// }
+///
+/// let cli = Cli::parse();
+/// let layers = Layers::new()
+/// 	.defaults(MyConfig::default())
+/// 	.file(Abserde::default())
+/// 	.env("MYAPP")
+/// 	.args(&cli.config);
+/// let my_config = layers.load_layered::<MyConfig>()?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, clap::Args)]
+pub struct ConfigArgs {
+	/// Override a config field, such as window.width=1920.
+	#[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_pair)]
+	pub set: Vec<(String, String)>,
+}
+
+impl Layers {
+	/// Add a layer overriding config fields with the given command-line arguments.
+	pub fn args(self, args: &ConfigArgs) -> Self {
+		self.set(args.set.clone())
+	}
+}
+
+// Parse a `KEY=VALUE` argument.
+fn parse_pair(arg: &str) -> Result<(String, String), String> {
+	match arg.split_once('=') {
+		Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+		_ => Err(format!("expected KEY=VALUE, found {}", arg)),
+	}
+}
@@ -0,0 +1,169 @@
+//! Collections of configs of the same type, such as per-project or per-server settings, each kept in a
+//! config file of its own within a directory of the collection.
+
+use std::fs::read_dir;
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Component, Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{check_dir_name, manager, Abserde, Config, Error, Location, Result, Storage};
+
+/// Keyed collection of configs of the same type, such as settings for each project or server, kept in config
+/// files named after their keys, such as `servers/staging.json`.
+///
+/// The directory of the collection is named after it, next to the config file of the [Abserde], and its
+/// config files are stored in the format, and with the other options, of the [Abserde].
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Server {
+/// # 	url: String,
+/// # }
+/// #
+/// let servers = ConfigCollection::<Server>::new(Abserde::default(), "servers");
+///
+/// servers.insert(
+/// 	"staging",
+/// 	&Server {
+/// 		url: "https://staging.example.com".to_string(),
+/// 	},
+/// )?;
+///
+/// for key in servers.list()? {
+/// 	let server = servers.get(&key)?;
+/// }
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+pub struct ConfigCollection<T> {
+	abserde: Abserde,
+	name: String,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ConfigCollection<T>
+where
+	T: Serialize,
+	T: DeserializeOwned,
+{
+	/// Create a collection with the given name, kept in a directory of that name next to the config file.
+	pub fn new<S: Into<String>>(abserde: Abserde, name: S) -> Self {
+		Self {
+			abserde,
+			name: name.into(),
+			marker: PhantomData,
+		}
+	}
+
+	/// Return the keys of the configs in the collection, in alphabetical order.
+	///
+	/// Only collections of config files can be listed, so other storage fails with an [Error::Unsupported].
+	pub fn list(&self) -> Result<Vec<String>> {
+		if self.abserde.storage != Storage::File {
+			return Err(Error::Unsupported(
+				"only collections of config files can be listed".to_string(),
+			));
+		}
+
+		let dir = self.dir()?;
+		let entries = match read_dir(&dir) {
+			Ok(entries) => entries,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(err) => return Err(Error::file(&dir)(err)),
+		};
+		// Config files are named after their keys, followed by the same extensions, such as `.json`.
+		let suffix = self.item(&dir, "").default_name()?;
+		let mut keys = Vec::new();
+
+		for entry in entries {
+			let entry = entry?;
+
+			if !entry.file_type()?.is_file() {
+				continue;
+			}
+
+			if let Some(key) = entry
+				.file_name()
+				.to_str()
+				.and_then(|file_name| file_name.strip_suffix(&suffix))
+				.filter(|key| !key.is_empty())
+			{
+				keys.push(key.to_string());
+			}
+		}
+
+		keys.sort();
+
+		Ok(keys)
+	}
+
+	/// Load the config with the given key, returning none if there is no such config.
+	pub fn get(&self, key: &str) -> Result<Option<T>> {
+		manager::load_if_exists(&self.abserde_for(key)?)
+	}
+
+	/// Save the config with the given key, replacing any config already kept with it.
+	pub fn insert(&self, key: &str, config: &T) -> Result<()> {
+		config.save_config(&self.abserde_for(key)?)
+	}
+
+	/// Delete the config with the given key, returning whether there was such a config.
+	pub fn remove(&self, key: &str) -> Result<bool> {
+		let abserde = self.abserde_for(key)?;
+
+		if !abserde.exists()? {
+			return Ok(false);
+		}
+
+		abserde.delete()?;
+
+		Ok(true)
+	}
+
+	/// Return the [Abserde] of the config with the given key, such as to watch it or keep backups of it.
+	///
+	/// Fails with an [Error::Unsupported] if the key isn't a valid file name, such as one holding a path
+	/// separator.
+	pub fn abserde_for(&self, key: &str) -> Result<Abserde> {
+		let mut components = Path::new(key).components();
+
+		match (components.next(), components.next()) {
+			(Some(Component::Normal(_)), None) => Ok(self.item(&self.dir()?, key)),
+			_ => Err(Error::Unsupported(format!(
+				"collection key {:?} is not a valid config file name",
+				key
+			))),
+		}
+	}
+
+	// Directory of the collection, next to the config file.
+	fn dir(&self) -> Result<PathBuf> {
+		check_dir_name("collection", &self.name)?;
+
+		let config_path = self.abserde.config_path()?;
+
+		Ok(config_path
+			.parent()
+			.unwrap_or(Path::new(""))
+			.join(&self.name))
+	}
+
+	// Abserde of the config with the given key, within the directory of the collection.
+	fn item(&self, dir: &Path, key: &str) -> Abserde {
+		Abserde {
+			location: Location::Dir(dir.to_owned()),
+			name: Some(key.to_string()),
+			profile: None,
+			section_files: Default::default(),
+			namespace: None,
+			..self.abserde.clone()
+		}
+	}
+}
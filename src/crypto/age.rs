@@ -0,0 +1,57 @@
+//! Encryption to age recipients, using the standard age file format.
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use ::age::{Decryptor, Encryptor, Identity, IdentityFile, Recipient};
+
+use crate::{Error, Result};
+
+/// Encrypt serialised config data to the given age recipients.
+pub(crate) fn age_encrypt(plaintext: &[u8], recipients: &[String]) -> Result<Vec<u8>> {
+	let recipients = recipients
+		.iter()
+		.map(|recipient| {
+			recipient
+				.parse::<::age::x25519::Recipient>()
+				.map_err(|err| {
+					Error::Encryption(
+						format!("invalid age recipient {}: {}", recipient, err).into(),
+					)
+				})
+		})
+		.collect::<Result<Vec<_>>>()?;
+	let encryptor = Encryptor::with_recipients(
+		recipients
+			.iter()
+			.map(|recipient| recipient as &dyn Recipient),
+	)
+	.map_err(|err| Error::Encryption(err.into()))?;
+	let mut ciphertext = Vec::new();
+	let mut writer = encryptor.wrap_output(&mut ciphertext)?;
+
+	writer.write_all(plaintext)?;
+	writer.finish()?;
+
+	Ok(ciphertext)
+}
+
+/// Decrypt age encrypted config data using the identities in the given identity file.
+pub(crate) fn age_decrypt(data: &[u8], identity: &Path) -> Result<Vec<u8>> {
+	let identities = IdentityFile::from_file(identity.to_string_lossy().into_owned())?
+		.into_identities()
+		.map_err(|err| Error::Encryption(err.into()))?;
+	let decryptor = Decryptor::new_buffered(data).map_err(|err| Error::Encryption(err.into()))?;
+	let mut reader = decryptor
+		.decrypt(
+			identities
+				.iter()
+				.map(|identity| identity.as_ref() as &dyn Identity),
+		)
+		.map_err(|err| Error::Encryption(err.into()))?;
+	let mut plaintext = Vec::new();
+
+	reader.read_to_end(&mut plaintext)?;
+
+	Ok(plaintext)
+}
@@ -0,0 +1,52 @@
+//! Sources of the passphrases and keys used to encrypt and sign config files.
+
+use std::env::var;
+use std::fmt::{self, Debug};
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{Error, Result};
+
+/// Source of the passphrase used to encrypt and decrypt config files, or of the key used to sign them.
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub enum KeySource {
+	/// Passphrase provided directly.
+	Passphrase(String),
+
+	/// Passphrase read from the named environment variable.
+	Env(String),
+
+	/// Passphrase read from a file, ignoring any trailing newline.
+	File(PathBuf),
+}
+
+impl KeySource {
+	// Resolve the passphrase from its source.
+	pub(crate) fn passphrase(&self) -> Result<Zeroizing<String>> {
+		Ok(Zeroizing::new(match self {
+			KeySource::Passphrase(passphrase) => passphrase.clone(),
+			KeySource::Env(name) => var(name).map_err(|_| {
+				Error::Encryption(
+					format!("passphrase environment variable {} is not set", name).into(),
+				)
+			})?,
+			KeySource::File(path) => Zeroizing::new(read_to_string(path)?)
+				.trim_end_matches(['\r', '\n'])
+				.to_string(),
+		}))
+	}
+}
+
+// Passphrases must never end up in logs, so only the kind of source is shown.
+impl Debug for KeySource {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			KeySource::Passphrase(_) => write!(f, "Passphrase(..)"),
+			KeySource::Env(name) => f.debug_tuple("Env").field(name).finish(),
+			KeySource::File(path) => f.debug_tuple("File").field(path).finish(),
+		}
+	}
+}
@@ -0,0 +1,23 @@
+//! Encryption of config files at rest, and signing of them.
+
+#[cfg(feature = "age")]
+mod age;
+#[cfg(any(feature = "encryption", feature = "signing"))]
+mod key;
+#[cfg(feature = "encryption")]
+mod passphrase;
+#[cfg(feature = "encryption")]
+mod secret;
+#[cfg(feature = "signing")]
+mod signature;
+
+#[cfg(feature = "age")]
+pub(crate) use self::age::*;
+#[cfg(any(feature = "encryption", feature = "signing"))]
+pub use key::*;
+#[cfg(feature = "encryption")]
+pub(crate) use passphrase::*;
+#[cfg(feature = "encryption")]
+pub use secret::*;
+#[cfg(feature = "signing")]
+pub(crate) use signature::*;
@@ -0,0 +1,87 @@
+//! Passphrase-based encryption using AES-256-GCM, with keys derived by Argon2.
+//!
+//! Encrypted files start with a short header identifying the encryption scheme, followed by the random
+//! salt used to derive the key, the random nonce, and finally the ciphertext. Passphrases and derived keys are
+//! wiped from memory once they're no longer needed.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+use super::KeySource;
+use crate::{Error, Result};
+
+const MAGIC: &[u8] = b"abserde\x01";
+pub(super) const SALT_LEN: usize = 16;
+pub(super) const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+// Derive an AES-256 key from a passphrase and salt.
+pub(super) fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm> {
+	let mut key = Zeroizing::new([0; KEY_LEN]);
+
+	Argon2::default()
+		.hash_password_into(passphrase.as_bytes(), salt, &mut *key)
+		.map_err(|err| {
+			Error::Encryption(format!("failed to derive encryption key: {}", err).into())
+		})?;
+
+	Ok(Aes256Gcm::new(&(*key).into()))
+}
+
+/// Encrypt serialised config data with a key derived from the given passphrase.
+pub(crate) fn encrypt(plaintext: &[u8], key_source: &KeySource) -> Result<Vec<u8>> {
+	let mut salt = [0; SALT_LEN];
+	let mut nonce = [0; NONCE_LEN];
+
+	rand::thread_rng().fill_bytes(&mut salt);
+	rand::thread_rng().fill_bytes(&mut nonce);
+
+	let cipher = derive_key(&key_source.passphrase()?, &salt)?;
+	let ciphertext = cipher
+		.encrypt(
+			Nonce::from_slice(&nonce),
+			Payload {
+				msg: plaintext,
+				aad: MAGIC,
+			},
+		)
+		.map_err(|_| Error::Encryption("failed to encrypt config".into()))?;
+
+	Ok([MAGIC, &salt, &nonce, &ciphertext].concat())
+}
+
+/// Decrypt config data previously encrypted with [encrypt].
+pub(crate) fn decrypt(data: &[u8], key_source: &KeySource) -> Result<Vec<u8>> {
+	let Some(data) = data.strip_prefix(MAGIC) else {
+		return Err(Error::Encryption(
+			"config file is not encrypted, or uses an unsupported encryption scheme".into(),
+		));
+	};
+
+	if data.len() < SALT_LEN + NONCE_LEN {
+		return Err(Error::Encryption(
+			"encrypted config file is truncated".into(),
+		));
+	}
+
+	let (salt, data) = data.split_at(SALT_LEN);
+	let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+	let cipher = derive_key(&key_source.passphrase()?, salt)?;
+
+	cipher
+		.decrypt(
+			Nonce::from_slice(nonce),
+			Payload {
+				msg: ciphertext,
+				aad: MAGIC,
+			},
+		)
+		.map_err(|_| {
+			Error::Encryption(
+				"failed to decrypt config file, the passphrase may be incorrect".into(),
+			)
+		})
+}
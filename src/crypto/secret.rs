@@ -0,0 +1,306 @@
+//! Field-level encryption of config values wrapped in [Secret].
+//!
+//! Secret values are serialised to JSON, encrypted using AES-256-GCM and stored as strings of the form
+//! `enc:v1:<base64>`, where the base64 data holds the salt used to derive the key, the random nonce and the
+//! ciphertext. Keys are derived once per salt, so a config file with many secrets only runs Argon2 once.
+//!
+//! Secrets, and the plaintext they're serialised to before being encrypted, are wiped from memory when dropped.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::{ptr, result};
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::RngCore;
+use serde::de::{self, DeserializeOwned};
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use super::passphrase::{derive_key, NONCE_LEN, SALT_LEN};
+use super::KeySource;
+use crate::{Error, Result};
+
+const PREFIX: &str = "enc:v1:";
+const AAD: &[u8] = b"abserde secret\x01";
+
+/// Config value which is encrypted on its own when saved, leaving the rest of the config file readable.
+///
+/// Secrets are encrypted and decrypted by an [Abserde](crate::Abserde) using
+/// [Encryption::Fields](crate::Encryption::Fields). Serialising a secret in any other way fails, so that it
+/// can't be written out in plain text by accident. Plain values are accepted when loading, so secrets can be
+/// entered by hand, and are encrypted the next time the config is saved.
+///
+/// Secrets are wiped from memory when dropped, so the wrapped value must implement [Zeroize], as strings,
+/// numbers and collections of them do. Config types can derive it with the derive feature of the re-exported
+/// [zeroize](crate::zeroize) crate.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// #[derive(Serialize, Deserialize)]
+/// struct MyConfig {
+/// 	username: String,
+/// 	password: Secret<String>,
+/// }
+/// ```
+#[derive(PartialEq, Eq, Clone, Default)]
+pub struct Secret<T: Zeroize>(pub T);
+
+impl<T: Zeroize> Secret<T> {
+	/// Consume the secret, returning the wrapped value, which is then no longer wiped when dropped.
+	pub fn into_inner(self) -> T {
+		let secret = ManuallyDrop::new(self);
+
+		// SAFETY: the secret is never dropped, so its value is moved out of it exactly once.
+		unsafe { ptr::read(&secret.0) }
+	}
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+	fn from(value: T) -> Self {
+		Secret(value)
+	}
+}
+
+impl<T: Zeroize> Deref for Secret<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.0
+	}
+}
+
+impl<T: Zeroize> DerefMut for Secret<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.0
+	}
+}
+
+// Secrets must never end up in logs.
+impl<T: Zeroize> Debug for Secret<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Secret(..)")
+	}
+}
+
+impl<T: Zeroize> Zeroize for Secret<T> {
+	fn zeroize(&mut self) {
+		self.0.zeroize();
+	}
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+	fn drop(&mut self) {
+		self.0.zeroize();
+	}
+}
+
+impl<T: Zeroize> ZeroizeOnDrop for Secret<T> {}
+
+impl<T: Zeroize + Serialize> Serialize for Secret<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+		let plaintext = Zeroizing::new(serde_json::to_vec(&self.0).map_err(ser::Error::custom)?);
+		let encrypted = encrypt(&plaintext).map_err(ser::Error::custom)?;
+
+		serializer.serialize_str(&encrypted)
+	}
+}
+
+impl<'de, T: Zeroize + DeserializeOwned> Deserialize<'de> for Secret<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+		let value = Value::deserialize(deserializer)?;
+		let value = match value.as_str().and_then(|value| value.strip_prefix(PREFIX)) {
+			Some(encrypted) => serde_json::from_slice(&Zeroizing::new(
+				decrypt(encrypted).map_err(de::Error::custom)?,
+			)),
+			None => serde_json::from_value(value),
+		};
+
+		value.map(Secret).map_err(de::Error::custom)
+	}
+}
+
+// Key available to secrets serialised or deserialised on the current thread.
+struct SecretKey {
+	passphrase: Zeroizing<String>,
+
+	// Salt of the key used to encrypt secrets, generated on first use.
+	salt: Option<[u8; SALT_LEN]>,
+
+	// Keys derived so far, by salt.
+	ciphers: HashMap<Vec<u8>, Aes256Gcm>,
+}
+
+impl SecretKey {
+	// Get the key for a salt, deriving it if necessary.
+	fn cipher(&mut self, salt: &[u8]) -> Result<&Aes256Gcm> {
+		if !self.ciphers.contains_key(salt) {
+			let cipher = derive_key(&self.passphrase, salt)?;
+
+			self.ciphers.insert(salt.to_vec(), cipher);
+		}
+
+		Ok(&self.ciphers[salt])
+	}
+}
+
+thread_local! {
+	static SECRET_KEY: RefCell<Option<SecretKey>> = const { RefCell::new(None) };
+}
+
+/// Makes a key available to secrets serialised or deserialised on the current thread, until dropped.
+pub(crate) struct SecretKeyScope {
+	previous: Option<SecretKey>,
+}
+
+impl SecretKeyScope {
+	pub(crate) fn enter(key_source: &KeySource) -> Result<Self> {
+		let key = SecretKey {
+			passphrase: key_source.passphrase()?,
+			salt: None,
+			ciphers: HashMap::new(),
+		};
+
+		Ok(Self {
+			previous: SECRET_KEY.with(|current| current.replace(Some(key))),
+		})
+	}
+}
+
+impl Drop for SecretKeyScope {
+	fn drop(&mut self) {
+		SECRET_KEY.with(|current| *current.borrow_mut() = self.previous.take());
+	}
+}
+
+// Run a function with the key for the current thread.
+fn with_key<R>(f: impl FnOnce(&mut SecretKey) -> Result<R>) -> Result<R> {
+	SECRET_KEY.with(|current| match current.borrow_mut().as_mut() {
+		Some(key) => f(key),
+		None => Err(Error::Unsupported(
+			"secrets can only be saved and loaded by an Abserde using Encryption::Fields"
+				.to_string(),
+		)),
+	})
+}
+
+// Encrypt a serialised secret value.
+fn encrypt(plaintext: &[u8]) -> Result<String> {
+	let mut nonce = [0; NONCE_LEN];
+
+	rand::thread_rng().fill_bytes(&mut nonce);
+
+	with_key(|key| {
+		let salt = *key.salt.get_or_insert_with(|| {
+			let mut salt = [0; SALT_LEN];
+
+			rand::thread_rng().fill_bytes(&mut salt);
+
+			salt
+		});
+		let ciphertext = key
+			.cipher(&salt)?
+			.encrypt(
+				Nonce::from_slice(&nonce),
+				Payload {
+					msg: plaintext,
+					aad: AAD,
+				},
+			)
+			.map_err(|_| Error::Encryption("failed to encrypt secret".into()))?;
+
+		Ok(format!(
+			"{}{}",
+			PREFIX,
+			STANDARD.encode([salt.as_slice(), &nonce, &ciphertext].concat())
+		))
+	})
+}
+
+// Decrypt a secret value previously encrypted with [encrypt], without its prefix.
+fn decrypt(encrypted: &str) -> Result<Vec<u8>> {
+	let data = STANDARD
+		.decode(encrypted)
+		.map_err(|_| Error::Encryption("encrypted secret is not valid base64".into()))?;
+
+	if data.len() < SALT_LEN + NONCE_LEN {
+		return Err(Error::Encryption("encrypted secret is truncated".into()));
+	}
+
+	let (salt, data) = data.split_at(SALT_LEN);
+	let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+
+	with_key(|key| {
+		key.cipher(salt)?
+			.decrypt(
+				Nonce::from_slice(nonce),
+				Payload {
+					msg: ciphertext,
+					aad: AAD,
+				},
+			)
+			.map_err(|_| {
+				Error::Encryption(
+					"failed to decrypt secret, the passphrase may be incorrect".into(),
+				)
+			})
+	})
+}
+
+// Re-encrypt the secrets within a value, encrypted with the old passphrase, with the new passphrase.
+pub(crate) fn rotate_secrets(value: &mut Value, old: &KeySource, new: &KeySource) -> Result<()> {
+	let mut plaintexts = Vec::new();
+
+	{
+		let _scope = SecretKeyScope::enter(old)?;
+
+		visit_secrets(value, &mut |encrypted| {
+			plaintexts.push(Zeroizing::new(decrypt(encrypted)?));
+
+			Ok(None)
+		})?;
+	}
+
+	let _scope = SecretKeyScope::enter(new)?;
+	let mut plaintexts = plaintexts.into_iter();
+
+	// Secrets are visited in the same order, so each is given back its own plaintext.
+	visit_secrets(value, &mut |_| {
+		plaintexts
+			.next()
+			.map(|plaintext| encrypt(&plaintext))
+			.transpose()
+	})
+}
+
+// Call a function with each encrypted secret within a value, without its prefix, replacing the secret with any
+// string it returns.
+fn visit_secrets<F>(value: &mut Value, f: &mut F) -> Result<()>
+where
+	F: FnMut(&str) -> Result<Option<String>>,
+{
+	match value {
+		Value::String(s) => {
+			if let Some(replacement) = s.strip_prefix(PREFIX).map(&mut *f).transpose()?.flatten() {
+				*s = replacement;
+			}
+		}
+		Value::Array(values) => values
+			.iter_mut()
+			.try_for_each(|value| visit_secrets(value, f))?,
+		Value::Object(map) => map
+			.values_mut()
+			.try_for_each(|value| visit_secrets(value, f))?,
+		_ => {}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,47 @@
+//! Signing of config files using HMAC-SHA256, so changes made to them other than by saving the config are
+//! detected when loading.
+//!
+//! Signed files start with a short header identifying the signing scheme, followed by the signature, and finally
+//! the config file as it would be saved unsigned, so signing can be combined with encryption and compression.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::KeySource;
+use crate::{Error, Result};
+
+const MAGIC: &[u8] = b"abserde-signed\x01";
+const SIGNATURE_LEN: usize = 32;
+
+// Start an HMAC keyed with the key from its source.
+fn mac(key_source: &KeySource) -> Result<Hmac<Sha256>> {
+	Ok(Hmac::new_from_slice(key_source.passphrase()?.as_bytes()).expect("HMAC key of any length"))
+}
+
+/// Sign config data with the key from the given source.
+pub(crate) fn sign(data: &[u8], key_source: &KeySource) -> Result<Vec<u8>> {
+	let mut mac = mac(key_source)?;
+
+	mac.update(data);
+
+	Ok([MAGIC, &mac.finalize().into_bytes(), data].concat())
+}
+
+/// Verify the signature of config data previously signed with [sign], returning the data without it.
+pub(crate) fn verify<'a>(data: &'a [u8], key_source: &KeySource) -> Result<&'a [u8]> {
+	// Unsigned config files are rejected like tampered ones, as they may have been written by hand.
+	let Some(data) = data
+		.strip_prefix(MAGIC)
+		.filter(|data| data.len() >= SIGNATURE_LEN)
+	else {
+		return Err(Error::SignatureMismatch);
+	};
+	let (signature, data) = data.split_at(SIGNATURE_LEN);
+	let mut mac = mac(key_source)?;
+
+	mac.update(data);
+	mac.verify_slice(signature)
+		.map_err(|_| Error::SignatureMismatch)?;
+
+	Ok(data)
+}
@@ -0,0 +1,78 @@
+//! Config formats implemented outside of this crate, used with [Format::Custom](crate::Format::Custom).
+
+use std::fmt::Debug;
+use std::io;
+use std::ptr;
+
+use crate::Result;
+
+/// A config format implemented outside of this crate, such as a proprietary or niche format, used with
+/// [Format::Custom](crate::Format::Custom). Enabled with the custom feature.
+///
+/// Configs are passed through [erased_serde], re-exported so formats use the same version, and a format can be
+/// implemented with any serde data format. Configs are read and written as bytes, without the byte order marks
+/// and UTF-16 of text editors being handled.
+///
+/// ```no_run
+/// # use std::io;
+/// # use std::sync::Arc;
+/// # use abserde::*;
+/// #
+/// #[derive(Debug)]
+/// struct Json5;
+///
+/// impl ConfigFormat for Json5 {
+/// 	fn extension(&self) -> &str {
+/// 		"json5"
+/// 	}
+///
+/// 	fn serialize(
+/// 		&self,
+/// 		config: &dyn erased_serde::Serialize,
+/// 		writer: &mut dyn io::Write,
+/// 	) -> Result<()> {
+/// 		serde_json::to_writer_pretty(writer, config).map_err(|err| Error::Serialize(err.into()))
+/// 	}
+///
+/// 	fn deserialize(
+/// 		&self,
+/// 		reader: &mut dyn io::Read,
+/// 	) -> Result<Box<dyn erased_serde::Deserializer<'static>>> {
+/// 		let value: serde_json::Value =
+/// 			serde_json::from_reader(reader).map_err(|err| Error::Deserialize(err.into()))?;
+///
+/// 		Ok(Box::new(<dyn erased_serde::Deserializer>::erase(value)))
+/// 	}
+/// }
+///
+/// let my_abserde = Abserde {
+/// 	format: Format::Custom(Arc::new(Json5)),
+/// 	..Default::default()
+/// };
+/// ```
+pub trait ConfigFormat: Debug + Send + Sync {
+	/// Extension of config files in this format, without the dot, such as `json5`, giving the default config
+	/// file name.
+	fn extension(&self) -> &str;
+
+	/// Serialise a config to the given writer.
+	fn serialize(
+		&self,
+		config: &dyn erased_serde::Serialize,
+		writer: &mut dyn io::Write,
+	) -> Result<()>;
+
+	/// Deserialise the config read from the given reader, returning a deserialiser which owns what it needs,
+	/// such as a document tree of the format.
+	fn deserialize(
+		&self,
+		reader: &mut dyn io::Read,
+	) -> Result<Box<dyn erased_serde::Deserializer<'static>>>;
+}
+
+impl PartialEq for dyn ConfigFormat {
+	// Custom formats can't be compared by what they do, so they're equal only when they're the same one.
+	fn eq(&self, other: &Self) -> bool {
+		ptr::addr_eq(self, other)
+	}
+}
@@ -0,0 +1,31 @@
+//! Support for code generated by the [AbserdeConfig](crate::AbserdeConfig) derive macro, which isn't meant to be
+//! used directly.
+
+use std::env::{var, VarError};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Value of a field overridden by the given environment variable, if it is set.
+///
+/// As with [Layers::env](crate::Layers::env), values are parsed as JSON, such as numbers and booleans, falling
+/// back to the raw string for fields which hold strings.
+pub fn env_override<T: DeserializeOwned>(name: &str) -> Result<Option<T>> {
+	let raw = match var(name) {
+		Ok(raw) => raw,
+		Err(VarError::NotPresent) => return Ok(None),
+		Err(err) => return Err(Error::Deserialize(override_error(name, err).into())),
+	};
+	let value = serde_json::from_str(&raw)
+		.or_else(|_| serde_json::from_value(Value::String(raw)))
+		.map_err(|err| Error::Deserialize(override_error(name, err).into()))?;
+
+	Ok(Some(value))
+}
+
+// Describe why the value of an environment variable couldn't be used.
+fn override_error<E: std::fmt::Display>(name: &str, err: E) -> String {
+	format!("environment variable {}: {}", name, err)
+}
@@ -0,0 +1,92 @@
+//! Untyped configs, for apps which don't know the config type at compile time, held in [DynamicConfig].
+
+use std::ops::{Deref, DerefMut};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{value, Error, Result};
+
+/// Config of any shape, loaded and saved in any format, with typed access to its keys by dot-separated path.
+///
+/// This suits plugin systems and tools which can't know the config type at compile time. The config is held
+/// as a [Value], which it dereferences to, and starts out as an empty object.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let mut my_config = DynamicConfig::load_config(&Abserde::default())?;
+/// let width = my_config.get::<u32>("window.width")?;
+///
+/// my_config.set("theme", "dark")?;
+/// my_config.save_config(&Abserde::default())?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DynamicConfig {
+	value: Value,
+}
+
+impl DynamicConfig {
+	/// Create an empty config.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Get the value of a key, given by its dot-separated path such as `window.width`, deserialised as the
+	/// given type.
+	///
+	/// Items of arrays are given by their index, such as `recent.0`. Returns none if there is no such key, and
+	/// an [Error::Deserialize] if its value isn't of the given type.
+	pub fn get<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>> {
+		value::get(&self.value, path)
+			.map(|value| T::deserialize(value).map_err(|err| Error::Deserialize(err.into())))
+			.transpose()
+	}
+
+	/// Set the value of a key, given by its dot-separated path such as `window.width`.
+	///
+	/// Missing objects along the path are created. Items of arrays are given by their index, and must already
+	/// exist.
+	pub fn set<T: Serialize>(&mut self, path: &str, value: T) -> Result<()> {
+		let value = serde_json::to_value(value).map_err(|err| Error::Serialize(err.into()))?;
+
+		value::set(&mut self.value, path, value)
+	}
+
+	/// Consume the config, returning its value.
+	pub fn into_inner(self) -> Value {
+		self.value
+	}
+}
+
+impl Default for DynamicConfig {
+	fn default() -> Self {
+		Self {
+			value: Value::Object(Map::new()),
+		}
+	}
+}
+
+impl From<Value> for DynamicConfig {
+	fn from(value: Value) -> Self {
+		Self { value }
+	}
+}
+
+impl Deref for DynamicConfig {
+	type Target = Value;
+
+	fn deref(&self) -> &Value {
+		&self.value
+	}
+}
+
+impl DerefMut for DynamicConfig {
+	fn deref_mut(&mut self) -> &mut Value {
+		&mut self.value
+	}
+}
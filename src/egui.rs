@@ -0,0 +1,81 @@
+//! Adapter for persisting the state of eframe apps, such as the sizes of windows and panels, through abserde.
+
+use std::collections::BTreeMap;
+
+use eframe::Storage;
+
+use crate::{manager, Abserde, Config, Result};
+
+/// Storage for eframe apps backed by an [Abserde], so app state is kept in the config directory of the
+/// platform, in the format of the [Abserde], in place of eframe's own RON file.
+///
+/// Values are kept in memory as strings, by key, as eframe gives them, and saved to the config file as a map
+/// when flushed, if they've changed. Saving is best-effort, as [Storage::flush] can't fail, so errors are
+/// logged. Enabled with the egui feature.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use eframe::Storage;
+/// #
+/// let mut storage = AbserdeStorage::new(Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	name: Some("state".to_string()),
+/// 	..Default::default()
+/// })?;
+///
+/// storage.set_string("theme", "\"dark\"".to_string());
+/// storage.flush();
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+pub struct AbserdeStorage {
+	abserde: Abserde,
+	values: BTreeMap<String, String>,
+	dirty: bool,
+}
+
+impl AbserdeStorage {
+	/// Create storage for an eframe app, loading the values saved to the config file, if there is one.
+	pub fn new(abserde: Abserde) -> Result<Self> {
+		let values = manager::load_if_exists(&abserde)?.unwrap_or_default();
+
+		Ok(Self {
+			abserde,
+			values,
+			dirty: false,
+		})
+	}
+
+	/// Return the [Abserde] the values are saved with.
+	pub fn abserde(&self) -> &Abserde {
+		&self.abserde
+	}
+}
+
+impl Storage for AbserdeStorage {
+	fn get_string(&self, key: &str) -> Option<String> {
+		self.values.get(key).cloned()
+	}
+
+	fn set_string(&mut self, key: &str, value: String) {
+		if self.values.get(key) != Some(&value) {
+			self.values.insert(key.to_string(), value);
+			self.dirty = true;
+		}
+	}
+
+	fn flush(&mut self) {
+		if !self.dirty {
+			return;
+		}
+
+		if let Err(err) = self.values.save_config(&self.abserde) {
+			log!(error, "failed to save eframe storage: {}", err);
+
+			return;
+		}
+
+		self.dirty = false;
+	}
+}
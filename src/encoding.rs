@@ -0,0 +1,60 @@
+//! Detection of the text encoding of config files, so those saved by editors as UTF-16 or with a byte order mark
+//! can be loaded. Configs are always saved as UTF-8 without a byte order mark.
+
+use std::io::{self, Cursor, Read};
+
+// Byte order marks of the encodings which are detected.
+const UTF8_BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+const UTF16_LE_BOM: [u8; 2] = [0xff, 0xfe];
+const UTF16_BE_BOM: [u8; 2] = [0xfe, 0xff];
+
+// Read a text config as UTF-8, removing any byte order mark, and transcoding UTF-16.
+pub(crate) fn decode<'a, R: Read + 'a>(mut reader: R) -> io::Result<Box<dyn Read + 'a>> {
+	let mut start = Vec::with_capacity(UTF8_BOM.len());
+
+	reader
+		.by_ref()
+		.take(UTF8_BOM.len() as u64)
+		.read_to_end(&mut start)?;
+
+	if start.starts_with(&UTF8_BOM) {
+		return Ok(Box::new(reader));
+	}
+
+	if start.starts_with(&UTF16_LE_BOM) || start.starts_with(&UTF16_BE_BOM) {
+		reader.read_to_end(&mut start)?;
+
+		return Ok(Box::new(Cursor::new(transcode_utf16(&start)?)));
+	}
+
+	Ok(Box::new(Cursor::new(start).chain(reader)))
+}
+
+// Transcode the contents of a text config to UTF-8 if they're UTF-16.
+#[cfg(feature = "mmap")]
+pub(crate) fn transcode(data: &[u8]) -> Option<io::Result<Vec<u8>>> {
+	(data.starts_with(&UTF16_LE_BOM) || data.starts_with(&UTF16_BE_BOM))
+		.then(|| transcode_utf16(data))
+}
+
+// Remove any UTF-8 byte order mark from the contents of a text config.
+#[cfg(feature = "mmap")]
+pub(crate) fn strip_bom(data: &[u8]) -> &[u8] {
+	data.strip_prefix(&UTF8_BOM).unwrap_or(data)
+}
+
+// Transcode UTF-16 starting with a byte order mark to UTF-8.
+fn transcode_utf16(data: &[u8]) -> io::Result<Vec<u8>> {
+	let little_endian = data.starts_with(&UTF16_LE_BOM);
+	let units = data[UTF16_LE_BOM.len()..].chunks(2).map(|unit| match unit {
+		[low, high] if little_endian => u16::from_le_bytes([*low, *high]),
+		[high, low] => u16::from_be_bytes([*high, *low]),
+		// An odd trailing byte is made an unpaired surrogate, so it fails like other invalid UTF-16.
+		_ => 0xdc00,
+	});
+
+	char::decode_utf16(units)
+		.collect::<Result<String, _>>()
+		.map(String::into_bytes)
+		.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
@@ -0,0 +1,63 @@
+//! Envelopes recording metadata alongside saved configs.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{Format, Result};
+
+/// Config wrapped with the schema version of its type, and the version of abserde that saved it.
+#[derive(Serialize)]
+pub(crate) struct Envelope<'a, T> {
+	abserde_version: &'static str,
+	schema_version: u64,
+	config: &'a T,
+}
+
+impl<'a, T> Envelope<'a, T> {
+	pub(crate) fn new(schema_version: u64, config: &'a T) -> Self {
+		Self {
+			abserde_version: env!("CARGO_PKG_VERSION"),
+			schema_version,
+			config,
+		}
+	}
+}
+
+// Fields identifying an envelope, with everything else ignored.
+#[derive(Deserialize)]
+struct Header {
+	abserde_version: Option<String>,
+}
+
+// Envelope being loaded.
+#[derive(Deserialize)]
+struct Opened<T> {
+	schema_version: u64,
+	config: T,
+}
+
+// Deserialise a config, removing its envelope and returning the schema version recorded in it.
+// Configs saved without an envelope are deserialised as they are.
+pub(crate) fn open<T: DeserializeOwned>(format: &Format, data: &[u8]) -> Result<(T, Option<u64>)> {
+	match format.deserialize::<Header, _>(data) {
+		Ok(Header {
+			abserde_version: Some(_),
+		}) => {
+			let opened: Opened<T> = format.deserialize(data)?;
+
+			Ok((opened.config, Some(opened.schema_version)))
+		}
+		_ => Ok((format.deserialize(data)?, None)),
+	}
+}
+
+// Like [open], but the config may borrow from the data.
+#[cfg(feature = "mmap")]
+pub(crate) fn open_borrowed<'a, T: Deserialize<'a>>(format: &Format, data: &'a [u8]) -> Result<T> {
+	match format.deserialize_slice::<Header>(data) {
+		Ok(Header {
+			abserde_version: Some(_),
+		}) => Ok(format.deserialize_slice::<Opened<T>>(data)?.config),
+		_ => format.deserialize_slice(data),
+	}
+}
@@ -0,0 +1,303 @@
+//! Errors returned when managing configs.
+
+use std::error;
+use std::fmt::{self, Display};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Conflict, Format};
+
+// Underlying error of a failure without an error type of its own in this crate.
+type BoxError = Box<dyn error::Error + Send + Sync>;
+
+/// Error returned when managing configs.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct MyConfig;
+/// #
+/// let my_config = match MyConfig::load_config(&Abserde::default()) {
+/// 	Err(err) if matches!(err.root(), Error::NotFound(_)) => MyConfig::default(),
+/// 	result => result?,
+/// };
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+	/// An error occurred while operating on a config file.
+	///
+	/// Errors returned by [Config](crate::Config) and [Abserde](crate::Abserde) methods are wrapped in this
+	/// context whenever the config file can be located. Use [Error::root] to match on the underlying error.
+	#[error("failed to {operation} config file {} as {format:?}: {source}", .path.display())]
+	Context {
+		/// Operation which failed.
+		operation: Operation,
+
+		/// Path of the config file.
+		path: PathBuf,
+
+		/// Format of the config file.
+		format: Format,
+
+		/// Underlying error.
+		#[source]
+		source: Box<Error>,
+	},
+
+	/// The config file, or the backup being loaded, doesn't exist.
+	#[error("config file {} not found", .0.display())]
+	NotFound(PathBuf),
+
+	/// Access to the config file, or its directory, was denied, such as when saving a system-wide config
+	/// without administrator rights.
+	#[error("permission denied for config file {}", .0.display())]
+	PermissionDenied(PathBuf),
+
+	/// The config file is locked by another process, which didn't release it in time, as given by
+	/// [Abserde::lock_timeout](crate::Abserde::lock_timeout).
+	#[error("config file {} is locked by another process", .0.display())]
+	Locked(PathBuf),
+
+	/// The config file is a symbolic link, so it wasn't saved or deleted, as refused by
+	/// [SymlinkPolicy::Refuse](crate::SymlinkPolicy::Refuse).
+	#[error("config file {} is a symbolic link", .0.display())]
+	Symlink(PathBuf),
+
+	/// The config file can be accessed by other users, or is owned by another user, so it wasn't loaded, as
+	/// found when [Abserde::audit_permissions](crate::Abserde::audit_permissions) is enabled.
+	#[error("config file {} has insecure permissions: {reason}", .path.display())]
+	InsecurePermissions {
+		/// Path of the config file.
+		path: PathBuf,
+
+		/// Why the permissions are insecure, such as the config file being writable by other users.
+		reason: String,
+	},
+
+	/// A dot-separated path to a key of the config, such as `window.width`, doesn't lead to a value which
+	/// can be set, such as when it passes through a string.
+	#[error("invalid config key path {path:?}: {reason}")]
+	InvalidKeyPath {
+		/// The dot-separated path.
+		path: String,
+
+		/// Why the path is invalid.
+		reason: String,
+	},
+
+	/// A format or location given as a string, such as with a command-line flag, couldn't be parsed.
+	#[error("invalid {kind} {input:?}: {reason}")]
+	Parse {
+		/// What was being parsed, such as `format`.
+		kind: &'static str,
+
+		/// The string which couldn't be parsed.
+		input: String,
+
+		/// Why the string couldn't be parsed, listing what was expected.
+		reason: String,
+	},
+
+	/// A config file includes itself, through the given config file, as found when
+	/// [Abserde::include_key](crate::Abserde::include_key) is given.
+	#[error("config file {} includes itself", .0.display())]
+	IncludeCycle(PathBuf),
+
+	/// An environment variable referenced in a string value of the config isn't set, as found when
+	/// [Abserde::interpolate](crate::Abserde::interpolate) is enabled.
+	#[error("environment variable {0} referenced in config is not set")]
+	UndefinedVariable(String),
+
+	/// The config being saved holds other values for the given keys, which are locked by the policy file, as found
+	/// when [Abserde::policy](crate::Abserde::policy) is given.
+	#[error("config keys {} are locked by policy", .0.join(", "))]
+	LockedKeys(Vec<String>),
+
+	/// The config file doesn't load as the config saved to it, holding other values for the given keys, as found
+	/// when [Abserde::verify_saves](crate::Abserde::verify_saves) is enabled.
+	#[error("config file {} doesn't load as saved, with keys {} changed", .path.display(), .keys.join(", "))]
+	RoundTrip {
+		/// Path of the config file.
+		path: PathBuf,
+
+		/// Keys whose values changed, such as `window.width`, where nested keys are separated by `.`.
+		keys: Vec<String>,
+	},
+
+	/// No system directory was detected for the location of the config file, so it can't be located.
+	#[error("no system directory detected for config location")]
+	NoSystemConfigDir,
+
+	/// Reading or writing a file failed.
+	#[error(transparent)]
+	Io(#[from] io::Error),
+
+	/// The config couldn't be serialised in the config format.
+	#[error("failed to serialise config: {0}")]
+	Serialize(#[source] BoxError),
+
+	/// The config file couldn't be deserialised from the config format.
+	#[error("failed to deserialise config: {0}")]
+	Deserialize(#[source] BoxError),
+
+	/// The config file was modified since it was loaded.
+	#[error(transparent)]
+	Conflict(#[from] Conflict),
+
+	/// The config is invalid, as checked by [ValidatedConfig](crate::ValidatedConfig), with the errors of each
+	/// invalid field. Enabled with the validator feature.
+	#[cfg(feature = "validator")]
+	#[error("invalid config: {0}")]
+	Validation(#[from] validator::ValidationErrors),
+
+	/// A JSON Patch couldn't be applied to the config, such as when a path doesn't exist. Enabled with the
+	/// json-patch feature.
+	#[cfg(feature = "json-patch")]
+	#[error("failed to patch config: {0}")]
+	Patch(#[from] json_patch::PatchError),
+
+	/// A `test` operation of a JSON Patch failed, so the patch wasn't applied to the config. Enabled with the
+	/// json-patch feature.
+	#[cfg(feature = "json-patch")]
+	#[error("config patch operation {operation} failed: expected {expected} at {path:?}")]
+	PatchTestFailed {
+		/// Index of the failed operation in the patch.
+		operation: usize,
+
+		/// JSON Pointer tested by the operation, such as `/window/width`.
+		path: String,
+
+		/// Value the operation expected at the path.
+		expected: serde_json::Value,
+
+		/// Value at the path when the operation was applied, or none if there was no value.
+		actual: Option<serde_json::Value>,
+	},
+
+	/// The config couldn't be migrated to the current version of the config type.
+	#[error("failed to migrate config: {0}")]
+	Migration(String),
+
+	/// The options of the [Abserde](crate::Abserde) don't support the requested operation.
+	#[error("{0}")]
+	Unsupported(String),
+
+	/// Encrypting, decrypting or signing a config failed. Enabled with the encryption, age or signing features.
+	#[cfg(any(feature = "encryption", feature = "age", feature = "signing"))]
+	#[error("{0}")]
+	Encryption(#[source] BoxError),
+
+	/// The signature of the config file doesn't match it, so it was changed other than by saving the config, or
+	/// isn't signed, as found when [Abserde::signing](crate::Abserde::signing) is given. Enabled with the signing
+	/// feature.
+	#[cfg(feature = "signing")]
+	#[error("config file signature doesn't match, so it was changed since it was saved")]
+	SignatureMismatch,
+
+	/// Storing or fetching a value in the OS keychain failed. Enabled with the keyring feature.
+	#[cfg(feature = "keyring")]
+	#[error("{0}")]
+	Keychain(#[source] BoxError),
+
+	/// Watching the config file for changes failed. Enabled with the notify feature.
+	#[cfg(feature = "notify")]
+	#[error("failed to watch config file: {0}")]
+	Watch(#[from] notify::Error),
+
+	/// Reading or writing the config database failed. Enabled with the sqlite feature.
+	#[cfg(feature = "sqlite")]
+	#[error("config database failed: {0}")]
+	Database(#[from] rusqlite::Error),
+
+	/// Committing the config file to its git repository, or reading its history, failed. Enabled with the git
+	/// feature.
+	#[cfg(feature = "git")]
+	#[error("config git repository failed: {0}")]
+	Git(#[from] git2::Error),
+
+	/// A request to the config server or object storage failed, or it refused the request. Enabled with the
+	/// http or s3 features.
+	#[cfg(any(feature = "http", feature = "s3"))]
+	#[error("config server request failed: {0}")]
+	Http(#[from] ureq::Error),
+
+	/// Accessing the browser's storage failed, such as when its quota is exceeded. Only on wasm32 targets.
+	#[cfg(target_arch = "wasm32")]
+	#[error("browser storage failed: {0}")]
+	Browser(String),
+
+	/// Installing the shutdown handler failed. Enabled with the shutdown feature.
+	#[cfg(feature = "shutdown")]
+	#[error("failed to install shutdown handler: {0}")]
+	Shutdown(#[from] ctrlc::Error),
+
+	/// The spawner dropped a blocking task before it completed. Enabled with the async feature.
+	#[cfg(feature = "async")]
+	#[error("spawner dropped the task before it completed")]
+	Cancelled,
+}
+
+impl Error {
+	/// Return the underlying error, without any context.
+	pub fn root(&self) -> &Error {
+		match self {
+			Error::Context { source, .. } => source.root(),
+			error => error,
+		}
+	}
+
+	// Convert an error from accessing the given file, reporting a missing file or denied access as such.
+	pub(crate) fn file(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+		move |err| match err.kind() {
+			io::ErrorKind::NotFound => Error::NotFound(path.to_owned()),
+			io::ErrorKind::PermissionDenied => Error::PermissionDenied(path.to_owned()),
+			_ => Error::Io(err),
+		}
+	}
+
+	// Whether loading a config file failed because it's corrupt, as opposed to it being locked, unreadable or
+	// encrypted with another key, which a backup wouldn't fix.
+	pub(crate) fn is_corrupt(&self) -> bool {
+		match self.root() {
+			Error::Deserialize(_) => true,
+			// Compressed data which can't be decompressed is reported as invalid.
+			Error::Io(err) => matches!(
+				err.kind(),
+				io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+			),
+			#[cfg(feature = "signing")]
+			Error::SignatureMismatch => true,
+			_ => false,
+		}
+	}
+}
+
+/// Operation on a config file, recorded in the context of an [Error].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Operation {
+	/// Loading the config file.
+	Load,
+
+	/// Saving the config file.
+	Save,
+
+	/// Deleting the config file.
+	Delete,
+}
+
+impl Display for Operation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Operation::Load => write!(f, "load"),
+			Operation::Save => write!(f, "save"),
+			Operation::Delete => write!(f, "delete"),
+		}
+	}
+}
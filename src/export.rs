@@ -0,0 +1,98 @@
+//! Export and import of configs to and from files chosen by the user, such as for "Export settings…" and
+//! "Import settings…" menu items.
+
+use std::fs::File;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{write_template_file, Abserde, Config, Error, Format, Operation, Result};
+
+impl Abserde {
+	/// Export the stored config to the given path in the given format, such as a file chosen by the user.
+	///
+	/// The config is loaded as the given config type, so it can be exported in a different format to the
+	/// config file. With [Format::FromExtension], the format is inferred from the extension of the path. The
+	/// exported file is neither compressed nor encrypted.
+	///
+	/// ```no_run
+	/// # use abserde::*;
+	/// # use serde::{Serialize, Deserialize};
+	/// #
+	/// # #[derive(Serialize, Deserialize)]
+	/// # struct MyConfig {
+	/// # 	theme: String,
+	/// # }
+	/// #
+	/// Abserde::default().export_to::<MyConfig, _>("settings.toml", &Format::FromExtension)?;
+	/// #
+	/// # Ok::<(), Error>(())
+	/// ```
+	pub fn export_to<T, P>(&self, path: P, format: &Format) -> Result<()>
+	where
+		T: Serialize + DeserializeOwned,
+		P: AsRef<Path>,
+	{
+		let path = path.as_ref();
+		let format = transfer_format(path, format)?;
+		let config: T = self.with_context(Operation::Load, || {
+			self.read_config_file(&self.load_path()?)
+		})?;
+		let mut data = Vec::new();
+
+		self.serialize_as(&format, &config, &mut data)?;
+
+		write_template_file(path, &data)
+	}
+
+	/// Import a config from the given path in the given format, such as a file chosen by the user, replacing
+	/// the stored config and returning the imported config.
+	///
+	/// The imported file must deserialise as the given config type, or the stored config is left as it was, and
+	/// an [Error::Deserialize] is returned. With [Format::FromExtension], the format is inferred from the
+	/// extension of the path.
+	///
+	/// ```no_run
+	/// # use abserde::*;
+	/// # use serde::{Serialize, Deserialize};
+	/// #
+	/// # #[derive(Serialize, Deserialize)]
+	/// # struct MyConfig {
+	/// # 	theme: String,
+	/// # }
+	/// #
+	/// let my_config = Abserde::default().import_from::<MyConfig, _>("settings.toml", &Format::FromExtension)?;
+	/// #
+	/// # Ok::<(), Error>(())
+	/// ```
+	pub fn import_from<T, P>(&self, path: P, format: &Format) -> Result<T>
+	where
+		T: Serialize + DeserializeOwned,
+		P: AsRef<Path>,
+	{
+		let path = path.as_ref();
+		let format = transfer_format(path, format)?;
+		let config = T::load_config_from(File::open(path).map_err(Error::file(path))?, &format)?;
+
+		config.save_config(self)?;
+
+		Ok(config)
+	}
+}
+
+// Format of an exported or imported file, inferring it from its extension if needed.
+fn transfer_format(path: &Path, format: &Format) -> Result<Format> {
+	match format {
+		Format::FromExtension => path
+			.extension()
+			.and_then(|extension| Format::from_extension(&extension.to_string_lossy()))
+			.ok_or_else(|| {
+				Error::Unsupported(format!(
+					"can't infer format from file name {}",
+					path.display()
+				))
+			}),
+		format => Ok(format.clone()),
+	}
+}
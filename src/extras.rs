@@ -0,0 +1,74 @@
+//! Preservation of config file keys which the config type doesn't recognise, wrapped in [WithExtras].
+
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Config which keeps any top-level keys of the config file that the config type doesn't recognise.
+///
+/// The extra keys are written back when the config is saved, so a config file written by a newer version of
+/// an app doesn't lose settings when an older version saves it. Unrecognised keys of nested values aren't
+/// kept. As the config type is flattened into the config file, it can't hold 128-bit integers.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize)]
+/// # struct MyConfig {
+/// # 	theme: String,
+/// # }
+/// #
+/// let mut my_config = WithExtras::<MyConfig>::load_config(&Abserde::default())?;
+///
+/// my_config.theme = "dark".to_string();
+/// my_config.save_config(&Abserde::default())?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct WithExtras<T> {
+	/// The config.
+	#[serde(flatten)]
+	pub config: T,
+
+	/// Keys of the config file which the config type doesn't recognise, along with their values.
+	#[serde(flatten)]
+	pub extras: Map<String, Value>,
+}
+
+impl<T> WithExtras<T> {
+	/// Wrap a config, without any extra keys.
+	pub fn new(config: T) -> Self {
+		Self {
+			config,
+			extras: Map::new(),
+		}
+	}
+
+	/// Consume the wrapper, returning the config and discarding any extra keys.
+	pub fn into_inner(self) -> T {
+		self.config
+	}
+}
+
+impl<T> From<T> for WithExtras<T> {
+	fn from(config: T) -> Self {
+		Self::new(config)
+	}
+}
+
+impl<T> Deref for WithExtras<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.config
+	}
+}
+
+impl<T> DerefMut for WithExtras<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.config
+	}
+}
@@ -0,0 +1,109 @@
+//! Loading of a single top-level key of a config file, without deserialising the rest of it.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::result;
+
+use serde::de::{self, DeserializeOwned, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::{Abserde, Operation, Result};
+
+thread_local! {
+	// Top-level key being loaded on the current thread.
+	static KEY: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Load a single top-level key of the config file, deserialised as the given type, such as a section of the
+/// config which an app needs at startup.
+///
+/// The values of every other key are skipped rather than deserialised, so very large configs aren't loaded
+/// in full, although INI and TOML config files are always parsed in full. Returns none if there is no such
+/// key.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize)]
+/// # struct UserData {
+/// # 	theme: String,
+/// # }
+/// #
+/// let user_data = load_field::<UserData>(&Abserde::default(), "user_data")?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+pub fn load_field<T: DeserializeOwned>(abserde: &Abserde, key: &str) -> Result<Option<T>> {
+	let _scope = KeyScope::enter(key);
+
+	abserde.with_context(Operation::Load, || {
+		let field: Field<T> = abserde.read_config_file(&abserde.load_path()?)?;
+
+		Ok(field.0)
+	})
+}
+
+// Makes the key being loaded available on the current thread, until dropped.
+struct KeyScope {
+	previous: Option<String>,
+}
+
+impl KeyScope {
+	fn enter(key: &str) -> Self {
+		Self {
+			previous: KEY.with(|current| current.replace(Some(key.to_string()))),
+		}
+	}
+}
+
+impl Drop for KeyScope {
+	fn drop(&mut self) {
+		KEY.with(|current| *current.borrow_mut() = self.previous.take());
+	}
+}
+
+// Value of the key being loaded, deserialised from a config.
+struct Field<T>(Option<T>);
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Field<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+		let key = KEY
+			.with(|current| current.borrow().clone())
+			.ok_or_else(|| de::Error::custom("no config key is being loaded"))?;
+
+		deserializer.deserialize_map(FieldVisitor {
+			key,
+			marker: PhantomData,
+		})
+	}
+}
+
+// Visits the top-level keys of a config, deserialising only the value of the key being loaded.
+struct FieldVisitor<T> {
+	key: String,
+	marker: PhantomData<T>,
+}
+
+impl<'de, T: DeserializeOwned> Visitor<'de> for FieldVisitor<T> {
+	type Value = Field<T>;
+
+	fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "a config with keys")
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> result::Result<Field<T>, A::Error> {
+		let mut value = None;
+
+		while let Some(key) = map.next_key::<String>()? {
+			if value.is_none() && key == self.key {
+				value = Some(map.next_value()?);
+			} else {
+				map.next_value::<IgnoredAny>()?;
+			}
+		}
+
+		Ok(Field(value))
+	}
+}
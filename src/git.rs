@@ -0,0 +1,179 @@
+//! History of config files kept in a git repository, enabled with [Abserde::git].
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use git2::{ErrorCode, Repository, Signature, Sort};
+
+use crate::{utc_date_time, Abserde, Error, Operation, Result};
+
+/// Revision of a config file in its git repository, as listed by [Abserde::history].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Revision {
+	/// ID of the commit, which can be given to [Abserde::checkout].
+	pub id: String,
+
+	/// Message of the commit, such as `Save config.json of MyApp at 2024-01-31T23:59:59Z`.
+	pub message: String,
+
+	/// When the revision was committed.
+	pub time: SystemTime,
+}
+
+impl Abserde {
+	/// List the revisions of the config file in its git repository, starting with the most recent.
+	///
+	/// Only commits which changed the config file are listed. Fails with an [Error::Unsupported] unless
+	/// [Abserde::git] is enabled.
+	pub fn history(&self) -> Result<Vec<Revision>> {
+		self.with_context(Operation::Load, || {
+			let (repo, file) = self.repository()?;
+			let mut revwalk = repo.revwalk()?;
+
+			match revwalk.push_head() {
+				Err(err) if err.code() == ErrorCode::UnbornBranch => return Ok(Vec::new()),
+				result => result?,
+			}
+
+			revwalk.set_sorting(Sort::TIME)?;
+
+			let mut revisions = Vec::new();
+
+			for id in revwalk {
+				let commit = repo.find_commit(id?)?;
+				let entry_id = commit.tree()?.get_path(&file).ok().map(|entry| entry.id());
+				let parent_entry_id = match commit.parents().next() {
+					Some(parent) => parent.tree()?.get_path(&file).ok().map(|entry| entry.id()),
+					None => None,
+				};
+
+				if entry_id != parent_entry_id {
+					revisions.push(Revision {
+						id: commit.id().to_string(),
+						message: commit.message().unwrap_or_default().trim_end().to_string(),
+						time: UNIX_EPOCH
+							+ Duration::from_secs(
+								commit.time().seconds().try_into().unwrap_or_default(),
+							),
+					});
+				}
+			}
+
+			Ok(revisions)
+		})
+	}
+
+	/// Restore the config file as it was at the given revision, such as a commit ID from [Abserde::history].
+	///
+	/// The restored config file is saved and committed as usual, so a checkout can be undone. Revisions are
+	/// given in any form git accepts, such as `HEAD~2`. Fails with an [Error::NotFound] if the config file
+	/// didn't exist at the revision, and with an [Error::Unsupported] unless [Abserde::git] is enabled.
+	pub fn checkout(&self, revision: &str) -> Result<()> {
+		self.with_context(Operation::Save, || {
+			let (repo, file) = self.repository()?;
+			let tree = repo.revparse_single(revision)?.peel_to_commit()?.tree()?;
+			let entry = match tree.get_path(&file) {
+				Err(err) if err.code() == ErrorCode::NotFound => {
+					return Err(Error::NotFound(self.config_path()?))
+				}
+				result => result?,
+			};
+			let blob = repo.find_blob(entry.id())?;
+
+			self.write_data(blob.content())
+		})
+	}
+
+	// Open the git repository holding the config file, along with the path of the config file within it.
+	fn repository(&self) -> Result<(Repository, PathBuf)> {
+		if !self.git {
+			return Err(Error::Unsupported(
+				"git must be enabled to keep the history of the config file".to_string(),
+			));
+		}
+
+		let (repo_dir, file) = self.repository_paths()?;
+
+		Ok((Repository::open(repo_dir)?, file))
+	}
+
+	// Directory of the git repository, which is the config directory of the app, or the directory of a config
+	// file given by path, along with the path of the config file within it.
+	fn repository_paths(&self) -> Result<(PathBuf, PathBuf)> {
+		let config_path = self.config_path()?;
+		let repo_dir = match self.base_dir() {
+			Ok(dir) => dir,
+			Err(_) => config_path
+				.parent()
+				.ok_or(Error::NoSystemConfigDir)?
+				.to_owned(),
+		};
+		let file = config_path
+			.strip_prefix(&repo_dir)
+			.map_err(|_| Error::NotFound(config_path.clone()))?
+			.to_owned();
+
+		Ok((repo_dir, file))
+	}
+
+	// Commit the config file as it now is, creating the git repository if needed. Nothing is committed if the
+	// config file hasn't changed.
+	pub(crate) fn commit(&self, operation: Operation) -> Result<()> {
+		let (repo_dir, file) = self.repository_paths()?;
+		let repo = match Repository::open(&repo_dir) {
+			Err(err) if err.code() == ErrorCode::NotFound => Repository::init(&repo_dir)?,
+			result => result?,
+		};
+		let mut index = repo.index()?;
+
+		if repo_dir.join(&file).exists() {
+			index.add_path(&file)?;
+		} else if index.get_path(&file, 0).is_some() {
+			index.remove_path(&file)?;
+		}
+
+		index.write()?;
+
+		let tree = repo.find_tree(index.write_tree()?)?;
+		let parent = match repo.head() {
+			Err(err) if err.code() == ErrorCode::UnbornBranch => None,
+			result => Some(result?.peel_to_commit()?),
+		};
+
+		if parent.as_ref().map(|parent| parent.tree_id()) == Some(tree.id()) {
+			return Ok(());
+		}
+
+		let [year, month, day, hour, minute, second] = utc_date_time(SystemTime::now());
+		let message = format!(
+			"{} {} of {} at {:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+			match operation {
+				Operation::Delete => "Delete",
+				_ => "Save",
+			},
+			file.display(),
+			self.app,
+			year,
+			month,
+			day,
+			hour,
+			minute,
+			second
+		);
+		// Fall back to a signature of our own when git has no user configured.
+		let signature = repo
+			.signature()
+			.or_else(|_| Signature::now("abserde", "abserde@localhost"))?;
+
+		repo.commit(
+			Some("HEAD"),
+			&signature,
+			&signature,
+			&message,
+			&tree,
+			&parent.iter().collect::<Vec<_>>(),
+		)?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,237 @@
+//! Reloading of configs when the process receives SIGHUP, the conventional way of asking a daemon to re-read
+//! its config, with [Config::reload_on_hangup](crate::Config::reload_on_hangup). Only on Unix.
+//!
+//! A single signal handler is installed for the process, which wakes a thread through a socket, as little else
+//! is safe in a signal handler. That thread passes each signal on to every config being reloaded. Any handler
+//! installed before it, such as the one for [on_shutdown](crate::on_shutdown), is still called for each signal.
+
+use std::io::{self, Read};
+use std::os::unix::io::IntoRawFd;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Mutex, PoisonError};
+use std::{mem, ptr, thread};
+
+use serde::de::DeserializeOwned;
+
+use crate::{reload_config, Abserde, Result};
+
+// Socket written to by the signal handler, which is -1 until the handler is installed.
+static SIGNAL_FD: AtomicI32 = AtomicI32::new(-1);
+
+// Handler of SIGHUP before this one was installed, along with its flags, which is called after this one.
+static PREVIOUS_HANDLER: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+static PREVIOUS_FLAGS: AtomicI32 = AtomicI32::new(0);
+
+// Configs being reloaded, by ID, each woken through its channel.
+static LISTENERS: Mutex<Listeners> = Mutex::new(Listeners {
+	next_id: 0,
+	senders: Vec::new(),
+});
+
+struct Listeners {
+	next_id: u64,
+	senders: Vec<(u64, Sender<()>)>,
+}
+
+/// Reloads a config whenever the process receives SIGHUP, until dropped.
+///
+/// Created by [Config::reload_on_hangup](crate::Config::reload_on_hangup).
+#[derive(Debug)]
+pub struct HangupReloader {
+	id: u64,
+}
+
+impl Drop for HangupReloader {
+	fn drop(&mut self) {
+		// Dropping the sender ends the thread reloading the config.
+		LISTENERS
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.senders
+			.retain(|(id, _)| *id != self.id);
+	}
+}
+
+// Start reloading a config on SIGHUP, calling back with the reloaded config each time.
+pub(crate) fn reload_on_hangup<T, F>(abserde: &Abserde, callback: F) -> Result<HangupReloader>
+where
+	T: DeserializeOwned,
+	F: FnMut(Result<T>) + Send + 'static,
+{
+	let abserde = abserde.clone();
+	let (sender, receiver) = channel();
+	let mut listeners = LISTENERS.lock().unwrap_or_else(PoisonError::into_inner);
+
+	if SIGNAL_FD.load(Ordering::SeqCst) < 0 {
+		install()?;
+	}
+
+	let id = listeners.next_id;
+
+	listeners.next_id += 1;
+	listeners.senders.push((id, sender));
+
+	thread::spawn(move || reload_on_signal(&abserde, receiver, callback));
+
+	Ok(HangupReloader { id })
+}
+
+// Reload the config whenever a signal is received, until the reloader is dropped.
+fn reload_on_signal<T, F>(abserde: &Abserde, receiver: Receiver<()>, mut callback: F)
+where
+	T: DeserializeOwned,
+	F: FnMut(Result<T>),
+{
+	while receiver.recv().is_ok() {
+		// Search locations may have changed, so the config file is located again each time.
+		match abserde.load_path() {
+			Ok(config_path) => reload_config(abserde, &config_path, &mut callback),
+			Err(err) => callback(Err(err)),
+		}
+	}
+}
+
+// Install the signal handler, along with the thread passing signals on to the configs being reloaded.
+fn install() -> Result<()> {
+	let (mut reader, writer) = UnixStream::pair()?;
+
+	// The handler mustn't block, even if signals arrive faster than they're passed on.
+	writer.set_nonblocking(true)?;
+
+	// SAFETY: sigaction is a plain C struct, for which all zeroes is a valid value.
+	let mut action: libc::sigaction = unsafe { mem::zeroed() };
+	// SAFETY: As above.
+	let mut previous: libc::sigaction = unsafe { mem::zeroed() };
+
+	action.sa_sigaction = on_hangup
+		as extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void)
+		as libc::sighandler_t;
+	action.sa_flags = libc::SA_RESTART | libc::SA_SIGINFO;
+
+	let fd = writer.into_raw_fd();
+
+	SIGNAL_FD.store(fd, Ordering::SeqCst);
+
+	// The previous handler is looked up before installing this one, so it's called for signals arriving as soon as
+	// this one is installed.
+	// SAFETY: Only the previous action is written, to a local. The action is fully initialised, and the handler
+	// only does what's safe in a signal handler.
+	let result = unsafe {
+		if libc::sigaction(libc::SIGHUP, ptr::null(), &mut previous) == 0 {
+			PREVIOUS_HANDLER.store(previous.sa_sigaction, Ordering::SeqCst);
+			PREVIOUS_FLAGS.store(previous.sa_flags as i32, Ordering::SeqCst);
+
+			libc::sigaction(libc::SIGHUP, &action, ptr::null_mut())
+		} else {
+			-1
+		}
+	};
+
+	if result != 0 {
+		let err = io::Error::last_os_error();
+
+		SIGNAL_FD.store(-1, Ordering::SeqCst);
+
+		// SAFETY: The socket was taken from the stream above, and nothing else uses it without the handler.
+		unsafe { libc::close(fd) };
+
+		return Err(err.into());
+	}
+
+	thread::spawn(move || {
+		let mut buf = [0; 64];
+
+		// Signals arriving together are passed on once, as each reload reads the latest config.
+		loop {
+			match reader.read(&mut buf) {
+				Ok(0) => break,
+				Ok(_) => {}
+				Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+				Err(_) => break,
+			}
+
+			for (_, sender) in &LISTENERS
+				.lock()
+				.unwrap_or_else(PoisonError::into_inner)
+				.senders
+			{
+				_ = sender.send(());
+			}
+		}
+	});
+
+	Ok(())
+}
+
+// Handle SIGHUP by waking the thread passing it on, then calling the previous handler.
+extern "C" fn on_hangup(
+	signal: libc::c_int,
+	info: *mut libc::siginfo_t,
+	context: *mut libc::c_void,
+) {
+	let fd = SIGNAL_FD.load(Ordering::Relaxed);
+	let errno = errno_location();
+	// SAFETY: errno is thread local, so it's only read here while the interrupted code is suspended.
+	let saved_errno = (!errno.is_null()).then(|| unsafe { *errno });
+
+	// SAFETY: write is safe in a signal handler, and the byte written is only a wake-up, so it doesn't matter if
+	// the socket is full.
+	unsafe { libc::write(fd, [0u8].as_ptr().cast(), 1) };
+
+	// The interrupted code may be about to read errno, which write may have changed.
+	if let Some(saved_errno) = saved_errno {
+		// SAFETY: As above.
+		unsafe { *errno = saved_errno };
+	}
+
+	let handler = PREVIOUS_HANDLER.load(Ordering::Relaxed);
+
+	// The default handler of SIGHUP ends the process, which reloading replaces.
+	if handler == libc::SIG_DFL || handler == libc::SIG_IGN {
+		return;
+	}
+
+	// SAFETY: The previous handler was installed for SIGHUP with these flags, so it has the signature they give,
+	// and is passed what the kernel passed to this one.
+	unsafe {
+		if PREVIOUS_FLAGS.load(Ordering::Relaxed) & libc::SA_SIGINFO != 0 {
+			mem::transmute::<
+				libc::sighandler_t,
+				extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void),
+			>(handler)(signal, info, context);
+		} else {
+			mem::transmute::<libc::sighandler_t, extern "C" fn(libc::c_int)>(handler)(signal);
+		}
+	}
+}
+
+// Location of errno for the current thread, or null on platforms it isn't known for.
+fn errno_location() -> *mut libc::c_int {
+	// SAFETY: These only return the location of errno for the current thread.
+	unsafe {
+		#[cfg(any(
+			target_os = "linux",
+			target_os = "emscripten",
+			target_os = "redox",
+			target_os = "hurd"
+		))]
+		return libc::__errno_location();
+		#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+		return libc::__errno();
+		#[cfg(any(
+			target_os = "macos",
+			target_os = "ios",
+			target_os = "tvos",
+			target_os = "watchos",
+			target_os = "freebsd",
+			target_os = "dragonfly"
+		))]
+		return libc::__error();
+		#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+		return libc::___errno();
+		#[allow(unreachable_code)]
+		ptr::null_mut()
+	}
+}
@@ -0,0 +1,180 @@
+//! Storage of configs on a remote server, selected with [Storage::Http](crate::Storage::Http).
+
+use std::fmt::{self, Debug};
+use std::fs::{self, create_dir_all, remove_file};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use ureq::Agent;
+
+use crate::{Error, Result};
+
+/// Remote server holding a config, used with [Storage::Http](crate::Storage::Http).
+///
+/// The config is fetched with a `GET` request to the URL, saved with a `PUT` request and deleted with a
+/// `DELETE` request. Payloads are the config as it would be stored in a config file, in the
+/// [Format](crate::Format) of the [Abserde](crate::Abserde) along with any compression and encryption, so the
+/// server only needs to store them as given. A `404 Not Found` response means there is no config.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	storage: Storage::Http(Remote {
+/// 		authorization: Some("Bearer my-token".to_string()),
+/// 		..Remote::new("https://config.example.com/users/42/config.json")
+/// 	}),
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub struct Remote {
+	/// URL of the config, such as `https://config.example.com/users/42/config.json`.
+	pub url: String,
+
+	/// Value of the `Authorization` header sent with every request, such as `Bearer <token>`. Disabled by
+	/// default.
+	pub authorization: Option<String>,
+
+	/// Time allowed for each request, from connecting until the whole response has been read. Defaults to
+	/// 30 seconds.
+	pub timeout: Duration,
+
+	/// Keep a copy of the config in the config file, at its usual location, which is loaded in its place when
+	/// the server can't be reached, such as when offline. Enabled by default.
+	pub cache: bool,
+}
+
+impl Remote {
+	/// Create a remote config at the given URL, using the default options.
+	pub fn new<S: Into<String>>(url: S) -> Self {
+		Self {
+			url: url.into(),
+			authorization: None,
+			timeout: Duration::from_secs(30),
+			cache: true,
+		}
+	}
+
+	// Agent sending requests for the config, giving up on them after the timeout.
+	fn agent(&self) -> Agent {
+		Agent::config_builder()
+			.timeout_global(Some(self.timeout))
+			.build()
+			.into()
+	}
+}
+
+impl Debug for Remote {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// Leave out the authorization, as it holds credentials.
+		f.debug_struct("Remote")
+			.field("url", &self.url)
+			.field("timeout", &self.timeout)
+			.field("cache", &self.cache)
+			.finish_non_exhaustive()
+	}
+}
+
+// Fetch the config from the server, or from the cache when the server can't be reached, returning nothing if
+// there is no config.
+pub(crate) fn read(remote: &Remote, cache_path: &Path) -> Result<Option<Vec<u8>>> {
+	let mut request = remote.agent().get(&remote.url);
+
+	if let Some(authorization) = &remote.authorization {
+		request = request.header("Authorization", authorization);
+	}
+
+	let data = match request.call() {
+		Ok(mut response) => Some(response.body_mut().read_to_vec()?),
+		Err(ureq::Error::StatusCode(404)) => None,
+		Err(err) if remote.cache && is_unreachable(&err) => {
+			return match fs::read(cache_path) {
+				Ok(data) => {
+					log!(
+						info,
+						"config server {} is unreachable, loading cached copy {}: {}",
+						remote.url,
+						cache_path.display(),
+						err
+					);
+
+					Ok(Some(data))
+				}
+				// Without a cached copy, the server being unreachable is the more useful error.
+				Err(_) => Err(err.into()),
+			};
+		}
+		Err(err) => return Err(err.into()),
+	};
+
+	update_cache(remote, cache_path, data.as_deref())?;
+
+	Ok(data)
+}
+
+// Send the config to the server, replacing any config it held before.
+pub(crate) fn write(remote: &Remote, cache_path: &Path, data: &[u8]) -> Result<()> {
+	let mut request = remote.agent().put(&remote.url);
+
+	if let Some(authorization) = &remote.authorization {
+		request = request.header("Authorization", authorization);
+	}
+
+	request.send(data)?;
+
+	update_cache(remote, cache_path, Some(data))
+}
+
+// Delete the config from the server, returning whether it held one.
+pub(crate) fn delete(remote: &Remote, cache_path: &Path) -> Result<bool> {
+	let mut request = remote.agent().delete(&remote.url);
+
+	if let Some(authorization) = &remote.authorization {
+		request = request.header("Authorization", authorization);
+	}
+
+	let deleted = match request.call() {
+		Ok(_) => true,
+		Err(ureq::Error::StatusCode(404)) => false,
+		Err(err) => return Err(err.into()),
+	};
+
+	update_cache(remote, cache_path, None)?;
+
+	Ok(deleted)
+}
+
+// Store the latest config known to be on the server in the cache, or remove the cache if there is none.
+fn update_cache(remote: &Remote, cache_path: &Path, data: Option<&[u8]>) -> Result<()> {
+	if !remote.cache {
+		return Ok(());
+	}
+
+	match data {
+		Some(data) => {
+			let cache_dir = cache_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+			create_dir_all(cache_dir).map_err(Error::file(cache_dir))?;
+			fs::write(cache_path, data).map_err(Error::file(cache_path))
+		}
+		None => match remove_file(cache_path) {
+			Err(err) if err.kind() != io::ErrorKind::NotFound => Err(Error::file(cache_path)(err)),
+			_ => Ok(()),
+		},
+	}
+}
+
+// Whether a request failed because the server couldn't be reached, rather than being refused by it.
+fn is_unreachable(err: &ureq::Error) -> bool {
+	matches!(
+		err,
+		ureq::Error::Io(_)
+			| ureq::Error::Timeout(_)
+			| ureq::Error::HostNotFound
+			| ureq::Error::ConnectionFailed
+	)
+}
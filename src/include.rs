@@ -0,0 +1,106 @@
+//! Config files including other config files, deep merged into them when loaded, enabled with
+//! [Abserde::include_key](crate::Abserde::include_key).
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::layers::merge;
+use crate::{Abserde, Error, Format, Result};
+
+impl Abserde {
+	// Read a config file, deep merging the config files it includes into it.
+	pub(crate) fn read_includes<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+	) -> Result<(T, Option<u64>)> {
+		let abserde = Abserde {
+			include_key: None,
+			..self.clone()
+		};
+		let (config, version) = abserde.read_config_file_versioned::<Value>(config_path)?;
+		let canonical_path = config_path
+			.canonicalize()
+			.map_err(Error::file(config_path))?;
+		let config = self.include(config_path, config, &mut vec![canonical_path])?;
+		let _field_keys = self.field_keys()?;
+
+		Ok((
+			T::deserialize(config).map_err(|err| Error::Deserialize(err.into()))?,
+			version,
+		))
+	}
+
+	// Deep merge a config, read from the given path, over the config files it includes, given the paths of
+	// the config files including it.
+	fn include(
+		&self,
+		config_path: &Path,
+		mut config: Value,
+		including: &mut Vec<PathBuf>,
+	) -> Result<Value> {
+		let Some(key) = &self.include_key else {
+			return Ok(config);
+		};
+		let include_paths = match config.as_object_mut().and_then(|map| map.remove(key)) {
+			None => return Ok(config),
+			Some(Value::String(include_path)) => vec![include_path],
+			Some(Value::Array(include_paths)) => include_paths
+				.into_iter()
+				.map(|include_path| match include_path {
+					Value::String(include_path) => Ok(include_path),
+					_ => Err(invalid(key)),
+				})
+				.collect::<Result<_>>()?,
+			Some(_) => return Err(invalid(key)),
+		};
+		let config_dir = config_path.parent().unwrap_or(Path::new(""));
+		let mut merged = Value::Object(Map::new());
+
+		// Later includes override earlier ones, and the including config overrides them all.
+		for include_path in include_paths {
+			let include_path = config_dir.join(include_path);
+			let canonical_path = include_path
+				.canonicalize()
+				.map_err(Error::file(&include_path))?;
+
+			if including.contains(&canonical_path) {
+				return Err(Error::IncludeCycle(include_path));
+			}
+
+			let included = self.read_included(&include_path)?;
+
+			including.push(canonical_path);
+
+			let included = self.include(&include_path, included, including)?;
+
+			including.pop();
+			merge(&mut merged, included);
+		}
+
+		merge(&mut merged, config);
+
+		Ok(merged)
+	}
+
+	// Read an included config file, in the format given by its extension, or otherwise in the format of the
+	// config file.
+	fn read_included(&self, include_path: &Path) -> Result<Value> {
+		let format = match include_path
+			.extension()
+			.and_then(|extension| Format::from_extension(&extension.to_string_lossy()))
+		{
+			Some(format) => format,
+			None => self.file_format()?,
+		};
+
+		format.deserialize(File::open(include_path).map_err(Error::file(include_path))?)
+	}
+}
+
+// Error for an include key holding something other than paths.
+fn invalid(key: &str) -> Error {
+	Error::Deserialize(format!("{} must be a path or a list of paths", key).into())
+}
@@ -0,0 +1,481 @@
+//! INI serialisation with support for one level of nested sections.
+//!
+//! [serde_ini] can read `[sections]` back into nested structs, but its serialiser refuses to write a
+//! plain value once a section has been started. This module collects the document first, so that all
+//! plain values can be written before any section, regardless of the field order in the config type.
+
+use std::io::Write;
+
+use serde::ser::{self, Impossible, Serialize};
+use serde_ini::ser::{Error, UnsupportedType};
+use serde_ini::{Item, LineEnding, Writer};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A top-level entry of an INI document.
+enum Entry {
+	Value(String),
+	Section(Vec<(String, String)>),
+}
+
+/// Serialise a value as INI, writing nested structs and maps as `[sections]`.
+pub(crate) fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+	W: Write,
+	T: ?Sized + Serialize,
+{
+	let entries = value.serialize(TopSerializer)?;
+	let mut writer = Writer::new(writer, LineEnding::Linefeed);
+	let mut empty = true;
+
+	for (key, entry) in &entries {
+		if let Entry::Value(value) = entry {
+			writer.write(&Item::Value {
+				key: key.clone(),
+				value: value.clone(),
+			})?;
+
+			empty = false;
+		}
+	}
+
+	for (name, entry) in entries {
+		if let Entry::Section(values) = entry {
+			if !empty {
+				writer.write(&Item::Empty)?;
+			}
+
+			writer.write(&Item::Section { name })?;
+
+			for (key, value) in values {
+				writer.write(&Item::Value { key, value })?;
+			}
+
+			empty = false;
+		}
+	}
+
+	Ok(())
+}
+
+// Serialiser methods shared by every serialiser in this module, turning scalars into strings.
+macro_rules! serialize_scalars {
+	($($method:ident: $ty:ty),*) => {
+		$(
+			fn $method(self, v: $ty) -> Result<Self::Ok> {
+				self.scalar(v.to_string())
+			}
+		)*
+
+		fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
+			Err(UnsupportedType::Bool.into())
+		}
+
+		fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+			Err(UnsupportedType::Bytes.into())
+		}
+
+		fn serialize_unit(self) -> Result<Self::Ok> {
+			Err(UnsupportedType::Unit.into())
+		}
+
+		fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+			Err(UnsupportedType::Unit.into())
+		}
+
+		fn serialize_unit_variant(
+			self,
+			_name: &'static str,
+			_variant_index: u32,
+			variant: &'static str,
+		) -> Result<Self::Ok> {
+			self.scalar(variant.to_string())
+		}
+
+		fn serialize_newtype_variant<T: ?Sized + Serialize>(
+			self,
+			_name: &'static str,
+			_variant_index: u32,
+			variant: &'static str,
+			_value: &T,
+		) -> Result<Self::Ok> {
+			// serde_ini has no unsupported type for enum variants holding values.
+			Err(Error::Custom(format!(
+				"enum variant {} holding a value can't be serialised",
+				variant
+			)))
+		}
+
+		fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+			Err(UnsupportedType::Seq.into())
+		}
+
+		fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+			Err(UnsupportedType::Seq.into())
+		}
+
+		fn serialize_tuple_struct(
+			self,
+			_name: &'static str,
+			_len: usize,
+		) -> Result<Self::SerializeTupleStruct> {
+			Err(UnsupportedType::Seq.into())
+		}
+
+		fn serialize_tuple_variant(
+			self,
+			_name: &'static str,
+			_variant_index: u32,
+			_variant: &'static str,
+			_len: usize,
+		) -> Result<Self::SerializeTupleVariant> {
+			Err(UnsupportedType::Seq.into())
+		}
+
+		fn serialize_struct_variant(
+			self,
+			_name: &'static str,
+			_variant_index: u32,
+			_variant: &'static str,
+			_len: usize,
+		) -> Result<Self::SerializeStructVariant> {
+			Err(UnsupportedType::Map.into())
+		}
+	};
+}
+
+// Serialises the whole document, which must be a struct or a map.
+struct TopSerializer;
+
+impl ser::Serializer for TopSerializer {
+	type Ok = Vec<(String, Entry)>;
+	type Error = Error;
+
+	type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+	type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+	type SerializeMap = TopMap;
+	type SerializeStruct = TopMap;
+	type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+	serialize_scalars!(
+		serialize_i8: i8, serialize_i16: i16, serialize_i32: i32, serialize_i64: i64,
+		serialize_u8: u8, serialize_u16: u16, serialize_u32: u32, serialize_u64: u64,
+		serialize_f32: f32, serialize_f64: f64, serialize_char: char
+	);
+
+	fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+		self.scalar(v.to_string())
+	}
+
+	fn serialize_none(self) -> Result<Self::Ok> {
+		Ok(Vec::new())
+	}
+
+	fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<Self::Ok> {
+		value.serialize(self)
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+		Ok(TopMap::default())
+	}
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+		Ok(TopMap::default())
+	}
+}
+
+impl TopSerializer {
+	fn scalar(self, _value: String) -> Result<Vec<(String, Entry)>> {
+		Err(Error::TopLevelMap)
+	}
+}
+
+#[derive(Default)]
+struct TopMap {
+	entries: Vec<(String, Entry)>,
+	key: Option<String>,
+}
+
+impl TopMap {
+	fn push<T: ?Sized + Serialize>(&mut self, key: String, value: &T) -> Result<()> {
+		if let Some(entry) = value.serialize(EntrySerializer)? {
+			self.entries.push((key, entry));
+		}
+
+		Ok(())
+	}
+}
+
+impl ser::SerializeStruct for TopMap {
+	type Ok = Vec<(String, Entry)>;
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<()> {
+		self.push(key.to_string(), value)
+	}
+
+	fn end(self) -> Result<Self::Ok> {
+		Ok(self.entries)
+	}
+}
+
+impl ser::SerializeMap for TopMap {
+	type Ok = Vec<(String, Entry)>;
+	type Error = Error;
+
+	fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+		self.key = Some(key.serialize(KeySerializer)?);
+
+		Ok(())
+	}
+
+	fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		let key = self.key.take().ok_or(Error::MapKeyMissing)?;
+
+		self.push(key, value)
+	}
+
+	fn end(self) -> Result<Self::Ok> {
+		Ok(self.entries)
+	}
+}
+
+// Serialises a top-level field, which is either a plain value or a section.
+struct EntrySerializer;
+
+impl EntrySerializer {
+	fn scalar(self, value: String) -> Result<Option<Entry>> {
+		Ok(Some(Entry::Value(value)))
+	}
+}
+
+impl ser::Serializer for EntrySerializer {
+	type Ok = Option<Entry>;
+	type Error = Error;
+
+	type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+	type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+	type SerializeMap = SectionMap;
+	type SerializeStruct = SectionMap;
+	type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+	serialize_scalars!(
+		serialize_i8: i8, serialize_i16: i16, serialize_i32: i32, serialize_i64: i64,
+		serialize_u8: u8, serialize_u16: u16, serialize_u32: u32, serialize_u64: u64,
+		serialize_f32: f32, serialize_f64: f64, serialize_char: char
+	);
+
+	fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+		self.scalar(v.to_string())
+	}
+
+	fn serialize_none(self) -> Result<Self::Ok> {
+		Ok(None)
+	}
+
+	fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<Self::Ok> {
+		value.serialize(self)
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+		Ok(SectionMap::default())
+	}
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+		Ok(SectionMap::default())
+	}
+}
+
+#[derive(Default)]
+struct SectionMap {
+	values: Vec<(String, String)>,
+	key: Option<String>,
+}
+
+impl SectionMap {
+	fn push<T: ?Sized + Serialize>(&mut self, key: String, value: &T) -> Result<()> {
+		if let Some(value) = value.serialize(ValueSerializer)? {
+			self.values.push((key, value));
+		}
+
+		Ok(())
+	}
+}
+
+impl ser::SerializeStruct for SectionMap {
+	type Ok = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<()> {
+		self.push(key.to_string(), value)
+	}
+
+	fn end(self) -> Result<Self::Ok> {
+		Ok(Some(Entry::Section(self.values)))
+	}
+}
+
+impl ser::SerializeMap for SectionMap {
+	type Ok = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+		self.key = Some(key.serialize(KeySerializer)?);
+
+		Ok(())
+	}
+
+	fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		let key = self.key.take().ok_or(Error::MapKeyMissing)?;
+
+		self.push(key, value)
+	}
+
+	fn end(self) -> Result<Self::Ok> {
+		Ok(Some(Entry::Section(self.values)))
+	}
+}
+
+// Serialises a value inside a section, where no further nesting is possible.
+struct ValueSerializer;
+
+impl ValueSerializer {
+	fn scalar(self, value: String) -> Result<Option<String>> {
+		Ok(Some(value))
+	}
+}
+
+impl ser::Serializer for ValueSerializer {
+	type Ok = Option<String>;
+	type Error = Error;
+
+	type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+	type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+	type SerializeMap = Impossible<Self::Ok, Self::Error>;
+	type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+	type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+	serialize_scalars!(
+		serialize_i8: i8, serialize_i16: i16, serialize_i32: i32, serialize_i64: i64,
+		serialize_u8: u8, serialize_u16: u16, serialize_u32: u32, serialize_u64: u64,
+		serialize_f32: f32, serialize_f64: f64, serialize_char: char
+	);
+
+	fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+		self.scalar(v.to_string())
+	}
+
+	fn serialize_none(self) -> Result<Self::Ok> {
+		Ok(None)
+	}
+
+	fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<Self::Ok> {
+		value.serialize(self)
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+		Err(UnsupportedType::Map.into())
+	}
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+		Err(UnsupportedType::Map.into())
+	}
+}
+
+// Serialises map keys, which must be strings.
+struct KeySerializer;
+
+impl KeySerializer {
+	fn scalar(self, _value: String) -> Result<String> {
+		Err(Error::NonStringKey)
+	}
+}
+
+impl ser::Serializer for KeySerializer {
+	type Ok = String;
+	type Error = Error;
+
+	type SerializeSeq = Impossible<Self::Ok, Self::Error>;
+	type SerializeTuple = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleStruct = Impossible<Self::Ok, Self::Error>;
+	type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+	type SerializeMap = Impossible<Self::Ok, Self::Error>;
+	type SerializeStruct = Impossible<Self::Ok, Self::Error>;
+	type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+	serialize_scalars!(
+		serialize_i8: i8, serialize_i16: i16, serialize_i32: i32, serialize_i64: i64,
+		serialize_u8: u8, serialize_u16: u16, serialize_u32: u32, serialize_u64: u64,
+		serialize_f32: f32, serialize_f64: f64
+	);
+
+	fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_char(self, v: char) -> Result<Self::Ok> {
+		Ok(v.to_string())
+	}
+
+	fn serialize_none(self) -> Result<Self::Ok> {
+		Err(Error::NonStringKey)
+	}
+
+	fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<Self::Ok> {
+		Err(Error::NonStringKey)
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<Self::Ok> {
+		value.serialize(self)
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+		Err(Error::NonStringKey)
+	}
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+		Err(Error::NonStringKey)
+	}
+}
@@ -0,0 +1,112 @@
+//! Expansion of environment variables in the string values of configs as they're loaded, enabled with
+//! [Abserde::interpolate](crate::Abserde::interpolate).
+//!
+//! Variables are written as `${NAME}` or `%NAME%`, and `$$` and `%%` stand for a literal `$` and `%`. Any
+//! other `$` or `%`, such as in `50%`, is left as it is.
+
+use std::env::var;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{Abserde, Error, Result};
+
+impl Abserde {
+	// Read a config file, expanding environment variables in its string values.
+	pub(crate) fn read_interpolated<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+	) -> Result<(T, Option<u64>)> {
+		let abserde = Abserde {
+			interpolate: false,
+			..self.clone()
+		};
+		let (mut config, version) = abserde.read_config_file_versioned::<Value>(config_path)?;
+
+		interpolate_value(&mut config, &|name| var(name).ok())?;
+
+		let _field_keys = self.field_keys()?;
+
+		Ok((
+			T::deserialize(config).map_err(|err| Error::Deserialize(err.into()))?,
+			version,
+		))
+	}
+}
+
+// Expand environment variables in every string within a value, looking them up with the given function.
+pub(crate) fn interpolate_value<V: Fn(&str) -> Option<String>>(
+	value: &mut Value,
+	var: &V,
+) -> Result<()> {
+	match value {
+		Value::String(s) => *s = interpolate(s, var)?,
+		Value::Array(values) => values
+			.iter_mut()
+			.try_for_each(|value| interpolate_value(value, var))?,
+		Value::Object(map) => map
+			.values_mut()
+			.try_for_each(|value| interpolate_value(value, var))?,
+		_ => {}
+	}
+
+	Ok(())
+}
+
+// Expand environment variables in a string, looking them up with the given function.
+fn interpolate<V: Fn(&str) -> Option<String>>(s: &str, var: &V) -> Result<String> {
+	let mut expanded = String::with_capacity(s.len());
+	let mut rest = s;
+
+	while let Some(start) = rest.find(['$', '%']) {
+		expanded.push_str(&rest[..start]);
+		rest = &rest[start..];
+
+		if rest.starts_with("$$") || rest.starts_with("%%") {
+			expanded.push_str(&rest[..1]);
+			rest = &rest[2..];
+		} else if let Some((name, len)) = variable(rest) {
+			expanded
+				.push_str(&var(name).ok_or_else(|| Error::UndefinedVariable(name.to_string()))?);
+			rest = &rest[len..];
+		} else {
+			expanded.push_str(&rest[..1]);
+			rest = &rest[1..];
+		}
+	}
+
+	expanded.push_str(rest);
+
+	Ok(expanded)
+}
+
+// Name of the variable referenced at the start of a string, such as `${HOME}` or `%APPDATA%`, along with the
+// length of the reference.
+fn variable(s: &str) -> Option<(&str, usize)> {
+	let (name, len) = match s.strip_prefix("${") {
+		Some(braced) => {
+			let end = braced.find('}')?;
+
+			(&braced[..end], end + 3)
+		}
+		None => {
+			let percent = s.strip_prefix('%')?;
+			let end = percent.find('%')?;
+
+			(&percent[..end], end + 2)
+		}
+	};
+
+	is_name(name).then_some((name, len))
+}
+
+// Whether a string can be the name of an environment variable, as written in a config, such as `APPDATA` or
+// `ProgramFiles(x86)`.
+fn is_name(name: &str) -> bool {
+	!name.is_empty()
+		&& !name.starts_with(|c: char| c.is_ascii_digit())
+		&& name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '(' | ')'))
+}
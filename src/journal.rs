@@ -0,0 +1,191 @@
+//! Write-ahead journal of saved configs, compacted into the config file, enabled with
+//! [Abserde::journal](crate::Abserde::journal).
+//!
+//! Each record of the journal holds the encoded contents of a saved config file, after a header of the length
+//! of the contents and their checksum, both little-endian. Records cut short by a crash fail their checksum, so
+//! they're skipped when loading and truncated before the next record is appended.
+
+use std::fs::{remove_file, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::{sync_dir, Abserde, Error, Operation, Result};
+
+// Length of the header before the contents of each record.
+const HEADER_LEN: u64 = 16;
+
+impl Abserde {
+	/// Compact the journal into the config file, if there is one, so the config file holds the last saved
+	/// config.
+	///
+	/// Journals are compacted automatically as configs are saved, so this is only needed before the config
+	/// file is read by other means, or before [Abserde::journal] is disabled, as journals are ignored when
+	/// loading without it.
+	pub fn compact_journal(&self) -> Result<()> {
+		self.with_context(Operation::Save, || {
+			let journal_path = journal_path(&self.config_path()?);
+
+			match latest(&journal_path)? {
+				Some(data) => self.compact(&journal_path, &data),
+				None => Ok(()),
+			}
+		})
+	}
+
+	// Append the encoded contents of the config file to its journal, compacting the journal into the config
+	// file once it holds enough records.
+	pub(crate) fn append_journal(&self, data: &[u8]) -> Result<()> {
+		let journal_path = journal_path(&self.config_path()?);
+		let journal_dir = journal_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+		self.permissions
+			.create_dir_all(journal_dir)
+			.map_err(Error::file(journal_dir))?;
+
+		let mut file = self
+			.permissions
+			.open_file(&journal_path)
+			.map_err(Error::file(&journal_path))?;
+		let records = append(&mut file, data).map_err(Error::file(&journal_path))?;
+
+		// The directory only needs flushing when the journal is created.
+		if records == 1 {
+			sync_dir(journal_dir)?;
+		}
+
+		if records >= self.journal {
+			self.compact(&journal_path, data)?;
+		}
+
+		Ok(())
+	}
+
+	// Write the encoded contents of the config file to the config file, flushing it to disk, then remove the
+	// journal.
+	fn compact(&self, journal_path: &Path, data: &[u8]) -> Result<()> {
+		let abserde = Abserde {
+			sync: true,
+			..self.clone()
+		};
+
+		abserde.write_config_file(|file| Ok(file.write_all(data)?))?;
+		remove_journal(journal_path)?;
+
+		log!(debug, "compacted config journal {}", journal_path.display());
+
+		Ok(())
+	}
+}
+
+// Path of the journal of a config file, next to it.
+pub(crate) fn journal_path(config_path: &Path) -> PathBuf {
+	let mut file_name = config_path.file_name().unwrap_or_default().to_owned();
+
+	file_name.push(".journal");
+
+	config_path.with_file_name(file_name)
+}
+
+// Encoded contents of the config file held by the last complete record of a journal, if there is one.
+pub(crate) fn latest(journal_path: &Path) -> Result<Option<Vec<u8>>> {
+	let mut file = match File::open(journal_path) {
+		Ok(file) => file,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+		Err(err) => return Err(Error::file(journal_path)(err)),
+	};
+	let records = records(&mut file).map_err(Error::file(journal_path))?;
+
+	for record in records.into_iter().rev() {
+		if let Some(data) = read_record(&mut file, record).map_err(Error::file(journal_path))? {
+			return Ok(Some(data));
+		}
+	}
+
+	Ok(None)
+}
+
+// Remove a journal, returning whether there was one.
+pub(crate) fn remove_journal(journal_path: &Path) -> Result<bool> {
+	match remove_file(journal_path) {
+		Ok(()) => Ok(true),
+		Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+		Err(err) => Err(Error::file(journal_path)(err)),
+	}
+}
+
+// Append a record to a journal and flush it to disk, after truncating any records cut short by a crash,
+// returning the number of records in the journal.
+fn append(file: &mut File, data: &[u8]) -> io::Result<usize> {
+	let mut records = records(file)?;
+
+	// Only the last record can have been cut short, as each record is flushed before the next is appended.
+	while let Some(&record) = records.last() {
+		if read_record(file, record)?.is_some() {
+			break;
+		}
+
+		records.pop();
+	}
+
+	let end = records
+		.last()
+		.map(|(offset, len)| offset + len)
+		.unwrap_or_default();
+
+	file.set_len(end)?;
+	file.seek(SeekFrom::Start(end))?;
+
+	let mut record = Vec::with_capacity(HEADER_LEN as usize + data.len());
+
+	record.extend_from_slice(&(data.len() as u64).to_le_bytes());
+	record.extend_from_slice(&checksum(data).to_le_bytes());
+	record.extend_from_slice(data);
+	file.write_all(&record)?;
+	file.sync_data()?;
+
+	Ok(records.len() + 1)
+}
+
+// Offsets and lengths of the contents of the records in a journal, up to the first one cut short.
+fn records(file: &mut File) -> io::Result<Vec<(u64, u64)>> {
+	let file_len = file.metadata()?.len();
+	let mut records = Vec::new();
+	let mut offset = 0;
+	let mut len = [0; 8];
+
+	while offset + HEADER_LEN <= file_len {
+		file.seek(SeekFrom::Start(offset))?;
+		file.read_exact(&mut len)?;
+
+		let len = u64::from_le_bytes(len);
+
+		match (offset + HEADER_LEN).checked_add(len) {
+			Some(end) if end <= file_len => {
+				records.push((offset + HEADER_LEN, len));
+				offset = end;
+			}
+			_ => break,
+		}
+	}
+
+	Ok(records)
+}
+
+// Read the contents of a record, if they match its checksum.
+fn read_record(file: &mut File, (offset, len): (u64, u64)) -> io::Result<Option<Vec<u8>>> {
+	let mut sum = [0; 8];
+	let mut data = vec![0; len as usize];
+
+	file.seek(SeekFrom::Start(offset - 8))?;
+	file.read_exact(&mut sum)?;
+	file.read_exact(&mut data)?;
+
+	Ok((checksum(&data) == u64::from_le_bytes(sum)).then_some(data))
+}
+
+// FNV-1a hash of the contents of a record, which is stable across platforms and versions of Rust.
+fn checksum(data: &[u8]) -> u64 {
+	data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+		(hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+	})
+}
@@ -0,0 +1,175 @@
+//! Storage of config values in the OS keychain, wrapped in [Keychain].
+//!
+//! Keychain values are serialised to JSON and stored in the OS keychain under the app name, with a random ID
+//! as the user. Only a placeholder of the form `keychain:<id>` is written to the config file.
+
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::ops::{Deref, DerefMut};
+use std::result;
+
+use keyring::Entry;
+use serde::de::{self, DeserializeOwned};
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Error, Result};
+
+const PREFIX: &str = "keychain:";
+
+/// Config value which is stored in the OS keychain, and never written to the config file.
+///
+/// On save the value is stored in the keychain, and the config file only contains a placeholder referencing it.
+/// On load the value is fetched back from the keychain. Keychain values can only be saved and loaded by an
+/// [Abserde](crate::Abserde), and are stored under its app name. Plain values are accepted when loading, so
+/// they can be entered by hand, and are moved to the keychain the next time the config is saved.
+///
+/// Keychain entries are not removed when config files are deleted.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// #[derive(Serialize, Deserialize)]
+/// struct MyConfig {
+/// 	username: String,
+/// 	api_token: Keychain<String>,
+/// }
+/// ```
+#[derive(PartialEq, Eq, Clone)]
+pub struct Keychain<T> {
+	id: String,
+	value: T,
+}
+
+impl<T> Keychain<T> {
+	/// Create a keychain value, with a new random ID for its keychain entry.
+	pub fn new(value: T) -> Self {
+		Self {
+			id: format!("{:016x}", rand::random::<u64>()),
+			value,
+		}
+	}
+
+	/// ID of the keychain entry for the value.
+	pub fn id(&self) -> &str {
+		&self.id
+	}
+
+	/// Consume the keychain value, returning the wrapped value.
+	pub fn into_inner(self) -> T {
+		self.value
+	}
+}
+
+impl<T: Default> Default for Keychain<T> {
+	fn default() -> Self {
+		Self::new(T::default())
+	}
+}
+
+impl<T> From<T> for Keychain<T> {
+	fn from(value: T) -> Self {
+		Self::new(value)
+	}
+}
+
+impl<T> Deref for Keychain<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.value
+	}
+}
+
+impl<T> DerefMut for Keychain<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.value
+	}
+}
+
+// Keychain values are usually secrets, so must never end up in logs.
+impl<T> Debug for Keychain<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Keychain")
+			.field("id", &self.id)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T: Serialize> Serialize for Keychain<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> result::Result<S::Ok, S::Error> {
+		let value = serde_json::to_string(&self.value).map_err(ser::Error::custom)?;
+
+		entry(&self.id)
+			.and_then(|entry| {
+				entry
+					.set_password(&value)
+					.map_err(|err| Error::Keychain(err.into()))
+			})
+			.map_err(ser::Error::custom)?;
+
+		serializer.serialize_str(&format!("{}{}", PREFIX, self.id))
+	}
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Keychain<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+		let value = serde_json::Value::deserialize(deserializer)?;
+
+		match value.as_str().and_then(|value| value.strip_prefix(PREFIX)) {
+			Some(id) => {
+				let value = entry(id)
+					.and_then(|entry| {
+						entry.get_password().map_err(|err| {
+							Error::Keychain(
+								format!("failed to fetch {} from the keychain: {}", id, err).into(),
+							)
+						})
+					})
+					.map_err(de::Error::custom)?;
+
+				Ok(Self {
+					id: id.to_string(),
+					value: serde_json::from_str(&value).map_err(de::Error::custom)?,
+				})
+			}
+			None => Ok(Self::new(
+				serde_json::from_value(value).map_err(de::Error::custom)?,
+			)),
+		}
+	}
+}
+
+thread_local! {
+	// Service under which keychain values serialised or deserialised on the current thread are stored.
+	static SERVICE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Makes the service for keychain entries available on the current thread, until dropped.
+pub(crate) struct KeychainScope {
+	previous: Option<String>,
+}
+
+impl KeychainScope {
+	pub(crate) fn enter(service: &str) -> Self {
+		Self {
+			previous: SERVICE.with(|current| current.replace(Some(service.to_string()))),
+		}
+	}
+}
+
+impl Drop for KeychainScope {
+	fn drop(&mut self) {
+		SERVICE.with(|current| *current.borrow_mut() = self.previous.take());
+	}
+}
+
+// Get the keychain entry with the given ID, for the current service.
+fn entry(id: &str) -> Result<Entry> {
+	SERVICE.with(|current| match current.borrow().as_deref() {
+		Some(service) => Entry::new(service, id).map_err(|err| Error::Keychain(err.into())),
+		None => Err(Error::Unsupported(
+			"keychain values can only be saved and loaded by an Abserde".to_string(),
+		)),
+	})
+}
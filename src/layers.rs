@@ -0,0 +1,400 @@
+//! Layered configs, merged from several sources in priority order.
+
+use std::env::vars_os;
+use std::fmt::{self, Debug};
+#[cfg(feature = "dotenv")]
+use std::path::PathBuf;
+#[cfg(feature = "dotenv")]
+use std::result;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{Abserde, Error, Operation, Result};
+
+type Defaults = Box<dyn Fn() -> Result<Value> + Send + Sync>;
+
+// Source of a layer.
+enum Layer {
+	Defaults(Defaults),
+	File(Box<Abserde>),
+	Env(String),
+	#[cfg(feature = "dotenv")]
+	Dotenv(PathBuf, String),
+	Overrides(Defaults),
+	Set(Vec<(String, String)>),
+}
+
+/// How the layers of a config are merged by [Layers::load_layered_with].
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct MyConfig {
+/// # 	plugins: Vec<String>,
+/// # }
+/// #
+/// let strategy = MergeStrategy {
+/// 	arrays: ArrayMerge::Append,
+/// 	null_deletes: true,
+/// 	..Default::default()
+/// };
+/// let my_config = Layers::new()
+/// 	.defaults(MyConfig::default())
+/// 	.file(Abserde::default())
+/// 	.load_layered_with::<MyConfig>(&strategy)?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MergeStrategy {
+	/// How arrays of higher layers are merged with those of lower layers. Defaults to [ArrayMerge::Replace].
+	pub arrays: ArrayMerge,
+
+	/// How maps of higher layers are merged with those of lower layers. Defaults to [MapMerge::Deep].
+	pub maps: MapMerge,
+
+	/// Whether null values of higher layers delete the values of lower layers, so the field takes its default
+	/// value, rather than setting it to null.
+	///
+	/// Null values of layers added with [Layers::overrides] are skipped whatever this is. Disabled by default.
+	pub null_deletes: bool,
+}
+
+/// How arrays of higher layers are merged with those of lower layers, as given by [MergeStrategy::arrays].
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum ArrayMerge {
+	/// Arrays replace those of lower layers entirely.
+	#[default]
+	Replace,
+
+	/// Arrays are appended to those of lower layers, such as to add plugins to those enabled by default.
+	Append,
+}
+
+/// How maps of higher layers are merged with those of lower layers, as given by [MergeStrategy::maps].
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum MapMerge {
+	/// Maps are merged key by key, at any depth.
+	#[default]
+	Deep,
+
+	/// Maps within fields replace those of lower layers entirely, while the fields of the config itself are
+	/// still merged one by one.
+	Replace,
+}
+
+impl MergeStrategy {
+	// Merge a higher priority layer into the merged lower priority layers, merging the fields of the config
+	// one by one whichever way maps are merged.
+	fn merge_layer(&self, lower: &mut Value, higher: Value) {
+		match (lower, higher) {
+			(Value::Object(lower), Value::Object(higher)) => self.merge_map(lower, higher),
+			(lower, higher) => self.merge(lower, higher),
+		}
+	}
+
+	// Merge a higher priority value into a lower priority one.
+	fn merge(&self, lower: &mut Value, higher: Value) {
+		match (lower, higher) {
+			(Value::Object(lower), Value::Object(higher)) if self.maps == MapMerge::Deep => {
+				self.merge_map(lower, higher)
+			}
+			(Value::Array(lower), Value::Array(higher)) if self.arrays == ArrayMerge::Append => {
+				lower.extend(higher)
+			}
+			(lower, higher) => *lower = higher,
+		}
+	}
+
+	// Merge a higher priority map into a lower priority one, key by key.
+	fn merge_map(&self, lower: &mut Map<String, Value>, higher: Map<String, Value>) {
+		for (key, value) in higher {
+			if value.is_null() && self.null_deletes {
+				lower.remove(&key);
+
+				continue;
+			}
+
+			match lower.get_mut(&key) {
+				Some(lower) => self.merge(lower, value),
+				None => {
+					lower.insert(key, value);
+				}
+			}
+		}
+	}
+}
+
+/// Sources of a config, in priority order, which are deep merged into one config when loaded.
+///
+/// Layers are added from the lowest priority to the highest, so later layers override earlier ones. Maps are
+/// merged key by key, at any depth, while any other value replaces the value of lower layers entirely, unless
+/// another [MergeStrategy] is given to [Layers::load_layered_with]. Config
+/// file layers are skipped when the config file doesn't exist. Environment variable layers, added with
+/// [Layers::env], override fields of the layers below them. Layers are merged through [serde_json::Value],
+/// so they can't hold 128-bit integers outside the range of 64-bit integers.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct MyConfig {
+/// # 	theme: String,
+/// # }
+/// #
+/// let layers = Layers::new()
+/// 	.defaults(MyConfig::default())
+/// 	.file(Abserde {
+/// 		location: Location::Path("/etc/myapp/config.json".into()),
+/// 		..Default::default()
+/// 	})
+/// 	.file(Abserde::default());
+/// let my_config = layers.load_layered::<MyConfig>()?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Layers {
+	layers: Vec<Layer>,
+}
+
+impl Layers {
+	/// Create layers without any sources.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a layer holding the given config, such as defaults embedded in the app.
+	pub fn defaults<T>(mut self, defaults: T) -> Self
+	where
+		T: Serialize + Send + Sync + 'static,
+	{
+		self.layers.push(Layer::Defaults(Box::new(move || {
+			serde_json::to_value(&defaults).map_err(|err| Error::Serialize(err.into()))
+		})));
+
+		self
+	}
+
+	/// Add a layer loaded from the config file managed by the given [Abserde].
+	pub fn file(mut self, abserde: Abserde) -> Self {
+		self.layers.push(Layer::File(Box::new(abserde)));
+
+		self
+	}
+
+	/// Add a layer overriding config fields from environment variables starting with the given prefix and `_`.
+	///
+	/// The rest of the variable name is the field name in lowercase, with `__` separating nested fields, so
+	/// `MYAPP_WINDOW__WIDTH=1920` sets `window.width` for the prefix `MYAPP`. Values are parsed as JSON, such as
+	/// numbers and booleans, unless lower layers hold a string for the field, or they aren't valid JSON.
+	pub fn env<S: Into<String>>(mut self, prefix: S) -> Self {
+		self.layers.push(Layer::Env(prefix.into()));
+
+		self
+	}
+
+	/// Add a layer overriding config fields from the variables of the given dotenv file, such as `.env`.
+	///
+	/// Variables are mapped to config fields the same way as those of [Layers::env], and the layer is skipped
+	/// when the file doesn't exist. Enabled with the dotenv feature.
+	#[cfg(feature = "dotenv")]
+	pub fn dotenv<P, S>(mut self, path: P, prefix: S) -> Self
+	where
+		P: Into<PathBuf>,
+		S: Into<String>,
+	{
+		self.layers.push(Layer::Dotenv(path.into(), prefix.into()));
+
+		self
+	}
+
+	/// Add a layer overriding config fields with the given partial config, such as command-line arguments.
+	///
+	/// Null values, such as `Option` fields set to `None`, are skipped, so they don't override lower layers.
+	pub fn overrides<T>(mut self, overrides: T) -> Self
+	where
+		T: Serialize + Send + Sync + 'static,
+	{
+		self.layers.push(Layer::Overrides(Box::new(move || {
+			let mut value =
+				serde_json::to_value(&overrides).map_err(|err| Error::Serialize(err.into()))?;
+
+			remove_nulls(&mut value);
+
+			Ok(value)
+		})));
+
+		self
+	}
+
+	/// Add a layer overriding config fields with the given key and value pairs, such as `--set` arguments.
+	///
+	/// Keys are field names, with `.` separating nested fields, such as `window.width`. Values are parsed the
+	/// same way as those of [Layers::env].
+	pub fn set<I, K, V>(mut self, pairs: I) -> Self
+	where
+		I: IntoIterator<Item = (K, V)>,
+		K: Into<String>,
+		V: Into<String>,
+	{
+		self.layers.push(Layer::Set(
+			pairs
+				.into_iter()
+				.map(|(key, value)| (key.into(), value.into()))
+				.collect(),
+		));
+
+		self
+	}
+
+	/// Load each layer and deep merge them into one config.
+	///
+	/// Any `Secret` or `Keychain` values are loaded using the [Abserde] of the
+	/// last config file layer.
+	pub fn load_layered<T: DeserializeOwned>(&self) -> Result<T> {
+		self.load_layered_with(&MergeStrategy::default())
+	}
+
+	/// Load each layer and merge them into one config, as given by the [MergeStrategy].
+	pub fn load_layered_with<T: DeserializeOwned>(&self, strategy: &MergeStrategy) -> Result<T> {
+		let mut merged = Value::Object(Map::new());
+		// Fields such as secrets are serialised and deserialised with the keys of the last config file, as the
+		// merged config would be saved to it.
+		let last_abserde = self.layers.iter().rev().find_map(|layer| match layer {
+			Layer::File(abserde) => Some(&**abserde),
+			_ => None,
+		});
+		let _field_keys = last_abserde.map(Abserde::field_keys).transpose()?;
+
+		for layer in &self.layers {
+			let value = match layer {
+				Layer::Defaults(defaults) => defaults()?,
+				Layer::File(abserde) => {
+					match abserde.with_context(Operation::Load, || {
+						abserde.read_config_file(&abserde.load_path()?)
+					}) {
+						Ok(value) => value,
+						Err(error) if matches!(error.root(), Error::NotFound(_)) => continue,
+						Err(error) => return Err(error),
+					}
+				}
+				Layer::Env(prefix) => {
+					let vars = vars_os().filter_map(|(name, raw)| {
+						Some((name.into_string().ok()?, raw.into_string().ok()?))
+					});
+
+					prefixed_value(prefix, vars, &merged)
+				}
+				#[cfg(feature = "dotenv")]
+				Layer::Dotenv(path, prefix) => {
+					let vars = match dotenvy::from_path_iter(path) {
+						Ok(vars) => vars.collect::<result::Result<Vec<_>, _>>(),
+						Err(error) if error.not_found() => continue,
+						Err(error) => Err(error),
+					};
+					let vars = vars.map_err(|err| match err {
+						dotenvy::Error::Io(err) => Error::Io(err),
+						err => Error::Deserialize(err.into()),
+					})?;
+
+					prefixed_value(prefix, vars, &merged)
+				}
+				Layer::Overrides(overrides) => overrides()?,
+				Layer::Set(pairs) => override_value(
+					pairs.iter().map(|(key, raw)| {
+						(key.split('.').map(str::to_owned).collect(), raw.clone())
+					}),
+					&merged,
+				),
+			};
+
+			strategy.merge_layer(&mut merged, value);
+		}
+
+		serde_json::from_value(merged).map_err(|err| Error::Deserialize(err.into()))
+	}
+}
+
+impl Debug for Layer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Layer::Defaults(_) => write!(f, "Defaults(..)"),
+			Layer::File(abserde) => f.debug_tuple("File").field(abserde).finish(),
+			Layer::Env(prefix) => f.debug_tuple("Env").field(prefix).finish(),
+			#[cfg(feature = "dotenv")]
+			Layer::Dotenv(path, prefix) => f.debug_tuple("Dotenv").field(path).field(prefix).finish(),
+			Layer::Overrides(_) => write!(f, "Overrides(..)"),
+			Layer::Set(pairs) => f.debug_tuple("Set").field(pairs).finish(),
+		}
+	}
+}
+
+// Build a layer from the variables with the given prefix, such as environment variables.
+fn prefixed_value<I>(prefix: &str, vars: I, lower: &Value) -> Value
+where
+	I: IntoIterator<Item = (String, String)>,
+{
+	let prefix = format!("{}_", prefix);
+	let pairs = vars.into_iter().filter_map(|(name, raw)| {
+		let path = name.strip_prefix(&prefix)?;
+
+		Some((path.split("__").map(str::to_lowercase).collect(), raw))
+	});
+
+	override_value(pairs, lower)
+}
+
+// Build a layer from pairs of field paths and raw values, using lower layers to tell which fields hold strings.
+fn override_value<I: IntoIterator<Item = (Vec<String>, String)>>(pairs: I, lower: &Value) -> Value {
+	let mut value = Value::Object(Map::new());
+
+	for (keys, raw) in pairs {
+		if keys.iter().any(String::is_empty) {
+			continue;
+		}
+
+		let is_string = keys
+			.iter()
+			.try_fold(lower, |value, key| value.get(key))
+			.is_some_and(Value::is_string);
+		let leaf = match is_string {
+			true => Value::String(raw),
+			false => serde_json::from_str(&raw).unwrap_or(Value::String(raw)),
+		};
+		let Some((last, parents)) = keys.split_last() else {
+			continue;
+		};
+		let mut current = &mut value;
+
+		for key in parents {
+			if !current[key].is_object() {
+				current[key] = Value::Object(Map::new());
+			}
+
+			current = &mut current[key];
+		}
+
+		current[last] = leaf;
+	}
+
+	value
+}
+
+// Remove null values from maps, at any depth.
+fn remove_nulls(value: &mut Value) {
+	if let Value::Object(map) = value {
+		map.retain(|_, value| !value.is_null());
+		map.values_mut().for_each(remove_nulls);
+	}
+}
+
+// Deep merge a higher priority value into a lower priority one.
+pub(crate) fn merge(lower: &mut Value, higher: Value) {
+	MergeStrategy::default().merge(lower, higher)
+}
@@ -46,10 +46,12 @@
 //! # #[derive(Serialize, Deserialize)]
 //! # struct MyConfig;
 //! #
-//! let my_abserde = Abserde::default();
+//! let my_abserde = for_crate!();
 //! ```
 //!
-//! Using [Abserde] in this way will use your crate as the name for the app config directory.
+//! Using [for_crate!] in this way will use your crate as the name for the app config directory. An
+//! [Abserde::default] is compiled within abserde, so it can only find the name of your crate in the
+//! `CARGO_PKG_NAME` environment variable at runtime, as set by `cargo run`, and is otherwise named `abserde`.
 //!
 //! Alternatively, you can also pass options to [Abserde] to change the location or format of your config file:
 //!
@@ -65,6 +67,7 @@
 //! 	app: "MyApp".to_string(),
 //! 	location: Location::Auto,
 //! 	format: Format::Json,
+//! 	..Default::default()
 //! };
 //! ```
 //!
@@ -82,6 +85,7 @@
 //! 	app: "MyApp".to_string(),
 //! 	location: Location::Auto,
 //! 	format: Format::PrettyJson(PrettyJsonIndent::Tab),
+//! 	..Default::default()
 //! };
 //! ```
 //!
@@ -97,6 +101,25 @@
 //! 	app: "MyApp".to_string(),
 //! 	location: Location::Auto,
 //! 	format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
+//! 	..Default::default()
+//! };
+//! ```
+//!
+//! Config files are written atomically by default, so a crash part way through a save never leaves a
+//! truncated file behind. This can be disabled to write directly to the config file instead:
+//!
+//! ```no_run
+//! # use serde::{Serialize, Deserialize};
+//! #
+//! # use abserde::*;
+//! #
+//! # #[derive(Serialize, Deserialize)]
+//! # struct MyConfig;
+//! #
+//! let my_abserde = Abserde {
+//! 	app: "MyApp".to_string(),
+//! 	atomic: false,
+//! 	..Default::default()
 //! };
 //! ```
 //!
@@ -114,6 +137,7 @@
 //! # 	app: "MyApp".to_string(),
 //! # 	location: Location::Auto,
 //! # 	format: Format::Json,
+//! # 	..Default::default()
 //! # };
 //! #
 //! let my_config = MyConfig::load_config(&my_abserde)?;
@@ -135,6 +159,7 @@
 //! # 	app: "MyApp".to_string(),
 //! # 	location: Location::Auto,
 //! # 	format: Format::Json,
+//! # 	..Default::default()
 //! # };
 //! #
 //! # let my_config = MyConfig::load_config(&my_abserde)?;
@@ -143,6 +168,31 @@
 //! # Ok::<(), Error>(())
 //! ```
 //!
+//! Keep backups of the previous config files on each save, which can be restored later:
+//!
+//! ```no_run
+//! # use serde::{Serialize, Deserialize};
+//! #
+//! # use abserde::*;
+//! #
+//! # #[derive(Serialize, Deserialize)]
+//! # struct MyConfig;
+//! #
+//! let my_abserde = Abserde {
+//! 	app: "MyApp".to_string(),
+//! 	backups: 3,
+//! 	..Default::default()
+//! };
+//!
+//! # let my_config = MyConfig::load_config(&my_abserde)?;
+//! my_config.save_config(&my_abserde)?;
+//!
+//! // Restore the config file as it was before the last save.
+//! my_abserde.restore_backup(1)?;
+//! #
+//! # Ok::<(), Error>(())
+//! ```
+//!
 //! Delete config file from disk:
 //!
 //! ```no_run
@@ -157,6 +207,7 @@
 //! # 	app: "MyApp".to_string(),
 //! # 	location: Location::Auto,
 //! # 	format: Format::Json,
+//! # 	..Default::default()
 //! # };
 //! #
 //! # let my_config = MyConfig::load_config(&my_abserde)?;
@@ -170,25 +221,218 @@
 #![deny(missing_docs)]
 #![allow(clippy::tabs_in_doc_comments)]
 
-use std::env::var;
+use std::collections::BTreeMap;
+use std::env::{var, var_os};
+use std::ffi::{OsStr, OsString};
 use std::fmt::Display;
-use std::fs::{create_dir_all, remove_dir, remove_file, File};
-use std::path::PathBuf;
+use std::fs::{
+	copy, create_dir_all, read, read_dir, remove_dir, remove_dir_all, remove_file, rename, File,
+};
+use std::iter::once;
+use std::path::{Component, Path, PathBuf};
 use std::str;
+#[cfg(feature = "custom")]
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use std::{io, result};
 
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+// Lets code generated by the derive macro, which refers to abserde by name, be tested within the crate.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as abserde;
+
+// Emit a log record at the given level when the log feature is enabled, such as
+// `log!(debug, "rotated backups")`. Nothing is logged otherwise, although the arguments are still checked.
+macro_rules! log {
+	($level:ident, $($arg:tt)+) => {
+		#[cfg(feature = "log")]
+		::log::$level!($($arg)+);
+		#[cfg(not(feature = "log"))]
+		if false {
+			_ = format_args!($($arg)+);
+		}
+	};
+}
+
+#[cfg(feature = "async")]
+mod asynchronous;
+mod audit;
+mod batch;
+mod builder;
+mod capture;
+#[cfg(feature = "clap")]
+mod cli;
+mod collection;
+#[cfg(any(feature = "encryption", feature = "age", feature = "signing"))]
+mod crypto;
+#[cfg(feature = "custom")]
+mod custom;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod derive;
+mod dynamic;
+#[cfg(feature = "egui")]
+mod egui;
+mod encoding;
+mod envelope;
+mod error;
+mod export;
+mod extras;
+mod field;
+#[cfg(feature = "git")]
+mod git;
+#[cfg(unix)]
+mod hangup;
+#[cfg(feature = "http")]
+mod http;
+mod include;
+#[cfg(feature = "ini")]
+mod ini;
+mod interpolate;
+mod journal;
+#[cfg(feature = "keyring")]
+mod keychain;
+mod layers;
+mod line_ending;
+#[cfg(target_arch = "wasm32")]
+mod local_storage;
+mod lock;
+mod manager;
+mod memory;
+mod migrate;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod mobile;
+mod namespace;
+#[cfg(all(target_arch = "wasm32", feature = "async"))]
+mod opfs;
+mod parse;
+#[cfg(feature = "json-patch")]
+mod patch;
+mod permissions;
+mod policy;
+#[cfg(feature = "yaml")]
+mod preserve;
+mod quarantine;
+#[cfg(feature = "encryption")]
+mod rotate;
+#[cfg(feature = "s3")]
+mod s3;
+#[cfg(feature = "derive")]
+mod sample;
+mod sandbox;
+#[cfg(feature = "schemars")]
+mod schema;
+mod sections;
+mod shared;
+#[cfg(feature = "shutdown")]
+mod shutdown;
+mod sort;
+#[cfg(feature = "config")]
+mod source;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod symlink;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tracing")]
+mod trace;
+mod unknown;
+#[cfg(feature = "validator")]
+mod validate;
+mod value;
+mod verify;
+mod version;
+#[cfg(feature = "notify")]
+mod watch;
+
+#[cfg(feature = "derive")]
+pub use abserde_derive::AbserdeConfig;
+#[cfg(all(feature = "async", feature = "notify"))]
+pub use asynchronous::ConfigStream;
+#[cfg(feature = "async")]
+pub use asynchronous::{set_spawner, AsyncConfig, Spawn, ThreadSpawner};
+pub use audit::{AuditChange, AuditEntry};
+pub use batch::Batch;
+pub use builder::AbserdeBuilder;
+pub use capture::Capture;
+#[cfg(feature = "clap")]
+pub use cli::ConfigArgs;
+pub use collection::ConfigCollection;
+#[cfg(any(feature = "encryption", feature = "signing"))]
+pub use crypto::KeySource;
+#[cfg(feature = "encryption")]
+pub use crypto::Secret;
+#[cfg(feature = "custom")]
+pub use custom::ConfigFormat;
+pub use dynamic::DynamicConfig;
+#[cfg(feature = "egui")]
+pub use egui::AbserdeStorage;
+#[cfg(feature = "custom")]
+pub use erased_serde;
+pub use error::{Error, Operation};
+pub use extras::WithExtras;
+pub use field::load_field;
+#[cfg(feature = "git")]
+pub use git::Revision;
+#[cfg(unix)]
+pub use hangup::HangupReloader;
+#[cfg(feature = "http")]
+pub use http::Remote;
+#[cfg(feature = "keyring")]
+pub use keychain::Keychain;
+pub use layers::{ArrayMerge, Layers, MapMerge, MergeStrategy};
+pub use line_ending::LineEnding;
+pub use manager::ConfigManager;
+pub use migrate::Migrations;
+#[cfg(feature = "mmap")]
+pub use mmap::MappedConfig;
+pub use mobile::set_android_files_dir;
+pub use permissions::FilePermissions;
+pub use policy::PolicyConflict;
+pub use quarantine::Quarantined;
+#[cfg(feature = "s3")]
+pub use s3::Bucket;
+#[cfg(feature = "derive")]
+pub use sample::DocumentedConfig;
+pub use sandbox::Environment;
+pub use shared::SharedConfig;
+#[cfg(feature = "shutdown")]
+pub use shutdown::{on_shutdown, run_shutdown_hooks, set_shutdown_exit_code, SHUTDOWN_EXIT_CODE};
+pub use symlink::SymlinkPolicy;
+#[cfg(feature = "validator")]
+pub use validate::ValidatedConfig;
+pub use version::{ConfigVersion, Conflict};
+#[cfg(feature = "notify")]
+pub use watch::{ConfigWatcher, WATCH_DEBOUNCE};
+#[cfg(feature = "encryption")]
+pub use zeroize;
 
-const MSG_NO_SYSTEM_CONFIG_DIR: &str = "no system config directory detected";
+const MSG_FORMAT_NOT_INFERRED: &str = "config format must be inferred from the config file name";
 
-/// Alias for generic Error type.
-pub type Error = anyhow::Error;
+// Size of the buffer config files are serialised through, which is large enough to keep system calls rare.
+const WRITE_BUFFER_SIZE: usize = 64 * 1024;
 
-/// Alias for Result type wrapping generic Error type.
+// Directory holding the directory of each profile, within the config directory.
+const PROFILES_DIR: &str = "profiles";
+
+#[cfg(all(target_arch = "wasm32", feature = "async"))]
+const OPFS_ASYNC_ONLY: &str =
+	"configs in the private file system can only be loaded and saved asynchronously";
+
+/// Alias for Result type wrapping [Error].
 pub type Result<T> = result::Result<T, Error>;
 
+// Buffer of serialised config data, which is wiped when dropped if configs may be encrypted, so plaintext
+// doesn't linger in memory.
+#[cfg(feature = "encryption")]
+type Plaintext = zeroize::Zeroizing<Vec<u8>>;
+#[cfg(not(feature = "encryption"))]
+type Plaintext = Vec<u8>;
+
 /// JSON pretty print indentation style selection.
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub enum PrettyJsonIndent {
 	/// Indent using a tab character.
 	#[default]
@@ -211,7 +455,7 @@ impl Display for PrettyJsonIndent {
 ///
 /// Each format is enabled as a feature. The json feature is included by default.
 /// All other format features are disabled by default.
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub enum Format {
 	// Default will become the first supported in order of preference.
 	#[default]
@@ -233,28 +477,273 @@ pub enum Format {
 	Pickle,
 
 	/// INI (Windows) format using the serde_ini crate.
+	///
+	/// Nested structs and maps one level deep are stored as `[sections]`.
 	#[cfg(feature = "ini")]
 	Ini,
 
 	/// TOML format using the toml crate.
 	#[cfg(feature = "toml")]
 	Toml,
+
+	/// Binary property list (Apple) format using the plist crate.
+	#[cfg(feature = "plist")]
+	Plist,
+
+	/// Format inferred from the extension of the config file name, using [Format::from_extension].
+	///
+	/// The config file name must be given using [Location::Path] or [Location::File].
+	FromExtension,
+
+	/// Format implemented outside of this crate. See [ConfigFormat]. Enabled with the custom feature.
+	///
+	/// Custom formats are equal only to themselves, and can't be serialised along with an [Abserde].
+	#[cfg(feature = "custom")]
+	#[serde(skip)]
+	Custom(Arc<dyn ConfigFormat>),
 }
 
 impl Format {
+	// Formats compiled into the binary, in order of preference.
+	const ENABLED: &'static [Format] = &[
+		#[cfg(feature = "json")]
+		Format::Json,
+		#[cfg(feature = "yaml")]
+		Format::Yaml,
+		#[cfg(feature = "pickle")]
+		Format::Pickle,
+		#[cfg(feature = "ini")]
+		Format::Ini,
+		#[cfg(feature = "toml")]
+		Format::Toml,
+		#[cfg(feature = "plist")]
+		Format::Plist,
+	];
+
 	/// Return default file name of config file for this format.
+	///
+	/// As [Format::FromExtension] has no extension of its own, its default file name has no extension.
 	pub fn default_name(&self) -> String {
+		self.file_name("config")
+	}
+
+	// File name of a config file in this format, with the given name before the extension.
+	fn file_name(&self, name: &str) -> String {
+		match self {
+			#[cfg(feature = "json")]
+			Format::PrettyJson(_) => Format::Json.file_name(name),
+			Format::FromExtension => name.to_string(),
+			#[cfg(feature = "custom")]
+			Format::Custom(format) => format!("{}.{}", name, format.extension()),
+			_ => format!("{}.{}", name, format!("{:?}", self).to_lowercase()),
+		}
+	}
+
+	/// Return the enabled format for a file extension, such as `toml`, if there is one.
+	pub fn from_extension(extension: &str) -> Option<Format> {
+		match extension.to_lowercase().as_str() {
+			#[cfg(feature = "json")]
+			"json" => Some(Format::Json),
+			#[cfg(feature = "yaml")]
+			"yaml" | "yml" => Some(Format::Yaml),
+			#[cfg(feature = "pickle")]
+			"pickle" | "pkl" => Some(Format::Pickle),
+			#[cfg(feature = "ini")]
+			"ini" => Some(Format::Ini),
+			#[cfg(feature = "toml")]
+			"toml" => Some(Format::Toml),
+			#[cfg(feature = "plist")]
+			"plist" => Some(Format::Plist),
+			_ => None,
+		}
+	}
+
+	// Serialise a config in this format to the given writer.
+	fn serialize<T, W>(&self, config: &T, writer: W) -> Result<()>
+	where
+		T: Serialize,
+		W: io::Write,
+	{
+		if *self == Format::FromExtension {
+			return Err(Error::Unsupported(MSG_FORMAT_NOT_INFERRED.to_string()));
+		}
+
+		#[cfg(feature = "custom")]
+		if let Format::Custom(format) = self {
+			let mut writer = writer;
+
+			return format.serialize(config, &mut writer);
+		}
+
+		self.serialize_to(config, writer).map_err(Error::Serialize)
+	}
+
+	fn serialize_to<T, W>(
+		&self,
+		config: &T,
+		mut writer: W,
+	) -> result::Result<(), Box<dyn std::error::Error + Send + Sync>>
+	where
+		T: Serialize,
+		W: io::Write,
+	{
+		match self {
+			#[cfg(feature = "json")]
+			Format::Json => {
+				serde_json::to_writer(writer, config)?;
+			}
+			#[cfg(feature = "json")]
+			Format::PrettyJson(indent) => {
+				let indent_string = indent.to_string();
+				let formatter =
+					serde_json::ser::PrettyFormatter::with_indent(indent_string.as_bytes());
+				let mut ser = serde_json::Serializer::with_formatter(&mut writer, formatter);
+				config.serialize(&mut ser)?;
+
+				writeln!(writer)?;
+			}
+			#[cfg(feature = "yaml")]
+			Format::Yaml => {
+				serde_yaml::to_writer(writer, config)?;
+			}
+			#[cfg(feature = "pickle")]
+			Format::Pickle => {
+				serde_pickle::to_writer(&mut writer, config, serde_pickle::SerOptions::new())?;
+			}
+			#[cfg(feature = "ini")]
+			Format::Ini => {
+				ini::to_writer(writer, config)?;
+			}
+			#[cfg(feature = "toml")]
+			Format::Toml => {
+				write!(writer, "{}", toml::to_string(config)?)?;
+			}
+			#[cfg(feature = "plist")]
+			Format::Plist => {
+				plist::to_writer_binary(writer, config)?;
+			}
+			Format::FromExtension => unreachable!(),
+			#[cfg(feature = "custom")]
+			Format::Custom(_) => unreachable!(),
+		}
+
+		Ok(())
+	}
+
+	// Deserialise a config in this format from the given reader.
+	fn deserialize<T, R>(&self, reader: R) -> Result<T>
+	where
+		T: DeserializeOwned,
+		R: io::Read,
+	{
+		if *self == Format::FromExtension {
+			return Err(Error::Unsupported(MSG_FORMAT_NOT_INFERRED.to_string()));
+		}
+
+		#[cfg(feature = "custom")]
+		if let Format::Custom(format) = self {
+			let mut reader = reader;
+
+			return serde_path_to_error::deserialize(format.deserialize(&mut reader)?)
+				.map_err(|err| Error::Deserialize(err.into()));
+		}
+
+		// Editors such as Notepad save text with a byte order mark, or as UTF-16.
+		match self.is_text() {
+			true => encoding::decode(reader)
+				.map_err(Into::into)
+				.and_then(|reader| self.deserialize_from(reader)),
+			false => self.deserialize_from(reader),
+		}
+		.map_err(Error::Deserialize)
+	}
+
+	// Whether configs in this format are text, rather than binary.
+	fn is_text(&self) -> bool {
 		match self {
-			Format::PrettyJson(_) => format!("config.{:?}", Format::Json).to_lowercase(),
-			_ => format!("config.{:?}", self).to_lowercase(),
+			#[cfg(feature = "pickle")]
+			Format::Pickle => false,
+			#[cfg(feature = "plist")]
+			Format::Plist => false,
+			#[cfg(feature = "custom")]
+			Format::Custom(_) => false,
+			_ => true,
 		}
 	}
+
+	fn deserialize_from<T, R>(
+		&self,
+		reader: R,
+	) -> result::Result<T, Box<dyn std::error::Error + Send + Sync>>
+	where
+		T: DeserializeOwned,
+		R: io::Read,
+	{
+		// Errors are reported along with the path of the field which failed, such as user_data.theme.
+		// The INI and TOML deserialisers consume the whole document when deserialising the top-level struct.
+		Ok(match self {
+			#[cfg(feature = "json")]
+			Format::Json | Format::PrettyJson(_) => {
+				let mut de = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+				let config = serde_path_to_error::deserialize(&mut de)?;
+
+				de.end()?;
+
+				config
+			}
+			#[cfg(feature = "yaml")]
+			Format::Yaml => serde_path_to_error::deserialize(serde_yaml::Deserializer::from_reader(
+				io::BufReader::new(reader),
+			))?,
+			#[cfg(feature = "pickle")]
+			Format::Pickle => {
+				let mut de = serde_pickle::Deserializer::new(
+					io::BufReader::new(reader),
+					serde_pickle::DeOptions::new(),
+				);
+				let config = serde_path_to_error::deserialize(&mut de)?;
+
+				de.end()?;
+
+				config
+			}
+			#[cfg(feature = "ini")]
+			Format::Ini => serde_path_to_error::deserialize(&mut serde_ini::Deserializer::from_read(
+				io::BufReader::new(reader),
+			))?,
+			#[cfg(feature = "toml")]
+			Format::Toml => {
+				let mut reader = reader;
+				let mut buf = String::new();
+
+				reader.read_to_string(&mut buf)?;
+
+				serde_path_to_error::deserialize(&mut toml::Deserializer::new(&buf))?
+			}
+			// The plist deserialiser isn't public, so errors aren't reported with the path of the field.
+			#[cfg(feature = "plist")]
+			Format::Plist => {
+				let mut reader = reader;
+				let mut buf = Vec::new();
+
+				reader.read_to_end(&mut buf)?;
+
+				plist::from_reader(io::Cursor::new(buf))?
+			}
+			Format::FromExtension => unreachable!(),
+			#[cfg(feature = "custom")]
+			Format::Custom(_) => unreachable!(),
+		})
+	}
 }
 
 /// Represents the location of a config file.
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub enum Location {
 	/// Automatically determines location of config file based on platform/OS.
+	///
+	/// On Android, the files directory of the app must first be given with [set_android_files_dir]. On iOS,
+	/// config files are kept in `Library/Preferences` within the sandbox of the app.
 	#[default]
 	Auto,
 
@@ -266,355 +755,8783 @@ pub enum Location {
 
 	/// Automatically determines config file name, with directory specified manually.
 	Dir(PathBuf),
+
+	/// Like [Location::Auto], but within the system data directory, for data such as window layouts.
+	AutoData,
+
+	/// Like [Location::Auto], but within the system cache directory, for data which can be recreated.
+	AutoCache,
+
+	/// Like [Location::Auto], but within the system state directory, for data such as recently used files.
+	///
+	/// Only Linux, Android and iOS have a state directory, so the config file can't be located on other
+	/// platforms.
+	AutoState,
+
+	/// Reads the location from the named environment variable, falling back to [Location::Auto] when it is
+	/// unset or empty.
+	///
+	/// A variable naming an existing directory is used like [Location::Dir], and any other is used like
+	/// [Location::Path].
+	EnvVar(String),
+
+	/// Automatically determines config file name, within the directory for the app in the system-wide config
+	/// directory.
+	///
+	/// This is `/etc` on Unix and `%ProgramData%` on Windows, for settings shared by every user of the machine,
+	/// such as those of daemons. Saving usually needs administrator rights, and fails with
+	/// [Error::PermissionDenied] otherwise.
+	System,
+
+	/// Stores the config in the macOS defaults system, as a native app would, for the app with the given
+	/// bundle identifier, such as `com.example.MyApp`. Enabled with the plist feature.
+	///
+	/// On macOS, the config is stored in `~/Library/Preferences/<bundle id>.plist` using [Format::Plist],
+	/// regardless of the format given. The file is read and written directly rather than through
+	/// `CFPreferences`, so compression and encryption should be disabled. Other platforms use
+	/// [Location::Auto] instead, along with the format given.
+	#[cfg(feature = "plist")]
+	UserDefaults(String),
+
+	/// Searches the given locations in order, loading the config file from the first one where it exists.
+	///
+	/// Configs are always saved to the first location, which is also used for deleting the config file. The
+	/// format is the same for every location, so with [Format::FromExtension] it is inferred from the first
+	/// location. An empty list is the same as [Location::Auto].
+	Search(Vec<Location>),
+
+	/// Like [Location::Search], but config files which fail to parse are skipped, loading the first one which
+	/// parses.
+	///
+	/// This suits a user config file which falls back to a system default, such as
+	/// `Location::Fallback(vec![Location::Auto, Location::System])`, until the user customises it. Configs are
+	/// always saved to the first location, so the system default is never overwritten. If none of the config
+	/// files parse, the error of the first one is returned.
+	Fallback(Vec<Location>),
+
+	/// Uses a different location on each platform, such as to keep a dotfile on Linux for backwards
+	/// compatibility while following the conventions of Windows and macOS.
+	Platform(Box<PlatformLocations>),
 }
 
-/// Represents an Abserde app, specifying how app settings are to be managed.
-#[derive(Debug, PartialEq, Clone)]
-pub struct Abserde {
-	/// App name under which app settings are typically to be stored.
-	pub app: String,
+/// Locations of the config file on each platform, used with [Location::Platform].
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	location: Location::Platform(Box::new(PlatformLocations {
+/// 		linux: Some(Location::Path(dirs::home_dir().unwrap().join(".myapp"))),
+/// 		..Default::default()
+/// 	})),
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlatformLocations {
+	/// Location on Windows.
+	pub windows: Option<Location>,
 
-	/// Location specification for where app settings are physically kept.
-	pub location: Location,
+	/// Location on macOS.
+	pub macos: Option<Location>,
 
-	/// Format for app setting storage and serialisation.
-	pub format: Format,
+	/// Location on Linux.
+	pub linux: Option<Location>,
+
+	/// Location on other platforms, and those without a location of their own. Defaults to [Location::Auto].
+	pub fallback: Location,
 }
 
-impl Abserde {
-	fn config_path(&self) -> Result<PathBuf> {
-		let system_config_dir = dirs::config_dir()
-			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, MSG_NO_SYSTEM_CONFIG_DIR))?;
-
-		Ok(match &self.location {
-			Location::Auto => system_config_dir
-				.join(&self.app)
-				.join(&self.format.default_name()),
-			Location::Path(path) => path.clone(),
-			Location::Dir(dir) => dir.join(&self.format.default_name()),
-			Location::File(file) => system_config_dir.join(&self.app).join(file),
-		})
+impl PlatformLocations {
+	/// Return the location on the platform the app was built for.
+	pub fn current(&self) -> &Location {
+		let location = if cfg!(windows) {
+			&self.windows
+		} else if cfg!(target_os = "macos") {
+			&self.macos
+		} else if cfg!(target_os = "linux") {
+			&self.linux
+		} else {
+			&None
+		};
+
+		location.as_ref().unwrap_or(&self.fallback)
 	}
+}
 
-	/// Delete settings file related to this app.
-	pub fn delete(&self) -> Result<()> {
-		let config_path = self.config_path()?;
+/// Storage backend holding configs, which are stored in config files by default.
+///
+/// Configs are located the same way with every backend, so named configs and profiles work as usual. Storage
+/// backends other than [Storage::File] are enabled with features.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// # #[cfg(feature = "sqlite")]
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	storage: Storage::Sqlite,
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum Storage {
+	/// Each config is stored in a config file of its own.
+	#[default]
+	File,
 
-		remove_file(&config_path)?;
+	/// Configs are stored in a single SQLite database, `config.sqlite`, in the directory of the config file,
+	/// keyed by the name of the config file. Enabled with the sqlite feature.
+	///
+	/// Each save is a single transaction, so saves are atomic and frequent small saves are cheap, and one
+	/// database can hold several named configs. Backups aren't kept, and the database isn't watched for changes.
+	#[cfg(feature = "sqlite")]
+	Sqlite,
 
-		match &self.location {
-			// Don't attempt to delete folder if manually specifying folder.
-			Location::Dir(_) => {}
-			// Attempt to delete parent folder if it is empty.
-			_ => {
-				let config_dir = config_path.parent().ok_or_else(|| {
-					io::Error::new(io::ErrorKind::NotFound, MSG_NO_SYSTEM_CONFIG_DIR)
-				})?;
+	/// Configs are kept in memory, shared by the whole process, keyed by the path of the config file within the
+	/// app's directory, such as `MyApp/config.json`.
+	///
+	/// Nothing is written to the file system, so tests are hermetic, and apps can run where there's no writable
+	/// file system. Configs are lost when the process exits. Backups aren't kept, and config files can't be
+	/// listed or watched.
+	Memory,
 
-				// Ignore any errors here, as they are sometimes expected.
-				_ = remove_dir(config_dir);
-			}
+	/// Configs are stored in the browser's `localStorage`, keyed by the path of the config file within the app's
+	/// directory, such as `MyApp/config.json`. Only on wasm32 targets.
+	///
+	/// Config files can't be located in the browser, so this is needed to load and save configs in web builds.
+	/// As localStorage only holds strings, configs must be serialised as text, without compression or
+	/// encryption. Backups aren't kept, and config files can't be listed.
+	#[cfg(target_arch = "wasm32")]
+	LocalStorage,
+
+	/// Config files are stored in the browser's Origin Private File System, at the path they would have within
+	/// the app's directory, such as `MyApp/config.json`. Only on wasm32 targets, with the async feature.
+	///
+	/// Unlike localStorage, the private file system holds large and binary configs. Browsers only allow it to
+	/// be accessed asynchronously, so configs must be loaded and saved with [AsyncConfig], and other operations
+	/// fail with [Error::Unsupported]. Backups aren't kept.
+	#[cfg(all(target_arch = "wasm32", feature = "async"))]
+	Opfs,
+
+	/// Configs are loaded from and saved to a remote server over HTTP, as described by the [Remote], so that
+	/// thin clients can keep their settings in one place. Enabled with the http feature.
+	///
+	/// The config file, at its usual location, caches the config for when the server can't be reached, unless
+	/// [Remote::cache] is disabled. Backups aren't kept, and the server isn't watched for changes.
+	#[cfg(feature = "http")]
+	Http(Remote),
+
+	/// Configs are stored as objects in an S3-compatible bucket, as described by the [Bucket], so that a fleet
+	/// of servers can share durable settings without a shared file system. Enabled with the s3 feature.
+	///
+	/// Objects are keyed by the path of the config file within the app's directory, such as
+	/// `MyApp/config.json`, after the prefix of the bucket. Backups aren't kept, and the bucket isn't watched
+	/// for changes.
+	#[cfg(feature = "s3")]
+	S3(Bucket),
+}
+
+impl Storage {
+	// Whether configs are stored under keys, such as `MyApp/config.json`, rather than at a location in the file
+	// system.
+	fn is_keyed(&self) -> bool {
+		match self {
+			Storage::File => false,
+			#[cfg(feature = "sqlite")]
+			Storage::Sqlite => false,
+			Storage::Memory => true,
+			#[cfg(target_arch = "wasm32")]
+			Storage::LocalStorage => true,
+			#[cfg(all(target_arch = "wasm32", feature = "async"))]
+			Storage::Opfs => true,
+			#[cfg(feature = "http")]
+			Storage::Http(_) => false,
+			#[cfg(feature = "s3")]
+			Storage::S3(_) => true,
 		}
+	}
+}
 
-		Ok(())
+/// Encryption of config files at rest.
+///
+/// Encryption is applied to the serialised config, so it can be combined with any [Format].
+/// Encryption methods other than [Encryption::None] are enabled with the encryption feature.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// # #[cfg(feature = "encryption")]
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	encryption: Encryption::Passphrase(KeySource::Env("MYAPP_PASSPHRASE".to_string())),
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum Encryption {
+	/// Config files are stored unencrypted.
+	#[default]
+	None,
+
+	/// AES-256-GCM encryption, with the key derived from a passphrase using Argon2.
+	#[cfg(feature = "encryption")]
+	Passphrase(KeySource),
+
+	/// Only values wrapped in [Secret] are encrypted, using AES-256-GCM with the key derived from a passphrase.
+	///
+	/// The rest of the config file is stored unencrypted, so it remains readable and can be edited by hand.
+	#[cfg(feature = "encryption")]
+	Fields(KeySource),
+
+	/// Encryption using [age](https://age-encryption.org), enabled with the age feature.
+	///
+	/// Config files are encrypted to public keys, and decrypted with a private key from an identity file,
+	/// as generated by `age-keygen`. This allows configs to be stored in shared places like dotfile repos.
+	#[cfg(feature = "age")]
+	Age {
+		/// Public keys of the recipients able to decrypt config files, such as `age1...`.
+		recipients: Vec<String>,
+
+		/// Path to an identity file, containing a private key matching one of the recipients.
+		identity: PathBuf,
+	},
+}
+
+impl Encryption {
+	// Whether whole config files are encrypted, rather than being stored as plain text.
+	fn encrypts_files(&self) -> bool {
+		match self {
+			Encryption::None => false,
+			#[cfg(feature = "encryption")]
+			Encryption::Passphrase(_) => true,
+			#[cfg(feature = "encryption")]
+			Encryption::Fields(_) => false,
+			#[cfg(feature = "age")]
+			Encryption::Age { .. } => true,
+		}
+	}
+
+	// Encrypt serialised config data.
+	fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+		match self {
+			Encryption::None => Ok(data.to_vec()),
+			#[cfg(feature = "encryption")]
+			Encryption::Fields(_) => Ok(data.to_vec()),
+			#[cfg(feature = "encryption")]
+			Encryption::Passphrase(key_source) => crypto::encrypt(data, key_source),
+			#[cfg(feature = "age")]
+			Encryption::Age { recipients, .. } => crypto::age_encrypt(data, recipients),
+		}
+	}
+
+	// Decrypt config data read from a config file.
+	fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+		match self {
+			Encryption::None => Ok(data.to_vec()),
+			#[cfg(feature = "encryption")]
+			Encryption::Fields(_) => Ok(data.to_vec()),
+			#[cfg(feature = "encryption")]
+			Encryption::Passphrase(key_source) => crypto::decrypt(data, key_source),
+			#[cfg(feature = "age")]
+			Encryption::Age { identity, .. } => crypto::age_decrypt(data, identity),
+		}
 	}
 }
 
-impl Default for Abserde {
-	fn default() -> Self {
-		Self {
-			app: var("CARGO_PKG_NAME").unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string()),
-			location: Default::default(),
-			format: Default::default(),
+/// Signing of config files, so changes made to them other than by saving the config, such as by hand, are
+/// detected when loading.
+///
+/// The signature is computed over the config file as it would otherwise be saved, so signing can be combined with
+/// any [Format], [Encryption] and [Compression]. Config files which were changed, or aren't signed, fail to load
+/// with an [Error::SignatureMismatch]. Signing methods other than [Signing::None] are enabled with the signing
+/// feature.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// # #[cfg(feature = "signing")]
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	signing: Signing::Hmac(KeySource::File("/etc/myapp/signing.key".into())),
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum Signing {
+	/// Config files are stored unsigned.
+	#[default]
+	None,
+
+	/// HMAC-SHA256 signature, keyed with a secret key kept from users who mustn't change the config.
+	#[cfg(feature = "signing")]
+	Hmac(KeySource),
+}
+
+impl Signing {
+	// Sign serialised config data.
+	fn sign(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+		match self {
+			Signing::None => Ok(data),
+			#[cfg(feature = "signing")]
+			Signing::Hmac(key_source) => crypto::sign(&data, key_source),
+		}
+	}
+
+	// Verify the signature of config data read from a config file, returning the data without it.
+	fn verify<'a>(&self, data: &'a [u8]) -> Result<&'a [u8]> {
+		match self {
+			Signing::None => Ok(data),
+			#[cfg(feature = "signing")]
+			Signing::Hmac(key_source) => crypto::verify(data, key_source),
 		}
 	}
 }
 
-/// Trait that apps can implement to store app settings.
+/// Compression of config files.
 ///
-/// Implementing types must also implement [serde::Serialize] and [serde::Deserialize] traits.
-pub trait Config {
-	/// Type of implementation.
-	type T;
+/// Compression is applied to the serialised config before any encryption, so it can be combined with any
+/// [Format] and [Encryption]. Compression methods other than [Compression::None] are enabled with features.
+/// When the config file name is determined automatically, an extension such as `.gz` is added for the compression.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// # #[cfg(feature = "gzip")]
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	compression: Compression::Gzip,
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum Compression {
+	/// Config files are stored uncompressed.
+	#[default]
+	None,
 
-	/// Load a config from disk into the implementing type.
-	fn load_config(abserde: &Abserde) -> Result<Self::T>;
+	/// Gzip compression, enabled with the gzip feature.
+	#[cfg(feature = "gzip")]
+	Gzip,
 
-	/// Save a config from the implementing type to disk.
-	fn save_config(&self, abserde: &Abserde) -> Result<()>;
+	/// Zstandard compression at the given level, enabled with the zstd feature.
+	///
+	/// Levels range from 1 to 22, with higher levels compressing better but more slowly.
+	/// Level 0 uses the default level of 3.
+	#[cfg(feature = "zstd")]
+	Zstd(i32),
 }
 
-impl<T> Config for T
-where
-	T: Serialize,
-	T: DeserializeOwned,
-{
-	type T = T;
+impl Compression {
+	/// Return the extension added to default config file names for this compression, if any.
+	pub fn extension(&self) -> Option<&'static str> {
+		match self {
+			Compression::None => None,
+			#[cfg(feature = "gzip")]
+			Compression::Gzip => Some("gz"),
+			#[cfg(feature = "zstd")]
+			Compression::Zstd(_) => Some("zst"),
+		}
+	}
 
-	fn load_config(abserde: &Abserde) -> Result<Self::T> {
-		let config_path = abserde.config_path()?;
+	// Compress serialised config data.
+	fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+		match self {
+			Compression::None => Ok(data.to_vec()),
+			#[cfg(feature = "gzip")]
+			Compression::Gzip => {
+				use io::Write;
 
-		Ok(match abserde.format {
-			#[cfg(feature = "json")]
-			Format::Json | Format::PrettyJson(_) => {
-				let file = File::open(config_path)?;
+				let mut encoder =
+					flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
 
-				serde_json::from_reader(io::BufReader::new(file))?
-			}
-			#[cfg(feature = "yaml")]
-			Format::Yaml => {
-				let file = File::open(config_path)?;
+				encoder.write_all(data)?;
 
-				serde_yaml::from_reader(io::BufReader::new(file))?
+				Ok(encoder.finish()?)
 			}
-			#[cfg(feature = "pickle")]
-			Format::Pickle => {
-				let file = File::open(config_path)?;
+			#[cfg(feature = "zstd")]
+			Compression::Zstd(level) => Ok(zstd::encode_all(data, *level)?),
+		}
+	}
 
-				serde_pickle::from_reader(io::BufReader::new(file), serde_pickle::DeOptions::new())?
-			}
-			#[cfg(feature = "ini")]
-			Format::Ini => {
-				let file = File::open(config_path)?;
+	// Decompress config data read from a config file.
+	fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+		match self {
+			Compression::None => Ok(data.to_vec()),
+			#[cfg(feature = "gzip")]
+			Compression::Gzip => {
+				use io::Read;
+
+				let mut buf = Vec::new();
+
+				flate2::read::GzDecoder::new(data)
+					.read_to_end(&mut buf)
+					.map_err(invalid_data)?;
+
+				Ok(buf)
+			}
+			#[cfg(feature = "zstd")]
+			Compression::Zstd(_) => Ok(zstd::decode_all(data).map_err(invalid_data)?),
+		}
+	}
+}
+
+// Keys available to fields of a config being serialised or deserialised, until dropped.
+pub(crate) struct FieldKeys {
+	#[cfg(feature = "keyring")]
+	_keychain: keychain::KeychainScope,
+	#[cfg(feature = "encryption")]
+	_secret_key: Option<crypto::SecretKeyScope>,
+}
+
+/// Options for deleting a config file with [Abserde::delete_with].
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeleteMode {
+	/// Also delete the directory holding the config file if it is left empty.
+	///
+	/// Directories given with [Location::Dir] are never deleted. Enabled by default.
+	pub remove_parent: bool,
+
+	/// Also delete any backups of the config file. Disabled by default.
+	pub remove_backups: bool,
+
+	/// Succeed when there is no config file to delete. Disabled by default.
+	pub ignore_missing: bool,
+
+	/// Move deleted files to the system trash, so they can be recovered, instead of deleting them permanently.
+	///
+	/// Disabled by default. Enabled with the trash feature.
+	#[cfg(feature = "trash")]
+	pub trash: bool,
+}
+
+impl Default for DeleteMode {
+	fn default() -> Self {
+		Self {
+			remove_parent: true,
+			remove_backups: false,
+			ignore_missing: false,
+			#[cfg(feature = "trash")]
+			trash: false,
+		}
+	}
+}
+
+/// Config file found in the directory of the config file, as listed by [Abserde::list_configs].
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConfigFile {
+	/// File name of the config file, such as `keybindings.json`.
+	pub name: String,
+
+	/// Path of the config file.
+	pub path: PathBuf,
+
+	/// Format of the config file, as given by its extension.
+	pub format: Format,
+
+	/// Size of the config file in bytes.
+	pub size: u64,
+
+	/// When the config file was last modified, if the platform records it.
+	pub modified: Option<SystemTime>,
+}
+
+/// Represents an Abserde app, specifying how app settings are to be managed.
+///
+/// An Abserde can itself be serialised, such as to describe where configs are stored in a file, or to send
+/// it to another process. Fields missing when deserialising take their default values. Secrets given
+/// directly, such as passphrases, are serialised along with it, so prefer reading them from the environment
+/// or a file when an Abserde is serialised.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Abserde {
+	/// App name under which app settings are typically to be stored.
+	///
+	/// Defaults to the `CARGO_PKG_NAME` environment variable at runtime, or otherwise `abserde`. Use
+	/// [for_crate!] or [Abserde::for_app] to name it after your crate instead.
+	pub app: String,
+
+	/// Reverse domain name of the organisation behind the app, such as `com` or `org.example`.
+	///
+	/// When a qualifier or organisation is given, the app's directory follows the conventions of the platform,
+	/// as with the directories crate: `com.Example-Corp.MyApp` on macOS, `Example Corp\MyApp` on Windows and
+	/// just `MyApp` elsewhere. Disabled by default.
+	pub qualifier: Option<String>,
+
+	/// Name of the organisation behind the app, such as `Example Corp`. See [Abserde::qualifier].
+	pub organization: Option<String>,
+
+	/// Name of the config, used in place of `config` in the default file name, such as `keybindings.json`.
+	///
+	/// This lets an app keep several configs in its directory. Ignored when the config file name is given with
+	/// [Location::Path] or [Location::File]. Disabled by default.
+	pub name: Option<String>,
+
+	/// Extension of the config file, used in place of the extension of its format in the default file name,
+	/// such as `conf` for `myapp.conf`.
+	///
+	/// With [Format::FromExtension], the format is inferred from this extension. An empty extension leaves the
+	/// file name without one. Ignored when the config file name is given with [Location::Path] or
+	/// [Location::File]. Disabled by default.
+	pub extension: Option<String>,
+
+	/// Top-level keys of the config kept in section files of their own, next to the config file, mapped to the
+	/// names of their files, used in place of `config` in their file names, such as `keybindings.toml`.
+	///
+	/// This lets users share some sections of a config, such as keybindings, without sharing the rest, such as
+	/// accounts. Configs are split into their section files when saved and reassembled when loaded, through
+	/// [serde_json::Value], so they must be maps. Section files are deleted along with the config file, and
+	/// keys without a section file are left to the config type. Disabled by default.
+	pub section_files: BTreeMap<String, String>,
+
+	/// Top-level key of the config file under which the config is kept, so several configs of different types
+	/// can share a single config file, such as `[ui]` and `[network]` in a TOML config file.
+	///
+	/// Saving a config rewrites the config file with only the value of its key replaced, keeping those of the
+	/// other configs, and deleting it removes just its key, deleting the config file once no other configs are
+	/// left in it. A config whose key is missing isn't found, as when there's no config file. Config files are
+	/// read and written through [serde_json::Value], so they must be maps, and configs can't hold 128-bit
+	/// integers. Concurrent saves of configs sharing a file should be serialised by the app, as each reads the
+	/// file before writing it. Disabled by default.
+	pub namespace: Option<String>,
+
+	/// Location of a policy file set by administrators of managed machines, such as [Location::System], whose
+	/// keys override those of the config file and are locked, so users can't change them.
+	///
+	/// Loading the config merges the policy file on top of the config file, key by key at any depth. Saving a
+	/// config holding other values for locked keys fails with an [Error::LockedKeys], or reverts them, as given
+	/// by [Abserde::policy_conflicts], and apps can grey out the keys returned by [Abserde::locked_keys]. The
+	/// policy file is read with the other options of the Abserde, such as its format, and a missing policy file
+	/// locks no keys. Configs should be loaded before being saved, so they hold the values of the policy.
+	/// Disabled by default.
+	pub policy: Option<Location>,
+
+	/// What happens when a config being saved holds other values for keys locked by [Abserde::policy]. Defaults
+	/// to [PolicyConflict::Reject].
+	pub policy_conflicts: PolicyConflict,
+
+	/// Profile of the config, such as `work`, which is kept in a directory of its own within the config
+	/// directory.
+	///
+	/// This lets users keep separate configs for each of their workspaces. Ignored when the config file is
+	/// located with [Location::Path]. Disabled by default.
+	pub profile: Option<String>,
+
+	/// Location specification for where app settings are physically kept.
+	pub location: Location,
+
+	/// Whether to prefer the system directories of the host over those of a Flatpak or Snap sandbox the app
+	/// runs in, such as `~/.config` over `~/.var/app/<app id>/config`.
+	///
+	/// This lets an app find the same config files however it was packaged. Directories of the host are only
+	/// used when the sandbox has been given access to them, such as with Flatpak's `--filesystem=xdg-config`,
+	/// and otherwise the directories of the sandbox are used. See [Environment] to detect sandboxes. Disabled by
+	/// default.
+	pub host_paths: bool,
+
+	/// Format for app setting storage and serialisation.
+	pub format: Format,
+
+	/// Line endings written to config files in text formats. Defaults to [LineEnding::Lf].
+	pub line_ending: LineEnding,
+
+	/// Sort the keys of maps and the fields of structs when saving config files in text formats, so the
+	/// config file only changes when the config does.
+	///
+	/// The keys of maps such as a `HashMap` are otherwise saved in a different order each time, making noisy
+	/// diffs of configs kept in a dotfile repository. TOML tables still follow plain values, as TOML requires.
+	/// Configs are converted to JSON values to sort them, so keys of maps are saved as strings, and values
+	/// serialised specially by a format, such as TOML datetimes, aren't supported. Disabled by default.
+	pub sort_keys: bool,
+
+	/// Keep the comments and anchors users write in YAML config files when saving, by patching changed values
+	/// into the existing config file rather than rewriting it.
+	///
+	/// This is best-effort: only top-level keys holding scalar values on a single line can be patched. When
+	/// anything else changed, the config file is rewritten as usual, losing its comments. Disabled by default.
+	pub preserve_comments: bool,
+
+	/// Storage backend holding the config, which is a config file by default.
+	pub storage: Storage,
+
+	/// Write config files atomically, by writing to a temporary file which then replaces the config file.
+	///
+	/// This prevents a crash or power loss during a save from leaving a truncated config behind.
+	/// Enabled by default.
+	pub atomic: bool,
+
+	/// Flush config files to disk before a save returns, including the parent directory on Unix.
+	///
+	/// This guarantees the last saved config survives a power loss, at the cost of slower saves.
+	/// Disabled by default.
+	pub sync: bool,
+
+	/// Read each config back as soon as it is saved, failing with an [Error::RoundTrip] if it doesn't load as it
+	/// was saved, such as when the config format rounds floats or can't hold a type.
+	///
+	/// This catches lossy config formats when saving, rather than the next time the config is loaded. The config
+	/// file is left as it was written. Configs with encrypted fields can't be verified. Disabled by default.
+	pub verify_saves: bool,
+
+	/// How config files which are symbolic links, such as those put in place by dotfile managers, are saved
+	/// and deleted. Defaults to [SymlinkPolicy::Follow].
+	pub symlinks: SymlinkPolicy,
+
+	/// Permissions given to config files and their directory when saving, such as to keep secrets private.
+	/// Defaults to [FilePermissions::Default].
+	pub permissions: FilePermissions,
+
+	/// Refuse to load config files which other users can read or write, or which are owned by another user,
+	/// failing with an [Error::InsecurePermissions], as ssh does for key files. Only on Unix.
+	///
+	/// Use [Abserde::check_permissions] instead to warn about insecure config files while still loading them.
+	/// Disabled by default.
+	pub audit_permissions: bool,
+
+	/// Number of previous config files to keep as backups, rotated on each save.
+	///
+	/// Backups are stored next to the config file, with `.bak.1` being the most recent.
+	/// Disabled by default.
+	pub backups: usize,
+
+	/// Number of saves appended to a write-ahead journal before it is compacted into the config file.
+	///
+	/// Each save appends the config to a journal next to the config file, with a `.journal` extension, and
+	/// flushes it to disk, which is cheaper than rewriting the config file. Once the journal holds this many
+	/// saves, the last one is written to the config file and the journal is removed, so saves are never lost or
+	/// torn, even by a power loss. The journal is read in place of the config file when loading, and backups are
+	/// only rotated when compacting. Use [Abserde::compact_journal] before disabling it. Only for config files.
+	/// Disabled by default.
+	pub journal: usize,
+
+	/// Hold an advisory lock on the config file while loading, saving or deleting it, so other processes doing
+	/// the same never see it part way through being saved.
+	///
+	/// The lock is held on a lock file next to the config file, with a `.lock` extension, which is shared by
+	/// loads and held exclusively by saves and deletes. Waits for other processes to release the lock, for up to
+	/// [Abserde::lock_timeout]. Only for config files. Disabled by default.
+	pub lock: bool,
+
+	/// Longest time to wait for other processes to release the lock on the config file, before failing with an
+	/// [Error::Locked], when [Abserde::lock] is enabled. A timeout of zero fails straight away. See also
+	/// [Config::try_load_config] and [Config::load_config_timeout]. Waits indefinitely by default.
+	pub lock_timeout: Option<Duration>,
+
+	/// Encryption applied to config files, including their backups.
+	pub encryption: Encryption,
+
+	/// Compression applied to config files, including their backups.
+	pub compression: Compression,
+
+	/// Signing of config files, including their backups, which are verified when loaded.
+	pub signing: Signing,
+
+	/// Save configs in an envelope recording this schema version, along with the version of abserde.
+	///
+	/// The envelope is removed again when loading, so config types don't need to hold version metadata.
+	/// Config files without an envelope can still be loaded. When migrating configs, the schema version in
+	/// the envelope is used as the version of the config file. Disabled by default.
+	pub schema_version: Option<u64>,
+
+	/// Default config document, in the format of the config file, such as one embedded with `include_str!`.
+	///
+	/// The defaults are loaded when there is no config file, and config files are deep merged over them, so
+	/// fields missing from a config file take their default values. Backups don't fall back to the defaults.
+	/// Disabled by default. Not serialised along with the Abserde, as it's embedded in the binary.
+	#[serde(skip)]
+	pub embedded_defaults: Option<&'static str>,
+
+	/// Key of configs listing other config files they include, such as `include`, whose configs are deep
+	/// merged into them when loaded.
+	///
+	/// The key holds a path or a list of paths, relative to the including config file, and is removed before
+	/// the config is deserialised. Later includes override earlier ones, and the including config overrides
+	/// them all. Included config files can include others in turn, and an [Error::IncludeCycle] is returned if
+	/// a config file includes itself. Each included config file is read in the format given by its extension,
+	/// or otherwise in the format of the config file. Configs are merged through [serde_json::Value]. Configs
+	/// can't be saved while this is set, as the included configs would be merged into the config file, and the
+	/// key dropped from it, so an [Error::Unsupported] is returned instead. Disabled by default.
+	pub include_key: Option<String>,
+
+	/// Expand environment variables in the string values of configs as they're loaded, such as `${HOME}` or
+	/// `%APPDATA%`, so users can write portable paths in their config files.
+	///
+	/// `$$` and `%%` stand for a literal `$` and `%`, and an [Error::UndefinedVariable] is returned for
+	/// variables which aren't set. Configs are expanded through [serde_json::Value]. Configs can't be saved
+	/// while this is enabled, as the variables would be replaced by their values, so an [Error::Unsupported]
+	/// is returned instead, including when setting values or applying patches. Disabled by default.
+	pub interpolate: bool,
+
+	/// Capture saved configs in memory, in place of storing them, for a dry run.
+	///
+	/// Configs are serialised, compressed and encrypted as usual, then recorded in the [Capture], where they
+	/// can be retrieved afterwards, while the stored config is left as it was. Configs are still loaded from
+	/// storage, and deletes aren't affected. Disabled by default. Not serialised along with the Abserde, as
+	/// captures are kept in memory.
+	#[serde(skip)]
+	pub capture: Option<Capture>,
+
+	/// Record each save and delete of the config in an append-only audit log, with the time, the process and
+	/// user making it, and the keys which changed, so it can be told who changed a setting and when.
+	///
+	/// The audit log is kept in the config directory, in `audit.jsonl`, as JSON lines, and can be read back
+	/// with [Abserde::audit_log]. The config file is read before and after each save to find what changed.
+	/// Values are left out for config files which are encrypted. Only for config files. Disabled by default.
+	pub audit: bool,
+
+	/// Keep the config directory as a git repository, committing the config file whenever it is saved or
+	/// deleted, so its history can be listed with [Abserde::history] and restored with [Abserde::checkout].
+	///
+	/// The repository is created in the config directory of the app if needed, or in the directory of a config
+	/// file given by path, and can be pushed elsewhere to sync configs between machines. Only the config file
+	/// itself is committed. Disabled by default. Enabled with the git feature.
+	#[cfg(feature = "git")]
+	pub git: bool,
+}
+
+impl Abserde {
+	// Default file name of the config file, including any extension for its compression.
+	fn default_name(&self) -> Result<String> {
+		let name = self.name.as_deref().unwrap_or("config");
+		let file_name = match self.extension.as_deref() {
+			Some("") => name.to_string(),
+			Some(extension) => format!("{}.{}", name, extension),
+			None if self.format == Format::FromExtension => {
+				return Err(Error::Unsupported(
+					"config file name or extension must be given to infer the format from its extension"
+						.to_string(),
+				));
+			}
+			None => self.format.file_name(name),
+		};
+
+		Ok(match self.compression.extension() {
+			Some(extension) => format!("{}.{}", file_name, extension),
+			None => file_name,
+		})
+	}
+
+	// Format of the config file, inferring it from the file name if needed.
+	fn file_format(&self) -> Result<Format> {
+		#[cfg(feature = "plist")]
+		if let (Location::UserDefaults(_), true) =
+			(self.platform_location(), cfg!(target_os = "macos"))
+		{
+			return Ok(Format::Plist);
+		}
+
+		if self.format != Format::FromExtension {
+			return Ok(self.format.clone());
+		}
+
+		let config_path = self.config_path()?;
+
+		self.format_of_name(&config_path).ok_or_else(|| {
+			Error::Unsupported(format!(
+				"can't infer config format from file name {}",
+				config_path.display()
+			))
+		})
+	}
+
+	// Format given by the extension of a config file name, if it has one.
+	fn format_of_name(&self, path: &Path) -> Option<Format> {
+		let mut file_name = Path::new(path.file_name()?);
+
+		// Look past the extension added for compression, such as in config.toml.gz.
+		if file_name.extension().is_some()
+			&& file_name.extension() == self.compression.extension().map(OsStr::new)
+		{
+			file_name = Path::new(file_name.file_stem().unwrap_or_default());
+		}
+
+		Format::from_extension(&file_name.extension()?.to_string_lossy())
+	}
+
+	// Config directory for the app within the system directory for its location, such as the system config
+	// directory.
+	fn app_config_dir(&self) -> Result<PathBuf> {
+		if cfg!(target_arch = "wasm32") {
+			return Err(Error::Unsupported(
+				"config files can't be located in the browser, so configs must be kept in localStorage"
+					.to_string(),
+			));
+		}
+
+		let system_dir = match self.resolved_location() {
+			Location::AutoData => self.user_dir(sandbox::UserDir::Data),
+			Location::AutoCache => self.user_dir(sandbox::UserDir::Cache),
+			Location::AutoState => self.user_dir(sandbox::UserDir::State),
+			Location::System => system_config_dir(),
+			_ => self.user_dir(sandbox::UserDir::Config),
+		};
+
+		Ok(system_dir
+			.ok_or(Error::NoSystemConfigDir)?
+			.join(self.app_dir_name()))
+	}
+
+	// Name of the app's directory within a system directory, following the conventions of the platform when a
+	// qualifier or organisation is given.
+	fn app_dir_name(&self) -> PathBuf {
+		if self.qualifier.is_none() && self.organization.is_none() {
+			return PathBuf::from(&self.app);
+		}
+
+		if cfg!(target_os = "macos") {
+			[
+				self.qualifier.as_deref(),
+				self.organization.as_deref(),
+				Some(&self.app),
+			]
+			.into_iter()
+			.flatten()
+			.filter(|part| !part.is_empty())
+			.map(|part| part.replace(' ', "-"))
+			.collect::<Vec<_>>()
+			.join(".")
+			.into()
+		} else if cfg!(windows) {
+			match &self.organization {
+				Some(organization) if !organization.is_empty() => {
+					Path::new(organization).join(&self.app)
+				}
+				_ => PathBuf::from(&self.app),
+			}
+		} else {
+			PathBuf::from(&self.app)
+		}
+	}
+
+	// Location of the config file, replacing a location read from an environment variable with the location it
+	// gives.
+	fn resolved_location(&self) -> Location {
+		match self.platform_location() {
+			Location::EnvVar(name) => match var_os(name).map(PathBuf::from) {
+				Some(path) if path.is_dir() => Location::Dir(path),
+				Some(path) if !path.as_os_str().is_empty() => Location::Path(path),
+				_ => Location::Auto,
+			},
+			#[cfg(feature = "plist")]
+			Location::UserDefaults(bundle_id) => match dirs::preference_dir() {
+				Some(dir) if cfg!(target_os = "macos") => {
+					Location::Path(dir.join(format!("{}.plist", bundle_id)))
+				}
+				_ => Location::Auto,
+			},
+			Location::Search(locations) | Location::Fallback(locations) => {
+				match locations.first() {
+					Some(location) => self.at(location).resolved_location(),
+					None => Location::Auto,
+				}
+			}
+			location => location.clone(),
+		}
+	}
+
+	// Location of the config file on this platform, choosing from any locations given for each platform.
+	fn platform_location(&self) -> &Location {
+		let mut location = &self.location;
+
+		while let Location::Platform(locations) = location {
+			location = locations.current();
+		}
+
+		location
+	}
+
+	// Copy of this Abserde at another location.
+	fn at(&self, location: &Location) -> Abserde {
+		Abserde {
+			location: location.clone(),
+			..self.clone()
+		}
+	}
+
+	// Path of the config file to load, which is the first of any locations being searched that holds one.
+	fn load_path(&self) -> Result<PathBuf> {
+		if let Location::Search(locations) | Location::Fallback(locations) =
+			self.platform_location()
+		{
+			let found = locations
+				.iter()
+				.filter_map(|location| self.at(location).config_path().ok())
+				.find(|config_path| config_path.exists());
+
+			if let Some(config_path) = found {
+				return Ok(config_path);
+			}
+		}
+
+		self.config_path()
+	}
+
+	fn config_path(&self) -> Result<PathBuf> {
+		Ok(match self.resolved_location() {
+			Location::Path(path) => path,
+			Location::File(file) => self.config_dir()?.join(file),
+			_ => self.config_dir()?.join(self.default_name()?),
+		})
+	}
+
+	// Directory holding the config file, which is the directory of its profile if it has one.
+	fn config_dir(&self) -> Result<PathBuf> {
+		match &self.profile {
+			Some(profile) => self.profile_dir(profile),
+			None => self.base_dir(),
+		}
+	}
+
+	// Directory holding config files without a profile, along with the directories of profiles.
+	fn base_dir(&self) -> Result<PathBuf> {
+		match self.resolved_location() {
+			Location::Path(_) => Err(Error::Unsupported(
+				"profiles can't be used with a config file path".to_string(),
+			)),
+			Location::Dir(dir) => Ok(dir),
+			_ if self.storage.is_keyed() => Ok(self.app_dir_name()),
+			_ => self.app_config_dir(),
+		}
+	}
+
+	// Directory of the given profile.
+	fn profile_dir(&self, profile: &str) -> Result<PathBuf> {
+		check_dir_name("profile", profile)?;
+
+		Ok(self.base_dir()?.join(PROFILES_DIR).join(profile))
+	}
+
+	// Paths of the config file and of the file written in its place, which differs when following a link,
+	// creating their directories.
+	fn write_paths(&self, atomic: bool) -> Result<(PathBuf, PathBuf)> {
+		let config_path = self.config_path()?;
+		let config_dir = config_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+		self.permissions
+			.create_dir_all(config_dir)
+			.map_err(Error::file(config_dir))?;
+
+		let write_path = self.symlinks.write_path(&config_path, atomic)?;
+		let write_dir = write_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+		// The directory of a link's target isn't the app's, so it isn't given the permissions of config
+		// directories.
+		if write_dir != config_dir {
+			create_dir_all(write_dir).map_err(Error::file(write_dir))?;
+		}
+
+		Ok((config_path, write_path))
+	}
+
+	// Create the config file and fill it using the given function, honouring the write options.
+	fn write_config_file<F>(&self, write: F) -> Result<()>
+	where
+		F: FnOnce(&mut File) -> Result<()>,
+	{
+		let (config_path, write_path) = self.write_paths(self.atomic)?;
+		let write_dir = write_path.parent().ok_or(Error::NoSystemConfigDir)?;
+		let write = |file: &mut File| -> Result<()> {
+			write(file)?;
+
+			if self.sync {
+				file.sync_all()?;
+			}
+
+			Ok(())
+		};
+
+		if self.atomic {
+			// The temporary file must be in the same directory for the rename to be atomic.
+			let temp_path = temp_path(&write_path);
+			let result = self
+				.permissions
+				.create_file(&temp_path)
+				.map_err(Error::file(&config_path))
+				.and_then(|mut file| write(&mut file))
+				.and_then(|_| self.rotate_backups(&config_path))
+				.and_then(|_| rename(&temp_path, &write_path).map_err(Error::file(&config_path)));
+
+			if result.is_err() {
+				// Ignore any errors here, as the original error is more useful.
+				_ = remove_file(&temp_path);
+			}
+
+			result?;
+		} else {
+			self.rotate_backups(&config_path)?;
+
+			write(
+				&mut self
+					.permissions
+					.create_file(&write_path)
+					.map_err(Error::file(&config_path))?,
+			)?;
+		}
+
+		if self.sync {
+			sync_dir(write_dir)?;
+		}
+
+		#[cfg(feature = "git")]
+		if self.git {
+			self.commit(Operation::Save)?;
+		}
+
+		Ok(())
+	}
+
+	// Write a config to the config file, in an envelope if enabled.
+	fn write_config<T: Serialize>(&self, config: &T) -> Result<()> {
+		// Configs are loaded with variables expanded, so saving them would replace the variables in the config
+		// file for good.
+		if self.interpolate {
+			return Err(Error::Unsupported(
+				"configs can't be saved while environment variables are interpolated".to_string(),
+			));
+		}
+
+		// Configs are loaded merged with the config files they include, which would be merged into the config
+		// file in place of including them.
+		if self.include_key.is_some() {
+			return Err(Error::Unsupported(
+				"configs can't be saved while config files are included".to_string(),
+			));
+		}
+
+		if self.policy.is_some() {
+			return self.write_with_policy(config);
+		}
+
+		if self.audit {
+			return self.write_audited(config);
+		}
+
+		if let Some(key) = &self.namespace {
+			return self.write_namespaced(config, key);
+		}
+
+		if !self.section_files.is_empty() {
+			return self.write_sections(config);
+		}
+
+		match self.schema_version {
+			Some(schema_version) => {
+				self.write_serialized(&envelope::Envelope::new(schema_version, config))
+			}
+			None => self.write_serialized(config),
+		}
+	}
+
+	// Serialise, compress, encrypt and write a config to the config file.
+	fn write_serialized<T: Serialize>(&self, config: &T) -> Result<()> {
+		let _field_keys = self.field_keys()?;
+
+		#[cfg(feature = "yaml")]
+		if self.preserve_comments && self.file_format()? == Format::Yaml {
+			if let Some(data) = self.patch_yaml(config)? {
+				let data = Plaintext::from(data);
+
+				return self.write_data(
+					&self.signing.sign(
+						self.encryption
+							.encrypt(&Plaintext::from(self.compression.compress(&data)?))?,
+					)?,
+				);
+			}
+		}
+
+		if self.storage == Storage::File
+			&& self.capture.is_none()
+			&& self.journal == 0
+			&& !self.encryption.encrypts_files()
+			&& self.compression == Compression::None
+			&& self.signing == Signing::None
+		{
+			let format = self.file_format()?;
+
+			return self.write_config_file(|file| {
+				use io::Write;
+
+				// Serialisers make many small writes, so they're buffered rather than each being a system call.
+				let mut writer = io::BufWriter::with_capacity(WRITE_BUFFER_SIZE, file);
+
+				self.serialize_as(&format, config, &mut writer)?;
+				writer.flush()?;
+
+				#[cfg(feature = "tracing")]
+				trace::record_bytes(io::Seek::stream_position(writer.get_mut())?);
+
+				Ok(())
+			});
+		}
+
+		self.write_data(&self.serialize_config(config)?)
+	}
+
+	// Serialise, compress and encrypt a config.
+	fn serialize_config<T: Serialize>(&self, config: &T) -> Result<Vec<u8>> {
+		let _field_keys = self.field_keys()?;
+
+		let mut buf = Plaintext::default();
+		let writer: &mut Vec<u8> = &mut buf;
+
+		self.serialize_as(&self.file_format()?, config, writer)?;
+
+		self.signing.sign(
+			self.encryption
+				.encrypt(&Plaintext::from(self.compression.compress(&buf)?))?,
+		)
+	}
+
+	// Serialise a config in a format, with the keys and line endings of text formats arranged as needed.
+	fn serialize_as<T, W>(&self, format: &Format, config: &T, writer: W) -> Result<()>
+	where
+		T: Serialize,
+		W: io::Write,
+	{
+		match (format.is_text(), self.sort_keys) {
+			(true, true) => {
+				let config = sort::sorted(config)?;
+
+				// TOML tables must follow plain values, as they do when serialising TOML's own values.
+				#[cfg(feature = "toml")]
+				if *format == Format::Toml {
+					let config = toml::Value::try_from(config)
+						.map_err(|err| Error::Serialize(err.into()))?;
+
+					return self.serialize_text(format, &config, writer);
+				}
+
+				self.serialize_text(format, &config, writer)
+			}
+			(true, false) => self.serialize_text(format, config, writer),
+			(false, _) => format.serialize(config, writer),
+		}
+	}
+
+	// Serialise a config in a text format, with its line endings converted as needed.
+	fn serialize_text<T, W>(&self, format: &Format, config: &T, writer: W) -> Result<()>
+	where
+		T: Serialize,
+		W: io::Write,
+	{
+		if self.line_ending.is_crlf() {
+			format.serialize(config, line_ending::CrLfWriter::new(writer))
+		} else {
+			format.serialize(config, writer)
+		}
+	}
+
+	// Store the encoded contents of the config file in its storage.
+	fn write_data(&self, data: &[u8]) -> Result<()> {
+		#[cfg(feature = "tracing")]
+		trace::record_bytes(data.len() as u64);
+
+		if let Some(capture) = &self.capture {
+			capture.record(self.config_path()?, data);
+
+			return Ok(());
+		}
+
+		match &self.storage {
+			Storage::File if self.journal > 0 => self.append_journal(data),
+			Storage::File => self.write_config_file(|file| {
+				use io::Write;
+
+				Ok(file.write_all(data)?)
+			}),
+			#[cfg(feature = "sqlite")]
+			Storage::Sqlite => sqlite::write(&self.config_path()?, data),
+			Storage::Memory => memory::write(&self.config_path()?, data),
+			#[cfg(target_arch = "wasm32")]
+			Storage::LocalStorage => local_storage::write(&self.config_path()?, data),
+			#[cfg(all(target_arch = "wasm32", feature = "async"))]
+			Storage::Opfs => Err(Error::Unsupported(OPFS_ASYNC_ONLY.to_string())),
+			#[cfg(feature = "http")]
+			Storage::Http(remote) => http::write(remote, &self.config_path()?, data),
+			#[cfg(feature = "s3")]
+			Storage::S3(bucket) => s3::write(bucket, &self.config_path()?, data),
+		}
+	}
+
+	// Read the encoded contents of a config file, which may be the config file itself or one of its backups,
+	// from its storage.
+	fn read_data(&self, config_path: &Path) -> Result<Vec<u8>> {
+		let data =
+			match &self.storage {
+				Storage::File => {
+					if self.audit_permissions {
+						permissions::audit(config_path)?;
+					}
+
+					let journaled = match self.journal {
+						0 => None,
+						_ => journal::latest(&journal::journal_path(config_path))?,
+					};
+
+					match journaled {
+						Some(data) => Ok(data),
+						None => read(config_path).map_err(Error::file(config_path)),
+					}
+				}
+				#[cfg(feature = "sqlite")]
+				Storage::Sqlite => sqlite::read(config_path)?
+					.ok_or_else(|| Error::NotFound(config_path.to_owned())),
+				Storage::Memory => memory::read(config_path)?
+					.ok_or_else(|| Error::NotFound(config_path.to_owned())),
+				#[cfg(target_arch = "wasm32")]
+				Storage::LocalStorage => local_storage::read(config_path)?
+					.ok_or_else(|| Error::NotFound(config_path.to_owned())),
+				#[cfg(all(target_arch = "wasm32", feature = "async"))]
+				Storage::Opfs => Err(Error::Unsupported(OPFS_ASYNC_ONLY.to_string())),
+				// Backups aren't kept on the server, so only the config itself can be read.
+				#[cfg(feature = "http")]
+				Storage::Http(remote) if config_path == self.config_path()? => http::read(remote, config_path)?
+					.ok_or_else(|| Error::NotFound(remote.url.clone().into())),
+				#[cfg(feature = "http")]
+				Storage::Http(_) => Err(Error::NotFound(config_path.to_owned())),
+				#[cfg(feature = "s3")]
+				Storage::S3(bucket) => s3::read(bucket, config_path)?
+					.ok_or_else(|| Error::NotFound(config_path.to_owned())),
+			}?;
+
+		#[cfg(feature = "tracing")]
+		trace::record_bytes(data.len() as u64);
+
+		Ok(data)
+	}
+
+	// Version of a config file as it is currently stored.
+	fn stored_version(&self, config_path: &Path) -> Result<ConfigVersion> {
+		match self.read_data(config_path) {
+			Ok(data) => Ok(ConfigVersion::of_data(Some(&data))),
+			Err(Error::NotFound(_)) => Ok(ConfigVersion::of_data(None)),
+			Err(err) => Err(err),
+		}
+	}
+
+	// Read, decrypt, decompress and deserialise a config file, which may be the config file itself or one of
+	// its backups.
+	fn read_config_file<T: DeserializeOwned>(&self, config_path: &Path) -> Result<T> {
+		Ok(self.read_config_file_versioned(config_path)?.0)
+	}
+
+	// Read a config file, also returning the schema version from its envelope, if it has one.
+	// A missing config file is read from the embedded defaults instead, if there are any.
+	fn read_config_file_versioned<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+	) -> Result<(T, Option<u64>)> {
+		if self.policy.is_some() {
+			return self.read_with_policy(config_path);
+		}
+
+		if let Some(key) = &self.namespace {
+			return self.read_namespaced(config_path, key);
+		}
+
+		if !self.section_files.is_empty() {
+			return self.read_sections(config_path);
+		}
+
+		if self.interpolate {
+			return self.read_interpolated(config_path);
+		}
+
+		if self.include_key.is_some() {
+			return self.read_includes(config_path);
+		}
+
+		let result = match self.platform_location() {
+			Location::Fallback(locations) => self.read_fallback(config_path, locations),
+			_ => self.read_file(config_path),
+		};
+
+		match (result, self.embedded_defaults) {
+			(Err(Error::NotFound(path)), Some(defaults)) if path == self.load_path()? => {
+				log!(
+					info,
+					"config file {} not found, loading embedded defaults",
+					path.display()
+				);
+
+				self.deserialize_config(defaults.as_bytes())
+			}
+			(result, _) => result,
+		}
+	}
+
+	// Read and deserialise a config file.
+	fn read_file<T: DeserializeOwned>(&self, config_path: &Path) -> Result<(T, Option<u64>)> {
+		if self.storage == Storage::File
+			&& self.journal == 0
+			&& !self.encryption.encrypts_files()
+			&& self.compression == Compression::None
+			&& self.signing == Signing::None
+		{
+			if self.audit_permissions {
+				permissions::audit(config_path)?;
+			}
+
+			File::open(config_path)
+				.map_err(Error::file(config_path))
+				.and_then(|file| {
+					#[cfg(feature = "tracing")]
+					trace::record_bytes(file.metadata()?.len());
+
+					let mut reader = RecordingReader {
+						reader: file,
+						error: None,
+					};
+					let result = self.deserialize_config(&mut reader);
+
+					// Deserialisers report failing to read the config file as failing to deserialise it.
+					match reader.error {
+						Some(err) if result.is_err() => Err(Error::file(config_path)(err)),
+						_ => result,
+					}
+				})
+		} else {
+			self.read_data(config_path)
+				.and_then(|data| self.parse_config(&data))
+		}
+	}
+
+	// Read the first config file which parses, of those at the locations of a fallback chain from the given
+	// config file on.
+	fn read_fallback<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+		locations: &[Location],
+	) -> Result<(T, Option<u64>)> {
+		let config_paths = locations
+			.iter()
+			.filter_map(|location| self.at(location).config_path().ok())
+			.skip_while(|path| path != config_path)
+			.collect::<Vec<_>>();
+
+		// Config files outside the chain, such as backups, are read as usual.
+		if config_paths.is_empty() {
+			return self.read_file(config_path);
+		}
+
+		let mut first_err = None;
+
+		for path in config_paths {
+			match self.read_file(&path) {
+				Err(Error::NotFound(_)) => {}
+				Err(err @ Error::Deserialize(_)) => {
+					log!(
+						warn,
+						"config file {} can't be parsed, falling back to the next location: {}",
+						path.display(),
+						err
+					);
+
+					first_err.get_or_insert(err);
+				}
+				result => return result,
+			}
+		}
+
+		Err(first_err.unwrap_or_else(|| Error::NotFound(config_path.to_owned())))
+	}
+
+	// Decrypt, decompress and deserialise the contents of a config file.
+	fn parse_config<T: DeserializeOwned>(&self, data: &[u8]) -> Result<(T, Option<u64>)> {
+		let data = Plaintext::from(self.compression.decompress(&Plaintext::from(
+			self.encryption.decrypt(self.signing.verify(data)?)?,
+		))?);
+
+		self.deserialize_config(data.as_slice())
+	}
+
+	// Read a config file, falling back to the other enabled formats if it can't be parsed in its own format.
+	fn read_config_file_lenient<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+	) -> Result<(T, Format)> {
+		let data = Plaintext::from(
+			self.compression.decompress(&Plaintext::from(
+				self.encryption
+					.decrypt(self.signing.verify(&self.read_data(config_path)?)?)?,
+			))?,
+		);
+		let format = self.file_format()?;
+		let error = match self.deserialize_config_as(&format, data.as_slice()) {
+			Ok((config, _)) => return Ok((config, format)),
+			Err(error) => error,
+		};
+
+		for other in Format::ENABLED.iter().filter(|other| **other != format) {
+			if let Ok((config, _)) = self.deserialize_config_as(other, data.as_slice()) {
+				log!(
+					info,
+					"config file {} isn't {:?}, loaded it as {:?}",
+					config_path.display(),
+					format,
+					other
+				);
+
+				return Ok((config, other.clone()));
+			}
+		}
+
+		Err(error)
+	}
+
+	// Deserialise a config in the format of the config file.
+	fn deserialize_config<T: DeserializeOwned, R: io::Read>(
+		&self,
+		reader: R,
+	) -> Result<(T, Option<u64>)> {
+		self.deserialize_config_as(&self.file_format()?, reader)
+	}
+
+	// Deserialise a config, making keys available to any fields that need them, and removing any envelope.
+	// Configs are merged over any embedded defaults.
+	fn deserialize_config_as<T: DeserializeOwned, R: io::Read>(
+		&self,
+		format: &Format,
+		reader: R,
+	) -> Result<(T, Option<u64>)> {
+		let _field_keys = self.field_keys()?;
+
+		if let Some(defaults) = self.embedded_defaults {
+			let mut merged: serde_json::Value = format.deserialize(defaults.as_bytes())?;
+			let (value, schema_version) = self.open_config(format, reader)?;
+
+			layers::merge(&mut merged, value);
+
+			return Ok((
+				serde_json::from_value(merged).map_err(|err| Error::Deserialize(err.into()))?,
+				schema_version,
+			));
+		}
+
+		self.open_config(format, reader)
+	}
+
+	// Deserialise a config, removing any envelope.
+	fn open_config<T: DeserializeOwned, R: io::Read>(
+		&self,
+		format: &Format,
+		mut reader: R,
+	) -> Result<(T, Option<u64>)> {
+		if self.schema_version.is_none() {
+			return Ok((format.deserialize(reader)?, None));
+		}
+
+		let mut data = Vec::new();
+
+		reader.read_to_end(&mut data)?;
+
+		envelope::open(format, &data)
+	}
+
+	// Run an operation on the config file, adding the context of the operation to any error it returns.
+	fn with_context<T, F>(&self, operation: Operation, f: F) -> Result<T>
+	where
+		F: FnOnce() -> Result<T>,
+	{
+		// Resolving the path can mean searching for the config file, so it's only done when it will be logged.
+		#[cfg(feature = "log")]
+		if log::log_enabled!(log::Level::Debug) {
+			let path = match operation {
+				Operation::Load => self.load_path(),
+				_ => self.config_path(),
+			};
+
+			if let Ok(path) = path {
+				log::debug!("{} config at {}", operation, path.display());
+			}
+		}
+
+		let f = || {
+			let _lock = self.lock_config(operation)?;
+
+			f()
+		};
+
+		#[cfg(feature = "tracing")]
+		let result = trace::instrument(self, operation, f);
+		#[cfg(not(feature = "tracing"))]
+		let result = f();
+
+		result.map_err(|error| {
+			let path = match operation {
+				Operation::Load => self.load_path(),
+				_ => self.config_path(),
+			};
+
+			match (&error, path) {
+				(Error::Context { .. }, _) | (_, Err(_)) => error,
+				(_, Ok(path)) => Error::Context {
+					operation,
+					path,
+					format: self.file_format().unwrap_or_else(|_| self.format.clone()),
+					source: Box::new(error),
+				},
+			}
+		})
+	}
+
+	// Make keys available to any fields that need them while a config is serialised or deserialised.
+	fn field_keys(&self) -> Result<FieldKeys> {
+		Ok(FieldKeys {
+			#[cfg(feature = "keyring")]
+			_keychain: keychain::KeychainScope::enter(&self.app),
+			#[cfg(feature = "encryption")]
+			_secret_key: match &self.encryption {
+				Encryption::Fields(key_source) => Some(crypto::SecretKeyScope::enter(key_source)?),
+				_ => None,
+			},
+		})
+	}
+
+	// Shift existing backups along by one, dropping the oldest, and back up the current config file.
+	fn rotate_backups(&self, config_path: &Path) -> Result<()> {
+		if self.backups == 0 || !config_path.exists() {
+			return Ok(());
+		}
+
+		for index in (1..self.backups).rev() {
+			let backup_path = backup_file_path(config_path, index);
+
+			if backup_path.exists() {
+				rename(&backup_path, backup_file_path(config_path, index + 1))?;
+			}
+		}
+
+		copy(config_path, backup_file_path(config_path, 1))?;
+
+		log!(
+			debug,
+			"rotated backups of config file {}, keeping {}",
+			config_path.display(),
+			self.backups
+		);
+
+		Ok(())
+	}
+
+	/// Create a builder for an [Abserde], which checks the combination of options when built.
+	pub fn builder() -> AbserdeBuilder {
+		AbserdeBuilder::default()
+	}
+
+	/// Create an [Abserde] for the given app, with every other option left as its default.
+	///
+	/// Pass `env!("CARGO_PKG_NAME")` to name the app after your crate, as [for_crate!] does.
+	pub fn for_app<S: Into<String>>(app: S) -> Self {
+		Self {
+			app: app.into(),
+			..Default::default()
+		}
+	}
+
+	/// Set the name of the config, so that it has a config file of its own, such as `keybindings.json`.
+	///
+	/// See [Abserde::name] for how the name is used.
+	pub fn named<S: Into<String>>(mut self, name: S) -> Self {
+		self.name = Some(name.into());
+
+		self
+	}
+
+	/// Set the default config document, such as one embedded with `include_str!`.
+	///
+	/// See [Abserde::embedded_defaults] for how the defaults are used.
+	pub fn with_embedded_defaults(mut self, defaults: &'static str) -> Self {
+		self.embedded_defaults = Some(defaults);
+
+		self
+	}
+
+	/// Return the path of a backup of the config file, where 1 is the most recent backup.
+	pub fn backup_path(&self, index: usize) -> Result<PathBuf> {
+		Ok(backup_file_path(&self.config_path()?, index))
+	}
+
+	/// Replace the config file with one of its backups, where 1 is the most recent backup.
+	///
+	/// The replaced config file is itself backed up as usual, so a restore can be undone.
+	pub fn restore_backup(&self, index: usize) -> Result<()> {
+		self.with_context(Operation::Save, || {
+			let backup_path = self.backup_path(index)?;
+
+			self.write_data(&self.read_data(&backup_path)?)?;
+
+			log!(
+				info,
+				"restored config from backup {}",
+				backup_path.display()
+			);
+
+			Ok(())
+		})
+	}
+
+	/// Adopt the config of a legacy app, such as after the app was renamed or its config moved.
+	///
+	/// If this app has no config file but the legacy app does, the legacy config is loaded, saved in this app's
+	/// location and format, and the legacy config file is deleted. Returns whether the legacy config was adopted.
+	pub fn adopt_from<T>(&self, legacy: &Abserde) -> Result<bool>
+	where
+		T: Serialize,
+		T: DeserializeOwned,
+	{
+		if self.exists()? || !legacy.exists()? {
+			return Ok(false);
+		}
+
+		let config: T = legacy.with_context(Operation::Load, || {
+			legacy.read_config_file(&legacy.load_path()?)
+		})?;
+
+		self.with_context(Operation::Save, || self.write_config(&config))?;
+		legacy.delete()?;
+
+		Ok(true)
+	}
+
+	/// List the config files in the directory of the config file, in alphabetical order.
+	///
+	/// Files are listed when their format can be inferred from their extension, taking the compression into
+	/// account, so backups and temporary files are left out. This includes other named configs, which can be
+	/// reset by deleting them with an [Abserde] located at their path.
+	pub fn list_configs(&self) -> Result<Vec<ConfigFile>> {
+		let config_path = self.config_path()?;
+		let config_dir = config_path.parent().ok_or(Error::NoSystemConfigDir)?;
+		let entries = match read_dir(config_dir) {
+			Ok(entries) => entries,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(err) => return Err(Error::file(config_dir)(err)),
+		};
+		let mut configs = Vec::new();
+
+		for entry in entries {
+			let entry = entry?;
+			let metadata = entry.metadata()?;
+			let path = entry.path();
+
+			if !metadata.is_file() {
+				continue;
+			}
+
+			let (Ok(name), Some(format)) =
+				(entry.file_name().into_string(), self.format_of_name(&path))
+			else {
+				continue;
+			};
+
+			configs.push(ConfigFile {
+				name,
+				path,
+				format,
+				size: metadata.len(),
+				modified: metadata.modified().ok(),
+			});
+		}
+
+		configs.sort_by(|a, b| a.name.cmp(&b.name));
+
+		Ok(configs)
+	}
+
+	/// Return whether the config file exists, without loading it.
+	pub fn exists(&self) -> Result<bool> {
+		let config_path = self.load_path()?;
+
+		match &self.storage {
+			Storage::File => Ok(config_path.exists()
+				|| (self.journal > 0 && journal::journal_path(&config_path).exists())),
+			#[cfg(feature = "sqlite")]
+			Storage::Sqlite => Ok(sqlite::read(&config_path)?.is_some()),
+			Storage::Memory => Ok(memory::read(&config_path)?.is_some()),
+			#[cfg(target_arch = "wasm32")]
+			Storage::LocalStorage => Ok(local_storage::read(&config_path)?.is_some()),
+			#[cfg(all(target_arch = "wasm32", feature = "async"))]
+			Storage::Opfs => Err(Error::Unsupported(OPFS_ASYNC_ONLY.to_string())),
+			#[cfg(feature = "http")]
+			Storage::Http(remote) => Ok(http::read(remote, &config_path)?.is_some()),
+			#[cfg(feature = "s3")]
+			Storage::S3(bucket) => Ok(s3::read(bucket, &config_path)?.is_some()),
+		}
+	}
+
+	/// Check that the config file can't be read or written by other users, and is owned by the current user,
+	/// failing with an [Error::InsecurePermissions] if not. Only on Unix.
+	///
+	/// This lets apps warn about insecure config files while still loading them, unlike
+	/// [Abserde::audit_permissions]. A missing config file passes.
+	pub fn check_permissions(&self) -> Result<()> {
+		self.with_context(Operation::Load, || permissions::audit(&self.load_path()?))
+	}
+
+	/// Write the default config to the given path, rather than the config file, such as an example config
+	/// installed by a packager at `/usr/share/doc/myapp/config.example.toml`.
+	///
+	/// The template is serialised in the format of the config file, or the format given by the extension of the
+	/// path when using [Format::FromExtension], without compression or encryption. See
+	/// [Abserde::write_commented_template] for templates documenting each field.
+	pub fn write_template<T, P>(&self, path: P) -> Result<()>
+	where
+		T: Serialize + Default,
+		P: AsRef<Path>,
+	{
+		let path = path.as_ref();
+		let mut data = Vec::new();
+
+		self.serialize_as(&self.template_format(path)?, &T::default(), &mut data)?;
+
+		write_template_file(path, &data)
+	}
+
+	// Format of a template written to the given path.
+	fn template_format(&self, path: &Path) -> Result<Format> {
+		match self.format {
+			Format::FromExtension => self.format_of_name(path).ok_or_else(|| {
+				Error::Unsupported(format!(
+					"can't infer template format from file name {}",
+					path.display()
+				))
+			}),
+			_ => self.file_format(),
+		}
+	}
+
+	/// Return whether the app is running for the first time, as neither the config file nor its directory exist.
+	///
+	/// Unlike a missing config file, a config directory left without a config file means the app has run
+	/// before, so onboarding doesn't need to be shown again.
+	pub fn is_first_run(&self) -> Result<bool> {
+		let config_path = self.load_path()?;
+
+		Ok(!config_path.exists()
+			&& !config_path
+				.parent()
+				.is_some_and(|config_dir| config_dir.exists()))
+	}
+
+	/// Create the config file with the default config if it doesn't exist, returning whether it was created.
+	///
+	/// An existing config file is left untouched, even if it can't be loaded.
+	pub fn ensure_exists<T>(&self) -> Result<bool>
+	where
+		T: Serialize,
+		T: Default,
+	{
+		self.with_context(Operation::Save, || {
+			if self.exists()? {
+				return Ok(false);
+			}
+
+			self.write_config(&T::default())?;
+
+			Ok(true)
+		})
+	}
+
+	/// Delete settings file related to this app.
+	///
+	/// This is the same as [Abserde::delete_with] using the default [DeleteMode].
+	pub fn delete(&self) -> Result<()> {
+		self.delete_with(DeleteMode::default())
+	}
+
+	/// Delete settings file related to this app, using the given options.
+	pub fn delete_with(&self, mode: DeleteMode) -> Result<()> {
+		if self.audit {
+			return self.delete_audited(mode);
+		}
+
+		if let Some(key) = &self.namespace {
+			return self.delete_namespaced(key, mode);
+		}
+
+		self.with_context(Operation::Delete, || {
+			let config_path = self.config_path()?;
+
+			let deleted = match &self.storage {
+				Storage::File => None,
+				#[cfg(feature = "sqlite")]
+				Storage::Sqlite => Some(sqlite::delete(&config_path)?),
+				Storage::Memory => Some(memory::delete(&config_path)?),
+				#[cfg(target_arch = "wasm32")]
+				Storage::LocalStorage => Some(local_storage::delete(&config_path)?),
+				#[cfg(all(target_arch = "wasm32", feature = "async"))]
+				Storage::Opfs => return Err(Error::Unsupported(OPFS_ASYNC_ONLY.to_string())),
+				#[cfg(feature = "http")]
+				Storage::Http(remote) => Some(http::delete(remote, &config_path)?),
+				#[cfg(feature = "s3")]
+				Storage::S3(bucket) => Some(s3::delete(bucket, &config_path)?),
+			};
+
+			// Configs which aren't stored in files have no backups or directory to delete.
+			if let Some(deleted) = deleted {
+				return match deleted {
+					false if !mode.ignore_missing => Err(Error::NotFound(config_path)),
+					_ => Ok(()),
+				};
+			}
+
+			// Journals are removed even when disabled, so they can't resurrect the config later.
+			let journaled = journal::remove_journal(&journal::journal_path(&config_path))?;
+
+			let delete_path = self.symlinks.delete_path(&config_path)?;
+
+			let result = delete_file(&delete_path, &mode).map_err(Error::file(&config_path));
+
+			self.remove_lock_file(&config_path)?;
+
+			match result {
+				Err(Error::NotFound(_)) if mode.ignore_missing || journaled => {}
+				result => result?,
+			}
+
+			if mode.remove_backups {
+				// Backups are numbered without gaps, so stop at the first one missing.
+				for index in 1.. {
+					match delete_file(&backup_file_path(&config_path, index), &mode) {
+						Ok(()) => {}
+						Err(err) if err.kind() == io::ErrorKind::NotFound => break,
+						Err(err) => return Err(err.into()),
+					}
+				}
+			}
+
+			self.delete_sections(&mode)?;
+
+			#[cfg(feature = "git")]
+			if self.git {
+				self.commit(Operation::Delete)?;
+			}
+
+			match self.resolved_location() {
+				// Don't attempt to delete folder if manually specifying folder.
+				Location::Dir(_) => {}
+				_ if !mode.remove_parent => {}
+				// Attempt to delete parent folder if it is empty.
+				_ => {
+					let config_dir = config_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+					// Ignore any errors here, as they are sometimes expected.
+					_ = remove_dir(config_dir);
+				}
+			}
+
+			Ok(())
+		})
+	}
+
+	/// Delete the config directory of this app, along with everything in it, such as backups.
+	///
+	/// Only the directory determined for the app within a system directory is ever deleted, so this fails for
+	/// config files located with [Location::Path] or [Location::Dir], including through [Location::EnvVar].
+	pub fn delete_all(&self) -> Result<()> {
+		self.with_context(Operation::Delete, || {
+			if let Location::Path(_) | Location::Dir(_) = self.resolved_location() {
+				return Err(Error::Unsupported(
+					"config directory can only be deleted when it is determined for the app"
+						.to_string(),
+				));
+			}
+
+			// Guard against app names resolving to the system config directory itself, or outside of it.
+			let names = [&self.qualifier, &self.organization]
+				.into_iter()
+				.flatten()
+				.filter(|name| !name.is_empty());
+
+			for name in once(&self.app).chain(names) {
+				check_dir_name("app", name)?;
+			}
+
+			let app_config_dir = self.app_config_dir()?;
+
+			remove_dir_all(&app_config_dir).map_err(Error::file(&app_config_dir))?;
+
+			Ok(())
+		})
+	}
+
+	/// Set the profile of the config, so that it is kept in a directory of its own, such as `profiles/work`.
+	///
+	/// See [Abserde::profile] for how the profile is used.
+	pub fn with_profile<S: Into<String>>(mut self, profile: S) -> Self {
+		self.profile = Some(profile.into());
+
+		self
+	}
+
+	/// Return the names of the profiles which have a directory, in alphabetical order.
+	pub fn list_profiles(&self) -> Result<Vec<String>> {
+		let profiles_dir = self.base_dir()?.join(PROFILES_DIR);
+		let entries = match read_dir(&profiles_dir) {
+			Ok(entries) => entries,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(err) => return Err(Error::file(&profiles_dir)(err)),
+		};
+		let mut profiles = Vec::new();
+
+		for entry in entries {
+			let entry = entry?;
+
+			if entry.file_type()?.is_dir() {
+				if let Ok(profile) = entry.file_name().into_string() {
+					profiles.push(profile);
+				}
+			}
+		}
+
+		profiles.sort();
+
+		Ok(profiles)
+	}
+
+	/// Copy the directory of a profile, with all of its config files, to a new profile.
+	///
+	/// Fails if the new profile already exists, so that none of its config files are overwritten.
+	pub fn copy_profile(&self, from: &str, to: &str) -> Result<()> {
+		let from_dir = self.profile_dir(from)?;
+		let to_dir = self.profile_dir(to)?;
+
+		if !from_dir.is_dir() {
+			return Err(Error::NotFound(from_dir));
+		}
+
+		if to_dir.exists() {
+			return Err(io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				format!("profile {} already exists", to),
+			)
+			.into());
+		}
+
+		copy_dir(&from_dir, &to_dir).map_err(Error::file(&to_dir))
+	}
+
+	/// Delete the directory of a profile, along with all of its config files.
+	pub fn delete_profile(&self, profile: &str) -> Result<()> {
+		let profile_dir = self.profile_dir(profile)?;
+
+		remove_dir_all(&profile_dir).map_err(Error::file(&profile_dir))
+	}
+}
+
+impl Default for Abserde {
+	fn default() -> Self {
+		Self {
+			app: var("CARGO_PKG_NAME").unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string()),
+			qualifier: None,
+			organization: None,
+			name: None,
+			extension: None,
+			section_files: BTreeMap::new(),
+			namespace: None,
+			policy: None,
+			policy_conflicts: Default::default(),
+			profile: None,
+			location: Default::default(),
+			host_paths: false,
+			format: Default::default(),
+			line_ending: Default::default(),
+			sort_keys: false,
+			preserve_comments: false,
+			storage: Default::default(),
+			atomic: true,
+			sync: false,
+			verify_saves: false,
+			symlinks: Default::default(),
+			permissions: Default::default(),
+			audit_permissions: false,
+			backups: 0,
+			journal: 0,
+			lock: false,
+			lock_timeout: None,
+			encryption: Default::default(),
+			compression: Default::default(),
+			signing: Default::default(),
+			schema_version: None,
+			embedded_defaults: None,
+			include_key: None,
+			interpolate: false,
+			capture: None,
+			audit: false,
+			#[cfg(feature = "git")]
+			git: false,
+		}
+	}
+}
+
+/// Create an [Abserde] for the app named after the crate calling the macro, with every other option left as
+/// its default.
+///
+/// The name is taken from `CARGO_PKG_NAME` as the calling crate is compiled, unlike [Abserde::default],
+/// which is compiled within abserde.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	format: Format::PrettyJson(PrettyJsonIndent::Tab),
+/// 	..for_crate!()
+/// };
+/// ```
+#[macro_export]
+macro_rules! for_crate {
+	() => {
+		$crate::Abserde::for_app(env!("CARGO_PKG_NAME"))
+	};
+}
+
+// Date and time in UTC of a system time, as the year, month, day, hour, minute and second.
+fn utc_date_time(time: SystemTime) -> [u64; 6] {
+	let secs = time
+		.duration_since(std::time::UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs();
+	let (days, secs) = (secs / 86400, secs % 86400);
+
+	// Convert days since the epoch to a civil date, using Howard Hinnant's civil_from_days algorithm.
+	let days = days + 719468;
+	let era = days / 146097;
+	let day_of_era = days % 146097;
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month_index = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+	let month = if month_index < 10 {
+		month_index + 3
+	} else {
+		month_index - 9
+	};
+	let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+	[year, month, day, secs / 3600, secs / 60 % 60, secs % 60]
+}
+
+// Check that a name, such as the app name, is a single directory name, so that it can't resolve to the
+// directory holding it, or outside of it.
+fn check_dir_name(kind: &str, name: &str) -> Result<()> {
+	let mut components = Path::new(name).components();
+
+	match (components.next(), components.next()) {
+		(Some(Component::Normal(_)), None) => Ok(()),
+		_ => Err(Error::Unsupported(format!(
+			"{} name {:?} is not a valid config directory name",
+			kind, name
+		))),
+	}
+}
+
+// Copy a directory, along with everything in it.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+	create_dir_all(to)?;
+
+	for entry in read_dir(from)? {
+		let entry = entry?;
+		let to = to.join(entry.file_name());
+
+		if entry.file_type()?.is_dir() {
+			copy_dir(&entry.path(), &to)?;
+		} else {
+			copy(entry.path(), to)?;
+		}
+	}
+
+	Ok(())
+}
+
+// System-wide config directory, shared by every user of the machine.
+#[cfg(windows)]
+fn system_config_dir() -> Option<PathBuf> {
+	var_os("ProgramData").map(PathBuf::from)
+}
+
+#[cfg(not(windows))]
+fn system_config_dir() -> Option<PathBuf> {
+	Some(PathBuf::from("/etc"))
+}
+
+// Write a template, creating its directory if needed.
+fn write_template_file(path: &Path, data: &[u8]) -> Result<()> {
+	if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+		create_dir_all(dir).map_err(Error::file(dir))?;
+	}
+
+	std::fs::write(path, data).map_err(Error::file(path))
+}
+
+// Flush a directory's entries to disk, so that files created or renamed in it survive power loss.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+	File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+	Ok(())
+}
+
+// Delete a file, or move it to the system trash if enabled.
+#[cfg_attr(not(feature = "trash"), allow(unused_variables))]
+fn delete_file(path: &Path, mode: &DeleteMode) -> io::Result<()> {
+	#[cfg(feature = "trash")]
+	if mode.trash {
+		// Report missing files as such, as the trash doesn't distinguish them from other errors.
+		path.symlink_metadata()?;
+
+		return trash::delete(path).map_err(io::Error::other);
+	}
+
+	remove_file(path)
+}
+
+// Reload a config which changed, calling back with it, unless the config file was deleted, as it may be replaced
+// by a later change.
+#[cfg(any(feature = "notify", unix))]
+fn reload_config<T, F>(abserde: &Abserde, config_path: &Path, callback: &mut F)
+where
+	T: DeserializeOwned,
+	F: FnMut(Result<T>),
+{
+	if config_path.exists() {
+		callback(abserde.with_context(Operation::Load, || abserde.read_config_file(config_path)));
+	}
+}
+
+// Return the path of a numbered backup of the given config file.
+fn backup_file_path(config_path: &Path, index: usize) -> PathBuf {
+	let mut file_name = config_path.file_name().unwrap_or_default().to_owned();
+
+	file_name.push(format!(".bak.{}", index));
+
+	config_path.with_file_name(file_name)
+}
+
+// Return a unique path for a hidden temporary file next to the given file.
+fn temp_path(path: &Path) -> PathBuf {
+	let mut file_name = OsString::from(".");
+
+	file_name.push(path.file_name().unwrap_or_default());
+	file_name.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+
+	path.with_file_name(file_name)
+}
+
+// Reader recording the first error reading from it, other than being interrupted.
+struct RecordingReader<R> {
+	reader: R,
+	error: Option<io::Error>,
+}
+
+impl<R: io::Read> io::Read for RecordingReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.reader.read(buf).inspect_err(|err| {
+			if self.error.is_none() && err.kind() != io::ErrorKind::Interrupted {
+				self.error = Some(io::Error::new(err.kind(), err.to_string()));
+			}
+		})
+	}
+}
+
+// Report an error decompressing a config as invalid data, so it's told apart from failing to read the config file.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn invalid_data(err: io::Error) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Trait that apps can implement to store app settings.
+///
+/// Implementing types must also implement [serde::Serialize] and [serde::Deserialize] traits.
+pub trait Config {
+	/// Type of implementation.
+	type T;
+
+	/// Load a config from disk into the implementing type.
+	fn load_config(abserde: &Abserde) -> Result<Self::T>;
+
+	/// Load a config from disk, failing with an [Error::Locked] straight away if another process holds the lock
+	/// on the config file, rather than waiting for it to be released. See [Abserde::lock].
+	///
+	/// This keeps a UI thread responsive while another process saves the config.
+	fn try_load_config(abserde: &Abserde) -> Result<Self::T>;
+
+	/// Load a config from disk, waiting up to the given time for another process to release the lock on the
+	/// config file before failing with an [Error::Locked]. See [Abserde::lock].
+	fn load_config_timeout(abserde: &Abserde, timeout: Duration) -> Result<Self::T>;
+
+	/// Load a config from disk, falling back to the most recent readable backup if the config file is corrupt.
+	///
+	/// Returns the config along with the path of the file it was loaded from, so apps can warn users when a
+	/// backup was used. If neither the config file nor any backup can be loaded, the original error is returned.
+	///
+	/// Only config files which can't be deserialised, decompressed or verified are treated as corrupt. Other
+	/// errors, such as a missing, locked or unreadable config file, or a wrong passphrase, are returned.
+	fn load_config_with_recovery(abserde: &Abserde) -> Result<(Self::T, PathBuf)>;
+
+	/// Load a config from disk, moving the config file aside and returning the default config if it can't be
+	/// parsed, so a corrupt config file can't stop an app from starting.
+	///
+	/// The config file is renamed to one named after it and the current time, such as
+	/// `config.json.corrupt-20240102T030405Z`, where users can repair it. Returns the config along with what was
+	/// quarantined, if anything, so apps can tell users what happened. A missing config file also gives the
+	/// default config. Other errors, such as a config file which can't be read or moved, are returned.
+	fn load_config_or_quarantine(abserde: &Abserde) -> Result<(Self::T, Option<Quarantined>)>
+	where
+		Self::T: Default;
+
+	/// Load a config from disk, or create the initial config using the given function if there is no config file.
+	///
+	/// This is useful when the initial config depends on information only available at runtime, such as the
+	/// size of the screen.
+	fn load_config_or_else<F>(abserde: &Abserde, initial: F) -> Result<Self::T>
+	where
+		F: FnOnce() -> Self::T;
+
+	/// Like [Config::load_config_or_else], but the initial config is also saved to disk straight away.
+	fn load_config_or_else_persisted<F>(abserde: &Abserde, initial: F) -> Result<Self::T>
+	where
+		F: FnOnce() -> Self::T;
+
+	/// Save a config from the implementing type to disk.
+	fn save_config(&self, abserde: &Abserde) -> Result<()>;
+
+	/// Load a config from disk, trying each of the other enabled formats if it can't be parsed in its own format.
+	///
+	/// Returns the config along with the format it was parsed in, so apps can convert config files saved by
+	/// builds using a different format. If no format can parse the config file, the original error is returned.
+	fn load_config_lenient(abserde: &Abserde) -> Result<(Self::T, Format)>;
+
+	/// Load a config from disk, along with the keys in the config file which the config type doesn't recognise.
+	///
+	/// Unknown keys are ignored when loading, so this lets apps warn users about misspelt settings. Keys of
+	/// nested values are given as paths, such as `window.hieght`.
+	fn load_config_with_unknown_keys(abserde: &Abserde) -> Result<(Self::T, Vec<String>)>;
+
+	/// Serialise a config to a string in the given format, without locating or writing a config file.
+	///
+	/// This is useful for sending configs to other processes, or showing them in an editor. Fails with an
+	/// [Error::Serialize] for formats which aren't text, such as pickle.
+	fn to_config_string(&self, format: &Format) -> Result<String>;
+
+	/// Deserialise a config from a string in the given format, without locating or reading a config file.
+	///
+	/// Strings holding a config saved with a [Abserde::schema_version] envelope are accepted too, with the
+	/// envelope removed.
+	fn from_config_str(config: &str, format: &Format) -> Result<Self::T>;
+
+	/// Serialise a config in the given format to any writer, such as a socket or standard output, without
+	/// locating or writing a config file.
+	fn save_config_to<W: io::Write>(&self, writer: W, format: &Format) -> Result<()>;
+
+	/// Deserialise a config in the given format from any reader, such as a socket or standard input, without
+	/// locating or reading a config file.
+	///
+	/// As with [Config::from_config_str], any [Abserde::schema_version] envelope is removed.
+	fn load_config_from<R: io::Read>(reader: R, format: &Format) -> Result<Self::T>;
+
+	/// Load a config from disk, migrating it from an older version of the config type if needed.
+	///
+	/// See [Migrations] for how the version of the config file is determined.
+	fn load_config_migrated(abserde: &Abserde, migrations: &Migrations<Self::T>)
+		-> Result<Self::T>;
+
+	/// Load a config from disk, along with the version of the config file it's saved to.
+	///
+	/// The config is loaded as with [Config::load_config]. When it's loaded from a config file other than the one
+	/// it's saved to, such as one found by a [Location::Search], the version is that of the config file it's
+	/// saved to, which is missing until the config is first saved.
+	fn load_config_versioned(abserde: &Abserde) -> Result<(Self::T, ConfigVersion)>;
+
+	/// Save a config to disk, unless the config file has been modified since the given version was taken.
+	///
+	/// Fails with an [Error::Conflict] if the config file was modified, for example by another process, so apps
+	/// can reload and merge changes instead of overwriting them. Returns the version of the saved config file.
+	fn save_config_if_unmodified(
+		&self,
+		abserde: &Abserde,
+		version: &ConfigVersion,
+	) -> Result<ConfigVersion>;
+
+	/// Watch the config file for changes on disk, calling back with the reloaded config after each change.
+	///
+	/// Changes are debounced by [WATCH_DEBOUNCE], so a burst of writes results in a single reload. Saves made
+	/// by the app itself are also seen. Watching stops when the returned [ConfigWatcher] is dropped.
+	/// Enabled with the notify feature.
+	#[cfg(feature = "notify")]
+	fn watch_config<F>(abserde: &Abserde, callback: F) -> Result<ConfigWatcher>
+	where
+		F: FnMut(Result<Self::T>) + Send + 'static;
+
+	/// Reload a config whenever the process receives SIGHUP, the conventional way of asking a daemon to re-read
+	/// its config, calling back with the reloaded config, until the returned reloader is dropped.
+	///
+	/// As with [Config::watch_config], the callback runs on a background thread, and isn't called if the config
+	/// file doesn't exist. Several configs can be reloaded at once. The shutdown feature handles SIGHUP too, so
+	/// this should be called after `on_shutdown`, which would replace its handler otherwise. Only on Unix.
+	///
+	/// ```no_run
+	/// # use std::sync::mpsc::channel;
+	/// # use abserde::*;
+	/// # use serde::{Serialize, Deserialize};
+	/// #
+	/// # #[derive(Serialize, Deserialize)]
+	/// # struct MyConfig;
+	/// #
+	/// let (sender, receiver) = channel();
+	/// let _reloader = MyConfig::reload_on_hangup(&Abserde::default(), move |config| {
+	/// 	_ = sender.send(config);
+	/// })?;
+	///
+	/// for config in receiver {
+	/// 	let my_config = config?;
+	/// }
+	/// #
+	/// # Ok::<(), Error>(())
+	/// ```
+	#[cfg(unix)]
+	fn reload_on_hangup<F>(abserde: &Abserde, callback: F) -> Result<HangupReloader>
+	where
+		F: FnMut(Result<Self::T>) + Send + 'static;
+}
+
+impl<T> Config for T
+where
+	T: Serialize,
+	T: DeserializeOwned,
+{
+	type T = T;
+
+	fn load_config(abserde: &Abserde) -> Result<Self::T> {
+		abserde.with_context(Operation::Load, || {
+			abserde.read_config_file(&abserde.load_path()?)
+		})
+	}
+
+	fn try_load_config(abserde: &Abserde) -> Result<Self::T> {
+		Self::load_config_timeout(abserde, Duration::ZERO)
+	}
+
+	fn load_config_timeout(abserde: &Abserde, timeout: Duration) -> Result<Self::T> {
+		Self::load_config(&Abserde {
+			lock_timeout: Some(timeout),
+			..abserde.clone()
+		})
+	}
+
+	fn load_config_with_recovery(abserde: &Abserde) -> Result<(Self::T, PathBuf)> {
+		abserde.with_context(Operation::Load, || {
+			let config_path = abserde.load_path()?;
+			let error = match abserde.read_config_file(&config_path) {
+				Ok(config) => return Ok((config, config_path)),
+				Err(error) => error,
+			};
+
+			// Only corrupt config files are recovered, as backups would be just as missing, locked or unreadable.
+			if !error.is_corrupt() {
+				return Err(error);
+			}
+
+			for index in 1..=abserde.backups {
+				let backup_path = backup_file_path(&config_path, index);
+
+				if let Ok(config) = abserde.read_config_file(&backup_path) {
+					log!(
+						info,
+						"config file {} is corrupt, recovered it from backup {}: {}",
+						config_path.display(),
+						backup_path.display(),
+						error
+					);
+
+					return Ok((config, backup_path));
+				}
+			}
+
+			Err(error)
+		})
+	}
+
+	fn load_config_or_quarantine(abserde: &Abserde) -> Result<(Self::T, Option<Quarantined>)>
+	where
+		Self::T: Default,
+	{
+		abserde.with_context(Operation::Load, || {
+			let config_path = abserde.load_path()?;
+
+			match abserde.read_config_file(&config_path) {
+				Ok(config) => Ok((config, None)),
+				Err(Error::NotFound(_)) => Ok((Default::default(), None)),
+				Err(error @ Error::Deserialize(_)) => {
+					let path = abserde.quarantine(&config_path)?;
+
+					Ok((Default::default(), Some(Quarantined { path, error })))
+				}
+				Err(error) => Err(error),
+			}
+		})
+	}
+
+	fn load_config_or_else<F>(abserde: &Abserde, initial: F) -> Result<Self::T>
+	where
+		F: FnOnce() -> Self::T,
+	{
+		Ok(manager::load_if_exists(abserde)?.unwrap_or_else(initial))
+	}
+
+	fn load_config_or_else_persisted<F>(abserde: &Abserde, initial: F) -> Result<Self::T>
+	where
+		F: FnOnce() -> Self::T,
+	{
+		if let Some(config) = manager::load_if_exists(abserde)? {
+			return Ok(config);
+		}
+
+		let config = initial();
+
+		abserde.with_context(Operation::Save, || abserde.write_verified(&config))?;
+
+		Ok(config)
+	}
+
+	fn save_config(&self, abserde: &Abserde) -> Result<()> {
+		abserde.with_context(Operation::Save, || abserde.write_verified(self))
+	}
+
+	fn load_config_lenient(abserde: &Abserde) -> Result<(Self::T, Format)> {
+		abserde.with_context(Operation::Load, || {
+			abserde.read_config_file_lenient(&abserde.load_path()?)
+		})
+	}
+
+	fn to_config_string(&self, format: &Format) -> Result<String> {
+		let mut buf = Vec::new();
+
+		format.serialize(self, &mut buf)?;
+
+		String::from_utf8(buf).map_err(|err| Error::Serialize(err.into()))
+	}
+
+	fn from_config_str(config: &str, format: &Format) -> Result<Self::T> {
+		Self::load_config_from(config.as_bytes(), format)
+	}
+
+	fn save_config_to<W: io::Write>(&self, writer: W, format: &Format) -> Result<()> {
+		use io::Write;
+
+		let mut writer = io::BufWriter::new(writer);
+
+		format.serialize(self, &mut writer)?;
+
+		Ok(writer.flush()?)
+	}
+
+	fn load_config_from<R: io::Read>(mut reader: R, format: &Format) -> Result<Self::T> {
+		// Envelopes are detected by deserialising twice, so the whole config is read first.
+		let mut data = Vec::new();
+
+		reader.read_to_end(&mut data)?;
+
+		Ok(envelope::open(format, &data)?.0)
+	}
+
+	fn load_config_with_unknown_keys(abserde: &Abserde) -> Result<(Self::T, Vec<String>)> {
+		abserde.with_context(Operation::Load, || {
+			let loaded: unknown::WithUnknownKeys<Self::T> =
+				abserde.read_config_file(&abserde.load_path()?)?;
+
+			Ok((loaded.config, loaded.unknown_keys))
+		})
+	}
+
+	fn load_config_migrated(
+		abserde: &Abserde,
+		migrations: &Migrations<Self::T>,
+	) -> Result<Self::T> {
+		abserde.with_context(Operation::Load, || {
+			let (value, schema_version) =
+				abserde.read_config_file_versioned(&abserde.load_path()?)?;
+			let (config, migrated) = {
+				let _field_keys = abserde.field_keys()?;
+				let (value, migrated) = migrations.migrate(value, schema_version)?;
+
+				(
+					serde_json::from_value(value).map_err(|err| Error::Deserialize(err.into()))?,
+					migrated,
+				)
+			};
+
+			if migrated && migrations.resaves() {
+				// Envelopes must record the version the config was migrated to.
+				Abserde {
+					schema_version: abserde.schema_version.map(|_| migrations.version()),
+					..abserde.clone()
+				}
+				.write_config(&config)?;
+			}
+
+			Ok(config)
+		})
+	}
+
+	fn load_config_versioned(abserde: &Abserde) -> Result<(Self::T, ConfigVersion)> {
+		abserde.with_context(Operation::Load, || {
+			// The version is taken before reading, so a config file changed in between is seen as modified when
+			// saving, rather than being overwritten.
+			let version = abserde.stored_version(&abserde.config_path()?)?;
+			let (config, _) = abserde.read_config_file_versioned(&abserde.load_path()?)?;
+
+			Ok((config, version))
+		})
+	}
+
+	fn save_config_if_unmodified(
+		&self,
+		abserde: &Abserde,
+		version: &ConfigVersion,
+	) -> Result<ConfigVersion> {
+		abserde.with_context(Operation::Save, || {
+			let config_path = abserde.config_path()?;
+
+			if abserde.stored_version(&config_path)? != *version {
+				return Err(Conflict { path: config_path }.into());
+			}
+
+			abserde.write_verified(self)?;
+
+			abserde.stored_version(&config_path)
+		})
+	}
+
+	#[cfg(feature = "notify")]
+	fn watch_config<F>(abserde: &Abserde, callback: F) -> Result<ConfigWatcher>
+	where
+		F: FnMut(Result<Self::T>) + Send + 'static,
+	{
+		abserde.with_context(Operation::Load, || watch::watch(abserde, callback))
+	}
+
+	#[cfg(unix)]
+	fn reload_on_hangup<F>(abserde: &Abserde, callback: F) -> Result<HangupReloader>
+	where
+		F: FnMut(Result<Self::T>) + Send + 'static,
+	{
+		abserde.with_context(Operation::Load, || {
+			hangup::reload_on_hangup(abserde, callback)
+		})
+	}
+}
+
+#[cfg(test)]
+#[allow(clippy::type_complexity)]
+mod tests {
+	use std::collections::HashMap;
+	use std::fmt::Debug;
+	use std::fs::{read_dir, write};
+
+	use fake::{Dummy, Fake, Faker};
+	use serde::{de::DeserializeOwned, Deserialize, Serialize};
+	use serial_test::serial;
+	use tempfile::{NamedTempFile, TempDir};
+
+	use crate::*;
+
+	const APP_NAME: &str = env!("CARGO_PKG_NAME");
+
+	// Test config type for serialisation formats that only accept basic types.
+	#[derive(Serialize, Deserialize, Debug, Default, Dummy, PartialEq)]
+	struct TestConfigSimple {
+		string_val: String,
+		i8_val: i8,
+		i16_val: i16,
+		i32_val: i32,
+		u8_val: u8,
+		u16_val: u16,
+		u32_val: u32,
+		f32_val: f32,
+	}
+
+	// More complex config type for serialisation formats that support advanced types.
+	#[derive(Serialize, Deserialize, Debug, Default, Dummy, PartialEq, Clone)]
+	struct TestConfigComplex {
+		string_val: String,
+		i8_val: i8,
+		i16_val: i16,
+		i32_val: i32,
+		i64_val: i64,
+		i128_val: i128,
+		u8_val: u8,
+		u16_val: u16,
+		u32_val: u32,
+		u64_val: u64,
+		u128_val: u128,
+		f32_val: f32,
+		f64_val: f64,
+		vec_1_val: Vec<i64>,
+		vec_2_val: Vec<(String, i8, i8, i32, i64, String, String, String)>,
+		vec_3_val: Vec<(f32, f32, f32, f64, f64)>,
+		hash_map_1_val: HashMap<String, String>,
+		hash_map_2_val: HashMap<i16, i64>,
+		hash_map_3_val: HashMap<i16, Vec<(String, i32, u8, HashMap<String, i32>)>>,
+		hash_map_4_val: HashMap<String, (f64, f32, i8)>,
+	}
+
+	// Config type with nested structs, for formats that store them as sections.
+	#[cfg(feature = "ini")]
+	#[derive(Serialize, Deserialize, Debug, Default, Dummy, PartialEq)]
+	struct TestConfigSections {
+		string_val: String,
+		window: TestConfigSectionsWindow,
+		i32_val: i32,
+		theme: TestConfigSectionsTheme,
+		f32_val: f32,
+	}
+
+	#[cfg(feature = "ini")]
+	#[derive(Serialize, Deserialize, Debug, Default, Dummy, PartialEq)]
+	struct TestConfigSectionsWindow {
+		width: u32,
+		height: u32,
+		title: String,
+	}
+
+	#[cfg(feature = "ini")]
+	#[derive(Serialize, Deserialize, Debug, Default, Dummy, PartialEq)]
+	struct TestConfigSectionsTheme {
+		name: String,
+		font_size: f32,
+	}
+
+	// Serve a single config over HTTP on a local port, returning the URL of the server. Requests are refused
+	// unless the given function accepts their path and authorization.
+	#[cfg(any(feature = "http", feature = "s3"))]
+	fn serve_config<F>(authorize: F) -> String
+	where
+		F: Fn(&str, &str) -> bool + Send + 'static,
+	{
+		use std::io::{BufRead, BufReader, Read, Write};
+		use std::net::TcpListener;
+
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+
+		std::thread::spawn(move || {
+			let mut stored = None;
+
+			for stream in listener.incoming() {
+				let mut reader = BufReader::new(stream.unwrap());
+				let mut request_line = String::new();
+				let mut content_length = 0;
+				let mut authorization = String::new();
+
+				reader.read_line(&mut request_line).unwrap();
+
+				loop {
+					let mut line = String::new();
+
+					reader.read_line(&mut line).unwrap();
+
+					let Some((name, value)) = line.trim_end().split_once(": ") else {
+						break;
+					};
+
+					match name.to_ascii_lowercase().as_str() {
+						"content-length" => content_length = value.parse().unwrap(),
+						"authorization" => authorization = value.to_string(),
+						_ => {}
+					}
+				}
+
+				let mut body = vec![0; content_length];
+
+				reader.read_exact(&mut body).unwrap();
+
+				let mut parts = request_line.split(' ');
+				let method = parts.next().unwrap();
+				let authorized = authorize(parts.next().unwrap(), &authorization);
+				let (status, response) = match (authorized, method) {
+					(false, _) => ("401 Unauthorized", None),
+					(true, "GET") => match &stored {
+						Some(data) => ("200 OK", Some(data)),
+						None => ("404 Not Found", None),
+					},
+					(true, "PUT") => {
+						stored = Some(body);
+
+						("204 No Content", None)
+					}
+					(true, "DELETE") => match stored.take() {
+						Some(_) => ("204 No Content", None),
+						None => ("404 Not Found", None),
+					},
+					_ => ("405 Method Not Allowed", None),
+				};
+				let response = response.cloned().unwrap_or_default();
+				let mut stream = reader.into_inner();
+
+				write!(
+					stream,
+					"HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+					status,
+					response.len()
+				)
+				.unwrap();
+				stream.write_all(&response).unwrap();
+			}
+		});
+
+		url
+	}
+
+	// Generic dispatch method.
+	fn test_save_load_delete<T>(abserde: &Abserde)
+	where
+		T: Serialize,
+		T: DeserializeOwned,
+		T: Dummy<Faker>,
+		T: PartialEq,
+		T: Debug,
+	{
+		let test_config_saved: T = Faker.fake();
+
+		test_config_saved.save_config(abserde).unwrap();
+
+		assert!(abserde.exists().unwrap());
+
+		let test_config_loaded = T::load_config(abserde).unwrap();
+
+		assert_eq!(test_config_saved, test_config_loaded);
+
+		abserde.delete().unwrap();
+
+		assert!(!abserde.exists().unwrap());
+		assert!(matches!(
+			T::load_config(abserde).unwrap_err().root(),
+			Error::NotFound(_)
+		));
+	}
+
+	#[test]
+	#[serial]
+	fn test_auto() {
+		test_save_load_delete::<TestConfigSimple>(&Abserde::default());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_json_auto() {
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Auto,
+			format: Format::Json,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_pretty_json_auto() {
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Auto,
+			format: Format::PrettyJson(PrettyJsonIndent::default()),
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_json_auto_data_cache() {
+		for (location, system_dir) in [
+			(Location::AutoData, dirs::data_dir()),
+			(Location::AutoCache, dirs::cache_dir()),
+		] {
+			let abserde = Abserde {
+				app: APP_NAME.to_string(),
+				location,
+				format: Format::Json,
+				..Default::default()
+			};
+
+			assert_eq!(
+				abserde.config_path().unwrap(),
+				system_dir.unwrap().join(APP_NAME).join("config.json")
+			);
+
+			test_save_load_delete::<TestConfigComplex>(&abserde);
+		}
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_json_env_var() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = |name: &str| Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::EnvVar(name.to_string()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		std::env::set_var(
+			"ABSERDE_TEST_ENV_VAR_PATH",
+			tmp_dir.path().join("custom.json"),
+		);
+		std::env::set_var("ABSERDE_TEST_ENV_VAR_DIR", tmp_dir.path());
+		std::env::set_var("ABSERDE_TEST_ENV_VAR_EMPTY", "");
+
+		assert_eq!(
+			abserde("ABSERDE_TEST_ENV_VAR_PATH").config_path().unwrap(),
+			tmp_dir.path().join("custom.json")
+		);
+		assert_eq!(
+			abserde("ABSERDE_TEST_ENV_VAR_DIR").config_path().unwrap(),
+			tmp_dir.path().join("config.json")
+		);
+
+		for name in ["ABSERDE_TEST_ENV_VAR_EMPTY", "ABSERDE_TEST_ENV_VAR_UNSET"] {
+			assert_eq!(
+				abserde(name).config_path().unwrap(),
+				Abserde {
+					location: Location::Auto,
+					..abserde(name)
+				}
+				.config_path()
+				.unwrap()
+			);
+		}
+
+		test_save_load_delete::<TestConfigComplex>(&abserde("ABSERDE_TEST_ENV_VAR_DIR"));
+
+		assert!(tmp_dir.path().exists());
+
+		test_save_load_delete::<TestConfigComplex>(&abserde("ABSERDE_TEST_ENV_VAR_UNSET"));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_json_auto_organization() {
+		let abserde = Abserde::builder()
+			.app(APP_NAME)
+			.qualifier("org")
+			.organization("Abserde Test")
+			.format(Format::Json)
+			.build()
+			.unwrap();
+		let app_dir = if cfg!(target_os = "macos") {
+			format!("org.Abserde-Test.{}", APP_NAME).into()
+		} else if cfg!(windows) {
+			Path::new("Abserde Test").join(APP_NAME)
+		} else {
+			PathBuf::from(APP_NAME)
+		};
+
+		assert_eq!(
+			abserde.config_path().unwrap(),
+			dirs::config_dir()
+				.unwrap()
+				.join(app_dir)
+				.join("config.json")
+		);
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		assert!(Abserde {
+			organization: Some("..".to_string()),
+			..abserde
+		}
+		.delete_all()
+		.is_err());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_named() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::PrettyJson(PrettyJsonIndent::Tab),
+			..Default::default()
+		};
+		let ui_abserde = abserde.clone().named("ui");
+		let keybindings_abserde = abserde.named("keybindings");
+		let ui_config: TestConfigComplex = Faker.fake();
+		let keybindings_config: TestConfigComplex = Faker.fake();
+
+		assert_eq!(
+			keybindings_abserde.config_path().unwrap(),
+			tmp_dir.path().join("keybindings.json")
+		);
+
+		ui_config.save_config(&ui_abserde).unwrap();
+		keybindings_config
+			.save_config(&keybindings_abserde)
+			.unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&ui_abserde).unwrap(),
+			ui_config
+		);
+		assert_eq!(
+			TestConfigComplex::load_config(&keybindings_abserde).unwrap(),
+			keybindings_config
+		);
+
+		test_save_load_delete::<TestConfigComplex>(&ui_abserde);
+
+		assert!(keybindings_abserde.exists().unwrap());
+	}
+
+	#[cfg(all(feature = "json", feature = "sqlite"))]
+	#[test]
+	fn test_json_dir_sqlite() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			storage: Storage::Sqlite,
+			..Default::default()
+		};
+		let ui_abserde = abserde.clone().named("ui");
+		let ui_config: TestConfigComplex = Faker.fake();
+
+		ui_config.save_config(&ui_abserde).unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		assert!(tmp_dir.path().join("config.sqlite").exists());
+		assert!(!abserde.config_path().unwrap().exists());
+		assert!(!ui_abserde.config_path().unwrap().exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&ui_abserde).unwrap(),
+			ui_config
+		);
+
+		let (_, version) = TestConfigComplex::load_config_versioned(&ui_abserde).unwrap();
+
+		Faker
+			.fake::<TestConfigComplex>()
+			.save_config(&ui_abserde)
+			.unwrap();
+
+		assert!(matches!(
+			ui_config
+				.save_config_if_unmodified(&ui_abserde, &version)
+				.unwrap_err()
+				.root(),
+			Error::Conflict(_)
+		));
+		assert!(abserde
+			.delete_with(DeleteMode {
+				ignore_missing: true,
+				..Default::default()
+			})
+			.is_ok());
+	}
+
+	#[cfg(all(feature = "json", feature = "http"))]
+	#[test]
+	fn test_json_dir_http() {
+		let tmp_dir = TempDir::new().unwrap();
+		let remote = Remote {
+			authorization: Some("Bearer token".to_string()),
+			..Remote::new(format!(
+				"{}/config.json",
+				serve_config(|_, authorization| authorization == "Bearer token")
+			))
+		};
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			storage: Storage::Http(remote.clone()),
+			..Default::default()
+		};
+		let config: TestConfigComplex = Faker.fake();
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		assert!(!abserde.config_path().unwrap().exists());
+
+		config.save_config(&abserde).unwrap();
+
+		assert!(abserde.config_path().unwrap().exists());
+
+		let unauthorized = Abserde {
+			storage: Storage::Http(Remote {
+				authorization: None,
+				..remote.clone()
+			}),
+			..abserde.clone()
+		};
+
+		assert!(matches!(
+			TestConfigComplex::load_config(&unauthorized)
+				.unwrap_err()
+				.root(),
+			Error::Http(ureq::Error::StatusCode(401))
+		));
+
+		// Nothing listens on the port once the listener is dropped, so the server can't be reached.
+		let unreachable_url = format!(
+			"http://{}/config.json",
+			std::net::TcpListener::bind("127.0.0.1:0")
+				.unwrap()
+				.local_addr()
+				.unwrap()
+		);
+		let offline = Abserde {
+			storage: Storage::Http(Remote {
+				url: unreachable_url,
+				..remote
+			}),
+			..abserde
+		};
+
+		assert_eq!(TestConfigComplex::load_config(&offline).unwrap(), config);
+	}
+
+	#[cfg(all(feature = "json", feature = "s3"))]
+	#[test]
+	fn test_json_auto_s3() {
+		let endpoint = serve_config(|path, authorization| {
+			path == "/my-bucket/configs/abserde/profiles/work/config.json"
+				&& authorization.starts_with("AWS4-HMAC-SHA256 Credential=my-key-id/")
+		});
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			format: Format::Json,
+			storage: Storage::S3(Bucket {
+				prefix: "configs/".to_string(),
+				..Bucket::new(endpoint, "us-east-1", "my-bucket", "my-key-id", "my-secret")
+			}),
+			..Default::default()
+		}
+		.with_profile("work");
+
+		assert_eq!(
+			abserde.config_path().unwrap(),
+			Path::new(APP_NAME)
+				.join("profiles")
+				.join("work")
+				.join("config.json")
+		);
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_auto_memory() {
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			format: Format::Json,
+			storage: Storage::Memory,
+			..Default::default()
+		}
+		.with_profile("memory");
+		let ui_abserde = abserde.clone().named("ui");
+		let ui_config: TestConfigComplex = Faker.fake();
+
+		assert_eq!(
+			abserde.config_path().unwrap(),
+			Path::new(APP_NAME)
+				.join("profiles")
+				.join("memory")
+				.join("config.json")
+		);
+
+		ui_config.save_config(&ui_abserde).unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		assert!(!ui_abserde.config_path().unwrap().exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&ui_abserde).unwrap(),
+			ui_config
+		);
+
+		ui_abserde.delete().unwrap();
+
+		assert!(!ui_abserde.exists().unwrap());
+	}
+
+	#[cfg(all(feature = "json", feature = "git"))]
+	#[test]
+	fn test_json_dir_git() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			git: true,
+			..Default::default()
+		};
+		let first_config: TestConfigComplex = Faker.fake();
+		let second_config: TestConfigComplex = Faker.fake();
+
+		assert!(abserde.history().is_err());
+
+		first_config.save_config(&abserde).unwrap();
+		first_config.save_config(&abserde).unwrap();
+		second_config.save_config(&abserde).unwrap();
+
+		let history = abserde.history().unwrap();
+
+		assert_eq!(history.len(), 2);
+		assert!(history[0].message.starts_with("Save config.json of "));
+		assert!(history[0].message.contains(APP_NAME));
+
+		abserde.checkout(&history[1].id).unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			first_config
+		);
+		assert_eq!(abserde.history().unwrap().len(), 3);
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		let history = abserde.history().unwrap();
+
+		assert!(history[0].message.starts_with("Delete config.json of "));
+		assert!(matches!(
+			abserde.checkout(&history[0].id).unwrap_err().root(),
+			Error::NotFound(_)
+		));
+		assert!(matches!(
+			Abserde {
+				git: false,
+				..abserde.clone()
+			}
+			.history()
+			.unwrap_err()
+			.root(),
+			Error::Unsupported(_)
+		));
+	}
+
+	#[cfg(all(feature = "json", feature = "testing"))]
+	#[test]
+	fn test_json_temp_abserde() {
+		let mut abserde = testing::TempAbserde::new(Format::Json);
+		let config: TestConfigComplex = Faker.fake();
+
+		assert!(abserde.config_path().unwrap().starts_with(abserde.dir()));
+
+		abserde.seed_with(&config).unwrap();
+
+		assert_eq!(TestConfigComplex::load_config(&abserde).unwrap(), config);
+		assert_eq!(
+			serde_json::from_slice::<TestConfigComplex>(&abserde.raw_contents().unwrap()).unwrap(),
+			config
+		);
+
+		abserde.app = "renamed".to_string();
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		let dir = abserde.dir().to_owned();
+
+		drop(abserde);
+
+		assert!(!dir.exists());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_capture() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let capture = Capture::new();
+		let capture_abserde = Abserde {
+			capture: Some(capture.clone()),
+			..abserde.clone()
+		};
+		let saved_config: TestConfigComplex = Faker.fake();
+		let captured_config: TestConfigComplex = Faker.fake();
+
+		saved_config.save_config(&abserde).unwrap();
+		captured_config.save_config(&capture_abserde).unwrap();
+
+		assert_eq!(capture_abserde.capture, Some(capture.clone()));
+		assert_ne!(capture_abserde.capture, Some(Capture::new()));
+		assert_eq!(
+			TestConfigComplex::load_config(&capture_abserde).unwrap(),
+			saved_config
+		);
+		assert_eq!(
+			serde_json::from_slice::<TestConfigComplex>(&capture.last().unwrap()).unwrap(),
+			captured_config
+		);
+
+		let writes = capture.take();
+
+		assert_eq!(writes.len(), 1);
+		assert_eq!(writes[0].0, abserde.config_path().unwrap());
+		assert!(capture.writes().is_empty());
+		assert_eq!(capture.last(), None);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_config_string() {
+		let config: TestConfigComplex = Faker.fake();
+		let string = config.to_config_string(&Format::Json).unwrap();
+
+		assert_eq!(string, serde_json::to_string(&config).unwrap());
+		assert_eq!(
+			TestConfigComplex::from_config_str(&string, &Format::Json).unwrap(),
+			config
+		);
+
+		let capture = Capture::new();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
+			schema_version: Some(2),
+			capture: Some(capture.clone()),
+			..Default::default()
+		};
+
+		config.save_config(&abserde).unwrap();
+
+		let saved = String::from_utf8(capture.last().unwrap()).unwrap();
+
+		assert_eq!(
+			TestConfigComplex::from_config_str(&saved, &abserde.format).unwrap(),
+			config
+		);
+		assert!(matches!(
+			TestConfigComplex::from_config_str("{", &Format::Json),
+			Err(Error::Deserialize(_))
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_config_stream() {
+		let config: TestConfigComplex = Faker.fake();
+		let mut stream = io::Cursor::new(Vec::new());
+
+		config.save_config_to(&mut stream, &Format::Json).unwrap();
+		config
+			.save_config_to(&mut stream, &Format::PrettyJson(PrettyJsonIndent::Tab))
+			.unwrap();
+
+		let data = stream.into_inner();
+		let first_len = serde_json::to_vec(&config).unwrap().len();
+
+		assert_eq!(
+			TestConfigComplex::load_config_from(&data[..first_len], &Format::Json).unwrap(),
+			config
+		);
+		assert_eq!(
+			TestConfigComplex::load_config_from(&data[first_len..], &Format::Json).unwrap(),
+			config
+		);
+		assert!(matches!(
+			TestConfigComplex::load_config_from(&data[..], &Format::Json),
+			Err(Error::Deserialize(_))
+		));
+	}
+
+	#[cfg(all(feature = "json", unix))]
+	#[test]
+	fn test_json_dir_private_permissions() {
+		use std::fs::{metadata, set_permissions, Permissions};
+		use std::os::unix::fs::PermissionsExt;
+
+		let tmp_dir = TempDir::new().unwrap();
+		let config_dir = tmp_dir.path().join("private");
+		let mode = |path: &Path| metadata(path).unwrap().permissions().mode() & 0o777;
+
+		for atomic in [true, false] {
+			let abserde = Abserde {
+				app: APP_NAME.to_string(),
+				location: Location::Dir(config_dir.clone()),
+				format: Format::Json,
+				atomic,
+				permissions: FilePermissions::Private,
+				..Default::default()
+			};
+			let config_path = abserde.config_path().unwrap();
+
+			test_save_load_delete::<TestConfigComplex>(&abserde);
+
+			let config: TestConfigComplex = Faker.fake();
+
+			remove_dir_all(&config_dir).unwrap();
+			config.save_config(&abserde).unwrap();
+
+			assert_eq!(mode(&config_path), 0o600);
+			assert_eq!(mode(&config_dir), 0o700);
+
+			// Config files are restricted again, but existing directories are left as they are.
+			set_permissions(&config_path, Permissions::from_mode(0o644)).unwrap();
+			set_permissions(&config_dir, Permissions::from_mode(0o755)).unwrap();
+			config.save_config(&abserde).unwrap();
+
+			assert_eq!(mode(&config_path), 0o600);
+			assert_eq!(mode(&config_dir), 0o755);
+
+			abserde.delete().unwrap();
+		}
+	}
+
+	#[cfg(all(feature = "json", feature = "derive"))]
+	#[test]
+	#[serial]
+	fn test_json_derive() {
+		#[derive(Serialize, Deserialize, AbserdeConfig, Debug, PartialEq)]
+		#[abserde(
+			app = "abserde-derive",
+			organization = "Abserde Tests",
+			name = "derived",
+			extension = "conf",
+			format = "json"
+		)]
+		struct TestConfigDerived {
+			#[abserde(env = "ABSERDE_DERIVE_PORT")]
+			port: u16,
+			#[abserde(env = "ABSERDE_DERIVE_HOST")]
+			host: String,
+			#[serde(rename = "display_name")]
+			#[abserde(file = "derived-name")]
+			name: String,
+		}
+
+		let abserde = TestConfigDerived::abserde();
+		let config = TestConfigDerived {
+			port: 8080,
+			host: "localhost".to_string(),
+			name: "derived".to_string(),
+		};
+
+		assert_eq!(abserde.app, "abserde-derive");
+		assert_eq!(abserde.organization.as_deref(), Some("Abserde Tests"));
+		assert_eq!(abserde.name.as_deref(), Some("derived"));
+		assert_eq!(abserde.extension.as_deref(), Some("conf"));
+		assert_eq!(abserde.format, Format::Json);
+		assert_eq!(
+			abserde.section_files,
+			BTreeMap::from([("display_name".to_string(), "derived-name".to_string())])
+		);
+		assert!(std::ptr::eq(abserde, TestConfigDerived::abserde()));
+
+		let section_path = abserde
+			.config_path()
+			.unwrap()
+			.with_file_name("derived-name.conf");
+
+		config.save().unwrap();
+
+		assert_eq!(TestConfigDerived::load().unwrap(), config);
+		assert_eq!(read(&section_path).unwrap(), b"\"derived\"");
+
+		std::env::set_var("ABSERDE_DERIVE_PORT", "9090");
+		std::env::set_var("ABSERDE_DERIVE_HOST", "127");
+
+		let overridden = TestConfigDerived::load();
+
+		std::env::set_var("ABSERDE_DERIVE_PORT", "not a port");
+
+		let invalid = TestConfigDerived::load();
+
+		std::env::remove_var("ABSERDE_DERIVE_PORT");
+		std::env::remove_var("ABSERDE_DERIVE_HOST");
+
+		assert_eq!(
+			overridden.unwrap(),
+			TestConfigDerived {
+				port: 9090,
+				host: "127".to_string(),
+				..config
+			}
+		);
+		assert!(matches!(invalid.unwrap_err(), Error::Deserialize(_)));
+
+		TestConfigDerived::delete().unwrap();
+
+		assert!(!section_path.exists());
+		assert!(matches!(
+			TestConfigDerived::load().unwrap_err().root(),
+			Error::NotFound(_)
+		));
+	}
+
+	#[cfg(all(feature = "derive", feature = "toml", feature = "yaml"))]
+	#[test]
+	fn test_derive_sample_document() {
+		/// Settings of the test app.
+		///
+		/// Edit with care.
+		#[derive(Serialize, Deserialize, AbserdeConfig, Debug, PartialEq)]
+		#[abserde(app = "abserde-derive")]
+		struct TestConfigDocumented {
+			/// Port to listen on.
+			#[serde(default, rename = "listen_port")]
+			port: u16,
+			hosts: Vec<String>,
+			/// Size of the main window.
+			window: TestWindow,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestWindow {
+			/// Not documented in samples, as only top-level fields are.
+			width: usize,
+		}
+
+		impl Default for TestConfigDocumented {
+			fn default() -> Self {
+				Self {
+					port: 8080,
+					hosts: vec!["localhost".to_string()],
+					window: TestWindow { width: 800 },
+				}
+			}
+		}
+
+		let toml = TestConfigDocumented::sample_document(&Format::Toml).unwrap();
+		let yaml = TestConfigDocumented::sample_document(&Format::Yaml).unwrap();
+
+		assert_eq!(
+			toml,
+			"# Settings of the test app.\n#\n# Edit with care.\n\n# Port to listen on.\nlisten_port = 8080\n\
+			 hosts = [\"localhost\"]\n\n# Size of the main window.\n[window]\nwidth = 800\n"
+		);
+		assert_eq!(
+			yaml,
+			"# Settings of the test app.\n#\n# Edit with care.\n\n# Port to listen on.\nlisten_port: 8080\n\
+			 hosts:\n- localhost\n# Size of the main window.\nwindow:\n  width: 800\n"
+		);
+		assert_eq!(
+			TestConfigDocumented::from_config_str(&toml, &Format::Toml).unwrap(),
+			TestConfigDocumented::default()
+		);
+		assert_eq!(
+			TestConfigDocumented::from_config_str(&yaml, &Format::Yaml).unwrap(),
+			TestConfigDocumented::default()
+		);
+		assert!(matches!(
+			TestConfigDocumented::sample_document(&Format::Json),
+			Err(Error::Unsupported(_))
+		));
+
+		let tmp_dir = TempDir::new().unwrap();
+		let template_path = tmp_dir.path().join("doc").join("config.example.yaml");
+
+		Abserde {
+			format: Format::FromExtension,
+			..Default::default()
+		}
+		.write_commented_template::<TestConfigDocumented, _>(&template_path)
+		.unwrap();
+
+		assert_eq!(std::fs::read_to_string(template_path).unwrap(), yaml);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_template() {
+		let tmp_dir = TempDir::new().unwrap();
+		let template_path = tmp_dir.path().join("doc").join("config.example.json");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("config")),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		abserde
+			.write_template::<HashMap<String, u32>, _>(&template_path)
+			.unwrap();
+
+		assert_eq!(std::fs::read_to_string(&template_path).unwrap(), "{}");
+		assert!(!abserde.exists().unwrap());
+		assert!(matches!(
+			Abserde {
+				format: Format::FromExtension,
+				..abserde
+			}
+			.write_template::<HashMap<String, u32>, _>(tmp_dir.path().join("config.example")),
+			Err(Error::Unsupported(_))
+		));
+	}
+
+	#[cfg(all(feature = "json", feature = "schemars"))]
+	#[test]
+	fn test_json_dir_schema() {
+		#[derive(Serialize, Deserialize, schemars::JsonSchema)]
+		struct TestConfigSchema {
+			/// Port to listen on.
+			port: u16,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		}
+		.named("server");
+		let schema_path = abserde.write_schema::<TestConfigSchema>().unwrap();
+
+		assert_eq!(schema_path, tmp_dir.path().join("server.schema.json"));
+		assert_eq!(schema_path, abserde.schema_path().unwrap());
+
+		let schema: serde_json::Value =
+			serde_json::from_str(&std::fs::read_to_string(&schema_path).unwrap()).unwrap();
+
+		assert_eq!(schema["title"], "TestConfigSchema");
+		assert_eq!(schema["properties"]["port"]["type"], "integer");
+		assert_eq!(
+			schema["properties"]["port"]["description"],
+			"Port to listen on."
+		);
+		assert_eq!(
+			abserde.schema_string::<TestConfigSchema>().unwrap(),
+			std::fs::read_to_string(&schema_path).unwrap()
+		);
+		assert!(matches!(
+			Abserde {
+				storage: Storage::Memory,
+				..abserde
+			}
+			.write_schema::<TestConfigSchema>()
+			.unwrap_err()
+			.root(),
+			Error::Unsupported(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_dynamic() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let mut config = DynamicConfig::new();
+
+		assert_eq!(*config, serde_json::json!({}));
+
+		config.set("window.width", 800u32).unwrap();
+		config.set("theme", "dark").unwrap();
+		config.save_config(&abserde).unwrap();
+
+		let config = DynamicConfig::load_config(&abserde).unwrap();
+
+		assert_eq!(config.get::<u32>("window.width").unwrap(), Some(800));
+		assert_eq!(
+			config.get::<String>("theme").unwrap(),
+			Some("dark".to_string())
+		);
+		assert_eq!(config.get::<u32>("window.height").unwrap(), None);
+		assert!(matches!(
+			config.get::<u32>("theme"),
+			Err(Error::Deserialize(_))
+		));
+		assert_eq!(
+			config.into_inner(),
+			serde_json::json!({"window": {"width": 800}, "theme": "dark"})
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "log"))]
+	#[test]
+	fn test_json_dir_log() {
+		use std::sync::Mutex;
+
+		// Logger recording the messages of every record, which is shared by all tests.
+		struct Recorder(Mutex<Vec<String>>);
+
+		impl log::Log for Recorder {
+			fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+				true
+			}
+
+			fn log(&self, record: &log::Record<'_>) {
+				self.0.lock().unwrap().push(record.args().to_string());
+			}
+
+			fn flush(&self) {}
+		}
+
+		static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+		_ = log::set_logger(&RECORDER);
+		log::set_max_level(log::LevelFilter::Debug);
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			backups: 1,
+			..Default::default()
+		};
+		let config_path = tmp_dir.path().join("config.json");
+		let backup_path = backup_file_path(&config_path, 1);
+		let config: TestConfigComplex = Faker.fake();
+
+		config.save_config(&abserde).unwrap();
+		config.save_config(&abserde).unwrap();
+		write(&config_path, "corrupt").unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config_with_recovery(&abserde).unwrap(),
+			(config, backup_path.clone())
+		);
+
+		let records = RECORDER.0.lock().unwrap();
+		let expected = [
+			format!("save config at {}", config_path.display()),
+			format!(
+				"rotated backups of config file {}, keeping 1",
+				config_path.display()
+			),
+			format!("load config at {}", config_path.display()),
+		];
+
+		for expected in expected {
+			assert!(records.contains(&expected), "missing {}", expected);
+		}
+
+		assert!(records.iter().any(|record| record.starts_with(&format!(
+			"config file {} is corrupt, recovered it from backup {}: ",
+			config_path.display(),
+			backup_path.display()
+		))));
+	}
+
+	#[cfg(all(feature = "json", feature = "tracing"))]
+	#[test]
+	fn test_json_dir_tracing() {
+		use std::collections::BTreeMap;
+		use std::sync::{Arc, Mutex};
+
+		use tracing::field::{Field, Visit};
+		use tracing::span::{Attributes, Id, Record};
+		use tracing::{Event, Metadata, Subscriber};
+		use tracing_core::span::Current;
+
+		// Subscriber recording the fields of spans, and the messages of events.
+		#[derive(Clone, Default)]
+		struct Recorder {
+			spans: Arc<Mutex<Vec<BTreeMap<String, String>>>>,
+			metadata: Arc<Mutex<Vec<&'static Metadata<'static>>>>,
+			entered: Arc<Mutex<Vec<Id>>>,
+			events: Arc<Mutex<Vec<String>>>,
+		}
+
+		struct Fields<'a>(&'a mut BTreeMap<String, String>);
+
+		impl Visit for Fields<'_> {
+			fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+				self.0
+					.insert(field.name().to_string(), format!("{:?}", value));
+			}
+		}
+
+		impl Subscriber for Recorder {
+			fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+				true
+			}
+
+			fn new_span(&self, span: &Attributes<'_>) -> Id {
+				let mut spans = self.spans.lock().unwrap();
+				let mut fields = BTreeMap::new();
+
+				span.record(&mut Fields(&mut fields));
+				spans.push(fields);
+				self.metadata.lock().unwrap().push(span.metadata());
+
+				Id::from_u64(spans.len() as u64)
+			}
+
+			fn record(&self, span: &Id, values: &Record<'_>) {
+				values.record(&mut Fields(
+					&mut self.spans.lock().unwrap()[span.into_u64() as usize - 1],
+				));
+			}
+
+			fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+			fn event(&self, event: &Event<'_>) {
+				let mut fields = BTreeMap::new();
+
+				event.record(&mut Fields(&mut fields));
+				self.events
+					.lock()
+					.unwrap()
+					.push(fields.remove("message").unwrap_or_default());
+			}
+
+			fn enter(&self, span: &Id) {
+				self.entered.lock().unwrap().push(span.clone());
+			}
+
+			fn exit(&self, _span: &Id) {
+				self.entered.lock().unwrap().pop();
+			}
+
+			fn current_span(&self) -> Current {
+				match self.entered.lock().unwrap().last() {
+					Some(span) => Current::new(
+						span.clone(),
+						self.metadata.lock().unwrap()[span.into_u64() as usize - 1],
+					),
+					None => Current::none(),
+				}
+			}
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let config_path = tmp_dir.path().join("config.json");
+		let config: TestConfigComplex = Faker.fake();
+		let recorder = Recorder::default();
+
+		tracing::subscriber::with_default(recorder.clone(), || {
+			assert!(TestConfigComplex::load_config(&abserde).is_err());
+			config.save_config(&abserde).unwrap();
+			TestConfigComplex::load_config(&abserde).unwrap();
+		});
+
+		let spans = recorder.spans.lock().unwrap();
+		let bytes = std::fs::metadata(&config_path).unwrap().len().to_string();
+
+		assert_eq!(spans.len(), 3);
+		assert_eq!(spans[0].get("operation").unwrap(), "load");
+		assert_eq!(spans[0].get("bytes"), None);
+		assert_eq!(spans[1].get("operation").unwrap(), "save");
+		assert_eq!(spans[1].get("app").unwrap(), APP_NAME);
+		assert_eq!(
+			spans[1].get("path").unwrap(),
+			&config_path.display().to_string()
+		);
+		assert_eq!(spans[1].get("format").unwrap(), "Json");
+		assert_eq!(spans[1].get("bytes").unwrap(), &bytes);
+		assert_eq!(spans[2].get("operation").unwrap(), "load");
+		assert_eq!(spans[2].get("bytes").unwrap(), &bytes);
+		assert_eq!(
+			*recorder.events.lock().unwrap(),
+			[
+				"config load failed",
+				"config save finished",
+				"config load finished"
+			]
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "mmap"))]
+	#[test]
+	fn test_json_dir_mmap() {
+		#[derive(Deserialize)]
+		struct Borrowed<'a> {
+			name: &'a str,
+			values: Vec<u32>,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			schema_version: Some(1),
+			..Default::default()
+		};
+
+		// SAFETY: The config file is only changed in place once the mapped config is dropped.
+		assert!(matches!(
+			unsafe { abserde.map_config() }.unwrap_err().root(),
+			Error::NotFound(_)
+		));
+
+		serde_json::json!({"name": "dataset", "values": [1, 2, 3]})
+			.save_config(&abserde)
+			.unwrap();
+
+		let mapped = unsafe { abserde.map_config() }.unwrap();
+		let config: Borrowed = mapped.config().unwrap();
+
+		assert!(mapped.is_mapped());
+		assert_eq!(config.name, "dataset");
+		assert_eq!(config.values, [1, 2, 3]);
+		assert!(mapped
+			.as_bytes()
+			.as_ptr_range()
+			.contains(&config.name.as_ptr()));
+
+		drop(mapped);
+
+		let utf16 = r#"{"name": "utf16", "values": []}"#.encode_utf16();
+
+		write(
+			tmp_dir.path().join("config.json"),
+			[0xff, 0xfe]
+				.into_iter()
+				.chain(utf16.flat_map(u16::to_le_bytes))
+				.collect::<Vec<_>>(),
+		)
+		.unwrap();
+
+		let mapped = unsafe { abserde.map_config() }.unwrap();
+
+		assert!(!mapped.is_mapped());
+		assert_eq!(mapped.config::<Borrowed>().unwrap().name, "utf16");
+
+		let abserde = Abserde {
+			storage: Storage::Memory,
+			..abserde
+		};
+
+		serde_json::json!({"name": "memory", "values": []})
+			.save_config(&abserde)
+			.unwrap();
+
+		let mapped = unsafe { abserde.map_config() }.unwrap();
+
+		assert!(!mapped.is_mapped());
+		assert_eq!(mapped.config::<Borrowed>().unwrap().name, "memory");
+		abserde.delete().unwrap();
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_export() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("app")),
+			format: Format::PrettyJson(PrettyJsonIndent::Tab),
+			..Default::default()
+		};
+		let export_path = tmp_dir.path().join("exports").join("settings.json");
+		let configs: [TestConfigComplex; 2] = Faker.fake();
+
+		configs[0].save_config(&abserde).unwrap();
+		abserde
+			.export_to::<TestConfigComplex, _>(&export_path, &Format::FromExtension)
+			.unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config_from(
+				read(&export_path).unwrap().as_slice(),
+				&Format::Json
+			)
+			.unwrap(),
+			configs[0]
+		);
+
+		configs[1].save_config(&abserde).unwrap();
+
+		assert_eq!(
+			abserde
+				.import_from::<TestConfigComplex, _>(&export_path, &Format::Json)
+				.unwrap(),
+			configs[0]
+		);
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			configs[0]
+		);
+
+		write(&export_path, r#"{"theme": "dark"}"#).unwrap();
+
+		assert!(matches!(
+			abserde
+				.import_from::<TestConfigComplex, _>(&export_path, &Format::Json)
+				.unwrap_err(),
+			Error::Deserialize(_)
+		));
+		assert!(matches!(
+			abserde
+				.import_from::<TestConfigComplex, _>(
+					tmp_dir.path().join("missing.json"),
+					&Format::Json
+				)
+				.unwrap_err(),
+			Error::NotFound(_)
+		));
+		assert!(matches!(
+			abserde
+				.export_to::<TestConfigComplex, _>(
+					tmp_dir.path().join("settings"),
+					&Format::FromExtension
+				)
+				.unwrap_err(),
+			Error::Unsupported(_)
+		));
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			configs[0]
+		);
+	}
+
+	#[test]
+	fn test_for_crate() {
+		assert_eq!(for_crate!().app, APP_NAME);
+		assert_eq!(
+			Abserde::for_app("MyApp"),
+			Abserde {
+				app: "MyApp".to_string(),
+				..Default::default()
+			}
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_abserde_serde() {
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			name: Some("keybindings".to_string()),
+			location: Location::Search(vec![
+				Location::Dir(PathBuf::from("/etc/myapp")),
+				Location::EnvVar("MYAPP_CONFIG".to_string()),
+				Location::Auto,
+			]),
+			format: Format::PrettyJson(PrettyJsonIndent::Spaces(2)),
+			line_ending: LineEnding::CrLf,
+			backups: 3,
+			lock_timeout: Some(Duration::from_millis(500)),
+			..Default::default()
+		};
+		let json = serde_json::to_string(&abserde).unwrap();
+
+		assert_eq!(serde_json::from_str::<Abserde>(&json).unwrap(), abserde);
+
+		let abserde: Abserde = serde_json::from_str(
+			r#"{"app": "MyApp", "location": {"Dir": "/etc/myapp"}, "format": "Json"}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			abserde,
+			Abserde {
+				app: "MyApp".to_string(),
+				location: Location::Dir(PathBuf::from("/etc/myapp")),
+				format: Format::Json,
+				..Default::default()
+			}
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_parse_format_location() {
+		for (s, format) in [
+			("json", Format::Json),
+			("pretty-json", Format::PrettyJson(PrettyJsonIndent::Tab)),
+			(
+				"pretty-json:2",
+				Format::PrettyJson(PrettyJsonIndent::Spaces(2)),
+			),
+			("from-extension", Format::FromExtension),
+		] {
+			assert_eq!(s.parse::<Format>().unwrap(), format);
+			assert_eq!(format.to_string(), s);
+		}
+
+		assert_eq!(
+			"Pretty-JSON:tab".parse::<Format>().unwrap(),
+			Format::PrettyJson(PrettyJsonIndent::Tab)
+		);
+
+		match "bson".parse::<Format>().unwrap_err() {
+			Error::Parse { kind, reason, .. } => {
+				assert_eq!(kind, "format");
+				assert!(reason.contains("json"));
+				assert!(reason.contains("pretty-json"));
+			}
+			err => panic!("unexpected error: {}", err),
+		}
+
+		assert!(matches!(
+			"pretty-json:wide".parse::<Format>(),
+			Err(Error::Parse { .. })
+		));
+
+		for (s, location) in [
+			("auto", Location::Auto),
+			("auto-state", Location::AutoState),
+			("system", Location::System),
+			("dir:/etc/myapp", Location::Dir(PathBuf::from("/etc/myapp"))),
+			(
+				"path:config.json",
+				Location::Path(PathBuf::from("config.json")),
+			),
+			(
+				"env:MYAPP_CONFIG",
+				Location::EnvVar("MYAPP_CONFIG".to_string()),
+			),
+			(
+				"search:dir:/etc/myapp;auto",
+				Location::Search(vec![
+					Location::Dir(PathBuf::from("/etc/myapp")),
+					Location::Auto,
+				]),
+			),
+			(
+				"fallback:auto;system",
+				Location::Fallback(vec![Location::Auto, Location::System]),
+			),
+			(
+				"platform:linux=path:/home/me/.myapp;fallback=auto",
+				Location::Platform(Box::new(PlatformLocations {
+					linux: Some(Location::Path(PathBuf::from("/home/me/.myapp"))),
+					..Default::default()
+				})),
+			),
+		] {
+			assert_eq!(s.parse::<Location>().unwrap(), location);
+			assert_eq!(location.to_string(), s);
+		}
+
+		assert!(matches!(
+			"dir:".parse::<Location>(),
+			Err(Error::Parse { .. })
+		));
+		assert!(matches!(
+			"elsewhere".parse::<Location>(),
+			Err(Error::Parse { .. })
+		));
+	}
+
+	#[cfg(feature = "custom")]
+	#[test]
+	fn test_custom_dir_format() {
+		// Keys and values as lines of `key=value`, with values in JSON.
+		#[derive(Debug)]
+		struct KeyValue;
+
+		impl ConfigFormat for KeyValue {
+			fn extension(&self) -> &str {
+				"kv"
+			}
+
+			fn serialize(
+				&self,
+				config: &dyn erased_serde::Serialize,
+				writer: &mut dyn io::Write,
+			) -> Result<()> {
+				let value =
+					serde_json::to_value(config).map_err(|err| Error::Serialize(err.into()))?;
+
+				for (key, value) in value.as_object().unwrap() {
+					writeln!(writer, "{}={}", key, value)?;
+				}
+
+				Ok(())
+			}
+
+			fn deserialize(
+				&self,
+				reader: &mut dyn io::Read,
+			) -> Result<Box<dyn erased_serde::Deserializer<'static>>> {
+				let mut data = String::new();
+
+				reader.read_to_string(&mut data)?;
+
+				let map = data
+					.lines()
+					.map(|line| {
+						let (key, value) = line.split_once('=').unwrap();
+
+						Ok((key.to_string(), serde_json::from_str(value)?))
+					})
+					.collect::<result::Result<serde_json::Map<_, _>, serde_json::Error>>()
+					.map_err(|err| Error::Deserialize(err.into()))?;
+
+				Ok(Box::new(<dyn erased_serde::Deserializer>::erase(
+					serde_json::Value::Object(map),
+				)))
+			}
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let format = Format::Custom(std::sync::Arc::new(KeyValue));
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: format.clone(),
+			..Default::default()
+		};
+		let test_config: TestConfigSimple = Faker.fake();
+
+		assert_eq!(
+			abserde.config_path().unwrap(),
+			tmp_dir.path().join("config.kv")
+		);
+		assert_eq!(format, format.clone());
+		assert_ne!(format, Format::Custom(std::sync::Arc::new(KeyValue)));
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert!(
+			String::from_utf8(read(abserde.config_path().unwrap()).unwrap())
+				.unwrap()
+				.contains("string_val=")
+		);
+		assert_eq!(
+			TestConfigSimple::load_config(&abserde).unwrap(),
+			test_config
+		);
+
+		write(abserde.config_path().unwrap(), "string_val=1").unwrap();
+
+		assert!(matches!(
+			TestConfigSimple::load_config(&abserde).unwrap_err().root(),
+			Error::Deserialize(_)
+		));
+	}
+
+	#[cfg(feature = "yaml")]
+	#[test]
+	fn test_yaml_dir_preserve_comments() {
+		#[derive(Serialize, Deserialize, PartialEq, Debug)]
+		struct Config {
+			name: String,
+			width: u32,
+			tags: Vec<String>,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Yaml,
+			preserve_comments: true,
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+		let mut config = Config {
+			name: "My App".to_string(),
+			width: 800,
+			tags: vec!["a".to_string()],
+		};
+
+		config.save_config(&abserde).unwrap();
+		write(
+			&config_path,
+			"# Settings of My App\nname: 'My App' # shown in the title bar\nwidth: &width 800\ntags:\n- a # first\n",
+		)
+		.unwrap();
+
+		config.name = "Your App: 2".to_string();
+		config.width = 1024;
+		config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			std::fs::read_to_string(&config_path).unwrap(),
+			"# Settings of My App\nname: 'Your App: 2' # shown in the title bar\nwidth: &width 1024\ntags:\n- a # first\n"
+		);
+		assert_eq!(Config::load_config(&abserde).unwrap(), config);
+
+		config.tags.push("b".to_string());
+		config.save_config(&abserde).unwrap();
+
+		assert!(!std::fs::read_to_string(&config_path).unwrap().contains('#'));
+		assert_eq!(Config::load_config(&abserde).unwrap(), config);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_include() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			include_key: Some("include".to_string()),
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+
+		std::fs::create_dir(tmp_dir.path().join("nested")).unwrap();
+		write(
+			&config_path,
+			r#"{"include": ["base.json", "colors.json"], "a": 1}"#,
+		)
+		.unwrap();
+		write(
+			tmp_dir.path().join("base.json"),
+			r#"{"include": "nested/extra.json", "a": 0, "b": {"x": 1, "y": 2}}"#,
+		)
+		.unwrap();
+		write(tmp_dir.path().join("colors.json"), r#"{"b": {"y": 3}}"#).unwrap();
+		write(tmp_dir.path().join("nested/extra.json"), r#"{"c": 3}"#).unwrap();
+
+		assert_eq!(
+			serde_json::Value::load_config(&abserde).unwrap(),
+			serde_json::json!({"a": 1, "b": {"x": 1, "y": 3}, "c": 3})
+		);
+
+		// Saving would merge the included config files into the config file.
+		assert!(matches!(
+			serde_json::Value::load_config(&abserde)
+				.unwrap()
+				.save_config(&abserde)
+				.unwrap_err()
+				.root(),
+			Error::Unsupported(_)
+		));
+		assert_eq!(
+			std::fs::read_to_string(&config_path).unwrap(),
+			r#"{"include": ["base.json", "colors.json"], "a": 1}"#
+		);
+
+		write(
+			tmp_dir.path().join("nested/extra.json"),
+			r#"{"include": "../config.json"}"#,
+		)
+		.unwrap();
+
+		assert!(matches!(
+			serde_json::Value::load_config(&abserde).unwrap_err().root(),
+			Error::IncludeCycle(path) if path.ends_with("config.json")
+		));
+
+		write(&config_path, r#"{"include": "missing.json"}"#).unwrap();
+
+		assert!(matches!(
+			serde_json::Value::load_config(&abserde).unwrap_err().root(),
+			Error::NotFound(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_interpolate() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			interpolate: true,
+			..Default::default()
+		};
+		let var = |name: &str| match name {
+			"HOME" => Some("/home/user".to_string()),
+			"APPDATA" => Some("C:\\Users\\user\\AppData\\Roaming".to_string()),
+			_ => None,
+		};
+		let mut value = serde_json::json!({
+			"data": "${HOME}/.local/share",
+			"paths": ["%APPDATA%\\MyApp", "$${HOME}", "100%% of ${HOME}", "50% and 20%", "$5 ${unclosed"],
+			"port": 8080,
+		});
+
+		interpolate::interpolate_value(&mut value, &var).unwrap();
+
+		assert_eq!(
+			value,
+			serde_json::json!({
+				"data": "/home/user/.local/share",
+				"paths": [
+					"C:\\Users\\user\\AppData\\Roaming\\MyApp",
+					"${HOME}",
+					"100% of /home/user",
+					"50% and 20%",
+					"$5 ${unclosed",
+				],
+				"port": 8080,
+			})
+		);
+
+		std::env::set_var("ABSERDE_TEST_INTERPOLATE_DIR", "/srv/myapp");
+		write(
+			abserde.config_path().unwrap(),
+			r#"{"data_dir": "${ABSERDE_TEST_INTERPOLATE_DIR}/data"}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			HashMap::<String, String>::load_config(&abserde).unwrap(),
+			HashMap::from([("data_dir".to_string(), "/srv/myapp/data".to_string())])
+		);
+
+		// Saving would replace the variables with their values in the config file.
+		let config = HashMap::<String, String>::load_config(&abserde).unwrap();
+
+		assert!(matches!(
+			config.save_config(&abserde).unwrap_err().root(),
+			Error::Unsupported(_)
+		));
+		assert!(matches!(
+			abserde
+				.set_value("data_dir", serde_json::json!("$${HOME}"))
+				.unwrap_err()
+				.root(),
+			Error::Unsupported(_)
+		));
+		assert_eq!(
+			std::fs::read_to_string(abserde.config_path().unwrap()).unwrap(),
+			r#"{"data_dir": "${ABSERDE_TEST_INTERPOLATE_DIR}/data"}"#
+		);
+
+		write(
+			abserde.config_path().unwrap(),
+			r#"{"data_dir": "${ABSERDE_TEST_INTERPOLATE_UNSET}/data"}"#,
+		)
+		.unwrap();
+
+		assert!(matches!(
+			HashMap::<String, String>::load_config(&abserde).unwrap_err().root(),
+			Error::UndefinedVariable(name) if name == "ABSERDE_TEST_INTERPOLATE_UNSET"
+		));
+	}
+
+	#[test]
+	fn test_mobile_dirs() {
+		let files_dir = Path::new("/data/user/0/com.example.myapp/files");
+		let home_dir = Path::new("/var/mobile/Containers/Data/Application/MyApp");
+
+		assert_eq!(
+			mobile::android_dir_in(files_dir, sandbox::UserDir::Config),
+			files_dir.join("config")
+		);
+		assert_eq!(
+			mobile::android_dir_in(files_dir, sandbox::UserDir::Cache),
+			Path::new("/data/user/0/com.example.myapp/cache")
+		);
+		assert_eq!(
+			mobile::ios_dir_in(home_dir, sandbox::UserDir::Config),
+			home_dir.join("Library/Preferences")
+		);
+		assert_eq!(
+			mobile::ios_dir_in(home_dir, sandbox::UserDir::Data),
+			home_dir.join("Library/Application Support")
+		);
+		assert_eq!(
+			mobile::ios_dir_in(home_dir, sandbox::UserDir::Cache),
+			home_dir.join("Library/Caches")
+		);
+	}
+
+	#[test]
+	fn test_sandbox_host_dirs() {
+		let vars = |vars: &'static [(&'static str, &'static str)]| {
+			move |name: &'static str| {
+				vars.iter()
+					.find(|(var, _)| *var == name)
+					.map(|(_, value)| OsString::from(value))
+			}
+		};
+		let flatpak = vars(&[
+			("FLATPAK_ID", "org.example.MyApp"),
+			("HOME", "/home/user"),
+			("HOST_XDG_CONFIG_HOME", "/home/user/.xdg-config"),
+		]);
+		let snap = vars(&[
+			("SNAP", "/snap/myapp/42"),
+			("SNAP_NAME", "myapp"),
+			("HOME", "/home/user/snap/myapp/42"),
+			("SNAP_REAL_HOME", "/home/user"),
+		]);
+		let native = vars(&[("HOME", "/home/user")]);
+
+		assert_eq!(
+			Environment::detect_with(flatpak, false),
+			Environment::Flatpak
+		);
+		assert_eq!(Environment::detect_with(snap, false), Environment::Snap);
+		assert_eq!(Environment::detect_with(native, false), Environment::Native);
+		assert_eq!(Environment::detect_with(native, true), Environment::Flatpak);
+		assert_eq!(
+			Environment::Flatpak.host_dir(sandbox::UserDir::Config, flatpak),
+			Some(PathBuf::from("/home/user/.xdg-config"))
+		);
+		assert_eq!(
+			Environment::Flatpak.host_dir(sandbox::UserDir::Data, flatpak),
+			Some(PathBuf::from("/home/user/.local/share"))
+		);
+		assert_eq!(
+			Environment::Snap.host_dir(sandbox::UserDir::Config, snap),
+			Some(PathBuf::from("/home/user/.config"))
+		);
+		assert_eq!(
+			Environment::Native.host_dir(sandbox::UserDir::Config, native),
+			None
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_namespace() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let simple_abserde = abserde.clone().namespaced("simple");
+		let window_abserde = abserde.clone().namespaced("window");
+		let simple_config: TestConfigSimple = Faker.fake();
+		let window_config =
+			HashMap::from([("width".to_string(), 1920), ("height".to_string(), 1080)]);
+
+		assert!(matches!(
+			TestConfigSimple::load_config(&simple_abserde)
+				.unwrap_err()
+				.root(),
+			Error::NotFound(_)
+		));
+
+		simple_config.save_config(&simple_abserde).unwrap();
+		window_config.save_config(&window_abserde).unwrap();
+
+		let configs: HashMap<String, serde_json::Value> =
+			serde_json::from_slice(&read(abserde.config_path().unwrap()).unwrap()).unwrap();
+
+		assert_eq!(configs.len(), 2);
+		assert_eq!(
+			TestConfigSimple::load_config(&simple_abserde).unwrap(),
+			simple_config
+		);
+		assert_eq!(
+			HashMap::<String, u32>::load_config(&window_abserde).unwrap(),
+			window_config
+		);
+
+		let simple_config: TestConfigSimple = Faker.fake();
+
+		simple_config.save_config(&simple_abserde).unwrap();
+
+		assert_eq!(
+			TestConfigSimple::load_config(&simple_abserde).unwrap(),
+			simple_config
+		);
+		assert_eq!(
+			HashMap::<String, u32>::load_config(&window_abserde).unwrap(),
+			window_config
+		);
+
+		simple_abserde.delete().unwrap();
+
+		assert!(matches!(
+			TestConfigSimple::load_config(&simple_abserde)
+				.unwrap_err()
+				.root(),
+			Error::NotFound(_)
+		));
+		assert!(simple_abserde.delete().is_err());
+		assert_eq!(
+			HashMap::<String, u32>::load_config(&window_abserde).unwrap(),
+			window_config
+		);
+
+		window_abserde.delete().unwrap();
+
+		assert!(!abserde.config_path().unwrap().exists());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_audit() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			audit: true,
+			..Default::default()
+		};
+		let test_config: TestConfigSimple = Faker.fake();
+		let changed_config = TestConfigSimple {
+			string_val: format!("{} changed", test_config.string_val),
+			..Faker.fake()
+		};
+
+		assert!(abserde.audit_log().unwrap().is_empty());
+
+		test_config.save_config(&abserde).unwrap();
+		changed_config.save_config(&abserde).unwrap();
+		TestConfigSimple {
+			string_val: "renamed".to_string(),
+			..changed_config
+		}
+		.save_config(&abserde)
+		.unwrap();
+		abserde.delete().unwrap();
+
+		let entries = abserde.audit_log().unwrap();
+
+		assert!(tmp_dir.path().join("audit.jsonl").exists());
+		assert_eq!(
+			entries
+				.iter()
+				.map(|entry| entry.operation)
+				.collect::<Vec<_>>(),
+			[
+				Operation::Save,
+				Operation::Save,
+				Operation::Save,
+				Operation::Delete
+			]
+		);
+		assert!(entries.iter().all(|entry| entry.pid == std::process::id()));
+		assert_eq!(entries[0].changes.len(), 8);
+		assert!(entries[0].changes.iter().all(|change| change.old.is_none()));
+		assert_eq!(
+			entries[2].changes,
+			[AuditChange {
+				key: "string_val".to_string(),
+				old: Some(changed_config.string_val.into()),
+				new: Some("renamed".into()),
+			}]
+		);
+		assert_eq!(entries[3].changes.len(), 8);
+		assert!(entries[3].changes.iter().all(|change| change.new.is_none()));
+		assert!(Abserde {
+			name: Some("other".to_string()),
+			..abserde
+		}
+		.audit_log()
+		.unwrap()
+		.is_empty());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_verify_saves() {
+		#[derive(Serialize, Deserialize)]
+		struct TestConfigLossy {
+			name: String,
+			#[serde(skip_deserializing)]
+			session: u32,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			verify_saves: true,
+			..Default::default()
+		};
+		let test_config: TestConfigSimple = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			TestConfigSimple::load_config(&abserde).unwrap(),
+			test_config
+		);
+
+		let lossy_config = TestConfigLossy {
+			name: "lossy".to_string(),
+			session: 1,
+		};
+
+		assert!(matches!(
+			lossy_config.save_config(&abserde).unwrap_err().root(),
+			Error::RoundTrip { keys, .. } if *keys == ["session"]
+		));
+		assert!(lossy_config
+			.save_config(&Abserde {
+				verify_saves: false,
+				..abserde
+			})
+			.is_ok());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_policy() {
+		let tmp_dir = TempDir::new().unwrap();
+		let policy_path = tmp_dir.path().join("policy.json");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("user")),
+			format: Format::Json,
+			..Default::default()
+		};
+		let policy_abserde = Abserde {
+			policy: Some(Location::Path(policy_path.clone())),
+			..abserde.clone()
+		};
+		let test_config = TestConfigSimple {
+			string_val: "user".to_string(),
+			u8_val: 1,
+			..Faker.fake()
+		};
+
+		assert!(policy_abserde.locked_keys().unwrap().is_empty());
+
+		write(&policy_path, r#"{"u8_val": 7, "string_val": "locked"}"#).unwrap();
+		test_config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			policy_abserde.locked_keys().unwrap(),
+			vec!["string_val".to_string(), "u8_val".to_string()]
+		);
+
+		let loaded_config = TestConfigSimple::load_config(&policy_abserde).unwrap();
+
+		assert_eq!(
+			loaded_config,
+			TestConfigSimple {
+				string_val: "locked".to_string(),
+				u8_val: 7,
+				..test_config
+			}
+		);
+		assert!(matches!(
+			test_config.save_config(&policy_abserde).unwrap_err().root(),
+			Error::LockedKeys(keys) if *keys == ["string_val", "u8_val"]
+		));
+
+		loaded_config.save_config(&policy_abserde).unwrap();
+		test_config
+			.save_config(&Abserde {
+				policy_conflicts: PolicyConflict::Revert,
+				..policy_abserde
+			})
+			.unwrap();
+
+		assert_eq!(
+			TestConfigSimple::load_config(&abserde).unwrap(),
+			loaded_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_sections() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+		struct TestConfigSections {
+			theme: String,
+			#[serde(default)]
+			keybindings: HashMap<String, String>,
+			#[serde(default, skip_serializing_if = "Option::is_none")]
+			accounts: Option<Vec<String>>,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		}
+		.with_section_file("keybindings", "keybindings")
+		.with_section_file("accounts", "accounts");
+		let config_path = tmp_dir.path().join("config.json");
+		let keybindings_path = tmp_dir.path().join("keybindings.json");
+		let accounts_path = tmp_dir.path().join("accounts.json");
+		let config = TestConfigSections {
+			theme: "dark".to_string(),
+			keybindings: HashMap::from([("quit".to_string(), "ctrl+q".to_string())]),
+			accounts: Some(vec!["work".to_string()]),
+		};
+
+		config.save_config(&abserde).unwrap();
+
+		assert_eq!(read(&config_path).unwrap(), br#"{"theme":"dark"}"#);
+		assert_eq!(read(&keybindings_path).unwrap(), br#"{"quit":"ctrl+q"}"#);
+		assert_eq!(read(&accounts_path).unwrap(), br#"["work"]"#);
+		assert_eq!(TestConfigSections::load_config(&abserde).unwrap(), config);
+
+		std::fs::remove_file(&keybindings_path).unwrap();
+
+		assert_eq!(
+			TestConfigSections::load_config(&abserde).unwrap(),
+			TestConfigSections {
+				keybindings: HashMap::new(),
+				..config.clone()
+			}
+		);
+
+		let config = TestConfigSections {
+			accounts: None,
+			..config
+		};
+
+		config.save_config(&abserde).unwrap();
+
+		assert!(!accounts_path.exists());
+		assert_eq!(TestConfigSections::load_config(&abserde).unwrap(), config);
+
+		abserde.delete().unwrap();
+
+		assert!(!config_path.exists());
+		assert!(!keybindings_path.exists());
+		assert!(matches!(
+			[0].save_config(&abserde).unwrap_err().root(),
+			Error::Unsupported(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_batch() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			backups: 1,
+			..Default::default()
+		};
+		let ui_abserde = abserde.clone().named("ui");
+		let keybindings_abserde = abserde.clone().named("keybindings");
+		let new_abserde = abserde.clone().named("new");
+		let ui_configs: [TestConfigComplex; 3] = Faker.fake();
+		let keybindings_configs: [TestConfigComplex; 3] = Faker.fake();
+		let file_names = || {
+			let mut file_names = read_dir(tmp_dir.path())
+				.unwrap()
+				.map(|entry| entry.unwrap().file_name())
+				.collect::<Vec<_>>();
+
+			file_names.sort();
+			file_names
+		};
+
+		ui_configs[0].save_config(&ui_abserde).unwrap();
+		keybindings_configs[0]
+			.save_config(&keybindings_abserde)
+			.unwrap();
+
+		Batch::new()
+			.add(&ui_configs[1], &ui_abserde)
+			.add(&keybindings_configs[1], &keybindings_abserde)
+			.save()
+			.unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&ui_abserde).unwrap(),
+			ui_configs[1]
+		);
+		assert_eq!(
+			TestConfigComplex::load_config(&keybindings_abserde).unwrap(),
+			keybindings_configs[1]
+		);
+
+		let unserialisable = HashMap::from([((1, 2), 3)]);
+
+		assert!(matches!(
+			Batch::new()
+				.add(&ui_configs[2], &ui_abserde)
+				.add(&unserialisable, &keybindings_abserde)
+				.save()
+				.unwrap_err()
+				.root(),
+			Error::Serialize(_)
+		));
+
+		// A directory in place of a config file can't be replaced, so the batch is rolled back.
+		std::fs::create_dir(tmp_dir.path().join("blocked.json")).unwrap();
+		write(tmp_dir.path().join("blocked.json").join("file"), "").unwrap();
+
+		let saved_file_names = file_names();
+
+		assert!(Batch::new()
+			.add(&ui_configs[2], &ui_abserde)
+			.add(&keybindings_configs[2], &new_abserde)
+			.add(&keybindings_configs[2], &abserde.clone().named("blocked"))
+			.save()
+			.is_err());
+		assert_eq!(
+			TestConfigComplex::load_config(&ui_abserde).unwrap(),
+			ui_configs[1]
+		);
+		assert!(!new_abserde.exists().unwrap());
+		assert_eq!(file_names(), saved_file_names);
+		assert!(matches!(
+			Batch::new()
+				.add(
+					&ui_configs[2],
+					&Abserde {
+						storage: Storage::Memory,
+						..ui_abserde.clone()
+					}
+				)
+				.save()
+				.unwrap_err()
+				.root(),
+			Error::Unsupported(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_batch_namespace() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let ui_abserde = abserde.clone().namespaced("ui");
+		let net_abserde = abserde.clone().namespaced("net");
+		let ui_configs: [TestConfigSimple; 2] = Faker.fake();
+		let net_config: TestConfigSimple = Faker.fake();
+
+		ui_configs[0].save_config(&ui_abserde).unwrap();
+		net_config.save_config(&net_abserde).unwrap();
+
+		Batch::new()
+			.add(&ui_configs[1], &ui_abserde)
+			.add(&ui_configs[1], &abserde.clone().named("other"))
+			.save()
+			.unwrap();
+
+		// Configs saved in a batch are merged into the shared config file, keeping the other configs in it.
+		assert_eq!(
+			TestConfigSimple::load_config(&ui_abserde).unwrap(),
+			ui_configs[1]
+		);
+		assert_eq!(
+			TestConfigSimple::load_config(&net_abserde).unwrap(),
+			net_config
+		);
+		assert!(matches!(
+			Batch::new()
+				.add(&ui_configs[0], &ui_abserde)
+				.add(&net_config, &net_abserde)
+				.save()
+				.unwrap_err()
+				.root(),
+			Error::Unsupported(_)
+		));
+		assert_eq!(
+			TestConfigSimple::load_config(&ui_abserde).unwrap(),
+			ui_configs[1]
+		);
+	}
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_batch_policy() {
+		let tmp_dir = TempDir::new().unwrap();
+		let policy_path = tmp_dir.path().join("policy.json");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("user")),
+			format: Format::Json,
+			policy: Some(Location::Path(policy_path.clone())),
+			..Default::default()
+		};
+		let test_config = TestConfigSimple {
+			u8_val: 1,
+			..Faker.fake()
+		};
+
+		write(&policy_path, r#"{"u8_val": 7}"#).unwrap();
+
+		assert!(matches!(
+			Batch::new()
+				.add(&test_config, &abserde)
+				.save()
+				.unwrap_err()
+				.root(),
+			Error::LockedKeys(keys) if *keys == ["u8_val"]
+		));
+		assert!(!abserde.exists().unwrap());
+
+		Batch::new()
+			.add(
+				&test_config,
+				&Abserde {
+					policy_conflicts: PolicyConflict::Revert,
+					..abserde.clone()
+				},
+			)
+			.save()
+			.unwrap();
+
+		assert_eq!(
+			TestConfigSimple::load_config(&Abserde {
+				policy: None,
+				..abserde
+			})
+			.unwrap(),
+			TestConfigSimple {
+				u8_val: 7,
+				..test_config
+			}
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_lock() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			lock: true,
+			..Default::default()
+		};
+		let lock_path = tmp_dir.path().join("config.json.lock");
+		let config: TestConfigComplex = Faker.fake();
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		config.save_config(&abserde).unwrap();
+
+		let lock_file = File::open(&lock_path).unwrap();
+
+		lock_file.lock_shared().unwrap();
+
+		assert_eq!(
+			TestConfigComplex::try_load_config(&abserde).unwrap(),
+			config
+		);
+		assert!(matches!(
+			config
+				.save_config(&Abserde {
+					lock_timeout: Some(Duration::ZERO),
+					..abserde.clone()
+				})
+				.unwrap_err()
+				.root(),
+			Error::Locked(_)
+		));
+
+		lock_file.unlock().unwrap();
+		lock_file.lock().unwrap();
+
+		assert!(matches!(
+			TestConfigComplex::try_load_config(&abserde)
+				.unwrap_err()
+				.root(),
+			Error::Locked(_)
+		));
+
+		let start = std::time::Instant::now();
+
+		assert!(matches!(
+			TestConfigComplex::load_config_timeout(&abserde, Duration::from_millis(50))
+				.unwrap_err()
+				.root(),
+			Error::Locked(_)
+		));
+		assert!(start.elapsed() >= Duration::from_millis(50));
+
+		let unlock = std::thread::spawn(move || {
+			std::thread::sleep(Duration::from_millis(20));
+			lock_file.unlock().unwrap();
+		});
+
+		assert_eq!(
+			TestConfigComplex::load_config_timeout(&abserde, Duration::from_secs(10)).unwrap(),
+			config
+		);
+
+		unlock.join().unwrap();
+
+		abserde.delete().unwrap();
+
+		assert!(!lock_path.exists());
+		assert!(matches!(
+			abserde.delete().unwrap_err().root(),
+			Error::NotFound(_)
+		));
+		assert!(!lock_path.exists());
+
+		let app_abserde = Abserde {
+			location: Location::Path(tmp_dir.path().join("app").join("config.json")),
+			..abserde.clone()
+		};
+
+		assert!(matches!(
+			app_abserde.delete().unwrap_err().root(),
+			Error::NotFound(_)
+		));
+		assert!(!tmp_dir.path().join("app").exists());
+		assert!(app_abserde.is_first_run().unwrap());
+	}
+
+	#[cfg(all(feature = "json", unix))]
+	#[test]
+	fn test_json_dir_symlinks() {
+		use std::os::unix::fs::symlink;
+
+		let tmp_dir = TempDir::new().unwrap();
+		let target_path = tmp_dir.path().join("dotfiles").join("config.json");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("app")),
+			format: Format::Json,
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+		let configs: [TestConfigComplex; 3] = Faker.fake();
+		let is_link = |path: &Path| path.symlink_metadata().unwrap().file_type().is_symlink();
+		let link = || {
+			_ = std::fs::remove_file(&config_path);
+			configs[0]
+				.save_config(&Abserde {
+					location: Location::Path(target_path.clone()),
+					..abserde.clone()
+				})
+				.unwrap();
+			symlink(
+				Path::new("..").join("dotfiles").join("config.json"),
+				&config_path,
+			)
+			.unwrap();
+		};
+
+		std::fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+		link();
+
+		for atomic in [true, false] {
+			let abserde = Abserde {
+				atomic,
+				..abserde.clone()
+			};
+
+			configs[1].save_config(&abserde).unwrap();
+
+			assert!(is_link(&config_path));
+			assert_eq!(
+				TestConfigComplex::load_config(&abserde).unwrap(),
+				configs[1]
+			);
+		}
+
+		abserde.delete().unwrap();
+
+		assert!(is_link(&config_path));
+		assert!(!target_path.exists());
+		assert!(!abserde.exists().unwrap());
+
+		configs[2].save_config(&abserde).unwrap();
+
+		assert!(is_link(&config_path));
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			configs[2]
+		);
+
+		let target =
+			|| serde_json::from_slice::<TestConfigComplex>(&read(&target_path).unwrap()).unwrap();
+
+		for atomic in [true, false] {
+			let abserde = Abserde {
+				symlinks: SymlinkPolicy::Replace,
+				atomic,
+				..abserde.clone()
+			};
+
+			link();
+			configs[1].save_config(&abserde).unwrap();
+
+			assert!(!is_link(&config_path));
+			assert_eq!(
+				TestConfigComplex::load_config(&abserde).unwrap(),
+				configs[1]
+			);
+			assert_eq!(target(), configs[0]);
+
+			link();
+			abserde.delete().unwrap();
+
+			assert!(config_path.symlink_metadata().is_err());
+			assert_eq!(target(), configs[0]);
+		}
+
+		let abserde = Abserde {
+			symlinks: SymlinkPolicy::Refuse,
+			..abserde.clone()
+		};
+
+		link();
+
+		assert!(matches!(
+			configs[1].save_config(&abserde).unwrap_err().root(),
+			Error::Symlink(_)
+		));
+		assert!(matches!(
+			abserde.delete().unwrap_err().root(),
+			Error::Symlink(_)
+		));
+		assert!(is_link(&config_path));
+		assert_eq!(target(), configs[0]);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_sort_keys() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigMaps {
+			version: u32,
+			aliases: HashMap<String, String>,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let keys = (0..20).map(|index| format!("alias{:02}", index));
+		let config = |keys: Vec<String>| TestConfigMaps {
+			version: 1,
+			aliases: keys.into_iter().map(|key| (key.clone(), key)).collect(),
+		};
+		let configs = [
+			config(keys.clone().collect()),
+			config(keys.clone().rev().collect()),
+		];
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			sort_keys: true,
+			..Default::default()
+		};
+
+		configs[0].save_config(&abserde).unwrap();
+
+		assert_eq!(
+			std::fs::read_to_string(abserde.config_path().unwrap()).unwrap(),
+			format!(
+				"{{\"aliases\":{{{}}},\"version\":1}}",
+				keys.map(|key| format!("\"{}\":\"{}\"", key, key))
+					.collect::<Vec<_>>()
+					.join(",")
+			)
+		);
+
+		#[allow(unused_mut)]
+		let mut formats = vec![Format::PrettyJson(PrettyJsonIndent::Tab)];
+
+		#[cfg(feature = "toml")]
+		formats.push(Format::Toml);
+		#[cfg(feature = "yaml")]
+		formats.push(Format::Yaml);
+
+		for format in formats {
+			let abserde = Abserde {
+				format,
+				..abserde.clone()
+			};
+			let saved = configs.each_ref().map(|config| {
+				config.save_config(&abserde).unwrap();
+
+				assert_eq!(&TestConfigMaps::load_config(&abserde).unwrap(), config);
+
+				std::fs::read(abserde.config_path().unwrap()).unwrap()
+			});
+
+			assert_eq!(saved[0], saved[1]);
+		}
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_extension() {
+		let tmp_dir = TempDir::new().unwrap();
+		let config: TestConfigComplex = Faker.fake();
+
+		for (name, extension, format, file_name) in [
+			("settings", "json", Format::FromExtension, "settings.json"),
+			("myapp", "conf", Format::Json, "myapp.conf"),
+			("myapprc", "", Format::Json, "myapprc"),
+		] {
+			let abserde = Abserde::builder()
+				.app(APP_NAME)
+				.location(Location::Dir(tmp_dir.path().into()))
+				.format(format)
+				.name(name)
+				.extension(extension)
+				.build()
+				.unwrap();
+
+			assert_eq!(
+				abserde.config_path().unwrap(),
+				tmp_dir.path().join(file_name)
+			);
+
+			test_save_load_delete::<TestConfigComplex>(&abserde);
+
+			config.save_config(&abserde).unwrap();
+
+			assert_eq!(
+				serde_json::from_slice::<TestConfigComplex>(
+					&std::fs::read(tmp_dir.path().join(file_name)).unwrap()
+				)
+				.unwrap(),
+				config
+			);
+		}
+
+		for extension in ["conf", ""] {
+			assert!(matches!(
+				Abserde::builder()
+					.format(Format::FromExtension)
+					.extension(extension)
+					.build()
+					.unwrap_err(),
+				Error::Unsupported(_)
+			));
+		}
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_line_ending() {
+		let tmp_dir = TempDir::new().unwrap();
+		let config = serde_json::json!({
+			"theme": "dark",
+			"window": {"width": "800", "height": "600"},
+		});
+		#[allow(unused_mut)]
+		let mut formats = vec![Format::PrettyJson(PrettyJsonIndent::Tab)];
+
+		#[cfg(feature = "ini")]
+		formats.push(Format::Ini);
+		#[cfg(feature = "toml")]
+		formats.push(Format::Toml);
+		#[cfg(feature = "yaml")]
+		formats.push(Format::Yaml);
+
+		for format in formats {
+			let abserde = Abserde {
+				app: APP_NAME.to_string(),
+				location: Location::Dir(tmp_dir.path().into()),
+				format: format.clone(),
+				line_ending: LineEnding::CrLf,
+				..Default::default()
+			};
+			let config_path = abserde.config_path().unwrap();
+
+			config.save_config(&abserde).unwrap();
+
+			let text = std::fs::read_to_string(&config_path).unwrap();
+
+			assert_eq!(
+				text,
+				config
+					.to_config_string(&format)
+					.unwrap()
+					.replace('\n', "\r\n")
+			);
+			assert_eq!(serde_json::Value::load_config(&abserde).unwrap(), config);
+
+			config
+				.save_config(&Abserde {
+					line_ending: LineEnding::Lf,
+					..abserde.clone()
+				})
+				.unwrap();
+
+			assert!(!std::fs::read_to_string(&config_path)
+				.unwrap()
+				.contains('\r'));
+		}
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_encodings() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let config_path = tmp_dir.path().join("config.json");
+		let config = serde_json::json!({"theme": "dark", "name": "caf\u{e9} \u{1f600}"});
+		let text = config.to_string();
+		let utf16 = text.encode_utf16().collect::<Vec<_>>();
+		let encodings = [
+			[&[0xef, 0xbb, 0xbf], text.as_bytes()].concat(),
+			[0xff, 0xfe]
+				.into_iter()
+				.chain(utf16.iter().flat_map(|unit| unit.to_le_bytes()))
+				.collect(),
+			[0xfe, 0xff]
+				.into_iter()
+				.chain(utf16.iter().flat_map(|unit| unit.to_be_bytes()))
+				.collect(),
+		];
+
+		for data in encodings {
+			write(&config_path, data).unwrap();
+
+			let loaded = serde_json::Value::load_config(&abserde).unwrap();
+
+			assert_eq!(loaded, config);
+
+			loaded.save_config(&abserde).unwrap();
+
+			assert_eq!(std::fs::read(&config_path).unwrap(), text.as_bytes());
+		}
+
+		write(&config_path, [0xff, 0xfe, 0x00, 0xd8, 0x7b]).unwrap();
+
+		assert!(matches!(
+			serde_json::Value::load_config(&abserde).unwrap_err().root(),
+			Error::Deserialize(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_journal() {
+		use std::io::Write;
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			journal: 3,
+			backups: 1,
+			..Default::default()
+		};
+		let config_path = tmp_dir.path().join("config.json");
+		let journal_path = tmp_dir.path().join("config.json.journal");
+		let configs: Vec<TestConfigComplex> = (0..4).map(|_| Faker.fake()).collect();
+
+		configs[0].save_config(&abserde).unwrap();
+		configs[1].save_config(&abserde).unwrap();
+
+		assert!(!config_path.exists());
+		assert!(abserde.exists().unwrap());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			configs[1]
+		);
+
+		// A record cut short by a crash is skipped, then truncated by the next save.
+		std::fs::OpenOptions::new()
+			.append(true)
+			.open(&journal_path)
+			.unwrap()
+			.write_all(&[255; 20])
+			.unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			configs[1]
+		);
+
+		configs[2].save_config(&abserde).unwrap();
+
+		assert!(!journal_path.exists());
+		assert!(!backup_file_path(&config_path, 1).exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&Abserde {
+				journal: 0,
+				..abserde.clone()
+			})
+			.unwrap(),
+			configs[2]
+		);
+
+		configs[3].save_config(&abserde).unwrap();
+		abserde.compact_journal().unwrap();
+
+		assert!(!journal_path.exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			configs[3]
+		);
+		assert!(backup_file_path(&config_path, 1).exists());
+
+		configs[0].save_config(&abserde).unwrap();
+		abserde.delete().unwrap();
+
+		assert!(!journal_path.exists());
+		assert!(!config_path.exists());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_load_field() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		assert!(matches!(
+			load_field::<u32>(&abserde, "width").unwrap_err().root(),
+			Error::NotFound(_)
+		));
+
+		serde_json::json!({
+			"window": {"width": 800, "height": 600},
+			"user_data": {"theme": "dark"},
+			"recent": ["a", "b"],
+		})
+		.save_config(&abserde)
+		.unwrap();
+
+		assert_eq!(
+			load_field::<HashMap<String, String>>(&abserde, "user_data").unwrap(),
+			Some(HashMap::from([("theme".to_string(), "dark".to_string())]))
+		);
+		assert_eq!(
+			load_field::<Vec<String>>(&abserde, "recent").unwrap(),
+			Some(vec!["a".to_string(), "b".to_string()])
+		);
+		assert_eq!(load_field::<u32>(&abserde, "missing").unwrap(), None);
+		assert!(matches!(
+			load_field::<u32>(&abserde, "window").unwrap_err().root(),
+			Error::Deserialize(_)
+		));
+
+		let abserde = Abserde {
+			schema_version: Some(1),
+			..abserde
+		};
+
+		serde_json::json!({"user_data": {"theme": "light"}})
+			.save_config(&abserde)
+			.unwrap();
+
+		assert_eq!(
+			load_field::<HashMap<String, String>>(&abserde, "user_data").unwrap(),
+			Some(HashMap::from([("theme".to_string(), "light".to_string())]))
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_value() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		assert!(matches!(
+			abserde.get_value("theme").unwrap_err().root(),
+			Error::NotFound(_)
+		));
+
+		abserde
+			.set_value("theme", serde_json::json!("dark"))
+			.unwrap();
+		abserde
+			.set_value("window.width", serde_json::json!(800))
+			.unwrap();
+		abserde
+			.set_value("recent", serde_json::json!(["a", "b"]))
+			.unwrap();
+		abserde
+			.set_value("recent.1", serde_json::json!("c"))
+			.unwrap();
+
+		assert_eq!(
+			serde_json::Value::load_config(&abserde).unwrap(),
+			serde_json::json!({"theme": "dark", "window": {"width": 800}, "recent": ["a", "c"]})
+		);
+		assert_eq!(
+			abserde.get_value("window.width").unwrap(),
+			Some(serde_json::json!(800))
+		);
+		assert_eq!(
+			abserde.get_value("recent.0").unwrap(),
+			Some(serde_json::json!("a"))
+		);
+		assert_eq!(abserde.get_value("window.height").unwrap(), None);
+		assert_eq!(abserde.get_value("theme.name").unwrap(), None);
+		assert_eq!(
+			abserde.get_value("").unwrap(),
+			Some(serde_json::Value::load_config(&abserde).unwrap())
+		);
+		assert!(matches!(
+			abserde.set_value("theme.name", serde_json::json!("x")),
+			Err(Error::InvalidKeyPath { .. })
+		));
+		assert!(matches!(
+			abserde.set_value("recent.2", serde_json::json!("x")),
+			Err(Error::InvalidKeyPath { .. })
+		));
+	}
+
+	#[cfg(all(feature = "json", feature = "json-patch"))]
+	#[test]
+	fn test_json_dir_patch() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		assert!(matches!(
+			abserde
+				.apply_patch(&serde_json::json!([]))
+				.unwrap_err()
+				.root(),
+			Error::NotFound(_)
+		));
+
+		serde_json::json!({"window": {"width": 800}, "theme": "light", "recent": ["a"]})
+			.save_config(&abserde)
+			.unwrap();
+		abserde
+			.apply_patch(&serde_json::json!([
+				{"op": "test", "path": "/theme", "value": "light"},
+				{"op": "replace", "path": "/theme", "value": "dark"},
+				{"op": "add", "path": "/recent/-", "value": "b"},
+				{"op": "move", "from": "/window/width", "path": "/width"},
+				{"op": "remove", "path": "/window"},
+			]))
+			.unwrap();
+
+		let patched = serde_json::json!({"theme": "dark", "recent": ["a", "b"], "width": 800});
+
+		assert_eq!(serde_json::Value::load_config(&abserde).unwrap(), patched);
+
+		match abserde.apply_patch(&serde_json::json!([
+			{"op": "replace", "path": "/width", "value": 1024},
+			{"op": "test", "path": "/width", "value": 800},
+		])) {
+			Err(Error::PatchTestFailed {
+				operation,
+				path,
+				expected,
+				actual,
+			}) => {
+				assert_eq!(operation, 1);
+				assert_eq!(path, "/width");
+				assert_eq!(expected, 800);
+				assert_eq!(actual, Some(serde_json::json!(1024)));
+			}
+			result => panic!("unexpected result {:?}", result),
+		}
+
+		assert_eq!(serde_json::Value::load_config(&abserde).unwrap(), patched);
+		assert!(matches!(
+			abserde.apply_patch(&serde_json::json!([{"op": "remove", "path": "/missing"}])),
+			Err(Error::Patch(_))
+		));
+		assert!(matches!(
+			abserde.apply_patch(&serde_json::json!({"op": "remove"})),
+			Err(Error::Deserialize(_))
+		));
+	}
+
+	#[cfg(all(feature = "json", feature = "validator"))]
+	#[test]
+	fn test_json_dir_validator() {
+		use validator::{Validate, ValidationError, ValidationErrors};
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigValidated {
+			port: u16,
+		}
+
+		impl Validate for TestConfigValidated {
+			fn validate(&self) -> result::Result<(), ValidationErrors> {
+				let mut errors = ValidationErrors::new();
+
+				if self.port < 1024 {
+					errors.add("port", ValidationError::new("range"));
+				}
+
+				match errors.is_empty() {
+					true => Ok(()),
+					false => Err(errors),
+				}
+			}
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let valid = TestConfigValidated { port: 8080 };
+		let invalid = TestConfigValidated { port: 80 };
+		let is_invalid_port = |error: Error| matches!(error.root(), Error::Validation(errors) if errors.field_errors().contains_key("port"));
+
+		valid.save_config_validated(&abserde).unwrap();
+
+		assert_eq!(
+			TestConfigValidated::load_config_validated(&abserde).unwrap(),
+			valid
+		);
+		assert!(is_invalid_port(
+			invalid.save_config_validated(&abserde).unwrap_err()
+		));
+		assert_eq!(TestConfigValidated::load_config(&abserde).unwrap(), valid);
+
+		invalid.save_config(&abserde).unwrap();
+
+		assert!(is_invalid_port(
+			TestConfigValidated::load_config_validated(&abserde).unwrap_err()
+		));
+	}
+
+	#[cfg(all(feature = "json", unix))]
+	#[test]
+	fn test_json_dir_audit_permissions() {
+		use std::fs::{set_permissions, Permissions};
+		use std::os::unix::fs::PermissionsExt;
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			permissions: FilePermissions::Private,
+			audit_permissions: true,
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+
+		abserde.check_permissions().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+
+		let config: TestConfigComplex = Faker.fake();
+
+		config.save_config(&abserde).unwrap();
+		abserde.check_permissions().unwrap();
+
+		for (mode, reason) in [
+			(0o604, "readable by other users"),
+			(0o666, "writable by other users"),
+		] {
+			set_permissions(&config_path, Permissions::from_mode(mode)).unwrap();
+
+			for error in [
+				abserde.check_permissions().unwrap_err(),
+				TestConfigComplex::load_config(&abserde).unwrap_err(),
+			] {
+				assert!(matches!(
+					error.root(),
+					Error::InsecurePermissions { path, reason: r } if *path == config_path && r == reason
+				));
+			}
+		}
+
+		assert_eq!(
+			TestConfigComplex::load_config(&Abserde {
+				audit_permissions: false,
+				..abserde.clone()
+			})
+			.unwrap(),
+			config
+		);
+
+		config.save_config(&abserde).unwrap();
+
+		assert_eq!(TestConfigComplex::load_config(&abserde).unwrap(), config);
+	}
+
+	#[cfg(all(feature = "json", windows))]
+	#[test]
+	fn test_json_dir_private_permissions() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		for atomic in [true, false] {
+			let abserde = Abserde {
+				app: APP_NAME.to_string(),
+				location: Location::Dir(tmp_dir.path().join("private")),
+				format: Format::Json,
+				atomic,
+				permissions: FilePermissions::Private,
+				..Default::default()
+			};
+
+			test_save_load_delete::<TestConfigComplex>(&abserde);
+		}
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_collection() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			backups: 1,
+			..Default::default()
+		};
+		let collection = ConfigCollection::<TestConfigComplex>::new(abserde.clone(), "servers");
+		let test_configs: HashMap<String, TestConfigComplex> = ["staging", "production"]
+			.into_iter()
+			.map(|key| (key.to_string(), Faker.fake()))
+			.collect();
+
+		assert!(collection.list().unwrap().is_empty());
+		assert!(collection.get("staging").unwrap().is_none());
+
+		for (key, test_config) in &test_configs {
+			collection.insert(key, test_config).unwrap();
+			collection.insert(key, test_config).unwrap();
+		}
+
+		assert!(tmp_dir.path().join("servers").join("staging.json").exists());
+		assert!(tmp_dir
+			.path()
+			.join("servers")
+			.join("staging.json.bak.1")
+			.exists());
+		assert_eq!(collection.list().unwrap(), ["production", "staging"]);
+
+		for (key, test_config) in &test_configs {
+			assert_eq!(collection.get(key).unwrap().as_ref(), Some(test_config));
+		}
+
+		assert!(collection.remove("staging").unwrap());
+		assert!(!collection.remove("staging").unwrap());
+		assert_eq!(collection.list().unwrap(), ["production"]);
+		assert!(!abserde.exists().unwrap());
+		assert!(matches!(
+			collection.insert("../escape", &test_configs["production"]),
+			Err(Error::Unsupported(_))
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_profiles() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let work_abserde = abserde.clone().with_profile("work");
+		let config: TestConfigComplex = Faker.fake();
+		let work_config: TestConfigComplex = Faker.fake();
+
+		assert!(abserde.list_profiles().unwrap().is_empty());
+		assert_eq!(
+			work_abserde.config_path().unwrap(),
+			tmp_dir
+				.path()
+				.join("profiles")
+				.join("work")
+				.join("config.json")
+		);
+
+		config.save_config(&abserde).unwrap();
+		work_config.save_config(&work_abserde).unwrap();
+		abserde.copy_profile("work", "home").unwrap();
+
+		assert_eq!(abserde.list_profiles().unwrap(), ["home", "work"]);
+		assert_eq!(TestConfigComplex::load_config(&abserde).unwrap(), config);
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde.clone().with_profile("home")).unwrap(),
+			work_config
+		);
+		assert!(abserde.copy_profile("work", "home").is_err());
+		assert!(matches!(
+			abserde.copy_profile("missing", "other").unwrap_err(),
+			Error::NotFound(_)
+		));
+
+		abserde.delete_profile("work").unwrap();
+
+		assert_eq!(abserde.list_profiles().unwrap(), ["home"]);
+		assert!(!work_abserde.exists().unwrap());
+
+		for profile in ["", "..", "work/.."] {
+			assert!(abserde.delete_profile(profile).is_err());
+		}
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_list_configs() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			backups: 1,
+			..Default::default()
+		};
+
+		assert!(abserde.list_configs().unwrap().is_empty());
+
+		TestConfigComplex::default().save_config(&abserde).unwrap();
+		TestConfigComplex::default().save_config(&abserde).unwrap();
+		TestConfigComplex::default()
+			.save_config(&abserde.clone().named("ui"))
+			.unwrap();
+		write(tmp_dir.path().join("notes.txt"), "not a config").unwrap();
+		create_dir_all(tmp_dir.path().join("dir.json")).unwrap();
+
+		let configs = abserde.list_configs().unwrap();
+
+		assert_eq!(
+			configs
+				.iter()
+				.map(|config| &config.name)
+				.collect::<Vec<_>>(),
+			["config.json", "ui.json"]
+		);
+		assert_eq!(configs[0].path, abserde.config_path().unwrap());
+		assert_eq!(configs[0].format, Format::Json);
+		assert_eq!(
+			configs[0].size,
+			std::fs::metadata(abserde.config_path().unwrap())
+				.unwrap()
+				.len()
+		);
+		assert!(configs[0].modified.is_some());
+	}
+
+	#[cfg(all(feature = "json", unix))]
+	#[test]
+	fn test_json_system() {
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::System,
+			format: Format::Json,
+			..Default::default()
+		};
+
+		assert_eq!(
+			abserde.config_path().unwrap(),
+			Path::new("/etc").join(APP_NAME).join("config.json")
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_search() {
+		let tmp_dir = TempDir::new().unwrap();
+		let local_path = tmp_dir.path().join(".myapp.json");
+		let user_dir = tmp_dir.path().join("user");
+		let system_dir = tmp_dir.path().join("system");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Search(vec![
+				Location::Path(local_path.clone()),
+				Location::Dir(user_dir.clone()),
+				Location::Dir(system_dir.clone()),
+			]),
+			format: Format::Json,
+			..Default::default()
+		};
+		let system_config = TestConfigComplex::default();
+		let user_config: TestConfigComplex = Faker.fake();
+
+		assert!(!abserde.exists().unwrap());
+
+		system_config
+			.save_config(&abserde.at(&Location::Dir(system_dir)))
+			.unwrap();
+
+		assert!(abserde.exists().unwrap());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			system_config
+		);
+
+		user_config
+			.save_config(&abserde.at(&Location::Dir(user_dir)))
+			.unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			user_config
+		);
+
+		system_config.save_config(&abserde).unwrap();
+
+		assert!(local_path.exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			system_config
+		);
+
+		abserde.delete().unwrap();
+
+		assert!(!local_path.exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			user_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_platform() {
+		let tmp_dir = TempDir::new().unwrap();
+		let platform_dir = |platform| tmp_dir.path().join(platform);
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Platform(Box::new(PlatformLocations {
+				windows: Some(Location::Dir(platform_dir("windows"))),
+				macos: Some(Location::Dir(platform_dir("macos"))),
+				linux: Some(Location::Path(platform_dir("linux").join(".myapp"))),
+				fallback: Location::Dir(platform_dir("other")),
+			})),
+			format: Format::Json,
+			..Default::default()
+		};
+		let config_path = if cfg!(windows) {
+			platform_dir("windows").join("config.json")
+		} else if cfg!(target_os = "macos") {
+			platform_dir("macos").join("config.json")
+		} else if cfg!(target_os = "linux") {
+			platform_dir("linux").join(".myapp")
+		} else {
+			platform_dir("other").join("config.json")
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		assert_eq!(abserde.config_path().unwrap(), config_path);
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert!(config_path.exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+
+		let abserde = Abserde {
+			location: Location::Platform(Box::new(PlatformLocations {
+				fallback: Location::Dir(platform_dir("other")),
+				..Default::default()
+			})),
+			..abserde
+		};
+
+		assert_eq!(
+			abserde.config_path().unwrap(),
+			platform_dir("other").join("config.json")
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_fallback() {
+		let tmp_dir = TempDir::new().unwrap();
+		let user_dir = tmp_dir.path().join("user");
+		let system_dir = tmp_dir.path().join("system");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Fallback(vec![
+				Location::Dir(user_dir.clone()),
+				Location::Dir(system_dir.clone()),
+			]),
+			format: Format::Json,
+			..Default::default()
+		};
+		let user_abserde = abserde.at(&Location::Dir(user_dir));
+		let system_abserde = abserde.at(&Location::Dir(system_dir));
+		let system_config: TestConfigComplex = Faker.fake();
+		let user_config: TestConfigComplex = Faker.fake();
+
+		system_config.save_config(&system_abserde).unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			system_config
+		);
+
+		// A broken user config file falls back to the system default.
+		create_dir_all(user_abserde.config_path().unwrap().parent().unwrap()).unwrap();
+		write(user_abserde.config_path().unwrap(), "{ not json").unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			system_config
+		);
+
+		user_config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&user_abserde).unwrap(),
+			user_config
+		);
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			user_config
+		);
+
+		// Without a config file to fall back to, the error of the broken one is returned.
+		write(user_abserde.config_path().unwrap(), "{ not json").unwrap();
+		system_abserde.delete().unwrap();
+
+		assert!(matches!(
+			TestConfigComplex::load_config(&abserde).unwrap_err().root(),
+			Error::Deserialize(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_json_auto_delete_all() {
+		let abserde = Abserde {
+			app: format!("{}-delete-all", APP_NAME),
+			location: Location::Auto,
+			format: Format::Json,
+			backups: 1,
+			..Default::default()
+		};
+		let named_abserde = Abserde {
+			location: Location::File("named.json".into()),
+			..abserde.clone()
+		};
+		let config_dir = abserde.config_path().unwrap().parent().unwrap().to_owned();
+
+		TestConfigComplex::default().save_config(&abserde).unwrap();
+		TestConfigComplex::default().save_config(&abserde).unwrap();
+		TestConfigComplex::default()
+			.save_config(&named_abserde)
+			.unwrap();
+
+		assert!(abserde.backup_path(1).unwrap().exists());
+
+		named_abserde.delete_all().unwrap();
+
+		assert!(!config_dir.exists());
+
+		for app in ["", ".", "..", "app/.."] {
+			assert!(Abserde {
+				app: app.to_string(),
+				..abserde.clone()
+			}
+			.delete_all()
+			.is_err());
+		}
+
+		assert!(Abserde {
+			location: Location::Dir(config_dir),
+			..abserde
+		}
+		.delete_all()
+		.is_err());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_delete_with() {
+		let tmp_dir = TempDir::new().unwrap();
+		let config_dir = tmp_dir.path().join(APP_NAME);
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(config_dir.join("config.json")),
+			format: Format::Json,
+			backups: 2,
+			..Default::default()
+		};
+
+		for _ in 0..3 {
+			TestConfigComplex::default().save_config(&abserde).unwrap();
+		}
+
+		abserde
+			.delete_with(DeleteMode {
+				remove_parent: false,
+				..Default::default()
+			})
+			.unwrap();
+
+		assert!(!abserde.exists().unwrap());
+		assert!(abserde.backup_path(2).unwrap().exists());
+		assert!(abserde.delete().is_err());
+
+		abserde
+			.delete_with(DeleteMode {
+				remove_backups: true,
+				ignore_missing: true,
+				..Default::default()
+			})
+			.unwrap();
+
+		assert!(!config_dir.exists());
+	}
+
+	#[cfg(all(feature = "json", feature = "trash"))]
+	#[test]
+	fn test_json_path_delete_trash() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_dir.path().join(APP_NAME).join("config.json")),
+			format: Format::Json,
+			..Default::default()
+		};
+		let mode = DeleteMode {
+			trash: true,
+			..Default::default()
+		};
+
+		TestConfigComplex::default().save_config(&abserde).unwrap();
+		abserde.delete_with(mode.clone()).unwrap();
+
+		assert!(!abserde.exists().unwrap());
+
+		let error = abserde.delete_with(mode).unwrap_err();
+
+		assert!(matches!(error.root(), Error::NotFound(_)));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_pretty_json_path() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_json_file() {
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::File("custom_file.json".into()),
+			format: Format::Json,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	#[serial]
+	fn test_pretty_json_file() {
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::File("custom_file.json".into()),
+			format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_pretty_json_dir() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_not_atomic() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			atomic: false,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_sync() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			sync: true,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_sync_not_atomic() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			atomic: false,
+			sync: true,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_atomic_failed_save() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config_saved: TestConfigComplex = Faker.fake();
+
+		test_config_saved.save_config(&abserde).unwrap();
+
+		// JSON object keys must be strings, so this fails part way through serialisation.
+		let unserialisable = HashMap::from([((1, 2), 3)]);
+
+		assert!(unserialisable.save_config(&abserde).is_err());
+		assert_eq!(
+			test_config_saved,
+			TestConfigComplex::load_config(&abserde).unwrap()
+		);
+		assert_eq!(read_dir(tmp_dir.path()).unwrap().count(), 1);
+	}
+
+	// Save several configs in turn, checking each backup and restoring the oldest.
+	fn test_backups(abserde: &Abserde) {
+		let test_configs: Vec<TestConfigComplex> = (0..4).map(|_| Faker.fake()).collect();
+
+		for test_config in &test_configs {
+			test_config.save_config(abserde).unwrap();
+		}
+
+		for index in 1..=abserde.backups {
+			let backup = Abserde {
+				location: Location::Path(abserde.backup_path(index).unwrap()),
+				..abserde.clone()
+			};
+
+			assert_eq!(
+				test_configs[test_configs.len() - 1 - index],
+				TestConfigComplex::load_config(&backup).unwrap()
+			);
+		}
+
+		assert!(!abserde.backup_path(abserde.backups + 1).unwrap().exists());
+
+		abserde.restore_backup(abserde.backups).unwrap();
+
+		assert_eq!(
+			test_configs[test_configs.len() - 1 - abserde.backups],
+			TestConfigComplex::load_config(abserde).unwrap()
+		);
+		assert_eq!(
+			test_configs[test_configs.len() - 1],
+			TestConfigComplex::load_config(&Abserde {
+				location: Location::Path(abserde.backup_path(1).unwrap()),
+				..abserde.clone()
+			})
+			.unwrap()
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_backups() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		test_backups(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			backups: 2,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_backups_not_atomic() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		test_backups(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			atomic: false,
+			backups: 2,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_recovery() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			backups: 2,
+			..Default::default()
+		};
+		let test_configs: Vec<TestConfigComplex> = (0..3).map(|_| Faker.fake()).collect();
+
+		for test_config in &test_configs {
+			test_config.save_config(&abserde).unwrap();
+		}
+
+		let (test_config_loaded, path) =
+			TestConfigComplex::load_config_with_recovery(&abserde).unwrap();
+
+		assert_eq!(test_configs[2], test_config_loaded);
+		assert_eq!(abserde.config_path().unwrap(), path);
+
+		write(abserde.config_path().unwrap(), "{").unwrap();
+		write(abserde.backup_path(1).unwrap(), "").unwrap();
+
+		let (test_config_loaded, path) =
+			TestConfigComplex::load_config_with_recovery(&abserde).unwrap();
+
+		assert_eq!(test_configs[0], test_config_loaded);
+		assert_eq!(abserde.backup_path(2).unwrap(), path);
+
+		write(abserde.backup_path(2).unwrap(), "[]").unwrap();
+
+		assert!(TestConfigComplex::load_config_with_recovery(&abserde).is_err());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_recovery_unreadable() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			backups: 1,
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+		let test_configs: Vec<TestConfigComplex> = (0..2).map(|_| Faker.fake()).collect();
+
+		for test_config in &test_configs {
+			test_config.save_config(&abserde).unwrap();
+		}
+
+		// A config file which can't be read isn't corrupt, so the backup isn't loaded in its place.
+		remove_file(&config_path).unwrap();
+		create_dir_all(&config_path).unwrap();
+
+		assert!(matches!(
+			TestConfigComplex::load_config_with_recovery(&abserde)
+				.unwrap_err()
+				.root(),
+			Error::Io(_)
+		));
+
+		remove_dir(&config_path).unwrap();
+		write(&config_path, "{").unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config_with_recovery(&abserde).unwrap(),
+			(test_configs[0].clone(), abserde.backup_path(1).unwrap())
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_quarantine() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+		let test_config: TestConfigComplex = Faker.fake();
+
+		let (config, quarantined) = TestConfigComplex::load_config_or_quarantine(&abserde).unwrap();
+
+		assert_eq!(config, TestConfigComplex::default());
+		assert!(quarantined.is_none());
+
+		test_config.save_config(&abserde).unwrap();
+
+		let (config, quarantined) = TestConfigComplex::load_config_or_quarantine(&abserde).unwrap();
+
+		assert_eq!(config, test_config);
+		assert!(quarantined.is_none());
+
+		write(&config_path, "{ \"a\": 1,").unwrap();
+
+		let (config, quarantined) = TestConfigComplex::load_config_or_quarantine(&abserde).unwrap();
+		let quarantined = quarantined.unwrap();
+
+		assert_eq!(config, TestConfigComplex::default());
+		assert!(matches!(quarantined.error, Error::Deserialize(_)));
+		assert!(!config_path.exists());
+		assert_eq!(quarantined.path.parent(), config_path.parent());
+		assert!(quarantined
+			.path
+			.file_name()
+			.unwrap()
+			.to_string_lossy()
+			.starts_with("config.json.corrupt-"));
+		assert_eq!(read(&quarantined.path).unwrap(), b"{ \"a\": 1,");
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_or_else() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+		let test_config: TestConfigComplex = Faker.fake();
+
+		assert_eq!(
+			TestConfigComplex::load_config_or_else(&abserde, || test_config.clone()).unwrap(),
+			test_config
+		);
+		assert!(!config_path.exists());
+		assert_eq!(
+			TestConfigComplex::load_config_or_else_persisted(&abserde, || test_config.clone())
+				.unwrap(),
+			test_config
+		);
+		assert_eq!(
+			TestConfigComplex::load_config_or_else(&abserde, || unreachable!()).unwrap(),
+			test_config
+		);
+		assert_eq!(
+			TestConfigComplex::load_config_or_else_persisted(&abserde, || unreachable!()).unwrap(),
+			test_config
+		);
+
+		write(&config_path, "not a config").unwrap();
+
+		assert!(
+			TestConfigComplex::load_config_or_else(&abserde, TestConfigComplex::default).is_err()
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_first_run() {
+		let tmp_dir = TempDir::new().unwrap();
+		let config_dir = tmp_dir.path().join(APP_NAME);
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(config_dir.join("config.json")),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		assert!(abserde.is_first_run().unwrap());
+
+		TestConfigComplex::default().save_config(&abserde).unwrap();
+
+		assert!(!abserde.is_first_run().unwrap());
+
+		remove_file(abserde.config_path().unwrap()).unwrap();
+
+		assert!(!abserde.is_first_run().unwrap());
+
+		remove_dir(&config_dir).unwrap();
+
+		assert!(abserde.is_first_run().unwrap());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_ensure_exists() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("nested")),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		assert!(abserde.ensure_exists::<TestConfigComplex>().unwrap());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			TestConfigComplex::default()
+		);
+
+		let test_config: TestConfigComplex = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert!(!abserde.ensure_exists::<TestConfigComplex>().unwrap());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_manager() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+		let mut manager =
+			ConfigManager::<TestConfigComplex>::load_or_default(abserde.clone()).unwrap();
+
+		assert_eq!(*manager.get(), TestConfigComplex::default());
+		assert!(manager.is_dirty());
+		assert!(manager.save().unwrap());
+		assert!(config_path.exists());
+		assert!(!manager.is_dirty());
+
+		remove_file(&config_path).unwrap();
+
+		assert!(!manager.save().unwrap());
+		assert!(!config_path.exists());
+
+		let test_config: TestConfigComplex = Faker.fake();
+
+		*manager.get_mut() = test_config.clone();
+
+		assert!(manager.is_dirty());
+		assert!(manager.save().unwrap());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+
+		manager.set(Faker.fake());
+		manager.reload().unwrap();
+
+		assert!(!manager.is_dirty());
+		assert_eq!(manager.into_inner(), test_config);
+		assert_eq!(
+			*ConfigManager::<TestConfigComplex>::load(abserde)
+				.unwrap()
+				.get(),
+			test_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_shared() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let shared = SharedConfig::<TestConfigComplex>::load_or_default(abserde.clone()).unwrap();
+
+		assert!(shared.persist().unwrap());
+		assert!(!shared.persist().unwrap());
+
+		let test_config: TestConfigComplex = Faker.fake();
+		let handles: Vec<_> = (0..4)
+			.map(|_| {
+				let shared = shared.clone();
+				let test_config = test_config.clone();
+
+				std::thread::spawn(move || *shared.write() = test_config)
+			})
+			.collect();
+
+		for handle in handles {
+			handle.join().unwrap();
+		}
+
+		assert!(shared.is_dirty());
+		assert_eq!(*shared.read(), test_config);
+		assert!(shared.persist().unwrap());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+
+		*shared.write() = Faker.fake();
+		shared.reload().unwrap();
+
+		assert!(!shared.is_dirty());
+		assert_eq!(*shared.read(), test_config);
+	}
+
+	#[cfg(all(feature = "json", feature = "shutdown"))]
+	#[test]
+	#[serial]
+	fn test_json_dir_shared_shutdown() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+		let shared = SharedConfig::new(abserde.clone(), test_config.clone());
+
+		shared.persist_on_shutdown().unwrap();
+
+		assert!(!abserde.config_path().unwrap().exists());
+
+		run_shutdown_hooks();
+
+		assert!(!shared.is_dirty());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_versioned() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		let (loaded_config, version) = TestConfigComplex::load_config_versioned(&abserde).unwrap();
+
+		assert_eq!(loaded_config, test_config);
+
+		let version = loaded_config
+			.save_config_if_unmodified(&abserde, &version)
+			.unwrap();
+		let other_config: TestConfigComplex = Faker.fake();
+
+		other_config.save_config(&abserde).unwrap();
+
+		let error = test_config
+			.save_config_if_unmodified(&abserde, &version)
+			.unwrap_err();
+
+		assert!(
+			matches!(error.root(), Error::Conflict(Conflict { path }) if path == tmp_file.path())
+		);
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			other_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_versioned_search() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Search(vec![
+				Location::Dir(tmp_dir.path().join("user")),
+				Location::Dir(tmp_dir.path().join("system")),
+			]),
+			format: Format::Json,
+			..Default::default()
+		};
+		let system_abserde = Abserde {
+			location: Location::Dir(tmp_dir.path().join("system")),
+			..abserde.clone()
+		}
+		.namespaced("ui");
+		let ui_abserde = abserde.clone().namespaced("ui");
+		let test_configs: [TestConfigSimple; 3] = Faker.fake();
+
+		test_configs[0].save_config(&system_abserde).unwrap();
+
+		// The config is loaded from the system config file, but saved to the user config file.
+		let (loaded_config, version) =
+			TestConfigSimple::load_config_versioned(&ui_abserde).unwrap();
+
+		assert_eq!(loaded_config, test_configs[0]);
+
+		let version = test_configs[1]
+			.save_config_if_unmodified(&ui_abserde, &version)
+			.unwrap();
+		let (loaded_config, loaded_version) =
+			TestConfigSimple::load_config_versioned(&ui_abserde).unwrap();
+
+		assert_eq!(loaded_config, test_configs[1]);
+		assert_eq!(loaded_version, version);
+
+		test_configs[2]
+			.save_config(&abserde.clone().namespaced("net"))
+			.unwrap();
+
+		assert!(matches!(
+			test_configs[1]
+				.save_config_if_unmodified(&ui_abserde, &version)
+				.unwrap_err()
+				.root(),
+			Error::Conflict(_)
+		));
+		assert_eq!(
+			TestConfigSimple::load_config(&system_abserde).unwrap(),
+			test_configs[0]
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct TestConfigV0 {
+		name: String,
+	}
+
+	#[cfg(feature = "json")]
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct TestConfigV1 {
+		first_name: String,
+		last_name: String,
+	}
+
+	#[cfg(feature = "json")]
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct TestConfigV2 {
+		version: u64,
+		names: Vec<String>,
+	}
+
+	#[cfg(feature = "json")]
+	impl From<TestConfigV1> for TestConfigV2 {
+		fn from(config: TestConfigV1) -> Self {
+			Self {
+				version: 2,
+				names: vec![config.first_name, config.last_name],
+			}
+		}
+	}
+
+	// Migrations from TestConfigV0 through to TestConfigV2.
+	#[cfg(feature = "json")]
+	fn test_migrations() -> Migrations<TestConfigV2> {
+		Migrations::<TestConfigV0>::new()
+			.then(|config: TestConfigV0| {
+				let (first_name, last_name) = config.name.split_once(' ').unwrap();
+
+				TestConfigV1 {
+					first_name: first_name.to_string(),
+					last_name: last_name.to_string(),
+				}
+			})
+			.then_from::<TestConfigV2>()
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_migrated() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let migrated_config = TestConfigV2 {
+			version: 2,
+			names: vec!["Jane".to_string(), "Doe".to_string()],
+		};
+
+		TestConfigV0 {
+			name: "Jane Doe".to_string(),
+		}
+		.save_config(&abserde)
+		.unwrap();
+
+		assert_eq!(
+			TestConfigV2::load_config_migrated(&abserde, &test_migrations()).unwrap(),
+			migrated_config
+		);
+		assert!(TestConfigV2::load_config(&abserde).is_err());
+		assert_eq!(
+			TestConfigV2::load_config_migrated(&abserde, &test_migrations().resave(true)).unwrap(),
+			migrated_config
+		);
+		assert_eq!(
+			TestConfigV2::load_config(&abserde).unwrap(),
+			migrated_config
+		);
+		assert_eq!(
+			TestConfigV2::load_config_migrated(&abserde, &test_migrations()).unwrap(),
+			migrated_config
+		);
+
+		write(tmp_file.path(), r#"{"version": 3, "names": []}"#).unwrap();
+
+		assert!(TestConfigV2::load_config_migrated(&abserde, &test_migrations()).is_err());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_migrated_partial() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		write(
+			tmp_file.path(),
+			r#"{"schema": 1, "first_name": "Jane", "last_name": "Doe"}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			TestConfigV2::load_config_migrated(
+				&abserde,
+				&test_migrations().version_field("schema")
+			)
+			.unwrap()
+			.names,
+			vec!["Jane".to_string(), "Doe".to_string()]
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_envelope() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			schema_version: Some(3),
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		let contents: serde_json::Value =
+			serde_json::from_slice(&std::fs::read(tmp_file.path()).unwrap()).unwrap();
+
+		assert_eq!(contents["abserde_version"], env!("CARGO_PKG_VERSION"));
+		assert_eq!(contents["schema_version"], 3);
+		assert!(contents["config"].is_object());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+
+		test_config
+			.save_config(&Abserde {
+				schema_version: None,
+				..abserde.clone()
+			})
+			.unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_envelope_migrated() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			schema_version: Some(1),
+			..Default::default()
+		};
+
+		TestConfigV1 {
+			first_name: "Jane".to_string(),
+			last_name: "Doe".to_string(),
+		}
+		.save_config(&abserde)
+		.unwrap();
+
+		let abserde = Abserde {
+			schema_version: Some(2),
+			..abserde
+		};
+		let test_config =
+			TestConfigV2::load_config_migrated(&abserde, &test_migrations().resave(true)).unwrap();
+
+		assert_eq!(
+			test_config.names,
+			vec!["Jane".to_string(), "Doe".to_string()]
+		);
+
+		let contents: serde_json::Value =
+			serde_json::from_slice(&std::fs::read(tmp_file.path()).unwrap()).unwrap();
+
+		assert_eq!(contents["schema_version"], 2);
+		assert_eq!(TestConfigV2::load_config(&abserde).unwrap(), test_config);
+	}
+
+	#[cfg(all(feature = "json", feature = "yaml"))]
+	#[test]
+	fn test_yaml_dir_adopt_from() {
+		let tmp_dir = TempDir::new().unwrap();
+		let legacy = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("legacy")),
+			format: Format::Yaml,
+			..Default::default()
+		};
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join("current")),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		assert!(!abserde.adopt_from::<TestConfigComplex>(&legacy).unwrap());
+
+		test_config.save_config(&legacy).unwrap();
+
+		assert!(abserde.adopt_from::<TestConfigComplex>(&legacy).unwrap());
+		assert!(!legacy.config_path().unwrap().exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+
+		let other_config: TestConfigComplex = Faker.fake();
+
+		other_config.save_config(&legacy).unwrap();
+
+		assert!(!abserde.adopt_from::<TestConfigComplex>(&legacy).unwrap());
+		assert!(legacy.config_path().unwrap().exists());
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_error_field() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config = TestConfigComplex {
+			vec_1_val: vec![1, 2, 3],
+			..Faker.fake()
+		};
+
+		test_config.save_config(&abserde).unwrap();
+
+		let contents = std::fs::read_to_string(tmp_file.path()).unwrap();
+
+		write(
+			tmp_file.path(),
+			contents.replace("[1,2,3]", "[1,\"two\",3]"),
+		)
+		.unwrap();
+
+		let error = TestConfigComplex::load_config(&abserde).unwrap_err();
+
+		assert!(matches!(error.root(), Error::Deserialize(_)));
+		assert!(error.to_string().contains("vec_1_val[1]"));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_error_context() {
+		let tmp_dir = TempDir::new().unwrap();
+		let config_path = tmp_dir.path().join("config.json");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(config_path.clone()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		for (error, expected_operation) in [
+			(
+				TestConfigComplex::load_config(&abserde).unwrap_err(),
+				Operation::Load,
+			),
+			(abserde.delete().unwrap_err(), Operation::Delete),
+		] {
+			let Error::Context {
+				operation,
+				path,
+				format,
+				source,
+			} = &error
+			else {
+				panic!("error has no context: {}", error);
+			};
+
+			assert_eq!(*operation, expected_operation);
+			assert_eq!(*path, config_path);
+			assert_eq!(*format, Format::Json);
+			assert!(matches!(**source, Error::NotFound(_)));
+			assert!(error.to_string().contains(&*config_path.to_string_lossy()));
+		}
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_unknown_keys() {
+		#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+		struct Window {
+			width: u32,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+		struct TestConfigNested {
+			theme: String,
+			window: Window,
+		}
+
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config = TestConfigNested {
+			theme: "dark".to_string(),
+			window: Window { width: 1920 },
+		};
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			TestConfigNested::load_config_with_unknown_keys(&abserde).unwrap(),
+			(test_config, vec![])
+		);
+
+		write(
+			tmp_file.path(),
+			r#"{"theem": "light", "theme": "dark", "window": {"width": 1920, "hieght": 1080}}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			TestConfigNested::load_config_with_unknown_keys(&abserde)
+				.unwrap()
+				.1,
+			vec!["theem", "window.hieght"]
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_path_extras() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config: TestConfigSimple = Faker.fake();
+		let mut value = serde_json::to_value(&test_config).unwrap();
+
+		value["future_setting"] = serde_json::json!({"enabled": true});
+
+		write(tmp_file.path(), value.to_string()).unwrap();
+
+		let mut loaded = WithExtras::<TestConfigSimple>::load_config(&abserde).unwrap();
+
+		assert_eq!(loaded.config, test_config);
+		assert_eq!(
+			loaded.extras["future_setting"],
+			serde_json::json!({"enabled": true})
+		);
+
+		loaded.string_val = "changed".to_string();
+		loaded.save_config(&abserde).unwrap();
+
+		let value: serde_json::Value =
+			serde_json::from_slice(&std::fs::read(tmp_file.path()).unwrap()).unwrap();
+
+		assert_eq!(value["string_val"], "changed");
+		assert_eq!(
+			value["future_setting"],
+			serde_json::json!({"enabled": true})
+		);
+		assert_eq!(
+			TestConfigSimple::load_config(&abserde).unwrap(),
+			TestConfigSimple {
+				string_val: "changed".to_string(),
+				..test_config
+			}
+		);
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_layered() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct Window {
+			width: u32,
+			height: u32,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigNested {
+			theme: String,
+			window: Window,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let layer = |name: &str| Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().join(name)),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		create_dir_all(tmp_dir.path().join("system")).unwrap();
+		create_dir_all(tmp_dir.path().join("user")).unwrap();
+		write(
+			layer("system").config_path().unwrap(),
+			r#"{"theme": "blue", "window": {"width": 1024}}"#,
+		)
+		.unwrap();
+		write(layer("user").config_path().unwrap(), r#"{"theme": "dark"}"#).unwrap();
+
+		let layers = Layers::new()
+			.defaults(TestConfigNested {
+				theme: "light".to_string(),
+				window: Window {
+					width: 800,
+					height: 600,
+				},
+			})
+			.file(layer("system"))
+			.file(layer("missing"))
+			.file(layer("user"));
+
+		assert_eq!(
+			layers.load_layered::<TestConfigNested>().unwrap(),
+			TestConfigNested {
+				theme: "dark".to_string(),
+				window: Window {
+					width: 1024,
+					height: 600,
+				},
+			}
+		);
+
+		write(layer("user").config_path().unwrap(), "not a config").unwrap();
+
+		assert!(layers.load_layered::<TestConfigNested>().is_err());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_layered_strategy() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+		struct TestConfigPlugins {
+			plugins: Vec<String>,
+			colors: HashMap<String, String>,
+			#[serde(default)]
+			theme: String,
+		}
+
+		let tmp_file = NamedTempFile::new().unwrap();
+		let layers = Layers::new()
+			.defaults(TestConfigPlugins {
+				plugins: vec!["core".to_string()],
+				colors: HashMap::from([
+					("background".to_string(), "white".to_string()),
+					("text".to_string(), "black".to_string()),
+				]),
+				theme: "light".to_string(),
+			})
+			.overrides(serde_json::json!({
+				"plugins": ["git"],
+				"colors": {"background": "black"},
+			}));
+
+		assert_eq!(
+			layers.load_layered::<TestConfigPlugins>().unwrap(),
+			TestConfigPlugins {
+				plugins: vec!["git".to_string()],
+				colors: HashMap::from([
+					("background".to_string(), "black".to_string()),
+					("text".to_string(), "black".to_string()),
+				]),
+				theme: "light".to_string(),
+			}
+		);
+
+		let strategy = MergeStrategy {
+			arrays: ArrayMerge::Append,
+			maps: MapMerge::Replace,
+			null_deletes: true,
+		};
+
+		assert_eq!(
+			layers
+				.load_layered_with::<TestConfigPlugins>(&strategy)
+				.unwrap(),
+			TestConfigPlugins {
+				plugins: vec!["core".to_string(), "git".to_string()],
+				colors: HashMap::from([("background".to_string(), "black".to_string())]),
+				theme: "light".to_string(),
+			}
+		);
+
+		write(tmp_file.path(), r#"{"theme": null}"#).unwrap();
+
+		let layers = layers.file(Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		});
+
+		assert!(layers.load_layered::<TestConfigPlugins>().is_err());
+		assert_eq!(
+			layers
+				.load_layered_with::<TestConfigPlugins>(&strategy)
+				.unwrap()
+				.theme,
+			""
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "clap"))]
+	#[test]
+	fn test_json_dir_layered_args() {
+		use clap::Parser;
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct Window {
+			width: u32,
+			height: u32,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigNested {
+			theme: String,
+			window: Window,
+		}
+
+		#[derive(Parser, Serialize)]
+		struct Cli {
+			#[arg(long)]
+			theme: Option<String>,
+
+			#[command(flatten)]
+			#[serde(skip)]
+			config: ConfigArgs,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().to_owned()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		create_dir_all(tmp_dir.path()).unwrap();
+		write(
+			abserde.config_path().unwrap(),
+			r#"{"theme": "dark", "window": {"height": 768}}"#,
+		)
+		.unwrap();
+
+		let layers = |args: &[&str]| {
+			let cli = Cli::try_parse_from(args).unwrap();
+
+			Layers::new()
+				.defaults(TestConfigNested {
+					theme: "light".to_string(),
+					window: Window {
+						width: 800,
+						height: 600,
+					},
+				})
+				.file(abserde.clone())
+				.args(&cli.config)
+				.overrides(cli)
+		};
+
+		assert_eq!(
+			layers(&["app", "--set", "window.width=1920", "--set", "theme=123"])
+				.load_layered::<TestConfigNested>()
+				.unwrap(),
+			TestConfigNested {
+				theme: "123".to_string(),
+				window: Window {
+					width: 1920,
+					height: 768,
+				},
+			}
+		);
+		assert_eq!(
+			layers(&["app", "--theme", "blue"])
+				.load_layered::<TestConfigNested>()
+				.unwrap(),
+			TestConfigNested {
+				theme: "blue".to_string(),
+				window: Window {
+					width: 800,
+					height: 768,
+				},
+			}
+		);
+		assert!(Cli::try_parse_from(["app", "--set", "theme"]).is_err());
+	}
+
+	#[cfg(all(feature = "json", feature = "dotenv"))]
+	#[test]
+	fn test_json_layered_dotenv() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct Window {
+			width: u32,
+			height: u32,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigNested {
+			theme: String,
+			window: Window,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let dotenv_path = tmp_dir.path().join(".env");
+		let layers = Layers::new()
+			.defaults(TestConfigNested {
+				theme: "light".to_string(),
+				window: Window {
+					width: 800,
+					height: 600,
+				},
+			})
+			.dotenv(&dotenv_path, "MYAPP");
+
+		assert_eq!(
+			layers.load_layered::<TestConfigNested>().unwrap().theme,
+			"light"
+		);
+
+		write(
+			&dotenv_path,
+			"# Comment\nMYAPP_THEME=\"dark blue\"\nMYAPP_WINDOW__WIDTH=1920\nOTHER_THEME=red\n",
+		)
+		.unwrap();
+
+		assert_eq!(
+			layers.load_layered::<TestConfigNested>().unwrap(),
+			TestConfigNested {
+				theme: "dark blue".to_string(),
+				window: Window {
+					width: 1920,
+					height: 600,
+				},
+			}
+		);
+
+		write(&dotenv_path, "MYAPP_THEME='dark\n").unwrap();
+
+		assert!(matches!(
+			layers.load_layered::<TestConfigNested>().unwrap_err(),
+			Error::Deserialize(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_builder() {
+		let abserde = Abserde::builder()
+			.app(APP_NAME)
+			.format(Format::PrettyJson(PrettyJsonIndent::Spaces(2)))
+			.location(Location::File("settings.json".into()))
+			.backups(2)
+			.build()
+			.unwrap();
+
+		assert_eq!(
+			abserde,
+			Abserde {
+				app: APP_NAME.to_string(),
+				location: Location::File("settings.json".into()),
+				format: Format::PrettyJson(PrettyJsonIndent::Spaces(2)),
+				backups: 2,
+				..Default::default()
+			}
+		);
+		assert!(Abserde::builder()
+			.format(Format::FromExtension)
+			.location(Location::Path("/tmp/config.json".into()))
+			.build()
+			.is_ok());
+		assert!(Abserde::builder()
+			.location(Location::Path("/tmp/config.unknown".into()))
+			.build()
+			.is_ok());
+
+		for builder in [
+			Abserde::builder().app(""),
+			Abserde::builder().format(Format::FromExtension),
+			Abserde::builder()
+				.format(Format::FromExtension)
+				.location(Location::Path("/tmp/config".into())),
+		] {
+			assert!(matches!(
+				builder.build().unwrap_err(),
+				Error::Unsupported(_)
+			));
+		}
+	}
+
+	#[cfg(all(feature = "json", feature = "toml"))]
+	#[test]
+	fn test_toml_builder_mismatched_extension() {
+		let builder = Abserde::builder().format(Format::Toml);
+
+		assert!(builder
+			.clone()
+			.location(Location::Path("/tmp/config.toml".into()))
+			.build()
+			.is_ok());
+		assert!(matches!(
+			builder
+				.location(Location::File("config.json".into()))
+				.build()
+				.unwrap_err(),
+			Error::Unsupported(_)
+		));
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_embedded_defaults() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct Window {
+			width: u32,
+			height: u32,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigNested {
+			theme: String,
+			window: Window,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().to_owned()),
+			format: Format::Json,
+			backups: 1,
+			..Default::default()
+		}
+		.with_embedded_defaults(r#"{"theme": "light", "window": {"width": 800, "height": 600}}"#);
+
+		assert_eq!(
+			TestConfigNested::load_config(&abserde).unwrap(),
+			TestConfigNested {
+				theme: "light".to_string(),
+				window: Window {
+					width: 800,
+					height: 600,
+				},
+			}
+		);
+		assert!(!abserde.exists().unwrap());
+
+		create_dir_all(tmp_dir.path()).unwrap();
+		write(
+			abserde.config_path().unwrap(),
+			r#"{"window": {"width": 1024}}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			TestConfigNested::load_config(&abserde).unwrap(),
+			TestConfigNested {
+				theme: "light".to_string(),
+				window: Window {
+					width: 1024,
+					height: 600,
+				},
+			}
+		);
+		assert!(matches!(
+			abserde
+				.read_config_file::<TestConfigNested>(&abserde.backup_path(1).unwrap())
+				.unwrap_err(),
+			Error::NotFound(_)
+		));
+	}
+
+	#[cfg(all(feature = "json", feature = "egui"))]
+	#[test]
+	fn test_json_dir_egui_storage() {
+		use std::collections::BTreeMap;
+
+		use eframe::Storage;
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().to_owned()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let mut storage = AbserdeStorage::new(abserde.clone()).unwrap();
+
+		assert_eq!(storage.get_string("theme"), None);
+
+		storage.set_string("theme", "\"dark\"".to_string());
+		storage.set_string("window", "(width: 800)".to_string());
+
+		assert!(!abserde.exists().unwrap());
+
+		storage.flush();
+
+		let storage = AbserdeStorage::new(abserde.clone()).unwrap();
+
+		assert_eq!(storage.get_string("theme"), Some("\"dark\"".to_string()));
+		assert_eq!(
+			storage.get_string("window"),
+			Some("(width: 800)".to_string())
+		);
+		assert_eq!(
+			BTreeMap::<String, String>::load_config(&abserde)
+				.unwrap()
+				.len(),
+			2
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "config"))]
+	#[test]
+	fn test_json_dir_config_source() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct Window {
+			width: u32,
+			height: u32,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigNested {
+			theme: String,
+			window: Window,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().to_owned()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let load = || {
+			config::Config::builder()
+				.set_default("theme", "light")
+				.unwrap()
+				.set_default("window.width", 800)
+				.unwrap()
+				.set_default("window.height", 600)
+				.unwrap()
+				.add_source(abserde.clone())
+				.set_override("window.height", 768)
+				.unwrap()
+				.build()
+				.and_then(config::Config::try_deserialize::<TestConfigNested>)
+		};
+
+		assert_eq!(load().unwrap().theme, "light");
+
+		create_dir_all(tmp_dir.path()).unwrap();
+		write(
+			abserde.config_path().unwrap(),
+			r#"{"theme": "dark", "window": {"width": 1024}}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			load().unwrap(),
+			TestConfigNested {
+				theme: "dark".to_string(),
+				window: Window {
+					width: 1024,
+					height: 768,
+				},
+			}
+		);
+
+		write(abserde.config_path().unwrap(), "not a config").unwrap();
+
+		assert!(load().is_err());
+	}
+
+	#[cfg(feature = "json")]
+	#[test]
+	fn test_json_dir_layered_env() {
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct Window {
+			width: u32,
+			height: u32,
+		}
+
+		#[derive(Serialize, Deserialize, Debug, PartialEq)]
+		struct TestConfigNested {
+			theme: String,
+			window: Window,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().to_owned()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		create_dir_all(tmp_dir.path()).unwrap();
+		write(abserde.config_path().unwrap(), r#"{"theme": "dark"}"#).unwrap();
+		std::env::set_var("ABSERDE_TEST_LAYERED_ENV_THEME", "123");
+		std::env::set_var("ABSERDE_TEST_LAYERED_ENV_WINDOW__WIDTH", "1920");
+
+		let layers = Layers::new()
+			.defaults(TestConfigNested {
+				theme: "light".to_string(),
+				window: Window {
+					width: 800,
+					height: 600,
+				},
+			})
+			.file(abserde)
+			.env("ABSERDE_TEST_LAYERED_ENV");
+
+		assert_eq!(
+			layers.load_layered::<TestConfigNested>().unwrap(),
+			TestConfigNested {
+				theme: "123".to_string(),
+				window: Window {
+					width: 1920,
+					height: 600,
+				},
+			}
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "yaml"))]
+	#[test]
+	fn test_json_path_lenient() {
+		let tmp_dir = TempDir::new().unwrap();
+		let config_path = tmp_dir.path().join("config");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(config_path.clone()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		test_config
+			.save_config(&Abserde {
+				format: Format::Yaml,
+				..abserde.clone()
+			})
+			.unwrap();
+
+		assert!(TestConfigComplex::load_config(&abserde).is_err());
+		assert_eq!(
+			TestConfigComplex::load_config_lenient(&abserde).unwrap(),
+			(test_config.clone(), Format::Yaml)
+		);
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config_lenient(&abserde).unwrap(),
+			(test_config, Format::Json)
+		);
+
+		std::fs::write(&config_path, "not a config").unwrap();
+
+		assert!(TestConfigComplex::load_config_lenient(&abserde).is_err());
+	}
+
+	#[cfg(feature = "toml")]
+	#[test]
+	fn test_toml_path_from_extension() {
+		let tmp_dir = TempDir::new().unwrap();
+		let config_path = tmp_dir.path().join("settings.toml");
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(config_path.clone()),
+			format: Format::FromExtension,
+			..Default::default()
+		};
+		let test_config: TestConfigSimple = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			toml::from_str::<TestConfigSimple>(&std::fs::read_to_string(&config_path).unwrap())
+				.unwrap(),
+			test_config
+		);
+
+		test_save_load_delete::<TestConfigSimple>(&abserde);
+	}
+
+	#[cfg(all(feature = "yaml", feature = "gzip"))]
+	#[test]
+	fn test_yaml_path_from_extension_gzip() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_dir.path().join("settings.yml.gz")),
+			format: Format::FromExtension,
+			compression: Compression::Gzip,
+			..Default::default()
+		});
+	}
+
+	#[test]
+	fn test_from_extension_unknown() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		for location in [
+			Location::Path(tmp_dir.path().join("settings.unknown")),
+			Location::Path(tmp_dir.path().join("settings")),
+			Location::Dir(tmp_dir.path().into()),
+		] {
+			assert!(TestConfigComplex::default()
+				.save_config(&Abserde {
+					app: APP_NAME.to_string(),
+					location,
+					format: Format::FromExtension,
+					..Default::default()
+				})
+				.is_err());
+		}
+	}
+
+	#[cfg(all(feature = "json", feature = "async"))]
+	#[test]
+	fn test_json_path_async() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		futures::executor::block_on(async {
+			test_config.save_config_async(&abserde).await.unwrap();
+
+			assert_eq!(
+				TestConfigComplex::load_config_async(&abserde)
+					.await
+					.unwrap(),
+				test_config
+			);
+		});
+	}
+
+	#[cfg(all(feature = "json", feature = "async"))]
+	#[test]
+	fn test_json_path_async_spawner() {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+
+		static SPAWNED: AtomicUsize = AtomicUsize::new(0);
+
+		struct CountingSpawner;
+
+		impl Spawn for CountingSpawner {
+			fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+				SPAWNED.fetch_add(1, Ordering::SeqCst);
+				std::thread::spawn(task);
+			}
+		}
+
+		set_spawner(CountingSpawner);
+
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		futures::executor::block_on(test_config.save_config_async(&abserde)).unwrap();
+
+		assert!(SPAWNED.load(Ordering::SeqCst) > 0);
+		assert_eq!(
+			futures::executor::block_on(TestConfigComplex::load_config_async(&abserde)).unwrap(),
+			test_config
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "notify"))]
+	#[test]
+	fn test_json_dir_watch() {
+		use std::sync::mpsc::channel;
+		use std::time::Duration;
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let (sender, receiver) = channel();
+		let watcher = TestConfigComplex::watch_config(&abserde, move |config| {
+			sender.send(config.unwrap()).unwrap();
+		})
+		.unwrap();
+
+		for _ in 0..2 {
+			let test_config: TestConfigComplex = Faker.fake();
+
+			test_config.save_config(&abserde).unwrap();
+
+			assert_eq!(
+				receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+				test_config
+			);
+		}
+
+		drop(watcher);
+		TestConfigComplex::default().save_config(&abserde).unwrap();
+
+		assert!(receiver.recv_timeout(WATCH_DEBOUNCE * 5).is_err());
+	}
+
+	#[cfg(all(feature = "json", unix))]
+	#[test]
+	#[serial]
+	fn test_json_dir_reload_on_hangup() {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+		use std::sync::mpsc::channel;
+		use std::time::Duration;
+
+		static HANGUPS: AtomicUsize = AtomicUsize::new(0);
+
+		extern "C" fn on_hangup(_: libc::c_int) {
+			HANGUPS.fetch_add(1, Ordering::SeqCst);
+		}
+
+		// This also replaces the shutdown handler installed by other tests, which would end the process.
+		// SAFETY: The handler only does what's safe in a signal handler.
+		unsafe {
+			libc::signal(
+				libc::SIGHUP,
+				on_hangup as extern "C" fn(libc::c_int) as libc::sighandler_t,
+			)
+		};
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let (sender, receiver) = channel();
+		let reloader = TestConfigComplex::reload_on_hangup(&abserde, move |config| {
+			sender.send(config.unwrap()).unwrap();
+		})
+		.unwrap();
+
+		for _ in 0..2 {
+			let test_config: TestConfigComplex = Faker.fake();
+
+			test_config.save_config(&abserde).unwrap();
+
+			assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+			// SAFETY: raise has no preconditions, and SIGHUP is handled rather than ending the process.
+			unsafe { libc::raise(libc::SIGHUP) };
+
+			assert_eq!(
+				receiver.recv_timeout(Duration::from_secs(5)).unwrap(),
+				test_config
+			);
+		}
+
+		drop(reloader);
+		unsafe { libc::raise(libc::SIGHUP) };
+
+		assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+		assert_eq!(HANGUPS.load(Ordering::SeqCst), 3);
+	}
+
+	#[cfg(all(feature = "json", feature = "async", feature = "notify"))]
+	#[test]
+	fn test_json_dir_watch_stream() {
+		use futures::StreamExt;
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let mut stream = TestConfigComplex::watch_config_stream(&abserde).unwrap();
+
+		futures::executor::block_on(async {
+			for _ in 0..2 {
+				let test_config: TestConfigComplex = Faker.fake();
+
+				test_config.save_config_async(&abserde).await.unwrap();
+
+				assert_eq!(stream.next().await.unwrap().unwrap(), test_config);
+			}
+		});
+	}
+
+	#[cfg(all(feature = "json", feature = "gzip"))]
+	#[test]
+	fn test_json_path_gzip() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			compression: Compression::Gzip,
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert!(std::fs::read(tmp_file.path())
+			.unwrap()
+			.starts_with(&[0x1f, 0x8b]));
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+	}
+
+	#[cfg(all(feature = "json", feature = "zstd"))]
+	#[test]
+	fn test_json_path_zstd() {
+		let tmp_file = NamedTempFile::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			compression: Compression::Zstd(19),
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert!(std::fs::read(tmp_file.path())
+			.unwrap()
+			.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]));
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+	}
+
+	#[cfg(all(feature = "json", feature = "zstd"))]
+	#[test]
+	fn test_json_dir_zstd() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			compression: Compression::Zstd(0),
+			..Default::default()
+		};
+
+		assert_eq!(
+			abserde.config_path().unwrap(),
+			tmp_dir.path().join("config.json.zst")
+		);
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+	}
+
+	#[cfg(all(feature = "json", feature = "gzip", feature = "encryption"))]
+	#[test]
+	fn test_json_path_gzip_encrypted() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Passphrase(KeySource::Passphrase("passphrase".to_string())),
+			compression: Compression::Gzip,
+			..Default::default()
+		});
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_path_encrypted() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigComplex>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Passphrase(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		});
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_encrypted_wrong_passphrase() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Passphrase(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		};
+		let mut test_config: TestConfigComplex = Faker.fake();
+
+		test_config.string_val = "secret value".to_string();
+		test_config.save_config(&abserde).unwrap();
+
+		let contents = std::fs::read(abserde.config_path().unwrap()).unwrap();
+
+		assert!(!String::from_utf8_lossy(&contents).contains("secret value"));
+		assert!(TestConfigComplex::load_config(&Abserde {
+			encryption: Encryption::Passphrase(KeySource::Passphrase("wrong".to_string())),
+			..abserde.clone()
+		})
+		.is_err());
+		assert!(TestConfigComplex::load_config(&Abserde {
+			encryption: Encryption::None,
+			..abserde.clone()
+		})
+		.is_err());
+	}
+
+	// Test config type with individually encrypted fields.
+	#[cfg(feature = "encryption")]
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct TestConfigSecrets {
+		username: String,
+		password: Secret<String>,
+		pins: Secret<Vec<u16>>,
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_path_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		};
+		let test_config = TestConfigSecrets {
+			username: "visible user".to_string(),
+			password: Secret("hidden password".to_string()),
+			pins: vec![1234, 5678].into(),
+		};
+
+		test_config.save_config(&abserde).unwrap();
+
+		let contents = std::fs::read_to_string(abserde.config_path().unwrap()).unwrap();
+
+		assert!(contents.contains("visible user"));
+		assert!(!contents.contains("hidden password"));
+		assert!(!contents.contains("1234"));
+		assert_eq!(
+			TestConfigSecrets::load_config(&abserde).unwrap(),
+			test_config
+		);
+		assert!(TestConfigSecrets::load_config(&Abserde {
+			encryption: Encryption::Fields(KeySource::Passphrase("wrong".to_string())),
+			..abserde.clone()
+		})
+		.is_err());
+		assert!(TestConfigSecrets::load_config(&Abserde {
+			encryption: Encryption::None,
+			..abserde.clone()
+		})
+		.is_err());
+		assert!(test_config
+			.save_config(&Abserde {
+				encryption: Encryption::None,
+				..abserde
+			})
+			.is_err());
+	}
+
+	// Save a config with encrypted fields and load it back, checking the fields were encrypted.
+	#[cfg(feature = "encryption")]
+	fn assert_secrets_round_trip(abserde: &Abserde) {
+		let test_config = TestConfigSecrets {
+			username: "visible user".to_string(),
+			password: Secret("hidden password".to_string()),
+			pins: vec![1234, 5678].into(),
+		};
+
+		test_config.save_config(abserde).unwrap();
+
+		let contents = std::fs::read_to_string(abserde.config_path().unwrap()).unwrap();
+
+		assert!(contents.contains("visible user"));
+		assert!(!contents.contains("hidden password"));
+		assert_eq!(
+			TestConfigSecrets::load_config(abserde).unwrap(),
+			test_config
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_dir_namespace_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		assert_secrets_round_trip(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			namespace: Some("accounts".to_string()),
+			..Default::default()
+		});
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_dir_sections_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		}
+		.with_section_file("password", "credentials");
+
+		assert_secrets_round_trip(&abserde);
+
+		let contents = std::fs::read_to_string(tmp_dir.path().join("credentials.json")).unwrap();
+
+		assert!(!contents.is_empty());
+		assert!(!contents.contains("hidden password"));
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_dir_policy_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+		let policy_path = tmp_dir.path().join("policy.json");
+
+		std::fs::write(&policy_path, r#"{"username": "visible user"}"#).unwrap();
+
+		assert_secrets_round_trip(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			policy: Some(Location::Path(policy_path)),
+			..Default::default()
+		});
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_dir_interpolate_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		};
+
+		// Configs can't be saved while interpolating, so they're only loaded that way.
+		assert_secrets_round_trip(&abserde);
+		assert_eq!(
+			TestConfigSecrets::load_config(&Abserde {
+				interpolate: true,
+				..abserde.clone()
+			})
+			.unwrap(),
+			TestConfigSecrets::load_config(&abserde).unwrap()
+		);
+	}
+
+	#[cfg(all(feature = "yaml", feature = "encryption"))]
+	#[test]
+	fn test_yaml_dir_preserve_comments_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Yaml,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			preserve_comments: true,
+			..Default::default()
+		};
+
+		assert_secrets_round_trip(&abserde);
+
+		// The config file exists now, so it's patched in place.
+		assert_secrets_round_trip(&abserde);
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_dir_include_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		};
+
+		assert_secrets_round_trip(&abserde);
+
+		let shared = TestConfigSecrets::load_config(&abserde).unwrap();
+		let including = Abserde {
+			name: Some("including".to_string()),
+			include_key: Some("include".to_string()),
+			..abserde.clone()
+		};
+
+		write(
+			including.config_path().unwrap(),
+			r#"{"include": "config.json", "username": "including user"}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			TestConfigSecrets::load_config(&including).unwrap(),
+			TestConfigSecrets {
+				username: "including user".to_string(),
+				..shared
+			}
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_dir_layered_secrets() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		};
+		let defaults = || TestConfigSecrets {
+			username: "default user".to_string(),
+			password: Secret("default password".to_string()),
+			pins: vec![1234].into(),
+		};
+
+		write(
+			abserde.config_path().unwrap(),
+			r#"{"username": "file user"}"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			Layers::new()
+				.defaults(defaults())
+				.file(abserde)
+				.load_layered::<TestConfigSecrets>()
+				.unwrap(),
+			TestConfigSecrets {
+				username: "file user".to_string(),
+				..defaults()
+			}
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "signing"))]
+	#[test]
+	fn test_json_dir_signing() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			signing: Signing::Hmac(KeySource::Passphrase("signing key".to_string())),
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+		let test_config: TestConfigComplex = Faker.fake();
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert_eq!(
+			TestConfigComplex::load_config(&abserde).unwrap(),
+			test_config
+		);
+		assert!(matches!(
+			TestConfigComplex::load_config(&Abserde {
+				signing: Signing::Hmac(KeySource::Passphrase("wrong".to_string())),
+				..abserde.clone()
+			})
+			.unwrap_err()
+			.root(),
+			Error::SignatureMismatch
+		));
+
+		let mut contents = read(&config_path).unwrap();
+		let last = contents.len() - 1;
+
+		contents[last] ^= 1;
+		write(&config_path, &contents).unwrap();
+
+		assert!(matches!(
+			TestConfigComplex::load_config(&abserde).unwrap_err().root(),
+			Error::SignatureMismatch
+		));
+
+		test_config
+			.save_config(&Abserde {
+				signing: Signing::None,
+				..abserde.clone()
+			})
+			.unwrap();
+
+		assert!(matches!(
+			TestConfigComplex::load_config(&abserde).unwrap_err().root(),
+			Error::SignatureMismatch
+		));
+	}
+
+	#[cfg(feature = "encryption")]
+	#[test]
+	fn test_secret_zeroize() {
+		use zeroize::Zeroize;
+
+		let mut secret = Secret("hidden password".to_string());
+
+		secret.zeroize();
+
+		assert!(secret.is_empty());
+		assert_eq!(Secret(vec![1234, 5678]).into_inner(), vec![1234, 5678]);
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_path_secrets_plain() {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Fields(KeySource::Passphrase("passphrase".to_string())),
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
+
+		create_dir_all(config_path.parent().unwrap()).unwrap();
+		write(
+			&config_path,
+			r#"{"username": "user", "password": "hand written", "pins": [1, 2]}"#,
+		)
+		.unwrap();
+
+		let test_config = TestConfigSecrets::load_config(&abserde).unwrap();
+
+		assert_eq!(*test_config.password, "hand written");
+		assert_eq!(*test_config.pins, vec![1, 2]);
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert!(!std::fs::read_to_string(&config_path)
+			.unwrap()
+			.contains("hand written"));
+		assert_eq!(
+			TestConfigSecrets::load_config(&abserde).unwrap(),
+			test_config
+		);
+	}
+
+	#[cfg(all(feature = "json", feature = "encryption"))]
+	#[test]
+	fn test_json_dir_rotate_key() {
+		let tmp_dir = TempDir::new().unwrap();
+		let old = KeySource::Passphrase("old passphrase".to_string());
+		let new = KeySource::Passphrase("new passphrase".to_string());
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			encryption: Encryption::Passphrase(old.clone()),
+			backups: 2,
+			..Default::default()
+		};
+		let rotated_abserde = Abserde {
+			encryption: Encryption::Passphrase(new.clone()),
+			..abserde.clone()
+		};
+		let test_configs: Vec<TestConfigComplex> = (0..3).map(|_| Faker.fake()).collect();
+
+		for test_config in &test_configs {
+			test_config.save_config(&abserde).unwrap();
+		}
+
+		abserde.rotate_key(old.clone(), new.clone()).unwrap();
+
+		assert!(TestConfigComplex::load_config(&abserde).is_err());
+		assert_eq!(
+			TestConfigComplex::load_config(&rotated_abserde).unwrap(),
+			test_configs[2]
+		);
+
+		for index in 1..=2 {
+			let backup_path = rotated_abserde.backup_path(index).unwrap();
+
+			assert_eq!(
+				rotated_abserde
+					.read_config_file::<TestConfigComplex>(&backup_path)
+					.unwrap(),
+				test_configs[2 - index]
+			);
+		}
 
-				serde_ini::from_read(io::BufReader::new(file))?
-			}
-			#[cfg(feature = "toml")]
-			Format::Toml => {
-				use io::Read;
+		let abserde = Abserde {
+			location: Location::Dir(tmp_dir.path().join("fields")),
+			encryption: Encryption::Fields(old.clone()),
+			..abserde
+		};
+		let test_config = TestConfigSecrets {
+			username: "visible user".to_string(),
+			password: Secret("hidden password".to_string()),
+			pins: vec![1234, 5678].into(),
+		};
 
-				let mut file = File::open(config_path)?;
-				let mut buf = String::new();
+		test_config.save_config(&abserde).unwrap();
+		abserde.rotate_key(old.clone(), new.clone()).unwrap();
 
-				file.read_to_string(&mut buf)?;
+		let contents = std::fs::read_to_string(abserde.config_path().unwrap()).unwrap();
 
-				toml::from_str(&buf)?
+		assert!(contents.contains("visible user"));
+		assert!(!contents.contains("hidden password"));
+		assert!(TestConfigSecrets::load_config(&abserde).is_err());
+		assert_eq!(
+			TestConfigSecrets::load_config(&Abserde {
+				encryption: Encryption::Fields(new.clone()),
+				..abserde.clone()
+			})
+			.unwrap(),
+			test_config
+		);
+		assert!(matches!(
+			Abserde {
+				encryption: Encryption::None,
+				..abserde
 			}
-		})
+			.rotate_key(old, new)
+			.unwrap_err()
+			.root(),
+			Error::Unsupported(_)
+		));
 	}
 
-	fn save_config(&self, abserde: &Abserde) -> Result<()> {
-		let config_path = abserde.config_path()?;
-		let config_dir = config_path
-			.parent()
-			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, MSG_NO_SYSTEM_CONFIG_DIR))?;
+	// In-memory keychain, as an OS keychain isn't always available to tests.
+	#[cfg(feature = "keyring")]
+	mod memory_keychain {
+		use std::any::Any;
+		use std::collections::BTreeMap;
+		use std::sync::Mutex;
 
-		create_dir_all(config_dir)?;
+		use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
 
-		match &abserde.format {
-			#[cfg(feature = "json")]
-			Format::Json => {
-				serde_json::to_writer(File::create(&config_path)?, self)?;
-			}
-			#[cfg(feature = "json")]
-			Format::PrettyJson(indent) => {
-				use io::Write;
+		static ENTRIES: Mutex<BTreeMap<(String, String), Vec<u8>>> = Mutex::new(BTreeMap::new());
 
-				let mut buf = Vec::new();
-				let indent_string = indent.to_string();
-				let formatter =
-					serde_json::ser::PrettyFormatter::with_indent(indent_string.as_bytes());
-				let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
-				self.serialize(&mut ser).unwrap();
+		struct MemoryCredential(String, String);
 
-				write!(File::create(&config_path)?, "{}\n", String::from_utf8(buf)?)?;
-			}
-			#[cfg(feature = "yaml")]
-			Format::Yaml => {
-				serde_yaml::to_writer(File::create(&config_path)?, self)?;
+		impl CredentialApi for MemoryCredential {
+			fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+				ENTRIES
+					.lock()
+					.unwrap()
+					.insert((self.0.clone(), self.1.clone()), secret.to_vec());
+
+				Ok(())
 			}
-			#[cfg(feature = "pickle")]
-			Format::Pickle => {
-				serde_pickle::to_writer(
-					&mut File::create(&config_path)?,
-					self,
-					serde_pickle::SerOptions::new(),
-				)?;
+
+			fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+				ENTRIES
+					.lock()
+					.unwrap()
+					.get(&(self.0.clone(), self.1.clone()))
+					.cloned()
+					.ok_or(keyring::Error::NoEntry)
 			}
-			#[cfg(feature = "ini")]
-			Format::Ini => {
-				serde_ini::to_writer(File::create(&config_path)?, self)?;
+
+			fn delete_credential(&self) -> keyring::Result<()> {
+				ENTRIES
+					.lock()
+					.unwrap()
+					.remove(&(self.0.clone(), self.1.clone()))
+					.map(|_| ())
+					.ok_or(keyring::Error::NoEntry)
 			}
-			#[cfg(feature = "toml")]
-			Format::Toml => {
-				use io::Write;
 
-				write!(File::create(&config_path)?, "{}", toml::to_string(self)?)?;
+			fn as_any(&self) -> &dyn Any {
+				self
 			}
 		}
 
-		Ok(())
-	}
-}
-
-#[cfg(test)]
-mod tests {
-	use std::collections::HashMap;
-	use std::fmt::Debug;
-
-	use fake::{Dummy, Fake, Faker};
-	use serde::{de::DeserializeOwned, Deserialize, Serialize};
-	use serial_test::serial;
-	use tempfile::{NamedTempFile, TempDir};
+		struct MemoryCredentialBuilder;
 
-	use crate::{Abserde, Config, Format, Location, PrettyJsonIndent};
+		impl CredentialBuilderApi for MemoryCredentialBuilder {
+			fn build(
+				&self,
+				_target: Option<&str>,
+				service: &str,
+				user: &str,
+			) -> keyring::Result<Box<Credential>> {
+				Ok(Box::new(MemoryCredential(
+					service.to_string(),
+					user.to_string(),
+				)))
+			}
 
-	const APP_NAME: &str = env!("CARGO_PKG_NAME");
+			fn as_any(&self) -> &dyn Any {
+				self
+			}
+		}
 
-	// Test config type for serialisation formats that only accept basic types.
-	#[derive(Serialize, Deserialize, Debug, Default, Dummy, PartialEq)]
-	struct TestConfigSimple {
-		string_val: String,
-		i8_val: i8,
-		i16_val: i16,
-		i32_val: i32,
-		u8_val: u8,
-		u16_val: u16,
-		u32_val: u32,
-		f32_val: f32,
+		pub fn install() {
+			keyring::set_default_credential_builder(Box::new(MemoryCredentialBuilder));
+		}
 	}
 
-	// More complex config type for serialisation formats that support advanced types.
-	#[derive(Serialize, Deserialize, Debug, Default, Dummy, PartialEq)]
-	struct TestConfigComplex {
-		string_val: String,
-		i8_val: i8,
-		i16_val: i16,
-		i32_val: i32,
-		i64_val: i64,
-		i128_val: i128,
-		u8_val: u8,
-		u16_val: u16,
-		u32_val: u32,
-		u64_val: u64,
-		u128_val: u128,
-		f32_val: f32,
-		f64_val: f64,
-		vec_1_val: Vec<i64>,
-		vec_2_val: Vec<(String, i8, i8, i32, i64, String, String, String)>,
-		vec_3_val: Vec<(f32, f32, f32, f64, f64)>,
-		hash_map_1_val: HashMap<String, String>,
-		hash_map_2_val: HashMap<i16, i64>,
-		hash_map_3_val: HashMap<i16, Vec<(String, i32, u8, HashMap<String, i32>)>>,
-		hash_map_4_val: HashMap<String, (f64, f32, i8)>,
+	// Test config type with values stored in the keychain.
+	#[cfg(feature = "keyring")]
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct TestConfigKeychain {
+		username: String,
+		token: Keychain<String>,
 	}
 
-	// Generic dispatch method.
-	fn test_save_load_delete<T>(abserde: &Abserde)
-	where
-		T: Serialize,
-		T: DeserializeOwned,
-		T: Dummy<Faker>,
-		T: PartialEq,
-		T: Debug,
-	{
-		let test_config_saved: T = Faker.fake();
+	#[cfg(all(feature = "json", feature = "keyring"))]
+	#[test]
+	fn test_json_path_keychain() {
+		memory_keychain::install();
 
-		test_config_saved.save_config(&abserde).unwrap();
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Json,
+			..Default::default()
+		};
+		let test_config = TestConfigKeychain {
+			username: "visible user".to_string(),
+			token: Keychain::new("hidden token".to_string()),
+		};
 
-		let test_config_loaded = T::load_config(&abserde).unwrap();
+		test_config.save_config(&abserde).unwrap();
 
-		assert_eq!(test_config_saved, test_config_loaded);
+		let contents = std::fs::read_to_string(abserde.config_path().unwrap()).unwrap();
 
-		abserde.delete().unwrap();
+		assert!(contents.contains("visible user"));
+		assert!(contents.contains(&format!("keychain:{}", test_config.token.id())));
+		assert!(!contents.contains("hidden token"));
+		assert_eq!(
+			TestConfigKeychain::load_config(&abserde).unwrap(),
+			test_config
+		);
+		assert!(TestConfigKeychain::load_config(&Abserde {
+			app: "other app".to_string(),
+			..abserde
+		})
+		.is_err());
+		assert!(serde_json::to_string(&test_config).is_err());
 	}
 
+	#[cfg(all(feature = "json", feature = "keyring"))]
 	#[test]
-	#[serial]
-	fn test_auto() {
-		test_save_load_delete::<TestConfigSimple>(&Abserde::default());
-	}
+	fn test_json_path_keychain_plain() {
+		memory_keychain::install();
 
-	#[cfg(feature = "json")]
-	#[test]
-	#[serial]
-	fn test_json_auto() {
-		test_save_load_delete::<TestConfigComplex>(&Abserde {
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
 			app: APP_NAME.to_string(),
-			location: Location::Auto,
+			location: Location::Dir(tmp_dir.path().into()),
 			format: Format::Json,
-		});
-	}
+			..Default::default()
+		};
+		let config_path = abserde.config_path().unwrap();
 
-	#[cfg(feature = "json")]
-	#[test]
-	#[serial]
-	fn test_pretty_json_auto() {
-		test_save_load_delete::<TestConfigComplex>(&Abserde {
-			app: APP_NAME.to_string(),
-			location: Location::Auto,
-			format: Format::PrettyJson(PrettyJsonIndent::default()),
-		});
+		create_dir_all(config_path.parent().unwrap()).unwrap();
+		write(
+			&config_path,
+			r#"{"username": "user", "token": "hand written"}"#,
+		)
+		.unwrap();
+
+		let test_config = TestConfigKeychain::load_config(&abserde).unwrap();
+
+		assert_eq!(*test_config.token, "hand written");
+
+		test_config.save_config(&abserde).unwrap();
+
+		assert!(!std::fs::read_to_string(&config_path)
+			.unwrap()
+			.contains("hand written"));
+		assert_eq!(
+			TestConfigKeychain::load_config(&abserde).unwrap(),
+			test_config
+		);
 	}
 
-	#[cfg(feature = "json")]
-	#[test]
-	fn test_json_path() {
-		let tmp_file = NamedTempFile::new().unwrap();
+	// Generate an age identity file, returning it along with the matching recipient.
+	#[cfg(feature = "age")]
+	fn age_identity() -> (NamedTempFile, String) {
+		use age::secrecy::ExposeSecret;
 
-		test_save_load_delete::<TestConfigComplex>(&Abserde {
-			app: APP_NAME.to_string(),
-			location: Location::Path(tmp_file.path().into()),
-			format: Format::Json,
-		});
+		let identity = age::x25519::Identity::generate();
+		let identity_file = NamedTempFile::new().unwrap();
+
+		write(identity_file.path(), identity.to_string().expose_secret()).unwrap();
+
+		(identity_file, identity.to_public().to_string())
 	}
 
-	#[cfg(feature = "json")]
+	#[cfg(all(feature = "json", feature = "age"))]
 	#[test]
-	fn test_pretty_json_path() {
+	fn test_json_path_age() {
 		let tmp_file = NamedTempFile::new().unwrap();
+		let (identity_file, recipient) = age_identity();
 
 		test_save_load_delete::<TestConfigComplex>(&Abserde {
 			app: APP_NAME.to_string(),
 			location: Location::Path(tmp_file.path().into()),
-			format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
-		});
-	}
-
-	#[cfg(feature = "json")]
-	#[test]
-	#[serial]
-	fn test_json_file() {
-		test_save_load_delete::<TestConfigComplex>(&Abserde {
-			app: APP_NAME.to_string(),
-			location: Location::File("custom_file.json".into()),
 			format: Format::Json,
+			encryption: Encryption::Age {
+				recipients: vec![recipient],
+				identity: identity_file.path().into(),
+			},
+			..Default::default()
 		});
 	}
 
-	#[cfg(feature = "json")]
-	#[test]
-	#[serial]
-	fn test_pretty_json_file() {
-		test_save_load_delete::<TestConfigComplex>(&Abserde {
-			app: APP_NAME.to_string(),
-			location: Location::File("custom_file.json".into()),
-			format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
-		});
-	}
-
-	#[cfg(feature = "json")]
+	#[cfg(all(feature = "json", feature = "age"))]
 	#[test]
-	fn test_json_dir() {
+	fn test_json_age_wrong_identity() {
 		let tmp_dir = TempDir::new().unwrap();
-
-		test_save_load_delete::<TestConfigComplex>(&Abserde {
+		let (identity_file, recipient) = age_identity();
+		let (other_identity_file, _) = age_identity();
+		let abserde = Abserde {
 			app: APP_NAME.to_string(),
 			location: Location::Dir(tmp_dir.path().into()),
 			format: Format::Json,
-		});
-	}
+			encryption: Encryption::Age {
+				recipients: vec![recipient.clone()],
+				identity: identity_file.path().into(),
+			},
+			..Default::default()
+		};
+		let test_config: TestConfigComplex = Faker.fake();
 
-	#[cfg(feature = "json")]
-	#[test]
-	fn test_pretty_json_dir() {
-		let tmp_dir = TempDir::new().unwrap();
+		test_config.save_config(&abserde).unwrap();
 
-		test_save_load_delete::<TestConfigComplex>(&Abserde {
-			app: APP_NAME.to_string(),
-			location: Location::Dir(tmp_dir.path().into()),
-			format: Format::PrettyJson(PrettyJsonIndent::Spaces(4)),
-		});
+		assert!(TestConfigComplex::load_config(&Abserde {
+			encryption: Encryption::Age {
+				recipients: vec![recipient],
+				identity: other_identity_file.path().into(),
+			},
+			..abserde.clone()
+		})
+		.is_err());
 	}
 
 	#[cfg(feature = "yaml")]
@@ -625,6 +9542,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Auto,
 			format: Format::Yaml,
+			..Default::default()
 		});
 	}
 
@@ -637,6 +9555,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Path(tmp_file.path().into()),
 			format: Format::Yaml,
+			..Default::default()
 		});
 	}
 
@@ -648,6 +9567,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::File("custom_file.yaml".into()),
 			format: Format::Yaml,
+			..Default::default()
 		});
 	}
 
@@ -660,6 +9580,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Dir(tmp_dir.path().into()),
 			format: Format::Yaml,
+			..Default::default()
 		});
 	}
 
@@ -671,6 +9592,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Auto,
 			format: Format::Pickle,
+			..Default::default()
 		});
 	}
 
@@ -683,6 +9605,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Path(tmp_file.path().into()),
 			format: Format::Pickle,
+			..Default::default()
 		});
 	}
 
@@ -694,6 +9617,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::File("custom_file.pickle".into()),
 			format: Format::Pickle,
+			..Default::default()
 		});
 	}
 
@@ -706,6 +9630,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Dir(tmp_dir.path().into()),
 			format: Format::Pickle,
+			..Default::default()
 		});
 	}
 
@@ -717,6 +9642,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Auto,
 			format: Format::Ini,
+			..Default::default()
 		});
 	}
 
@@ -729,6 +9655,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Path(tmp_file.path().into()),
 			format: Format::Ini,
+			..Default::default()
 		});
 	}
 
@@ -740,6 +9667,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::File("custom_file.ini".into()),
 			format: Format::Ini,
+			..Default::default()
 		});
 	}
 
@@ -752,9 +9680,68 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Dir(tmp_dir.path().into()),
 			format: Format::Ini,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "ini")]
+	#[test]
+	fn test_ini_sections_path() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigSections>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Ini,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "ini")]
+	#[test]
+	fn test_ini_sections_dir() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		test_save_load_delete::<TestConfigSections>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Ini,
+			..Default::default()
 		});
 	}
 
+	#[cfg(feature = "ini")]
+	#[test]
+	fn test_ini_dir_newtype_variant() {
+		#[derive(Serialize, Deserialize)]
+		enum Size {
+			Pixels(u32),
+		}
+
+		#[derive(Serialize, Deserialize)]
+		struct Window {
+			size: Size,
+		}
+
+		let tmp_dir = TempDir::new().unwrap();
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Ini,
+			..Default::default()
+		};
+		let error = Window {
+			size: Size::Pixels(1920),
+		}
+		.save_config(&abserde)
+		.unwrap_err();
+
+		assert!(matches!(
+			error.root(),
+			Error::Serialize(err) if err.to_string().contains("enum variant Pixels")
+		));
+	}
+
 	#[cfg(feature = "toml")]
 	#[test]
 	#[serial]
@@ -763,6 +9750,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Auto,
 			format: Format::Toml,
+			..Default::default()
 		});
 	}
 
@@ -775,6 +9763,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Path(tmp_file.path().into()),
 			format: Format::Toml,
+			..Default::default()
 		});
 	}
 
@@ -786,6 +9775,7 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::File("custom_file.toml".into()),
 			format: Format::Toml,
+			..Default::default()
 		});
 	}
 
@@ -798,6 +9788,92 @@ mod tests {
 			app: APP_NAME.to_string(),
 			location: Location::Dir(tmp_dir.path().into()),
 			format: Format::Toml,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "plist")]
+	#[test]
+	#[serial]
+	fn test_plist_auto() {
+		test_save_load_delete::<TestConfigSimple>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Auto,
+			format: Format::Plist,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "plist")]
+	#[test]
+	fn test_plist_path() {
+		let tmp_file = NamedTempFile::new().unwrap();
+
+		test_save_load_delete::<TestConfigSimple>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Path(tmp_file.path().into()),
+			format: Format::Plist,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "plist")]
+	#[test]
+	#[serial]
+	fn test_plist_file() {
+		test_save_load_delete::<TestConfigSimple>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::File("custom_file.plist".into()),
+			format: Format::Plist,
+			..Default::default()
+		});
+	}
+
+	#[cfg(feature = "plist")]
+	#[test]
+	fn test_plist_dir() {
+		let tmp_dir = TempDir::new().unwrap();
+
+		test_save_load_delete::<TestConfigSimple>(&Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::Dir(tmp_dir.path().into()),
+			format: Format::Plist,
+			..Default::default()
 		});
 	}
+
+	#[cfg(all(feature = "json", feature = "plist"))]
+	#[test]
+	#[serial]
+	fn test_json_user_defaults() {
+		let abserde = Abserde {
+			app: APP_NAME.to_string(),
+			location: Location::UserDefaults("org.abserde.test".to_string()),
+			format: Format::Json,
+			..Default::default()
+		};
+
+		if cfg!(target_os = "macos") {
+			assert_eq!(
+				abserde.config_path().unwrap(),
+				dirs::preference_dir()
+					.unwrap()
+					.join("org.abserde.test.plist")
+			);
+			assert_eq!(abserde.file_format().unwrap(), Format::Plist);
+		} else {
+			assert_eq!(
+				abserde.config_path().unwrap(),
+				Abserde {
+					location: Location::Auto,
+					..abserde.clone()
+				}
+				.config_path()
+				.unwrap()
+			);
+			assert_eq!(abserde.file_format().unwrap(), Format::Json);
+		}
+
+		test_save_load_delete::<TestConfigComplex>(&abserde);
+	}
 }
@@ -0,0 +1,76 @@
+//! Line endings of config files saved in text formats, given by
+//! [Abserde::line_ending](crate::Abserde::line_ending).
+
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+/// Line endings written to config files in text formats, such as JSON, YAML, TOML and INI.
+///
+/// Config files with any line endings can be loaded, whichever is chosen here. Binary formats are unaffected.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	line_ending: LineEnding::CrLf,
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum LineEnding {
+	/// Lines end with a line feed, as on Unix, which suits config files kept in a dotfile repo.
+	#[default]
+	Lf,
+
+	/// Lines end with a carriage return and a line feed, as on Windows, which suits legacy Windows editors.
+	CrLf,
+
+	/// Lines end as is usual for the platform, which is [LineEnding::CrLf] on Windows and [LineEnding::Lf]
+	/// elsewhere.
+	Native,
+}
+
+impl LineEnding {
+	// Whether lines end with a carriage return before the line feed.
+	pub(crate) fn is_crlf(&self) -> bool {
+		match self {
+			LineEnding::Lf => false,
+			LineEnding::CrLf => true,
+			LineEnding::Native => cfg!(windows),
+		}
+	}
+}
+
+// Writer ending the lines written through it with a carriage return and a line feed.
+pub(crate) struct CrLfWriter<W> {
+	inner: W,
+}
+
+impl<W: Write> CrLfWriter<W> {
+	pub(crate) fn new(inner: W) -> Self {
+		Self { inner }
+	}
+}
+
+impl<W: Write> Write for CrLfWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let mut start = 0;
+
+		// Serialisers only write bare line feeds, so each one is preceded by a carriage return.
+		for (index, _) in buf.iter().enumerate().filter(|(_, byte)| **byte == b'\n') {
+			self.inner.write_all(&buf[start..index])?;
+			self.inner.write_all(b"\r\n")?;
+			start = index + 1;
+		}
+
+		self.inner.write_all(&buf[start..])?;
+
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
@@ -0,0 +1,61 @@
+//! Storage of configs in the browser's `localStorage`, selected with
+//! [Storage::LocalStorage](crate::Storage::LocalStorage).
+//!
+//! Configs are stored under the name of the app's directory followed by the path the config file would have
+//! within it, such as `MyApp/keybindings.json`, so each app, profile and named config has a key of its own.
+
+use std::path::Path;
+
+use web_sys::wasm_bindgen::JsValue;
+
+use crate::{Error, Result};
+
+// Key of a config file within localStorage.
+fn key(config_path: &Path) -> String {
+	config_path.to_string_lossy().replace('\\', "/")
+}
+
+// The localStorage of the current window.
+fn local_storage() -> Result<web_sys::Storage> {
+	web_sys::window()
+		.ok_or_else(|| Error::Browser("no window available for localStorage".to_string()))?
+		.local_storage()
+		.map_err(browser_error)?
+		.ok_or_else(|| Error::Browser("localStorage is disabled".to_string()))
+}
+
+// Convert an exception thrown by the browser, such as when the storage quota is exceeded.
+fn browser_error(err: JsValue) -> Error {
+	Error::Browser(format!("{:?}", err))
+}
+
+// Read the stored data of a config file, if it has been stored.
+pub(crate) fn read(config_path: &Path) -> Result<Option<Vec<u8>>> {
+	Ok(local_storage()?
+		.get_item(&key(config_path))
+		.map_err(browser_error)?
+		.map(String::into_bytes))
+}
+
+// Store the data of a config file, replacing any stored before.
+pub(crate) fn write(config_path: &Path, data: &[u8]) -> Result<()> {
+	// localStorage only holds strings, so binary formats, compression and encryption can't be stored.
+	let data = std::str::from_utf8(data).map_err(|_| {
+		Error::Unsupported("localStorage can only hold configs serialised as text".to_string())
+	})?;
+
+	local_storage()?
+		.set_item(&key(config_path), data)
+		.map_err(browser_error)
+}
+
+// Delete the stored data of a config file, returning whether it had been stored.
+pub(crate) fn delete(config_path: &Path) -> Result<bool> {
+	let storage = local_storage()?;
+	let key = key(config_path);
+	let stored = storage.get_item(&key).map_err(browser_error)?.is_some();
+
+	storage.remove_item(&key).map_err(browser_error)?;
+
+	Ok(stored)
+}
@@ -0,0 +1,172 @@
+//! Advisory locks on config files held by loads, saves and deletes, enabled with
+//! [Abserde::lock](crate::Abserde::lock).
+//!
+//! Locks are held on a lock file next to the config file, as the config file itself is replaced when saved
+//! atomically. Loads share the lock, while saves and deletes hold it exclusively. Deletes remove the lock file
+//! along with the config file.
+
+use std::cell::RefCell;
+use std::fs::{self, File, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::result;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::{Abserde, Error, Operation, Result, Storage};
+
+// Time between attempts to take a lock held by another process, until the lock timeout passes.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+thread_local! {
+	// Lock files held by the current thread, so operations run within another on the same config don't wait
+	// for themselves.
+	static HELD: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+// Lock held on a config file, released when dropped.
+pub(crate) struct ConfigLock {
+	_file: File,
+	lock_path: PathBuf,
+}
+
+impl Drop for ConfigLock {
+	fn drop(&mut self) {
+		HELD.with(|held| held.borrow_mut().retain(|path| *path != self.lock_path));
+	}
+}
+
+impl Abserde {
+	// Lock the config file for an operation, if enabled, waiting for other processes to release it for up to
+	// the lock timeout.
+	pub(crate) fn lock_config(&self, operation: Operation) -> Result<Option<ConfigLock>> {
+		if !self.lock || self.storage != Storage::File {
+			return Ok(None);
+		}
+
+		let config_path = match operation {
+			Operation::Load => self.load_path()?,
+			_ => self.config_path()?,
+		};
+		let lock_path = lock_path(&config_path);
+
+		if HELD.with(|held| held.borrow().contains(&lock_path)) {
+			return Ok(None);
+		}
+		let config_dir = config_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+		// Only saves create the config directory, as there is nothing to load or delete without it.
+		if operation != Operation::Save && !config_dir.exists() {
+			return Ok(None);
+		}
+
+		self.permissions
+			.create_dir_all(config_dir)
+			.map_err(Error::file(config_dir))?;
+
+		let file = loop {
+			let file = self
+				.permissions
+				.open_file(&lock_path)
+				.map_err(Error::file(&lock_path))?;
+
+			acquire(&file, operation == Operation::Load, self.lock_timeout).map_err(
+				|err| match err {
+					TryLockError::WouldBlock => Error::Locked(config_path.clone()),
+					TryLockError::Error(err) => Error::file(&lock_path)(err),
+				},
+			)?;
+
+			// A delete may have removed the lock file while waiting for it, so the lock is only held once it's
+			// taken on the lock file at the path.
+			if is_current(&file, &lock_path) {
+				break file;
+			}
+		};
+
+		HELD.with(|held| held.borrow_mut().push(lock_path.clone()));
+
+		Ok(Some(ConfigLock {
+			_file: file,
+			lock_path,
+		}))
+	}
+
+	// Remove the lock file of a config file being deleted, if enabled, while its lock is still held.
+	pub(crate) fn remove_lock_file(&self, config_path: &Path) -> Result<()> {
+		if !self.lock {
+			return Ok(());
+		}
+
+		let lock_path = lock_path(config_path);
+
+		match fs::remove_file(&lock_path) {
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+			result => result.map_err(Error::file(&lock_path)),
+		}
+	}
+}
+
+// Path of the lock file of a config file, next to it.
+fn lock_path(config_path: &Path) -> PathBuf {
+	let mut file_name = config_path.file_name().unwrap_or_default().to_owned();
+
+	file_name.push(".lock");
+
+	config_path.with_file_name(file_name)
+}
+
+// Whether a locked file is still the lock file at its path, rather than one removed by a delete.
+fn is_current(file: &File, lock_path: &Path) -> bool {
+	let Ok(metadata) = fs::metadata(lock_path) else {
+		return false;
+	};
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::MetadataExt;
+
+		file.metadata().map_or(true, |file| {
+			(file.dev(), file.ino()) == (metadata.dev(), metadata.ino())
+		})
+	}
+	#[cfg(not(unix))]
+	{
+		_ = (file, metadata);
+
+		true
+	}
+}
+
+// Take a shared or exclusive lock on a file, waiting for it indefinitely without a timeout.
+fn acquire(
+	file: &File,
+	shared: bool,
+	timeout: Option<Duration>,
+) -> result::Result<(), TryLockError> {
+	let Some(timeout) = timeout else {
+		let result = if shared {
+			file.lock_shared()
+		} else {
+			file.lock()
+		};
+
+		return result.map_err(TryLockError::Error);
+	};
+	let deadline = Instant::now() + timeout;
+
+	loop {
+		let result = if shared {
+			file.try_lock_shared()
+		} else {
+			file.try_lock()
+		};
+
+		match result {
+			Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+				sleep(POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())));
+			}
+			result => return result,
+		}
+	}
+}
@@ -0,0 +1,141 @@
+//! Management of a loaded config, caching it in memory and tracking changes.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Abserde, Config, Error, Result};
+
+/// Owns an [Abserde] along with its loaded config, only writing the config to disk when it has changed.
+///
+/// The config is considered changed whenever it is mutably borrowed with [ConfigManager::get_mut] or replaced,
+/// until it is next saved or reloaded.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct MyConfig {
+/// # 	theme: String,
+/// # }
+/// #
+/// let mut manager = ConfigManager::<MyConfig>::load_or_default(Abserde::default())?;
+///
+/// manager.get_mut().theme = "dark".to_string();
+/// manager.save()?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+pub struct ConfigManager<T> {
+	abserde: Abserde,
+	config: T,
+	dirty: bool,
+}
+
+impl<T> ConfigManager<T>
+where
+	T: Serialize,
+	T: DeserializeOwned,
+{
+	/// Create a manager for a config which hasn't been saved yet, so is considered changed.
+	pub fn new(abserde: Abserde, config: T) -> Self {
+		Self {
+			abserde,
+			config,
+			dirty: true,
+		}
+	}
+
+	/// Create a manager, loading its config from disk.
+	pub fn load(abserde: Abserde) -> Result<Self> {
+		let config = T::load_config(&abserde)?;
+
+		Ok(Self {
+			abserde,
+			config,
+			dirty: false,
+		})
+	}
+
+	/// Create a manager, loading its config from disk, or using the default config if there is no config file.
+	pub fn load_or_default(abserde: Abserde) -> Result<Self>
+	where
+		T: Default,
+	{
+		Ok(match load_if_exists(&abserde)? {
+			Some(config) => Self {
+				abserde,
+				config,
+				dirty: false,
+			},
+			None => Self::new(abserde, T::default()),
+		})
+	}
+
+	/// Return the [Abserde] managing where and how the config is stored.
+	pub fn abserde(&self) -> &Abserde {
+		&self.abserde
+	}
+
+	/// Return the config.
+	pub fn get(&self) -> &T {
+		&self.config
+	}
+
+	/// Return the config for modification, marking it as changed.
+	pub fn get_mut(&mut self) -> &mut T {
+		self.dirty = true;
+
+		&mut self.config
+	}
+
+	/// Replace the config, marking it as changed.
+	pub fn set(&mut self, config: T) {
+		self.config = config;
+		self.dirty = true;
+	}
+
+	/// Return whether the config has changed since it was last loaded or saved.
+	pub fn is_dirty(&self) -> bool {
+		self.dirty
+	}
+
+	/// Save the config to disk if it has changed, returning whether it was written.
+	pub fn save(&mut self) -> Result<bool> {
+		if !self.dirty {
+			return Ok(false);
+		}
+
+		self.config.save_config(&self.abserde)?;
+		self.dirty = false;
+
+		Ok(true)
+	}
+
+	/// Reload the config from disk, discarding any unsaved changes.
+	pub fn reload(&mut self) -> Result<()> {
+		self.config = T::load_config(&self.abserde)?;
+		self.dirty = false;
+
+		Ok(())
+	}
+
+	/// Consume the manager, returning the config.
+	pub fn into_inner(self) -> T {
+		self.config
+	}
+}
+
+// Load a config from disk, returning nothing if there is no config file.
+pub(crate) fn load_if_exists<T>(abserde: &Abserde) -> Result<Option<T>>
+where
+	T: Serialize,
+	T: DeserializeOwned,
+{
+	match T::load_config(abserde) {
+		Ok(config) => Ok(Some(config)),
+		Err(error) if matches!(error.root(), Error::NotFound(_)) => Ok(None),
+		Err(error) => Err(error),
+	}
+}
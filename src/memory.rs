@@ -0,0 +1,36 @@
+//! Storage of configs in memory, selected with [Storage::Memory](crate::Storage::Memory).
+//!
+//! Configs are kept in a map shared by the whole process, keyed by the path the config file would have within
+//! the app's directory, such as `MyApp/keybindings.json`, so every [Abserde](crate::Abserde) of the same app
+//! sees the same configs until the process exits.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::Result;
+
+// Stored data of every config file, keyed by its path.
+static CONFIGS: Mutex<BTreeMap<PathBuf, Vec<u8>>> = Mutex::new(BTreeMap::new());
+
+// Lock the stored configs, which are left consistent even if a thread panicked holding the lock.
+fn configs() -> MutexGuard<'static, BTreeMap<PathBuf, Vec<u8>>> {
+	CONFIGS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+// Read the stored data of a config file, if it has been stored.
+pub(crate) fn read(config_path: &Path) -> Result<Option<Vec<u8>>> {
+	Ok(configs().get(config_path).cloned())
+}
+
+// Store the data of a config file, replacing any stored before.
+pub(crate) fn write(config_path: &Path, data: &[u8]) -> Result<()> {
+	configs().insert(config_path.to_owned(), data.to_owned());
+
+	Ok(())
+}
+
+// Delete the stored data of a config file, returning whether it had been stored.
+pub(crate) fn delete(config_path: &Path) -> Result<bool> {
+	Ok(configs().remove(config_path).is_some())
+}
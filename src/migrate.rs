@@ -0,0 +1,187 @@
+//! Migration of configs saved with older versions of a config type.
+//!
+//! Configs are migrated through [serde_json::Value], so that each step can deserialise the previous version of
+//! the config type and serialise the next, regardless of the format of the config file.
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+type Step = Box<dyn Fn(Value) -> Result<Value> + Send + Sync>;
+
+/// Migrations from older versions of a config type to the current version, `T`.
+///
+/// The version of a config file is read from a top-level version field, named `version` by default. Config
+/// files without the field are version 0, and each migration moves the config up by one version.
+/// The current config type should include the version field, so that it is saved along with the config.
+/// Alternatively, configs saved in an envelope with [Abserde::schema_version](crate::Abserde::schema_version)
+/// use the schema version recorded in the envelope instead.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// #[derive(Serialize, Deserialize)]
+/// struct MyConfigV0 {
+/// 	dark: bool,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct MyConfig {
+/// 	version: u64,
+/// 	theme: String,
+/// }
+///
+/// let migrations = Migrations::<MyConfigV0>::new().then(|config: MyConfigV0| MyConfig {
+/// 	version: 1,
+/// 	theme: if config.dark { "dark" } else { "light" }.to_string(),
+/// });
+/// let my_config = MyConfig::load_config_migrated(&Abserde::default(), &migrations)?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+pub struct Migrations<T> {
+	version_field: String,
+	steps: Vec<Step>,
+	resave: bool,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Migrations<T> {
+	/// Create migrations starting from version 0 of the config type, `T`.
+	pub fn new() -> Self {
+		Self {
+			version_field: "version".to_string(),
+			steps: Vec::new(),
+			resave: false,
+			marker: PhantomData,
+		}
+	}
+
+	/// Set the name of the top-level field holding the version of config files.
+	pub fn version_field<S: Into<String>>(mut self, version_field: S) -> Self {
+		self.version_field = version_field.into();
+
+		self
+	}
+
+	/// Save configs back to disk after they are migrated, so they are stored in the current version.
+	pub fn resave(mut self, resave: bool) -> Self {
+		self.resave = resave;
+
+		self
+	}
+
+	/// Return the current version, which is the number of migrations.
+	pub fn version(&self) -> u64 {
+		self.steps.len() as u64
+	}
+
+	/// Add a migration from the current version of the config type to the next version, `U`.
+	pub fn then<U, F>(self, migrate: F) -> Migrations<U>
+	where
+		T: DeserializeOwned + 'static,
+		U: Serialize + 'static,
+		F: Fn(T) -> U + Send + Sync + 'static,
+	{
+		let mut steps = self.steps;
+
+		steps.push(Box::new(move |value| {
+			let config =
+				serde_json::from_value(value).map_err(|err| Error::Deserialize(err.into()))?;
+
+			serde_json::to_value(migrate(config)).map_err(|err| Error::Serialize(err.into()))
+		}));
+
+		Migrations {
+			version_field: self.version_field,
+			steps,
+			resave: self.resave,
+			marker: PhantomData,
+		}
+	}
+
+	/// Add a migration from the current version of the config type to the next version, `U`, using [From].
+	pub fn then_from<U>(self) -> Migrations<U>
+	where
+		T: DeserializeOwned + 'static,
+		U: Serialize + From<T> + 'static,
+	{
+		self.then(U::from)
+	}
+
+	// Migrate a config to the current version, returning it along with whether it needed migrating.
+	// The version is taken from the config's envelope if it has one, otherwise from its version field.
+	pub(crate) fn migrate(
+		&self,
+		mut value: Value,
+		schema_version: Option<u64>,
+	) -> Result<(Value, bool)> {
+		let version = match (schema_version, value.get(&self.version_field)) {
+			(Some(schema_version), _) => Some(schema_version),
+			(None, None) => Some(0),
+			(None, Some(Value::Number(version))) => version.as_u64(),
+			(None, Some(Value::String(version))) => version.parse().ok(),
+			(None, Some(_)) => None,
+		};
+		let Some(version) = version else {
+			return Err(Error::Migration(format!(
+				"config version field {} is not a valid version",
+				self.version_field
+			)));
+		};
+
+		if version > self.version() {
+			return Err(Error::Migration(format!(
+				"config version {} is newer than the latest supported version {}",
+				version,
+				self.version()
+			)));
+		}
+
+		for step in &self.steps[version as usize..] {
+			value = step(value)?;
+		}
+
+		if let (None, Value::Object(map)) = (schema_version, &mut value) {
+			map.insert(self.version_field.clone(), self.version().into());
+		}
+
+		if version < self.version() {
+			log!(
+				info,
+				"migrated config from version {} to {}",
+				version,
+				self.version()
+			);
+		}
+
+		Ok((value, version < self.version()))
+	}
+
+	// Whether migrated configs are saved back to disk.
+	pub(crate) fn resaves(&self) -> bool {
+		self.resave
+	}
+}
+
+impl<T> Default for Migrations<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> Debug for Migrations<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Migrations")
+			.field("version_field", &self.version_field)
+			.field("version", &self.version())
+			.field("resave", &self.resave)
+			.finish()
+	}
+}
@@ -0,0 +1,199 @@
+//! Config files mapped into memory, so configs can borrow from them, enabled with the mmap feature.
+
+use std::fs::File;
+
+use memmap2::Mmap;
+use serde::Deserialize;
+
+use crate::{
+	encoding, envelope, permissions, Abserde, Compression, Error, Format, Operation, Plaintext,
+	Result, Signing, Storage, MSG_FORMAT_NOT_INFERRED,
+};
+
+/// Config file mapped into memory, which configs can be deserialised from without copying it, borrowing
+/// strings and byte arrays from it where the format allows.
+///
+/// Returned by [Abserde::map_config]. Config files saved as UTF-16 are transcoded, so they aren't mapped.
+/// JSON, YAML and TOML configs can borrow strings from the config file, unless they hold escape sequences,
+/// while other formats always deserialise owned data. Config types should accept owned data too, such as by
+/// using [Cow](std::borrow::Cow). Plists can't be deserialised this way.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::Deserialize;
+/// # use std::borrow::Cow;
+/// #
+/// #[derive(Deserialize)]
+/// struct Dataset<'a> {
+/// 	#[serde(borrow)]
+/// 	names: Vec<Cow<'a, str>>,
+/// }
+///
+/// // SAFETY: The config file is only ever saved atomically, so it isn't changed in place while it's mapped.
+/// let mapped = unsafe { Abserde::default().map_config() }?;
+/// let dataset: Dataset = mapped.config()?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+pub struct MappedConfig {
+	contents: Contents,
+	format: Format,
+}
+
+// Contents of a config file, mapped into memory or read when they can't be mapped, in which case they're
+// wiped from memory when dropped, as they may have been decrypted.
+#[derive(Debug)]
+enum Contents {
+	Mapped(Mmap),
+	Read(Plaintext),
+}
+
+impl MappedConfig {
+	// Wrap the contents of a config file, transcoding them to UTF-8 if they're UTF-16, which can't be borrowed
+	// from.
+	fn new(contents: Contents, format: Format) -> Result<Self> {
+		let mut mapped = Self { contents, format };
+
+		if let (true, Some(data)) = (
+			mapped.format.is_text(),
+			encoding::transcode(mapped.as_bytes()),
+		) {
+			mapped.contents = Contents::Read(Plaintext::from(
+				data.map_err(|err| Error::Deserialize(err.into()))?,
+			));
+		}
+
+		Ok(mapped)
+	}
+
+	/// Deserialise the config, which may borrow from the config file.
+	///
+	/// Any envelope is removed, but configs aren't merged over [Abserde::embedded_defaults] or migrated.
+	pub fn config<'a, T: Deserialize<'a>>(&'a self) -> Result<T> {
+		envelope::open_borrowed(&self.format, self.as_bytes())
+	}
+
+	/// Contents of the config file.
+	pub fn as_bytes(&self) -> &[u8] {
+		match &self.contents {
+			Contents::Mapped(mmap) => mmap,
+			Contents::Read(data) => data,
+		}
+	}
+
+	/// Whether the config file is mapped into memory, rather than having been read, as it is when it's
+	/// encrypted, compressed or journaled, or isn't stored in a file.
+	pub fn is_mapped(&self) -> bool {
+		matches!(self.contents, Contents::Mapped(_))
+	}
+}
+
+impl Abserde {
+	/// Map the config file into memory, so configs can be deserialised from it with [MappedConfig::config]
+	/// without copying it, such as those holding large embedded datasets.
+	///
+	/// Config files which are encrypted, compressed or journaled, or aren't stored in files, are read into
+	/// memory instead. Enabled with the mmap feature.
+	///
+	/// # Safety
+	///
+	/// The config file must not be changed in place, by this or any other process, until the [MappedConfig]
+	/// is dropped, such as by saving it with [Abserde::atomic] disabled, or truncating it, as the mapped
+	/// contents would change under configs borrowing from them. Atomic saves replace the config file, leaving
+	/// the mapped one untouched.
+	pub unsafe fn map_config(&self) -> Result<MappedConfig> {
+		self.with_context(Operation::Load, || {
+			let config_path = self.load_path()?;
+			let format = self.file_format()?;
+
+			if self.storage != Storage::File
+				|| self.journal > 0
+				|| self.encryption.encrypts_files()
+				|| self.compression != Compression::None
+				|| self.signing != Signing::None
+			{
+				let data = Plaintext::from(
+					self.compression.decompress(&Plaintext::from(
+						self.encryption
+							.decrypt(self.signing.verify(&self.read_data(&config_path)?)?)?,
+					))?,
+				);
+
+				return MappedConfig::new(Contents::Read(data), format);
+			}
+
+			if self.audit_permissions {
+				permissions::audit(&config_path)?;
+			}
+
+			let file = File::open(&config_path).map_err(Error::file(&config_path))?;
+			// SAFETY: The caller guarantees the file isn't changed in place while it's mapped.
+			let mmap = unsafe { Mmap::map(&file) }.map_err(Error::file(&config_path))?;
+
+			MappedConfig::new(Contents::Mapped(mmap), format)
+		})
+	}
+}
+
+impl Format {
+	// Deserialise a config in this format from a slice, which it may borrow from.
+	pub(crate) fn deserialize_slice<'a, T: Deserialize<'a>>(&self, data: &'a [u8]) -> Result<T> {
+		match self {
+			Format::FromExtension => Err(Error::Unsupported(MSG_FORMAT_NOT_INFERRED.to_string())),
+			#[cfg(feature = "plist")]
+			Format::Plist => Err(Error::Unsupported(
+				"configs can't be deserialised from mapped plists".to_string(),
+			)),
+			#[cfg(feature = "custom")]
+			Format::Custom(_) => Err(Error::Unsupported(
+				"configs can't be deserialised from mapped files in custom formats".to_string(),
+			)),
+			_ if self.is_text() => self
+				.deserialize_slice_from(encoding::strip_bom(data))
+				.map_err(Error::Deserialize),
+			_ => self
+				.deserialize_slice_from(data)
+				.map_err(Error::Deserialize),
+		}
+	}
+
+	fn deserialize_slice_from<'a, T: Deserialize<'a>>(
+		&self,
+		data: &'a [u8],
+	) -> std::result::Result<T, Box<dyn std::error::Error + Send + Sync>> {
+		Ok(match self {
+			#[cfg(feature = "json")]
+			Format::Json | Format::PrettyJson(_) => {
+				let mut de = serde_json::Deserializer::from_slice(data);
+				let config = serde_path_to_error::deserialize(&mut de)?;
+
+				de.end()?;
+
+				config
+			}
+			#[cfg(feature = "yaml")]
+			Format::Yaml => serde_path_to_error::deserialize(serde_yaml::Deserializer::from_slice(data))?,
+			#[cfg(feature = "pickle")]
+			Format::Pickle => {
+				let mut de = serde_pickle::Deserializer::new(data, serde_pickle::DeOptions::new());
+				let config = serde_path_to_error::deserialize(&mut de)?;
+
+				de.end()?;
+
+				config
+			}
+			#[cfg(feature = "ini")]
+			Format::Ini => serde_path_to_error::deserialize(&mut serde_ini::Deserializer::from_read(data))?,
+			#[cfg(feature = "toml")]
+			Format::Toml => serde_path_to_error::deserialize(&mut toml::Deserializer::new(
+				std::str::from_utf8(data)?,
+			))?,
+			#[cfg(feature = "plist")]
+			Format::Plist => unreachable!(),
+			Format::FromExtension => unreachable!(),
+			#[cfg(feature = "custom")]
+			Format::Custom(_) => unreachable!(),
+		})
+	}
+}
@@ -0,0 +1,60 @@
+//! System directories of apps on mobile platforms, Android and iOS, where each app keeps its files within a
+//! sandbox of its own.
+
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use crate::sandbox::UserDir;
+
+static ANDROID_FILES_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set the files directory of the app on Android, within which config files are located.
+///
+/// Android gives no environment variable for the directories of an app, so the directory given by
+/// `Context.getFilesDir()` must be passed from the Java or Kotlin side of the app, before any config is
+/// located there. Until it's set, config files can't be located on Android. Ignored on other platforms, so it
+/// can be called from code shared with them.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// set_android_files_dir("/data/user/0/com.example.myapp/files");
+/// ```
+pub fn set_android_files_dir<P: Into<PathBuf>>(files_dir: P) {
+	*ANDROID_FILES_DIR
+		.write()
+		.unwrap_or_else(PoisonError::into_inner) = Some(files_dir.into());
+}
+
+// System directory of the given kind on Android, within the files directory of the app, if it has been set.
+pub(crate) fn android_dir(dir: UserDir) -> Option<PathBuf> {
+	ANDROID_FILES_DIR
+		.read()
+		.unwrap_or_else(PoisonError::into_inner)
+		.as_deref()
+		.map(|files_dir| android_dir_in(files_dir, dir))
+}
+
+// System directory of the given kind within the given files directory of an app on Android.
+pub(crate) fn android_dir_in(files_dir: &Path, dir: UserDir) -> PathBuf {
+	match dir {
+		UserDir::Config => files_dir.join("config"),
+		UserDir::Data => files_dir.join("data"),
+		UserDir::State => files_dir.join("state"),
+		// Android keeps caches next to the files directory, as given by `Context.getCacheDir()`, and clears
+		// them when storage runs low.
+		UserDir::Cache => match files_dir.parent() {
+			Some(app_dir) => app_dir.join("cache"),
+			None => files_dir.join("cache"),
+		},
+	}
+}
+
+// System directory of the given kind on iOS, within the Library directory of the given sandbox of the app.
+pub(crate) fn ios_dir_in(home_dir: &Path, dir: UserDir) -> PathBuf {
+	home_dir.join("Library").join(match dir {
+		UserDir::Config => "Preferences",
+		UserDir::Data | UserDir::State => "Application Support",
+		UserDir::Cache => "Caches",
+	})
+}
@@ -0,0 +1,101 @@
+//! Configs sharing a single config file, each kept under a top-level key of its own, given by
+//! [Abserde::namespace](crate::Abserde::namespace).
+//!
+//! The config file is read and written through [serde_json::Value], so saving a config replaces only the value
+//! of its key, keeping those of the other configs.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{Abserde, DeleteMode, Error, Operation, Result};
+
+impl Abserde {
+	/// Keep the config under the given top-level key of the config file, sharing the file with other configs.
+	///
+	/// See [Abserde::namespace] for how shared config files are kept.
+	pub fn namespaced<S: Into<String>>(mut self, key: S) -> Self {
+		self.namespace = Some(key.into());
+
+		self
+	}
+
+	// Read the config under the key of this config in a shared config file.
+	pub(crate) fn read_namespaced<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+		key: &str,
+	) -> Result<(T, Option<u64>)> {
+		let (mut configs, version) = self
+			.without_namespace()
+			.read_config_file_versioned::<Value>(config_path)?;
+		let config = configs
+			.as_object_mut()
+			.and_then(|map| map.remove(key))
+			.ok_or_else(|| Error::NotFound(config_path.to_owned()))?;
+		let _field_keys = self.field_keys()?;
+
+		Ok((
+			T::deserialize(config).map_err(|err| Error::Deserialize(err.into()))?,
+			version,
+		))
+	}
+
+	// Write a config under the key of this config in a shared config file, keeping the other configs in it.
+	pub(crate) fn write_namespaced<T: Serialize>(&self, config: &T, key: &str) -> Result<()> {
+		let _field_keys = self.field_keys()?;
+		let config = serde_json::to_value(config).map_err(|err| Error::Serialize(err.into()))?;
+		let mut configs = self.read_shared()?;
+
+		configs.insert(key.to_string(), config);
+
+		self.without_namespace().write_config(&configs)
+	}
+
+	// Delete the config under the key of this config from a shared config file, deleting the file once it
+	// holds no other configs.
+	pub(crate) fn delete_namespaced(&self, key: &str, mode: DeleteMode) -> Result<()> {
+		let abserde = self.without_namespace();
+		let configs = abserde.with_context(Operation::Delete, || {
+			let mut configs = self.read_shared()?;
+
+			match configs.remove(key) {
+				Some(_) => Ok(Some(configs)),
+				None if mode.ignore_missing => Ok(None),
+				None => Err(Error::NotFound(abserde.config_path()?)),
+			}
+		})?;
+
+		match configs {
+			None => Ok(()),
+			Some(configs) if configs.is_empty() => abserde.delete_with(mode),
+			Some(configs) => {
+				abserde.with_context(Operation::Delete, || abserde.write_config(&configs))
+			}
+		}
+	}
+
+	// Configs in a shared config file, by their keys, which are none when there is no config file yet.
+	fn read_shared(&self) -> Result<Map<String, Value>> {
+		let abserde = self.without_namespace();
+
+		match abserde.read_config_file::<Value>(&abserde.config_path()?) {
+			Ok(Value::Object(configs)) => Ok(configs),
+			Ok(_) => Err(Error::Unsupported(
+				"only config files which are maps can be shared by configs".to_string(),
+			)),
+			Err(Error::NotFound(_)) => Ok(Map::new()),
+			Err(err) => Err(err),
+		}
+	}
+
+	// Copy of this Abserde for the whole shared config file.
+	fn without_namespace(&self) -> Abserde {
+		Abserde {
+			namespace: None,
+			..self.clone()
+		}
+	}
+}
@@ -0,0 +1,195 @@
+//! Storage of configs in the browser's Origin Private File System, selected with
+//! [Storage::Opfs](crate::Storage::Opfs).
+//!
+//! Config files are kept at the path they would have within the app's directory, such as
+//! `MyApp/keybindings.json`, in the origin's private directory. The browser only offers asynchronous access to
+//! it outside of workers, so configs are loaded and saved with [AsyncConfig](crate::AsyncConfig).
+
+use std::future::Future;
+use std::path::{Component, Path};
+
+use futures::channel::oneshot;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::js_sys::{self, Promise, Uint8Array};
+use web_sys::wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+	DomException, File, FileSystemDirectoryHandle, FileSystemFileHandle,
+	FileSystemGetDirectoryOptions, FileSystemGetFileOptions, FileSystemWritableFileStream,
+	WorkerGlobalScope,
+};
+
+use crate::envelope::Envelope;
+use crate::{Abserde, Error, Operation, Result};
+
+// Load a config, reading the config file from the private file system.
+pub(crate) async fn load_config<T: DeserializeOwned>(abserde: Abserde) -> Result<T> {
+	let config_path = abserde.load_path()?;
+	let data = run({
+		let config_path = config_path.clone();
+
+		async move { read(&config_path).await }
+	})
+	.await;
+
+	abserde.with_context(Operation::Load, || {
+		match (data?, abserde.embedded_defaults) {
+			(Some(data), _) => Ok(abserde.parse_config(&data)?.0),
+			(None, Some(defaults)) => Ok(abserde.deserialize_config(defaults.as_bytes())?.0),
+			(None, None) => Err(Error::NotFound(config_path.clone())),
+		}
+	})
+}
+
+// Save a config, writing the config file to the private file system.
+pub(crate) async fn save_config<T: Serialize>(abserde: Abserde, config: T) -> Result<()> {
+	let config_path = abserde.config_path()?;
+	let data = abserde.with_context(Operation::Save, || match abserde.schema_version {
+		Some(schema_version) => abserde.serialize_config(&Envelope::new(schema_version, &config)),
+		None => abserde.serialize_config(&config),
+	})?;
+
+	if let Some(capture) = &abserde.capture {
+		capture.record(config_path, &data);
+
+		return Ok(());
+	}
+
+	let result = run(async move { write(&config_path, &data).await }).await;
+
+	abserde.with_context(Operation::Save, || result)
+}
+
+// Run a future on the browser's event loop, as futures holding JavaScript values can't be sent between
+// threads, returning a future which can be sent, and waits for its result.
+fn run<R, F>(future: F) -> impl Future<Output = Result<R>> + Send
+where
+	R: Send + 'static,
+	F: Future<Output = Result<R>> + 'static,
+{
+	let (sender, receiver) = oneshot::channel();
+
+	spawn_local(async move {
+		// The receiver is gone if the future was dropped, in which case the result is unwanted.
+		let _ = sender.send(future.await);
+	});
+
+	async move { receiver.await.map_err(|_| Error::Cancelled)? }
+}
+
+// Read a config file, if it exists.
+async fn read(config_path: &Path) -> Result<Option<Vec<u8>>> {
+	let Some((dir, file_name)) = open_dir(config_path, false).await? else {
+		return Ok(None);
+	};
+	let file_handle: FileSystemFileHandle = match resolve(dir.get_file_handle(&file_name)).await {
+		Ok(file_handle) => file_handle,
+		Err(err) if is_not_found(&err) => return Ok(None),
+		Err(err) => return Err(browser_error(err)),
+	};
+	let file: File = resolve(file_handle.get_file())
+		.await
+		.map_err(browser_error)?;
+	let buffer = JsFuture::from(file.array_buffer())
+		.await
+		.map_err(browser_error)?;
+
+	Ok(Some(Uint8Array::new(&buffer).to_vec()))
+}
+
+// Write a config file, creating its directories if needed.
+//
+// Writes go to a swap file which only replaces the config file when closed, so saves are atomic.
+async fn write(config_path: &Path, data: &[u8]) -> Result<()> {
+	let (dir, file_name) = open_dir(config_path, true)
+		.await?
+		.ok_or_else(|| Error::NotFound(config_path.to_owned()))?;
+	let options = FileSystemGetFileOptions::new();
+
+	options.set_create(true);
+
+	let file_handle: FileSystemFileHandle =
+		resolve(dir.get_file_handle_with_options(&file_name, &options))
+			.await
+			.map_err(browser_error)?;
+	let stream: FileSystemWritableFileStream = resolve(file_handle.create_writable())
+		.await
+		.map_err(browser_error)?;
+
+	JsFuture::from(stream.write_with_u8_array(data).map_err(browser_error)?)
+		.await
+		.map_err(browser_error)?;
+	JsFuture::from(stream.close())
+		.await
+		.map_err(browser_error)?;
+
+	Ok(())
+}
+
+// Open the directory holding a config file, along with the name of the config file, returning nothing if the
+// directory doesn't exist and isn't to be created.
+async fn open_dir(
+	config_path: &Path,
+	create: bool,
+) -> Result<Option<(FileSystemDirectoryHandle, String)>> {
+	let file_name = config_path
+		.file_name()
+		.ok_or_else(|| Error::NotFound(config_path.to_owned()))?
+		.to_string_lossy()
+		.into_owned();
+	let mut dir: FileSystemDirectoryHandle = resolve(storage_manager()?.get_directory())
+		.await
+		.map_err(browser_error)?;
+	let options = FileSystemGetDirectoryOptions::new();
+
+	options.set_create(create);
+
+	for component in config_path.parent().into_iter().flat_map(Path::components) {
+		let Component::Normal(name) = component else {
+			continue;
+		};
+
+		dir =
+			match resolve(dir.get_directory_handle_with_options(&name.to_string_lossy(), &options))
+				.await
+			{
+				Ok(dir) => dir,
+				Err(err) if is_not_found(&err) => return Ok(None),
+				Err(err) => return Err(browser_error(err)),
+			};
+	}
+
+	Ok(Some((dir, file_name)))
+}
+
+// Storage manager of the current window or worker.
+fn storage_manager() -> Result<web_sys::StorageManager> {
+	let global = js_sys::global();
+
+	if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+		Ok(window.navigator().storage())
+	} else if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+		Ok(worker.navigator().storage())
+	} else {
+		Err(Error::Browser(
+			"no window or worker available for the private file system".to_string(),
+		))
+	}
+}
+
+// Wait for a promise, casting the value it resolves to.
+async fn resolve<T: JsCast>(promise: Promise) -> std::result::Result<T, JsValue> {
+	Ok(JsFuture::from(promise).await?.unchecked_into())
+}
+
+// Whether an exception thrown by the browser is due to a missing file or directory.
+fn is_not_found(err: &JsValue) -> bool {
+	err.dyn_ref::<DomException>()
+		.is_some_and(|err| err.name() == "NotFoundError")
+}
+
+// Convert an exception thrown by the browser, such as when the storage quota is exceeded.
+fn browser_error(err: JsValue) -> Error {
+	Error::Browser(format!("{:?}", err))
+}
@@ -0,0 +1,251 @@
+//! Parsing and display of formats and locations as strings, such as `pretty-json:2` and `dir:/etc/myapp`, so
+//! they can be given with command-line flags and environment variables.
+
+use std::fmt::{self, Display};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[cfg(feature = "json")]
+use crate::PrettyJsonIndent;
+use crate::{Error, Format, Location, PlatformLocations};
+
+// Separator of the locations of a search, as in `search:dir:/etc/myapp;auto`.
+const SEARCH_SEPARATOR: char = ';';
+
+impl Display for Format {
+	/// Display the format as it's parsed, such as `toml` or `pretty-json:2`.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			#[cfg(feature = "json")]
+			Format::Json => write!(f, "json"),
+			#[cfg(feature = "json")]
+			Format::PrettyJson(PrettyJsonIndent::Tab) => write!(f, "pretty-json"),
+			#[cfg(feature = "json")]
+			Format::PrettyJson(PrettyJsonIndent::Spaces(spaces)) => {
+				write!(f, "pretty-json:{}", spaces)
+			}
+			#[cfg(feature = "yaml")]
+			Format::Yaml => write!(f, "yaml"),
+			#[cfg(feature = "pickle")]
+			Format::Pickle => write!(f, "pickle"),
+			#[cfg(feature = "ini")]
+			Format::Ini => write!(f, "ini"),
+			#[cfg(feature = "toml")]
+			Format::Toml => write!(f, "toml"),
+			#[cfg(feature = "plist")]
+			Format::Plist => write!(f, "plist"),
+			Format::FromExtension => write!(f, "from-extension"),
+			// Custom formats can't be parsed, as they aren't known to this crate.
+			#[cfg(feature = "custom")]
+			Format::Custom(format) => write!(f, "custom:{}", format.extension()),
+		}
+	}
+}
+
+impl FromStr for Format {
+	type Err = Error;
+
+	/// Parse a format, such as `toml`, or `pretty-json:2` for JSON pretty-printed with two spaces, and
+	/// `pretty-json:tab` or just `pretty-json` for JSON pretty-printed with tabs.
+	///
+	/// Only formats compiled into the binary can be parsed, and the error lists them.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (name, indent) = match s.split_once(':') {
+			Some((name, indent)) => (name, Some(indent)),
+			None => (s, None),
+		};
+
+		match (name.to_lowercase().as_str(), indent) {
+			#[cfg(feature = "json")]
+			("pretty-json", indent) => Ok(Format::PrettyJson(match indent {
+				None => PrettyJsonIndent::Tab,
+				Some(indent) if indent.eq_ignore_ascii_case("tab") => PrettyJsonIndent::Tab,
+				Some(indent) => PrettyJsonIndent::Spaces(indent.parse().map_err(|_| {
+					invalid(
+						"format",
+						s,
+						"indent must be `tab` or a number of spaces".to_string(),
+					)
+				})?),
+			})),
+			("from-extension", None) => Ok(Format::FromExtension),
+			(name, None) => Format::ENABLED
+				.iter()
+				.find(|format| format.to_string() == name)
+				.cloned()
+				.ok_or_else(|| invalid("format", s, expected_formats())),
+			_ => Err(invalid("format", s, expected_formats())),
+		}
+	}
+}
+
+impl Display for Location {
+	/// Display the location as it's parsed, such as `auto` or `dir:/etc/myapp`.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Location::Auto => write!(f, "auto"),
+			Location::Path(path) => write!(f, "path:{}", path.display()),
+			Location::File(file) => write!(f, "file:{}", file.display()),
+			Location::Dir(dir) => write!(f, "dir:{}", dir.display()),
+			Location::AutoData => write!(f, "auto-data"),
+			Location::AutoCache => write!(f, "auto-cache"),
+			Location::AutoState => write!(f, "auto-state"),
+			Location::EnvVar(name) => write!(f, "env:{}", name),
+			Location::System => write!(f, "system"),
+			#[cfg(feature = "plist")]
+			Location::UserDefaults(bundle_id) => write!(f, "user-defaults:{}", bundle_id),
+			Location::Search(locations) => write_chain(f, "search", locations),
+			Location::Fallback(locations) => write_chain(f, "fallback", locations),
+			Location::Platform(locations) => {
+				write!(f, "platform:")?;
+
+				for (platform, location) in [
+					("windows", &locations.windows),
+					("macos", &locations.macos),
+					("linux", &locations.linux),
+				] {
+					if let Some(location) = location {
+						write!(f, "{}={}{}", platform, location, SEARCH_SEPARATOR)?;
+					}
+				}
+
+				write!(f, "fallback={}", locations.fallback)
+			}
+		}
+	}
+}
+
+impl FromStr for Location {
+	type Err = Error;
+
+	/// Parse a location, such as `auto`, `auto-data`, `auto-cache`, `auto-state` or `system`, or one given a
+	/// value after a colon, such as `path:/etc/myapp/config.toml`, `file:myapp.toml`, `dir:/etc/myapp`,
+	/// `env:MYAPP_CONFIG` or `user-defaults:com.example.MyApp`.
+	///
+	/// Locations to search are separated by semicolons, such as `search:dir:/etc/myapp;auto` or
+	/// `fallback:auto;system`, so locations within a search can't hold semicolons themselves. So are the
+	/// locations of each platform, given as `windows`, `macos`, `linux` and `fallback`, such as
+	/// `platform:linux=path:/home/me/.myapp;fallback=auto`, where a missing fallback is `auto`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (name, value) = match s.split_once(':') {
+			Some((name, value)) => (name, Some(value)),
+			None => (s, None),
+		};
+		let non_empty = |value: &str| match value {
+			"" => Err(invalid("location", s, format!("{} can't be empty", name))),
+			value => Ok(value.to_string()),
+		};
+
+		match (name.to_lowercase().as_str(), value) {
+			("auto", None) => Ok(Location::Auto),
+			("auto-data", None) => Ok(Location::AutoData),
+			("auto-cache", None) => Ok(Location::AutoCache),
+			("auto-state", None) => Ok(Location::AutoState),
+			("system", None) => Ok(Location::System),
+			("path", Some(path)) => Ok(Location::Path(PathBuf::from(non_empty(path)?))),
+			("file", Some(file)) => Ok(Location::File(PathBuf::from(non_empty(file)?))),
+			("dir", Some(dir)) => Ok(Location::Dir(PathBuf::from(non_empty(dir)?))),
+			("env", Some(name)) => Ok(Location::EnvVar(non_empty(name)?)),
+			#[cfg(feature = "plist")]
+			("user-defaults", Some(bundle_id)) => Ok(Location::UserDefaults(non_empty(bundle_id)?)),
+			("search", Some(locations)) => {
+				Ok(Location::Search(parse_chain(&non_empty(locations)?)?))
+			}
+			("fallback", Some(locations)) => {
+				Ok(Location::Fallback(parse_chain(&non_empty(locations)?)?))
+			}
+			("platform", Some(locations)) => {
+				let mut platform_locations = PlatformLocations::default();
+
+				for entry in non_empty(locations)?.split(SEARCH_SEPARATOR) {
+					let (platform, location) = entry.split_once('=').ok_or_else(|| {
+						invalid(
+							"location",
+							s,
+							"platform locations must be given as <platform>=<location>".to_string(),
+						)
+					})?;
+					let location = location.parse()?;
+
+					match platform.to_lowercase().as_str() {
+						"windows" => platform_locations.windows = Some(location),
+						"macos" => platform_locations.macos = Some(location),
+						"linux" => platform_locations.linux = Some(location),
+						"fallback" => platform_locations.fallback = location,
+						_ => {
+							return Err(invalid(
+								"location",
+								s,
+								format!(
+									"expected platform windows, macos, linux or fallback, not {}",
+									platform
+								),
+							))
+						}
+					}
+				}
+
+				Ok(Location::Platform(Box::new(platform_locations)))
+			}
+			_ => Err(invalid(
+				"location",
+				s,
+				format!(
+					"expected one of auto, auto-data, auto-cache, auto-state, system, path:<path>, \
+					 file:<name>, dir:<path>, env:<variable>, {}search:<location>;<location>, \
+					 fallback:<location>;<location>, platform:<platform>=<location>;<platform>=<location>",
+					if cfg!(feature = "plist") {
+						"user-defaults:<bundle id>, "
+					} else {
+						""
+					}
+				),
+			)),
+		}
+	}
+}
+
+// Write the locations of a search, separated by semicolons, after the given name.
+fn write_chain(f: &mut fmt::Formatter<'_>, name: &str, locations: &[Location]) -> fmt::Result {
+	write!(f, "{}:", name)?;
+
+	for (index, location) in locations.iter().enumerate() {
+		if index > 0 {
+			write!(f, "{}", SEARCH_SEPARATOR)?;
+		}
+
+		write!(f, "{}", location)?;
+	}
+
+	Ok(())
+}
+
+// Parse the locations of a search, separated by semicolons.
+fn parse_chain(locations: &str) -> Result<Vec<Location>, Error> {
+	locations.split(SEARCH_SEPARATOR).map(str::parse).collect()
+}
+
+// Error for a format or location which couldn't be parsed.
+fn invalid(kind: &'static str, input: &str, reason: String) -> Error {
+	Error::Parse {
+		kind,
+		input: input.to_string(),
+		reason,
+	}
+}
+
+// Reason listing the formats compiled into the binary, for a format which couldn't be parsed.
+fn expected_formats() -> String {
+	let mut formats = Format::ENABLED
+		.iter()
+		.map(|format| format.to_string())
+		.collect::<Vec<_>>();
+
+	if cfg!(feature = "json") {
+		formats.push("pretty-json[:tab|:<spaces>]".to_string());
+	}
+
+	formats.push("from-extension".to_string());
+
+	format!("expected one of {}", formats.join(", "))
+}
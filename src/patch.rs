@@ -0,0 +1,54 @@
+//! JSON Patch (RFC 6902) documents applied to stored configs, enabled with the json-patch feature.
+
+use json_patch::{Patch, PatchError, PatchErrorKind, PatchOperation};
+use serde_json::Value;
+
+use crate::{Abserde, Error, Operation, Result};
+
+impl Abserde {
+	/// Apply a JSON Patch (RFC 6902) document to the stored config, saving the patched config.
+	///
+	/// The patch is given as the JSON array of operations emitted by configuration management systems, and is
+	/// applied to the config file whatever its format. Either every operation is applied, or the config file is
+	/// left untouched. A failed `test` operation is returned as an [Error::PatchTestFailed], holding the
+	/// expected and actual values. Enabled with the json-patch feature.
+	pub fn apply_patch(&self, patch: &Value) -> Result<()> {
+		let patch: Patch =
+			serde_json::from_value(patch.clone()).map_err(|err| Error::Deserialize(err.into()))?;
+
+		// The config is read and saved under the same lock, so changes saved by others in between aren't lost.
+		self.with_context(Operation::Save, || {
+			let mut config: Value = self.with_context(Operation::Load, || {
+				self.read_config_file(&self.load_path()?)
+			})?;
+
+			// Failed patches are returned as they are, rather than as errors saving the config.
+			if let Err(err) = json_patch::patch(&mut config, &patch) {
+				return Ok(Err(
+					test_failure(config, &patch, &err).unwrap_or_else(|| err.into())
+				));
+			}
+
+			self.write_config(&config).map(Ok)
+		})?
+	}
+}
+
+// Describe a failed test operation, with the value it found at its path once the operations before it were
+// applied, as failed patches leave the config untouched.
+fn test_failure(mut config: Value, patch: &[PatchOperation], err: &PatchError) -> Option<Error> {
+	let test = match (&err.kind, patch.get(err.operation)) {
+		(PatchErrorKind::TestFailed, Some(PatchOperation::Test(test))) => test,
+		_ => return None,
+	};
+	let actual = json_patch::patch(&mut config, &patch[..err.operation])
+		.ok()
+		.and_then(|_| config.pointer(test.path.as_str()).cloned());
+
+	Some(Error::PatchTestFailed {
+		operation: err.operation,
+		path: test.path.to_string(),
+		expected: test.value.clone(),
+		actual,
+	})
+}
@@ -0,0 +1,277 @@
+//! Permissions of config files and their directory, set with [Abserde::permissions](crate::Abserde::permissions).
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(unix)]
+use crate::Error;
+use crate::Result;
+
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+
+#[cfg(windows)]
+use windows_sys::Win32::Security::{ACE_FLAGS, NO_INHERITANCE, SUB_CONTAINERS_AND_OBJECTS_INHERIT};
+
+// Unix modes of private config files and their directory, only accessible to their owner.
+#[cfg(unix)]
+const PRIVATE_FILE_MODE: u32 = 0o600;
+#[cfg(unix)]
+const PRIVATE_DIR_MODE: u32 = 0o700;
+
+/// Permissions given to config files, and the directory holding them, whenever a config is saved.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	permissions: FilePermissions::Private,
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum FilePermissions {
+	/// Permissions are left to the platform, such as the umask on Unix.
+	#[default]
+	Default,
+
+	/// Only the current user can access config files and their directory, as configs may hold secrets such as
+	/// tokens.
+	///
+	/// On Unix, config files are created with mode `0600` and their directory with mode `0700`. On Windows,
+	/// their access control lists are replaced with one granting access to the current user alone, without
+	/// inheriting any access from the directories above. Permissions of config files are enforced again on every
+	/// save, so files created or changed by other means are restricted too. Only directories created by abserde
+	/// are restricted, while existing ones, such as one given with [Location::Dir](crate::Location::Dir), are
+	/// left as they are.
+	Private,
+}
+
+impl FilePermissions {
+	// Create a directory, along with any missing parents, restricting the directories created if private.
+	// Existing directories are left as they are, as they may be shared with other apps.
+	pub(crate) fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+		match self {
+			FilePermissions::Default => fs::create_dir_all(dir),
+			#[cfg(unix)]
+			FilePermissions::Private => fs::DirBuilder::new()
+				.recursive(true)
+				.mode(PRIVATE_DIR_MODE)
+				.create(dir),
+			#[cfg(windows)]
+			FilePermissions::Private => {
+				let missing_dirs: Vec<&Path> = dir
+					.ancestors()
+					.take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+					.collect();
+
+				fs::create_dir_all(dir)?;
+
+				missing_dirs.into_iter().rev().try_for_each(|dir| {
+					restrict_to_current_user(dir, SUB_CONTAINERS_AND_OBJECTS_INHERIT)
+				})
+			}
+			#[cfg(not(any(unix, windows)))]
+			FilePermissions::Private => fs::create_dir_all(dir),
+		}
+	}
+
+	// Create or truncate a file for writing, restricting it if private.
+	pub(crate) fn create_file(&self, path: &Path) -> io::Result<File> {
+		let mut options = OpenOptions::new();
+
+		options.write(true).create(true).truncate(true);
+
+		self.open(&mut options, path)
+	}
+
+	// Open a file for appending only, creating it if missing, restricting it if private.
+	pub(crate) fn append_file(&self, path: &Path) -> io::Result<File> {
+		let mut options = OpenOptions::new();
+
+		options.append(true).create(true);
+
+		self.open(&mut options, path)
+	}
+
+	// Open a file for reading and appending, creating it if missing, restricting it if private.
+	pub(crate) fn open_file(&self, path: &Path) -> io::Result<File> {
+		let mut options = OpenOptions::new();
+
+		options.read(true).write(true).create(true).truncate(false);
+
+		self.open(&mut options, path)
+	}
+
+	// Open a file with the given options, restricting it if private.
+	fn open(&self, options: &mut OpenOptions, path: &Path) -> io::Result<File> {
+		match self {
+			FilePermissions::Default => options.open(path),
+			#[cfg(unix)]
+			FilePermissions::Private => {
+				let file = options.mode(PRIVATE_FILE_MODE).open(path)?;
+
+				// The mode only applies to new files, so existing files are restricted here.
+				file.set_permissions(fs::Permissions::from_mode(PRIVATE_FILE_MODE))?;
+
+				Ok(file)
+			}
+			#[cfg(windows)]
+			FilePermissions::Private => {
+				let file = options.open(path)?;
+
+				restrict_to_current_user(path, NO_INHERITANCE)?;
+
+				Ok(file)
+			}
+			#[cfg(not(any(unix, windows)))]
+			FilePermissions::Private => options.open(path),
+		}
+	}
+}
+
+// Check that a config file can't be accessed by other users, as ssh does for key files, failing with an
+// [Error::InsecurePermissions] if it can. Missing config files pass, so they can be reported as missing instead.
+#[cfg(unix)]
+pub(crate) fn audit(path: &Path) -> Result<()> {
+	let metadata = match fs::metadata(path) {
+		Ok(metadata) => metadata,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+		Err(err) => return Err(Error::file(path)(err)),
+	};
+	let insecure = |reason: &str| {
+		Err(Error::InsecurePermissions {
+			path: path.to_owned(),
+			reason: reason.to_string(),
+		})
+	};
+
+	// SAFETY: geteuid has no preconditions and can't fail.
+	if metadata.uid() != unsafe { libc::geteuid() } {
+		return insecure("owned by another user");
+	}
+
+	match metadata.mode() & 0o007 {
+		0 => Ok(()),
+		mode if mode & 0o002 != 0 => insecure("writable by other users"),
+		_ => insecure("readable by other users"),
+	}
+}
+
+// Other platforms don't have permissions comparable to ssh's checks, so every config file passes.
+#[cfg(not(unix))]
+pub(crate) fn audit(_path: &Path) -> Result<()> {
+	Ok(())
+}
+
+// Replace the access control list of a file or directory with one only granting the current user access, passed
+// on to files and directories within it as given.
+#[cfg(windows)]
+fn restrict_to_current_user(path: &Path, inheritance: ACE_FLAGS) -> io::Result<()> {
+	use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+	use windows_sys::Win32::Security::TOKEN_QUERY;
+	use windows_sys::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+	let mut token: HANDLE = std::ptr::null_mut();
+
+	// SAFETY: The pseudo handle of the current process is always valid, and the token handle is written to a
+	// local.
+	if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) } == 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	let result = restrict_to_token_user(path, token, inheritance);
+
+	// SAFETY: The token handle was opened above and isn't used again.
+	unsafe { CloseHandle(token) };
+
+	result
+}
+
+// Replace the access control list of a file or directory with one only granting the user of the given access
+// token access.
+#[cfg(windows)]
+fn restrict_to_token_user(
+	path: &Path,
+	token: windows_sys::Win32::Foundation::HANDLE,
+	inheritance: ACE_FLAGS,
+) -> io::Result<()> {
+	use std::iter::once;
+	use std::os::windows::ffi::OsStrExt;
+	use std::ptr::{null, null_mut};
+
+	use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS, GENERIC_ALL};
+	use windows_sys::Win32::Security::Authorization::{
+		SetEntriesInAclW, SetNamedSecurityInfoW, EXPLICIT_ACCESS_W, SET_ACCESS, SE_FILE_OBJECT,
+		TRUSTEE_IS_SID, TRUSTEE_IS_USER, TRUSTEE_W,
+	};
+	use windows_sys::Win32::Security::{
+		GetTokenInformation, TokenUser, ACL, DACL_SECURITY_INFORMATION,
+		PROTECTED_DACL_SECURITY_INFORMATION, TOKEN_USER,
+	};
+
+	let mut len = 0;
+
+	// SAFETY: Asking for the size of the token's user only writes the size, as no buffer is given. This call
+	// is expected to fail, with the size needed.
+	unsafe { GetTokenInformation(token, TokenUser, null_mut(), 0, &mut len) };
+
+	// The user is followed by its SID, so the buffer is allocated as u64s to align it for both.
+	let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+
+	// SAFETY: The buffer holds at least the size asked for.
+	if unsafe { GetTokenInformation(token, TokenUser, buf.as_mut_ptr().cast(), len, &mut len) } == 0
+	{
+		return Err(io::Error::last_os_error());
+	}
+
+	// SAFETY: The buffer was filled with a TOKEN_USER, and is aligned for it.
+	let user = unsafe { &*buf.as_ptr().cast::<TOKEN_USER>() };
+	let access = EXPLICIT_ACCESS_W {
+		grfAccessPermissions: GENERIC_ALL,
+		grfAccessMode: SET_ACCESS,
+		grfInheritance: inheritance,
+		Trustee: TRUSTEE_W {
+			TrusteeForm: TRUSTEE_IS_SID,
+			TrusteeType: TRUSTEE_IS_USER,
+			ptstrName: user.User.Sid.cast(),
+			..Default::default()
+		},
+	};
+	let mut acl: *mut ACL = null_mut();
+
+	// SAFETY: A single entry is given, whose SID lives in the buffer until the ACL has been built.
+	let err = unsafe { SetEntriesInAclW(1, &access, null(), &mut acl) };
+
+	if err != ERROR_SUCCESS {
+		return Err(io::Error::from_raw_os_error(err as i32));
+	}
+
+	let wide_path: Vec<u16> = path.as_os_str().encode_wide().chain(once(0)).collect();
+
+	// SAFETY: The path is null terminated, and the ACL was built above. Protecting the ACL stops access being
+	// inherited from the directories above.
+	let err = unsafe {
+		SetNamedSecurityInfoW(
+			wide_path.as_ptr(),
+			SE_FILE_OBJECT,
+			DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+			null_mut(),
+			null_mut(),
+			acl,
+			null(),
+		)
+	};
+
+	// SAFETY: The ACL was allocated by SetEntriesInAclW, and isn't used again.
+	unsafe { LocalFree(acl.cast()) };
+
+	match err {
+		ERROR_SUCCESS => Ok(()),
+		_ => Err(io::Error::from_raw_os_error(err as i32)),
+	}
+}
@@ -0,0 +1,190 @@
+//! Policy files set by administrators of managed machines, given by [Abserde::policy](crate::Abserde::policy),
+//! whose keys override and lock those of the config.
+//!
+//! Configs are merged with the policy through [serde_json::Value], as with [Layers](crate::Layers), so the values
+//! of locked keys can't be 128-bit integers outside the range of 64-bit integers.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{layers, Abserde, Error, Result};
+
+/// What happens when a config being saved holds values of keys locked by the policy, other than those of the
+/// policy, as given by [Abserde::policy_conflicts](crate::Abserde::policy_conflicts).
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	policy: Some(Location::Path("/etc/myapp/policy.json".into())),
+/// 	policy_conflicts: PolicyConflict::Revert,
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum PolicyConflict {
+	/// Saving the config fails with an [Error::LockedKeys], listing the keys whose values conflict.
+	#[default]
+	Reject,
+
+	/// The values of locked keys are silently reverted to those of the policy, and the rest of the config is
+	/// saved.
+	Revert,
+}
+
+impl Abserde {
+	/// Return the keys locked by the policy, such as `window.width`, so apps can stop users changing them, such
+	/// as by greying them out. See [Abserde::policy].
+	///
+	/// Nested keys are separated by `.`, and keys are returned in alphabetical order. No keys are locked when
+	/// there's no policy, or no policy file.
+	///
+	/// ```no_run
+	/// # use abserde::*;
+	/// #
+	/// let my_abserde = Abserde {
+	/// 	app: "MyApp".to_string(),
+	/// 	policy: Some(Location::System),
+	/// 	..Default::default()
+	/// };
+	///
+	/// let can_change_theme = !my_abserde.locked_keys()?.contains(&"theme".to_string());
+	/// #
+	/// # Ok::<(), Error>(())
+	/// ```
+	pub fn locked_keys(&self) -> Result<Vec<String>> {
+		let mut keys = Vec::new();
+
+		if let Some(policy) = self.read_policy()? {
+			locked_paths(&policy, "", &mut keys);
+		}
+
+		keys.sort();
+
+		Ok(keys)
+	}
+
+	// Read a config file, with the keys of the policy merged on top.
+	pub(crate) fn read_with_policy<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+	) -> Result<(T, Option<u64>)> {
+		let abserde = self.without_policy();
+		let Some(policy) = self.read_policy()? else {
+			return abserde.read_config_file_versioned(config_path);
+		};
+		let (mut config, version) = abserde.read_config_file_versioned::<Value>(config_path)?;
+
+		layers::merge(&mut config, policy);
+
+		let _field_keys = self.field_keys()?;
+
+		Ok((
+			T::deserialize(config).map_err(|err| Error::Deserialize(err.into()))?,
+			version,
+		))
+	}
+
+	// Write a config, rejecting values of keys locked by the policy other than its own, or reverting them.
+	pub(crate) fn write_with_policy<T: Serialize>(&self, config: &T) -> Result<()> {
+		let abserde = self.without_policy();
+		let Some(policy) = self.read_policy()? else {
+			return abserde.write_config(config);
+		};
+		let _field_keys = self.field_keys()?;
+		let mut value = serde_json::to_value(config).map_err(|err| Error::Serialize(err.into()))?;
+		let mut conflicts = Vec::new();
+
+		conflicting_paths(&policy, &value, "", &mut conflicts);
+
+		if conflicts.is_empty() {
+			return abserde.write_config(config);
+		}
+
+		match self.policy_conflicts {
+			PolicyConflict::Reject => Err(Error::LockedKeys(conflicts)),
+			PolicyConflict::Revert => {
+				log!(
+					info,
+					"reverted config keys {} locked by policy",
+					conflicts.join(", ")
+				);
+
+				layers::merge(&mut value, policy);
+
+				abserde.write_config(&value)
+			}
+		}
+	}
+
+	// Read the policy file, which is none when there's no policy or no policy file. Configs sharing a config file
+	// can share a policy file, each with its own key.
+	fn read_policy(&self) -> Result<Option<Value>> {
+		let Some(location) = &self.policy else {
+			return Ok(None);
+		};
+		let abserde = Abserde {
+			location: location.clone(),
+			profile: None,
+			section_files: Default::default(),
+			embedded_defaults: None,
+			..self.without_policy()
+		};
+
+		match abserde.read_config_file(&abserde.load_path()?) {
+			Ok(policy) => Ok(Some(policy)),
+			Err(Error::NotFound(_)) => Ok(None),
+			Err(err) => Err(err),
+		}
+	}
+
+	// Copy of this Abserde without a policy, for the config itself.
+	fn without_policy(&self) -> Abserde {
+		Abserde {
+			policy: None,
+			..self.clone()
+		}
+	}
+}
+
+// Collect the paths of the values set by a policy, which are those of everything but maps.
+fn locked_paths(policy: &Value, prefix: &str, paths: &mut Vec<String>) {
+	match policy {
+		Value::Object(map) => {
+			for (key, value) in map {
+				locked_paths(value, &join(prefix, key), paths);
+			}
+		}
+		_ => paths.push(prefix.to_string()),
+	}
+}
+
+// Collect the paths of the values set by a policy which the config holds other values for.
+fn conflicting_paths(policy: &Value, config: &Value, prefix: &str, paths: &mut Vec<String>) {
+	match policy {
+		Value::Object(map) => {
+			for (key, value) in map {
+				conflicting_paths(
+					value,
+					config.get(key).unwrap_or(&Value::Null),
+					&join(prefix, key),
+					paths,
+				);
+			}
+		}
+		policy if policy != config => paths.push(prefix.to_string()),
+		_ => {}
+	}
+}
+
+// Join a key onto the path of its parent, separated by `.`.
+fn join(prefix: &str, key: &str) -> String {
+	match prefix {
+		"" => key.to_string(),
+		prefix => format!("{}.{}", prefix, key),
+	}
+}
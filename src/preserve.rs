@@ -0,0 +1,163 @@
+//! Saving of configs by patching changed values into the existing config file, so the comments and anchors
+//! written by users survive, enabled with [Abserde::preserve_comments](crate::Abserde::preserve_comments).
+//!
+//! Only top-level keys holding scalar values on a single line can be patched. When anything else changed, or
+//! the patched document doesn't load as the saved config, the config file is rewritten as usual.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::{Abserde, Error, Result};
+
+impl Abserde {
+	// Contents of the YAML config file with the changed values of a config patched in, if it exists and only
+	// values which can be patched changed.
+	pub(crate) fn patch_yaml<T: Serialize>(&self, config: &T) -> Result<Option<Vec<u8>>> {
+		let config_path = self.config_path()?;
+		let data = match self.read_data(&config_path) {
+			Ok(data) => self
+				.compression
+				.decompress(&self.encryption.decrypt(self.signing.verify(&data)?)?)?,
+			Err(Error::NotFound(_)) => return Ok(None),
+			Err(err) => return Err(err),
+		};
+		let new = serde_yaml::to_value(config).map_err(|err| Error::Serialize(err.into()))?;
+		let patched = String::from_utf8(data).ok().and_then(|existing| {
+			let old = serde_yaml::from_str(&existing).ok()?;
+
+			patch(&existing, &old, &new)
+		});
+
+		if patched.is_none() {
+			log!(
+				debug,
+				"config file {} can't be patched, so comments aren't kept",
+				config_path.display()
+			);
+		}
+
+		Ok(patched.map(String::into_bytes))
+	}
+}
+
+// Patch the changed values of a config into a YAML document, if only top-level scalar values changed.
+fn patch(existing: &str, old: &Value, new: &Value) -> Option<String> {
+	let (Value::Mapping(old_map), Value::Mapping(new_map)) = (old, new) else {
+		return None;
+	};
+
+	if old_map.len() != new_map.len() {
+		return None;
+	}
+
+	let mut changed = HashMap::new();
+
+	for (key, value) in new_map {
+		let old_value = old_map.get(key)?;
+
+		if old_value != value {
+			match key {
+				Value::String(key) if is_scalar(old_value) && is_scalar(value) => {
+					changed.insert(key.as_str(), value)
+				}
+				_ => return None,
+			};
+		}
+	}
+
+	let mut patched = String::with_capacity(existing.len());
+
+	for line in existing.split_inclusive('\n') {
+		match entry(line).and_then(|(key, range)| Some((changed.remove(key)?, range))) {
+			Some((value, range)) => {
+				let value = serde_yaml::to_string(value).ok()?;
+				let value = value.trim_end_matches('\n');
+
+				if value.contains('\n') {
+					return None;
+				}
+
+				patched.push_str(&line[..range.start]);
+				patched.push_str(value);
+				patched.push_str(&line[range.end..]);
+			}
+			None => patched.push_str(line),
+		}
+	}
+
+	// Every changed value must have been patched, and the patched document must load as the new config.
+	(changed.is_empty() && serde_yaml::from_str::<Value>(&patched).ok()? == *new).then_some(patched)
+}
+
+// Whether a YAML value is a scalar, rather than a collection.
+fn is_scalar(value: &Value) -> bool {
+	!matches!(
+		value,
+		Value::Sequence(_) | Value::Mapping(_) | Value::Tagged(_)
+	)
+}
+
+// Key of a top-level entry on a line of a YAML document, along with the range of its scalar value on the
+// line, after any anchor and before any comment.
+fn entry(line: &str) -> Option<(&str, Range<usize>)> {
+	let content = line.trim_end_matches(['\n', '\r']);
+
+	if content.starts_with(|c: char| c.is_whitespace() || "#-?%&*!|>{[".contains(c)) {
+		return None;
+	}
+
+	let (key, after_key) = match content.chars().next()? {
+		quote @ ('"' | '\'') => {
+			let end = content[1..].find(quote)? + 1;
+
+			(&content[1..end], end + 1)
+		}
+		_ => {
+			let end = content
+				.find(": ")
+				.or_else(|| content.strip_suffix(':').map(str::len))?;
+
+			(content[..end].trim_end(), end)
+		}
+	};
+	content[after_key..].strip_prefix(':')?;
+
+	let mut start = after_key + 1;
+
+	start += content[start..].len() - content[start..].trim_start().len();
+
+	// Anchors are kept, with the value after them patched.
+	if content[start..].starts_with('&') {
+		start += content[start..].find(' ')?;
+		start += content[start..].len() - content[start..].trim_start().len();
+	}
+
+	let value = &content[start..];
+	let len = match value.chars().next()? {
+		'*' | '|' | '>' | '#' => return None,
+		quote @ ('"' | '\'') => quoted_len(value, quote)?,
+		_ => value.find(" #").unwrap_or(value.len()),
+	};
+
+	Some((key, start..start + value[..len].trim_end().len()))
+}
+
+// Length of a quoted scalar at the start of a string, including its quotes.
+fn quoted_len(value: &str, quote: char) -> Option<usize> {
+	let mut chars = value.char_indices().skip(1);
+
+	while let Some((index, c)) = chars.next() {
+		match c {
+			'\\' if quote == '"' => _ = chars.next(),
+			// Single quotes are escaped by doubling them.
+			'\'' if quote == '\'' && value[index + 1..].starts_with('\'') => _ = chars.next(),
+			c if c == quote => return Some(index + 1),
+			_ => {}
+		}
+	}
+
+	None
+}
@@ -0,0 +1,76 @@
+//! Moving config files which can't be parsed aside, so apps can start with their default config instead, with
+//! [Config::load_config_or_quarantine](crate::Config::load_config_or_quarantine).
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{utc_date_time, Abserde, DeleteMode, Error, Location, Result};
+
+/// A config file which couldn't be parsed, moved aside by
+/// [Config::load_config_or_quarantine](crate::Config::load_config_or_quarantine).
+#[derive(Debug)]
+pub struct Quarantined {
+	/// Path the config file was moved to, such as `config.json.corrupt-20240102T030405Z`.
+	pub path: PathBuf,
+
+	/// Error parsing the config file.
+	pub error: Error,
+}
+
+impl Abserde {
+	// Move a config file which can't be parsed aside, to a file named after it and the current time, in the same
+	// storage, returning the path it was moved to.
+	pub(crate) fn quarantine(&self, config_path: &Path) -> Result<PathBuf> {
+		// The server holds a single config, so there is nowhere to move it to.
+		#[cfg(feature = "http")]
+		if let crate::Storage::Http(_) = self.storage {
+			return Err(Error::Unsupported(
+				"configs stored on a server can't be quarantined".to_string(),
+			));
+		}
+
+		let quarantine_path = quarantine_file_path(config_path, SystemTime::now());
+		let data = self.read_data(config_path)?;
+
+		Abserde {
+			location: Location::Path(quarantine_path.clone()),
+			journal: 0,
+			backups: 0,
+			..self.clone()
+		}
+		.write_data(&data)?;
+
+		Abserde {
+			location: Location::Path(config_path.to_owned()),
+			..self.clone()
+		}
+		.delete_with(DeleteMode {
+			remove_parent: false,
+			ignore_missing: true,
+			..Default::default()
+		})?;
+
+		log!(
+			warn,
+			"config file {} is corrupt, moved it to {}",
+			config_path.display(),
+			quarantine_path.display()
+		);
+
+		Ok(quarantine_path)
+	}
+}
+
+// Return the path a corrupt config file is moved to at the given time, such as
+// `config.json.corrupt-20240102T030405Z`.
+fn quarantine_file_path(config_path: &Path, time: SystemTime) -> PathBuf {
+	let [year, month, day, hour, minute, second] = utc_date_time(time);
+	let mut file_name = config_path.file_name().unwrap_or_default().to_owned();
+
+	file_name.push(format!(
+		".corrupt-{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+		year, month, day, hour, minute, second
+	));
+
+	config_path.with_file_name(file_name)
+}
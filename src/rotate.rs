@@ -0,0 +1,96 @@
+//! Rotation of the passphrase of encrypted config files, re-encrypting them and their backups.
+
+use serde_json::Value;
+
+use crate::{
+	backup_file_path, crypto, journal, Abserde, Encryption, Error, KeySource, Location, Operation,
+	Plaintext, Result, Storage,
+};
+
+impl Abserde {
+	/// Re-encrypt the config file and its backups, encrypted with the old passphrase, with the new passphrase,
+	/// such as when the old one may have been compromised.
+	///
+	/// Each file is replaced as it would be when saved, so atomically by default. Only configs using
+	/// [Encryption::Passphrase] or [Encryption::Fields] can be rotated, which decide how the files are
+	/// encrypted, while their own key sources are ignored. Once rotated, the Abserde must be given the new
+	/// passphrase to load the config. Section files aren't rotated, and any journal is compacted into the config
+	/// file, so none of its records are left encrypted with the old passphrase.
+	///
+	/// ```no_run
+	/// # use abserde::*;
+	/// #
+	/// let my_abserde = Abserde {
+	/// 	encryption: Encryption::Passphrase(KeySource::Env("MYAPP_PASSPHRASE".to_string())),
+	/// 	..Default::default()
+	/// };
+	///
+	/// my_abserde.rotate_key(
+	/// 	KeySource::Env("MYAPP_PASSPHRASE".to_string()),
+	/// 	KeySource::Env("MYAPP_NEW_PASSPHRASE".to_string()),
+	/// )?;
+	/// #
+	/// # Ok::<(), Error>(())
+	/// ```
+	pub fn rotate_key(&self, old: KeySource, new: KeySource) -> Result<()> {
+		self.with_context(Operation::Save, || {
+			let config_path = self.config_path()?;
+			let mut paths = vec![config_path.clone()];
+
+			// Backups are numbered without gaps, so stop at the first one missing.
+			if self.storage == Storage::File {
+				paths.extend(
+					(1..)
+						.map(|index| backup_file_path(&config_path, index))
+						.take_while(|backup_path| backup_path.exists()),
+				);
+			}
+
+			for path in paths {
+				let data = self.read_data(&path)?;
+				let rotated = self.signing.sign(self.rotate_data(
+					self.signing.verify(&data)?,
+					&old,
+					&new,
+				)?)?;
+
+				Abserde {
+					location: Location::Path(path),
+					backups: 0,
+					journal: 0,
+					..self.clone()
+				}
+				.write_data(&rotated)?;
+			}
+
+			if self.storage == Storage::File {
+				journal::remove_journal(&journal::journal_path(&config_path))?;
+			}
+
+			Ok(())
+		})
+	}
+
+	// Re-encrypt the contents of a config file with the new passphrase.
+	fn rotate_data(&self, data: &[u8], old: &KeySource, new: &KeySource) -> Result<Vec<u8>> {
+		match &self.encryption {
+			// The config is compressed before being encrypted, so it can be re-encrypted as it is.
+			Encryption::Passphrase(_) => {
+				crypto::encrypt(&Plaintext::from(crypto::decrypt(data, old)?), new)
+			}
+			Encryption::Fields(_) => {
+				let format = self.file_format()?;
+				let mut config: Value = format.deserialize(&*self.compression.decompress(data)?)?;
+				let mut rotated = Vec::new();
+
+				crypto::rotate_secrets(&mut config, old, new)?;
+				self.serialize_as(&format, &config, &mut rotated)?;
+
+				self.compression.compress(&rotated)
+			}
+			_ => Err(Error::Unsupported(
+				"only configs encrypted with a passphrase can have it rotated".to_string(),
+			)),
+		}
+	}
+}
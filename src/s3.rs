@@ -0,0 +1,273 @@
+//! Storage of configs as objects in S3-compatible object storage, selected with
+//! [Storage::S3](crate::Storage::S3).
+//!
+//! Requests are signed with AWS Signature Version 4, and sent to path-style URLs, such as
+//! `https://s3.eu-west-1.amazonaws.com/my-bucket/configs/MyApp/config.json`, which every S3-compatible service
+//! accepts.
+
+use std::fmt::{self, Debug, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ureq::Agent;
+
+use crate::{utc_date_time, Result};
+
+type Response = ureq::http::Response<ureq::Body>;
+
+/// S3-compatible bucket holding configs, used with [Storage::S3](crate::Storage::S3).
+///
+/// Each config is an object keyed by the prefix followed by the path of the config file within the app's
+/// directory, such as `configs/MyApp/config.json`, so every app and profile has keys of its own. Objects hold
+/// the config as it would be stored in a config file.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	storage: Storage::S3(Bucket {
+/// 		prefix: "configs/".to_string(),
+/// 		..Bucket::new(
+/// 			"https://s3.eu-west-1.amazonaws.com",
+/// 			"eu-west-1",
+/// 			"my-bucket",
+/// 			std::env::var("AWS_ACCESS_KEY_ID").unwrap_or_default(),
+/// 			std::env::var("AWS_SECRET_ACCESS_KEY").unwrap_or_default(),
+/// 		)
+/// 	}),
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub struct Bucket {
+	/// URL of the storage service, such as `https://s3.eu-west-1.amazonaws.com` or `http://localhost:9000`.
+	pub endpoint: String,
+
+	/// Region of the bucket, such as `eu-west-1`. Services without regions usually accept `us-east-1`.
+	pub region: String,
+
+	/// Name of the bucket.
+	pub name: String,
+
+	/// Prefix of the object keys, such as `configs/`. Empty by default.
+	pub prefix: String,
+
+	/// Access key ID of the credentials signing requests.
+	pub access_key_id: String,
+
+	/// Secret access key of the credentials signing requests.
+	pub secret_access_key: String,
+
+	/// Session token of temporary credentials, such as those of an IAM role. Disabled by default.
+	pub session_token: Option<String>,
+
+	/// Time allowed for each request, from connecting until the whole response has been read. Defaults to
+	/// 30 seconds.
+	pub timeout: Duration,
+}
+
+impl Bucket {
+	/// Describe a bucket, along with the credentials to access it, using the default options.
+	pub fn new<E, R, N, K, S>(
+		endpoint: E,
+		region: R,
+		name: N,
+		access_key_id: K,
+		secret_access_key: S,
+	) -> Self
+	where
+		E: Into<String>,
+		R: Into<String>,
+		N: Into<String>,
+		K: Into<String>,
+		S: Into<String>,
+	{
+		Self {
+			endpoint: endpoint.into(),
+			region: region.into(),
+			name: name.into(),
+			prefix: String::new(),
+			access_key_id: access_key_id.into(),
+			secret_access_key: secret_access_key.into(),
+			session_token: None,
+			timeout: Duration::from_secs(30),
+		}
+	}
+
+	// Send a signed request for the object holding the given config file.
+	fn request(
+		&self,
+		method: &str,
+		config_path: &Path,
+		body: &[u8],
+	) -> std::result::Result<Response, ureq::Error> {
+		let key = format!(
+			"{}{}",
+			self.prefix,
+			config_path.to_string_lossy().replace('\\', "/")
+		);
+		let uri = format!("/{}/{}", encode(&self.name), encode(&key));
+		let endpoint = self.endpoint.trim_end_matches('/');
+		let host = endpoint
+			.split_once("://")
+			.map_or(endpoint, |(_, rest)| rest);
+		let payload_hash = hex(&Sha256::digest(body));
+		let (date, date_time) = timestamp(SystemTime::now());
+		let mut headers = vec![
+			("host", host.to_string()),
+			("x-amz-content-sha256", payload_hash.clone()),
+			("x-amz-date", date_time.clone()),
+		];
+
+		if let Some(session_token) = &self.session_token {
+			headers.push(("x-amz-security-token", session_token.clone()));
+		}
+
+		let signed_headers = headers
+			.iter()
+			.map(|(name, _)| *name)
+			.collect::<Vec<_>>()
+			.join(";");
+		let canonical_headers: String = headers
+			.iter()
+			.map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+			.collect();
+		let canonical_request = format!(
+			"{}\n{}\n\n{}\n{}\n{}",
+			method, uri, canonical_headers, signed_headers, payload_hash
+		);
+		let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+		let string_to_sign = format!(
+			"AWS4-HMAC-SHA256\n{}\n{}\n{}",
+			date_time,
+			scope,
+			hex(&Sha256::digest(canonical_request.as_bytes()))
+		);
+		let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+			hmac(
+				format!("AWS4{}", self.secret_access_key).as_bytes(),
+				date.as_bytes(),
+			),
+			|key, part| hmac(&key, part.as_bytes()),
+		);
+		let authorization = format!(
+			"AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+			self.access_key_id,
+			scope,
+			signed_headers,
+			hex(&hmac(&signing_key, string_to_sign.as_bytes()))
+		);
+		let agent: Agent = Agent::config_builder()
+			.timeout_global(Some(self.timeout))
+			.build()
+			.into();
+		let url = format!("{}{}", endpoint, uri);
+
+		// The host header is set by the agent itself, from the URL.
+		let headers = headers
+			.into_iter()
+			.skip(1)
+			.chain([("authorization", authorization)]);
+
+		match method {
+			"PUT" => headers
+				.fold(agent.put(&url), |request, (name, value)| {
+					request.header(name, value)
+				})
+				.send(body),
+			"DELETE" => headers
+				.fold(agent.delete(&url), |request, (name, value)| {
+					request.header(name, value)
+				})
+				.call(),
+			_ => headers
+				.fold(agent.get(&url), |request, (name, value)| {
+					request.header(name, value)
+				})
+				.call(),
+		}
+	}
+}
+
+impl Debug for Bucket {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// Leave out the secret access key and session token, as they are credentials.
+		f.debug_struct("Bucket")
+			.field("endpoint", &self.endpoint)
+			.field("region", &self.region)
+			.field("name", &self.name)
+			.field("prefix", &self.prefix)
+			.field("access_key_id", &self.access_key_id)
+			.field("timeout", &self.timeout)
+			.finish_non_exhaustive()
+	}
+}
+
+// Fetch the object holding a config file, returning nothing if there is none.
+pub(crate) fn read(bucket: &Bucket, config_path: &Path) -> Result<Option<Vec<u8>>> {
+	match bucket.request("GET", config_path, &[]) {
+		Ok(mut response) => Ok(Some(response.body_mut().read_to_vec()?)),
+		Err(ureq::Error::StatusCode(404)) => Ok(None),
+		Err(err) => Err(err.into()),
+	}
+}
+
+// Store the object holding a config file, replacing any stored before.
+pub(crate) fn write(bucket: &Bucket, config_path: &Path, data: &[u8]) -> Result<()> {
+	bucket.request("PUT", config_path, data)?;
+
+	Ok(())
+}
+
+// Delete the object holding a config file, returning whether there was one.
+pub(crate) fn delete(bucket: &Bucket, config_path: &Path) -> Result<bool> {
+	// S3 succeeds in deleting objects which don't exist, so check for the object first.
+	if read(bucket, config_path)?.is_none() {
+		return Ok(false);
+	}
+
+	bucket.request("DELETE", config_path, &[])?;
+
+	Ok(true)
+}
+
+// Percent-encode a path for a request URI, leaving unreserved characters and `/` as they are.
+fn encode(path: &str) -> String {
+	path.bytes().fold(String::new(), |mut encoded, byte| {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+				encoded.push(byte as char)
+			}
+			_ => _ = write!(encoded, "%{:02X}", byte),
+		}
+
+		encoded
+	})
+}
+
+// Date and time of a request, as used in signatures, such as `20240131` and `20240131T235959Z`.
+fn timestamp(time: SystemTime) -> (String, String) {
+	let [year, month, day, hour, minute, second] = utc_date_time(time);
+	let date = format!("{:04}{:02}{:02}", year, month, day);
+	let date_time = format!("{}T{:02}{:02}{:02}Z", date, hour, minute, second);
+
+	(date, date_time)
+}
+
+// HMAC-SHA256 of a message.
+fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+	// HMAC accepts keys of any length.
+	let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC key of any length");
+
+	mac.update(message);
+	mac.finalize().into_bytes().to_vec()
+}
+
+// Lowercase hex encoding of bytes.
+fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
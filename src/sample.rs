@@ -0,0 +1,155 @@
+//! Sample config documents, commented with the doc comments of config types, enabled with the derive feature.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{write_template_file, Abserde, Error, Format, Result};
+
+/// Documentation of a config type, captured from its doc comments by the [AbserdeConfig](crate::AbserdeConfig)
+/// derive macro, which implements this trait. Enabled with the derive feature.
+///
+/// ```
+/// # use abserde::*;
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// /// Settings of MyApp.
+/// #[derive(Serialize, Deserialize, AbserdeConfig, Default)]
+/// #[abserde(app = "MyApp")]
+/// struct MyConfig {
+/// 	/// Width of the main window, in pixels.
+/// 	window_width: usize,
+/// }
+///
+/// # #[cfg(feature = "toml")]
+/// assert_eq!(
+/// 	MyConfig::sample_document(&Format::Toml)?,
+/// 	"# Settings of MyApp.\n\n# Width of the main window, in pixels.\nwindow_width = 0\n",
+/// );
+/// # Ok::<(), Error>(())
+/// ```
+pub trait DocumentedConfig {
+	/// Doc comment of the config type.
+	const DOCS: &'static str;
+
+	/// Doc comments of the fields of the config type, along with the keys of the fields.
+	const FIELD_DOCS: &'static [(&'static str, &'static str)];
+
+	/// Generate a sample config document holding the default config, with each field commented by its doc
+	/// comment, so config files which users edit by hand document themselves.
+	///
+	/// Fails with an [Error::Unsupported] for formats without comments, as only TOML and YAML documents can be
+	/// commented.
+	fn sample_document(format: &Format) -> Result<String>
+	where
+		Self: Default + Serialize,
+	{
+		// TOML documents have tables, whose keys aren't top-level fields, while YAML documents nest by indenting.
+		let has_tables = match format {
+			#[cfg(feature = "toml")]
+			Format::Toml => Some(true),
+			#[cfg(feature = "yaml")]
+			Format::Yaml => Some(false),
+			_ => None,
+		};
+		let Some(has_tables) = has_tables else {
+			return Err(Error::Unsupported(
+				"sample documents can only be commented in TOML and YAML".to_string(),
+			));
+		};
+		let mut data = Vec::new();
+
+		format.serialize(&Self::default(), &mut data)?;
+
+		let document = String::from_utf8(data).map_err(|err| Error::Serialize(err.into()))?;
+		let mut sample = String::new();
+		let mut documented = Vec::new();
+		let mut in_tables = false;
+
+		if !Self::DOCS.is_empty() {
+			push_comment(&mut sample, Self::DOCS);
+			sample.push('\n');
+		}
+
+		for line in document.lines() {
+			let key = match has_tables {
+				true => toml_key(line, &mut in_tables),
+				false => yaml_key(line),
+			};
+			let docs = key.and_then(|key| {
+				Self::FIELD_DOCS
+					.iter()
+					.find(|(field, docs)| *field == key && !docs.is_empty())
+			});
+
+			// Fields split over several tables are only commented the first time.
+			if let Some((field, docs)) = docs {
+				if !documented.contains(field) {
+					documented.push(*field);
+					push_comment(&mut sample, docs);
+				}
+			}
+
+			sample.push_str(line);
+			sample.push('\n');
+		}
+
+		Ok(sample)
+	}
+}
+
+impl Abserde {
+	/// Write the default config to the given path, commented with the doc comments of its fields, such as an
+	/// example config installed by a packager. See [Abserde::write_template] and
+	/// [DocumentedConfig::sample_document]. Enabled with the derive feature.
+	pub fn write_commented_template<T, P>(&self, path: P) -> Result<()>
+	where
+		T: DocumentedConfig + Default + Serialize,
+		P: AsRef<Path>,
+	{
+		let path = path.as_ref();
+
+		write_template_file(
+			path,
+			T::sample_document(&self.template_format(path)?)?.as_bytes(),
+		)
+	}
+}
+
+// Append a doc comment as comment lines.
+fn push_comment(sample: &mut String, docs: &str) {
+	for line in docs.lines() {
+		sample.push_str(format!("# {}", line).trim_end());
+		sample.push('\n');
+	}
+}
+
+// Top-level key of a line of a TOML document, if it has one, such as `window` for a table header `[window.size]`.
+fn toml_key<'a>(line: &'a str, in_tables: &mut bool) -> Option<&'a str> {
+	if let Some(header) = line.strip_prefix('[') {
+		*in_tables = true;
+
+		let header = header.trim_start_matches('[');
+
+		return header.split(['.', ']']).next().map(unquote);
+	}
+
+	// Keys within tables belong to the table rather than the config.
+	match *in_tables || line.starts_with([' ', '\t', '#']) {
+		true => None,
+		false => line.split_once('=').map(|(key, _)| unquote(key.trim())),
+	}
+}
+
+// Top-level key of a line of a YAML document, if it has one.
+fn yaml_key(line: &str) -> Option<&str> {
+	match line.starts_with([' ', '\t', '#', '-']) {
+		true => None,
+		false => line.split_once(':').map(|(key, _)| unquote(key.trim())),
+	}
+}
+
+// Remove the quotes around a key, if it is quoted.
+fn unquote(key: &str) -> &str {
+	key.trim_matches(['"', '\''])
+}
@@ -0,0 +1,152 @@
+//! Detection of sandboxed environments, such as Flatpak and Snap, and the directories of the host visible
+//! within them, used with [Abserde::host_paths](crate::Abserde::host_paths).
+
+use std::env::var_os;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::{mobile, Abserde};
+
+/// Environment the app runs in, which may be a sandbox with system directories of its own.
+///
+/// Inside Flatpak and Snap sandboxes, the system directories of the user are kept within the sandbox, such as
+/// `~/.var/app/<app id>/config` for Flatpak and `~/snap/<snap>/<revision>/.config` for Snap, in place of
+/// `~/.config`, so the same app can find different config files depending on how it was packaged.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// if Environment::detect().is_sandboxed() {
+/// 	println!("config files are kept within the sandbox");
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum Environment {
+	/// Not in a sandbox, or in one which keeps the usual system directories.
+	#[default]
+	Native,
+
+	/// Flatpak sandbox.
+	Flatpak,
+
+	/// Snap sandbox.
+	Snap,
+}
+
+impl Environment {
+	/// Detect the environment the app runs in.
+	pub fn detect() -> Self {
+		if !cfg!(target_os = "linux") {
+			return Environment::Native;
+		}
+
+		Self::detect_with(var_os, Path::new("/.flatpak-info").exists())
+	}
+
+	/// Whether the app runs in a sandbox.
+	pub fn is_sandboxed(&self) -> bool {
+		*self != Environment::Native
+	}
+
+	// Detect the environment from the given environment variables, and whether Flatpak's info file exists.
+	pub(crate) fn detect_with<V: Fn(&'static str) -> Option<OsString>>(
+		var: V,
+		flatpak_info: bool,
+	) -> Self {
+		if flatpak_info || var("FLATPAK_ID").is_some() {
+			Environment::Flatpak
+		} else if var("SNAP").is_some() && var("SNAP_NAME").is_some() {
+			Environment::Snap
+		} else {
+			Environment::Native
+		}
+	}
+
+	// Directory of the host for the given kind of directory, from the given environment variables, if the
+	// environment is a sandbox.
+	pub(crate) fn host_dir<V: Fn(&'static str) -> Option<OsString>>(
+		&self,
+		dir: UserDir,
+		var: V,
+	) -> Option<PathBuf> {
+		let (host_var, home_path) = dir.host_var();
+		let non_empty = |name| var(name).filter(|value: &OsString| !value.is_empty());
+
+		match self {
+			Environment::Native => None,
+			// Flatpak gives the base directories of the host, unless they're the defaults within the home
+			// directory, which is the home directory of the host.
+			Environment::Flatpak => non_empty(host_var)
+				.map(PathBuf::from)
+				.or_else(|| Some(Path::new(&non_empty("HOME")?).join(home_path))),
+			// Snap moves the home directory into the sandbox, giving the home directory of the host separately.
+			Environment::Snap => Some(Path::new(&non_empty("SNAP_REAL_HOME")?).join(home_path)),
+		}
+	}
+}
+
+// Kinds of system directories of the user, following the XDG base directories.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum UserDir {
+	Config,
+	Data,
+	Cache,
+	State,
+}
+
+impl UserDir {
+	// Environment variable Flatpak gives the base directory of the host in, along with its default path within
+	// the home directory.
+	fn host_var(self) -> (&'static str, &'static str) {
+		match self {
+			UserDir::Config => ("HOST_XDG_CONFIG_HOME", ".config"),
+			UserDir::Data => ("HOST_XDG_DATA_HOME", ".local/share"),
+			UserDir::Cache => ("HOST_XDG_CACHE_HOME", ".cache"),
+			UserDir::State => ("HOST_XDG_STATE_HOME", ".local/state"),
+		}
+	}
+
+	// Directory as seen by the app, which is within the sandbox if there is one.
+	fn dir(self) -> Option<PathBuf> {
+		if cfg!(target_os = "android") {
+			return mobile::android_dir(self);
+		}
+
+		if cfg!(target_os = "ios") {
+			return dirs::home_dir().map(|home_dir| mobile::ios_dir_in(&home_dir, self));
+		}
+
+		match self {
+			UserDir::Config => dirs::config_dir(),
+			UserDir::Data => dirs::data_dir(),
+			UserDir::Cache => dirs::cache_dir(),
+			UserDir::State => dirs::state_dir(),
+		}
+	}
+}
+
+impl Abserde {
+	// System directory of the user, which is the directory of the host when host paths are preferred and the
+	// sandbox has been given access to it.
+	pub(crate) fn user_dir(&self, dir: UserDir) -> Option<PathBuf> {
+		if self.host_paths {
+			let environment = Environment::detect();
+
+			if let Some(host_dir) = environment
+				.host_dir(dir, var_os)
+				.filter(|host_dir| host_dir.is_dir())
+			{
+				log!(
+					debug,
+					"using directory {} of the host outside the {:?} sandbox",
+					host_dir.display(),
+					environment
+				);
+
+				return Some(host_dir);
+			}
+		}
+
+		dir.dir()
+	}
+}
@@ -0,0 +1,58 @@
+//! JSON Schemas of config types, written next to config files, enabled with the schemars feature.
+
+use std::fs;
+use std::path::PathBuf;
+
+use schemars::{schema_for, JsonSchema};
+
+use crate::{Abserde, Error, Operation, Result};
+
+impl Abserde {
+	/// Generate a JSON Schema describing the given config type, as a pretty printed JSON document.
+	///
+	/// Editors use schemas to complete and check config files which users edit by hand, whether they're JSON
+	/// or YAML. Enabled with the schemars feature.
+	pub fn schema_string<T: JsonSchema>(&self) -> Result<String> {
+		serde_json::to_string_pretty(&schema_for!(T)).map_err(|err| Error::Serialize(err.into()))
+	}
+
+	/// Path of the JSON Schema written by [Abserde::write_schema], next to the config file, such as
+	/// `config.schema.json` for `config.toml`. Enabled with the schemars feature.
+	pub fn schema_path(&self) -> Result<PathBuf> {
+		if self.storage.is_keyed() {
+			return Err(Error::Unsupported(
+				"schemas can only be written next to config files in the file system".to_string(),
+			));
+		}
+
+		let config_path = self.config_path()?;
+		let file_name = config_path
+			.file_name()
+			.unwrap_or_default()
+			.to_string_lossy();
+		let stem = file_name.split('.').next().unwrap_or_default();
+
+		Ok(config_path.with_file_name(format!("{}.schema.json", stem)))
+	}
+
+	/// Write a JSON Schema describing the given config type next to the config file, at
+	/// [Abserde::schema_path], returning its path.
+	///
+	/// Point editors at the schema, such as with a `$schema` key in JSON config files or a
+	/// `# yaml-language-server: $schema=<path>` comment in YAML ones, to complete and check config files.
+	/// Enabled with the schemars feature.
+	pub fn write_schema<T: JsonSchema>(&self) -> Result<PathBuf> {
+		self.with_context(Operation::Save, || {
+			let schema_path = self.schema_path()?;
+			let schema_dir = schema_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+			self.permissions
+				.create_dir_all(schema_dir)
+				.map_err(Error::file(schema_dir))?;
+			fs::write(&schema_path, self.schema_string::<T>()?)
+				.map_err(Error::file(&schema_path))?;
+
+			Ok(schema_path)
+		})
+	}
+}
@@ -0,0 +1,126 @@
+//! Top-level keys of configs kept in section files of their own, given by
+//! [Abserde::section_files](crate::Abserde::section_files).
+//!
+//! Configs are split and reassembled through [serde_json::Value], so the rest of the config is kept in the
+//! config file as usual, while each section file holds just the value of its key.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Abserde, DeleteMode, Error, Location, Result};
+
+impl Abserde {
+	/// Keep the given top-level key of the config in a section file of its own, with the given name in place
+	/// of `config`, such as `keybindings.toml`.
+	///
+	/// See [Abserde::section_files] for how section files are kept.
+	pub fn with_section_file<K: Into<String>, S: Into<String>>(mut self, key: K, name: S) -> Self {
+		self.section_files.insert(key.into(), name.into());
+
+		self
+	}
+
+	// Read a config file along with its section files, reassembling the config.
+	pub(crate) fn read_sections<T: DeserializeOwned>(
+		&self,
+		config_path: &Path,
+	) -> Result<(T, Option<u64>)> {
+		let (mut config, version) = self
+			.without_sections()
+			.read_config_file_versioned::<Value>(config_path)?;
+		let Value::Object(map) = &mut config else {
+			return Err(Error::Unsupported(
+				"only configs which are maps can be kept in section files".to_string(),
+			));
+		};
+
+		for (key, name) in &self.section_files {
+			let section = self.section(config_path, name)?;
+
+			// Sections without a file of their own are left to the config type, such as to take their defaults.
+			match section.read_config_file::<Value>(&section.config_path()?) {
+				Ok(value) => _ = map.insert(key.clone(), value),
+				Err(Error::NotFound(_)) => {}
+				Err(err) => return Err(err),
+			}
+		}
+
+		let _field_keys = self.field_keys()?;
+
+		Ok((
+			T::deserialize(config).map_err(|err| Error::Deserialize(err.into()))?,
+			version,
+		))
+	}
+
+	// Write a config to its config file and section files, splitting it.
+	pub(crate) fn write_sections<T: Serialize>(&self, config: &T) -> Result<()> {
+		let _field_keys = self.field_keys()?;
+		let mut config =
+			serde_json::to_value(config).map_err(|err| Error::Serialize(err.into()))?;
+		let Value::Object(map) = &mut config else {
+			return Err(Error::Unsupported(
+				"only configs which are maps can be kept in section files".to_string(),
+			));
+		};
+		let config_path = self.config_path()?;
+
+		for (key, name) in &self.section_files {
+			let section = self.section(&config_path, name)?;
+
+			match map.remove(key) {
+				Some(value) => section.write_config(&value)?,
+				// Keys which aren't serialised, such as those skipped when none, don't keep a section file behind.
+				None => section.delete_with(DeleteMode {
+					ignore_missing: true,
+					remove_parent: false,
+					..Default::default()
+				})?,
+			}
+		}
+
+		self.without_sections().write_config(&config)
+	}
+
+	// Delete the section files of a config file.
+	pub(crate) fn delete_sections(&self, mode: &DeleteMode) -> Result<()> {
+		let config_path = self.config_path()?;
+
+		for name in self.section_files.values() {
+			self.section(&config_path, name)?.delete_with(DeleteMode {
+				ignore_missing: true,
+				remove_parent: false,
+				..mode.clone()
+			})?;
+		}
+
+		Ok(())
+	}
+
+	// Abserde of a section file with the given name, next to a config file.
+	fn section(&self, config_path: &Path, name: &str) -> Result<Abserde> {
+		let section = Abserde {
+			name: Some(name.to_string()),
+			format: self.file_format()?,
+			schema_version: None,
+			embedded_defaults: None,
+			..self.without_sections()
+		};
+
+		Ok(Abserde {
+			location: Location::Path(config_path.with_file_name(section.default_name()?)),
+			..section
+		})
+	}
+
+	// Abserde of the config file alone, without its section files.
+	fn without_sections(&self) -> Abserde {
+		Abserde {
+			section_files: Default::default(),
+			..self.clone()
+		}
+	}
+}
@@ -0,0 +1,190 @@
+//! Thread-safe sharing of a loaded config.
+
+use std::fmt::{self, Debug};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::manager::load_if_exists;
+use crate::{Abserde, Config, Result};
+
+/// Handle to a config shared between threads, along with the [Abserde] used to store it.
+///
+/// Clones of the handle share the same config. Like [ConfigManager](crate::ConfigManager), the config is
+/// considered changed whenever it is locked for writing, and is only written to disk by
+/// [SharedConfig::persist] when it has changed.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize, Default)]
+/// # struct MyConfig {
+/// # 	theme: String,
+/// # }
+/// #
+/// let shared = SharedConfig::<MyConfig>::load_or_default(Abserde::default())?;
+/// let handle = shared.clone();
+///
+/// std::thread::spawn(move || handle.write().theme = "dark".to_string())
+/// 	.join()
+/// 	.unwrap();
+///
+/// shared.persist()?;
+/// #
+/// # Ok::<(), Error>(())
+/// ```
+pub struct SharedConfig<T> {
+	inner: Arc<Inner<T>>,
+}
+
+struct Inner<T> {
+	abserde: Abserde,
+	config: RwLock<T>,
+	dirty: AtomicBool,
+}
+
+impl<T> SharedConfig<T>
+where
+	T: Serialize,
+	T: DeserializeOwned,
+{
+	fn with_config(abserde: Abserde, config: T, dirty: bool) -> Self {
+		Self {
+			inner: Arc::new(Inner {
+				abserde,
+				config: RwLock::new(config),
+				dirty: AtomicBool::new(dirty),
+			}),
+		}
+	}
+
+	/// Share a config which hasn't been saved yet, so is considered changed.
+	pub fn new(abserde: Abserde, config: T) -> Self {
+		Self::with_config(abserde, config, true)
+	}
+
+	/// Share a config loaded from disk.
+	pub fn load(abserde: Abserde) -> Result<Self> {
+		let config = T::load_config(&abserde)?;
+
+		Ok(Self::with_config(abserde, config, false))
+	}
+
+	/// Share a config loaded from disk, or the default config if there is no config file.
+	pub fn load_or_default(abserde: Abserde) -> Result<Self>
+	where
+		T: Default,
+	{
+		Ok(match load_if_exists(&abserde)? {
+			Some(config) => Self::with_config(abserde, config, false),
+			None => Self::new(abserde, T::default()),
+		})
+	}
+
+	/// Return the [Abserde] managing where and how the config is stored.
+	pub fn abserde(&self) -> &Abserde {
+		&self.inner.abserde
+	}
+
+	/// Lock the config for reading.
+	pub fn read(&self) -> RwLockReadGuard<'_, T> {
+		self.inner
+			.config
+			.read()
+			.unwrap_or_else(PoisonError::into_inner)
+	}
+
+	/// Lock the config for writing, marking it as changed.
+	pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+		let config = self
+			.inner
+			.config
+			.write()
+			.unwrap_or_else(PoisonError::into_inner);
+
+		self.inner.dirty.store(true, Ordering::SeqCst);
+
+		config
+	}
+
+	/// Return whether the config has changed since it was last loaded or persisted.
+	pub fn is_dirty(&self) -> bool {
+		self.inner.dirty.load(Ordering::SeqCst)
+	}
+
+	/// Save the config to disk if it has changed, returning whether it was written.
+	pub fn persist(&self) -> Result<bool> {
+		// Holding the read lock keeps the config from changing while it is saved.
+		let config = self.read();
+
+		if !self.inner.dirty.swap(false, Ordering::SeqCst) {
+			return Ok(false);
+		}
+
+		if let Err(error) = config.save_config(&self.inner.abserde) {
+			self.inner.dirty.store(true, Ordering::SeqCst);
+
+			return Err(error);
+		}
+
+		Ok(true)
+	}
+
+	/// Reload the config from disk, discarding any unsaved changes.
+	pub fn reload(&self) -> Result<()> {
+		let config = T::load_config(&self.inner.abserde)?;
+		let mut current = self
+			.inner
+			.config
+			.write()
+			.unwrap_or_else(PoisonError::into_inner);
+
+		*current = config;
+		self.inner.dirty.store(false, Ordering::SeqCst);
+
+		Ok(())
+	}
+}
+
+#[cfg(feature = "shutdown")]
+impl<T> SharedConfig<T>
+where
+	T: Serialize,
+	T: DeserializeOwned,
+	T: Send + Sync + 'static,
+{
+	/// Persist the config when the app is asked to quit, so changes aren't lost. See
+	/// [on_shutdown](crate::on_shutdown). Errors saving the config are logged with the log feature. Enabled
+	/// with the shutdown feature.
+	pub fn persist_on_shutdown(&self) -> Result<()> {
+		let shared = self.clone();
+
+		crate::on_shutdown(move || {
+			// There is no caller left to return the error to.
+			if let Err(err) = shared.persist() {
+				log!(error, "failed to save config on shutdown: {}", err);
+			}
+		})
+	}
+}
+
+impl<T> Clone for SharedConfig<T> {
+	fn clone(&self) -> Self {
+		Self {
+			inner: Arc::clone(&self.inner),
+		}
+	}
+}
+
+impl<T: Debug> Debug for SharedConfig<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SharedConfig")
+			.field("abserde", &self.inner.abserde)
+			.field("config", &self.inner.config)
+			.field("dirty", &self.inner.dirty)
+			.finish()
+	}
+}
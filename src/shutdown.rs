@@ -0,0 +1,71 @@
+//! Flushing of config state when the app is asked to quit.
+//!
+//! A handler is installed for SIGINT, SIGTERM and SIGHUP on Unix, and console control events on Windows. It
+//! runs the registered hooks, then exits the process with the code set with [set_shutdown_exit_code].
+
+use std::mem::take;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use crate::Result;
+
+/// Exit code used by default when the process exits after running shutdown hooks, the one conventionally used
+/// after SIGINT.
+///
+/// The signal received isn't known to the handler, so apps expecting to be stopped by other signals, such as
+/// SIGTERM from a service manager, can choose another code with [set_shutdown_exit_code].
+pub const SHUTDOWN_EXIT_CODE: i32 = 130;
+
+type Hook = Box<dyn FnMut() + Send>;
+
+static HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static EXIT_CODE: AtomicI32 = AtomicI32::new(SHUTDOWN_EXIT_CODE);
+
+/// Register a hook to run when the app is asked to quit, installing the shutdown handler if needed.
+///
+/// Fails if the app has already installed its own handler for these signals, in which case it should call
+/// [run_shutdown_hooks] itself when quitting.
+pub fn on_shutdown<F: FnMut() + Send + 'static>(hook: F) -> Result<()> {
+	install()?;
+
+	HOOKS
+		.lock()
+		.unwrap_or_else(PoisonError::into_inner)
+		.push(Box::new(hook));
+
+	Ok(())
+}
+
+/// Set the exit code used when the process exits after running shutdown hooks, which is [SHUTDOWN_EXIT_CODE]
+/// by default.
+pub fn set_shutdown_exit_code(code: i32) {
+	EXIT_CODE.store(code, Ordering::SeqCst);
+}
+
+/// Run all registered shutdown hooks, in the order they were registered. Each hook only runs once.
+pub fn run_shutdown_hooks() {
+	let hooks = take(&mut *HOOKS.lock().unwrap_or_else(PoisonError::into_inner));
+
+	for mut hook in hooks {
+		hook();
+	}
+}
+
+// Install the shutdown handler, unless it is already installed.
+fn install() -> Result<()> {
+	if INSTALLED.swap(true, Ordering::SeqCst) {
+		return Ok(());
+	}
+
+	ctrlc::set_handler(|| {
+		run_shutdown_hooks();
+		process::exit(EXIT_CODE.load(Ordering::SeqCst));
+	})
+	.map_err(|err| {
+		INSTALLED.store(false, Ordering::SeqCst);
+
+		err.into()
+	})
+}
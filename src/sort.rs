@@ -0,0 +1,37 @@
+//! Sorting of the keys of configs before they're saved, enabled with
+//! [Abserde::sort_keys](crate::Abserde::sort_keys).
+
+use std::mem;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+// Convert a config to a value with the keys of its maps and the fields of its structs sorted.
+pub(crate) fn sorted<T: Serialize>(config: &T) -> Result<Value> {
+	let mut value = serde_json::to_value(config).map_err(|err| Error::Serialize(err.into()))?;
+
+	sort_keys(&mut value);
+
+	Ok(value)
+}
+
+// Sort the keys of the objects in a value, as serde_json only keeps them sorted without its preserve_order
+// feature, which another crate may enable.
+fn sort_keys(value: &mut Value) {
+	match value {
+		Value::Object(map) => {
+			let mut entries = mem::take(map).into_iter().collect::<Vec<_>>();
+
+			entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+			for (key, mut value) in entries {
+				sort_keys(&mut value);
+				map.insert(key, value);
+			}
+		}
+		Value::Array(items) => items.iter_mut().for_each(sort_keys),
+		_ => (),
+	}
+}
@@ -0,0 +1,83 @@
+//! Adapter for using abserde config files as sources of the config crate.
+
+use std::result;
+
+use config::{ConfigError, Map, Source, Value, ValueKind};
+
+use crate::{Abserde, Error, Operation};
+
+/// Config files located by abserde can be added as sources of a `config::ConfigBuilder`.
+///
+/// The config file is read with the options of the [Abserde], such as its format, encryption and compression.
+/// A missing config file provides no values, so other sources or defaults are used instead.
+/// Enabled with the config feature.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Serialize, Deserialize};
+/// #
+/// # #[derive(Serialize, Deserialize)]
+/// # struct MyConfig {
+/// # 	theme: String,
+/// # }
+/// #
+/// let my_config: MyConfig = config::Config::builder()
+/// 	.set_default("theme", "light")?
+/// 	.add_source(Abserde::default())
+/// 	.add_source(config::Environment::with_prefix("MYAPP"))
+/// 	.build()?
+/// 	.try_deserialize()?;
+/// #
+/// # Ok::<(), config::ConfigError>(())
+/// ```
+impl Source for Abserde {
+	fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+		Box::new(self.clone())
+	}
+
+	fn collect(&self) -> result::Result<Map<String, Value>, ConfigError> {
+		let origin = self.load_path().ok().map(|path| path.display().to_string());
+		let value = match self.with_context(Operation::Load, || {
+			self.read_config_file(&self.load_path()?)
+		}) {
+			Ok(value) => value,
+			Err(error) if matches!(error.root(), Error::NotFound(_)) => return Ok(Map::new()),
+			Err(error) => return Err(ConfigError::Foreign(Box::new(error))),
+		};
+
+		match to_config_value(value, origin.as_ref()).kind {
+			ValueKind::Table(table) => Ok(table),
+			_ => Err(ConfigError::Message(format!(
+				"config file {} doesn't hold a map",
+				origin.unwrap_or_default()
+			))),
+		}
+	}
+}
+
+// Convert a value read from a config file into a value of the config crate.
+fn to_config_value(value: serde_json::Value, origin: Option<&String>) -> Value {
+	let kind = match value {
+		serde_json::Value::Null => ValueKind::Nil,
+		serde_json::Value::Bool(value) => ValueKind::Boolean(value),
+		serde_json::Value::Number(value) => match (value.as_i64(), value.as_u64()) {
+			(Some(value), _) => ValueKind::I64(value),
+			(None, Some(value)) => ValueKind::U64(value),
+			(None, None) => ValueKind::Float(value.as_f64().unwrap_or_default()),
+		},
+		serde_json::Value::String(value) => ValueKind::String(value),
+		serde_json::Value::Array(values) => ValueKind::Array(
+			values
+				.into_iter()
+				.map(|value| to_config_value(value, origin))
+				.collect(),
+		),
+		serde_json::Value::Object(map) => ValueKind::Table(
+			map.into_iter()
+				.map(|(key, value)| (key, to_config_value(value, origin)))
+				.collect(),
+		),
+	};
+
+	Value::new(origin, kind)
+}
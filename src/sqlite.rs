@@ -0,0 +1,87 @@
+//! Storage of configs in a SQLite database, selected with [Storage::Sqlite](crate::Storage::Sqlite).
+//!
+//! Configs are stored as rows of a single table, keyed by the file name the config file would have, such as
+//! `keybindings.json`, with the serialised config as the value. The database is kept in the directory the
+//! config file would be in.
+
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{Error, Result};
+
+// File name of the database, within the directory of the config file.
+const DATABASE_NAME: &str = "config.sqlite";
+
+// Path of the database holding the given config file, along with the key of the config file.
+fn locate(config_path: &Path) -> Result<(PathBuf, String)> {
+	let config_dir = config_path.parent().ok_or(Error::NoSystemConfigDir)?;
+	let key = config_path
+		.file_name()
+		.ok_or_else(|| Error::NotFound(config_path.to_owned()))?
+		.to_string_lossy()
+		.into_owned();
+
+	Ok((config_dir.join(DATABASE_NAME), key))
+}
+
+// Open the database, creating its table if needed.
+fn open(database_path: &Path) -> Result<Connection> {
+	let connection = Connection::open(database_path)?;
+
+	connection.execute(
+		"CREATE TABLE IF NOT EXISTS configs (name TEXT PRIMARY KEY, data BLOB NOT NULL)",
+		[],
+	)?;
+
+	Ok(connection)
+}
+
+// Read the stored data of a config file, if it has been stored.
+pub(crate) fn read(config_path: &Path) -> Result<Option<Vec<u8>>> {
+	let (database_path, key) = locate(config_path)?;
+
+	// Don't create the database just to find it empty.
+	if !database_path.exists() {
+		return Ok(None);
+	}
+
+	Ok(open(&database_path)?
+		.query_row(
+			"SELECT data FROM configs WHERE name = ?1",
+			params![key],
+			|row| row.get(0),
+		)
+		.optional()?)
+}
+
+// Store the data of a config file, replacing any stored before in a single transaction.
+pub(crate) fn write(config_path: &Path, data: &[u8]) -> Result<()> {
+	let (database_path, key) = locate(config_path)?;
+	let config_dir = config_path.parent().ok_or(Error::NoSystemConfigDir)?;
+
+	create_dir_all(config_dir).map_err(Error::file(config_dir))?;
+
+	open(&database_path)?.execute(
+		"INSERT INTO configs (name, data) VALUES (?1, ?2) \
+		 ON CONFLICT (name) DO UPDATE SET data = excluded.data",
+		params![key, data],
+	)?;
+
+	Ok(())
+}
+
+// Delete the stored data of a config file, returning whether it had been stored.
+pub(crate) fn delete(config_path: &Path) -> Result<bool> {
+	let (database_path, key) = locate(config_path)?;
+
+	if !database_path.exists() {
+		return Ok(false);
+	}
+
+	let deleted =
+		open(&database_path)?.execute("DELETE FROM configs WHERE name = ?1", params![key])?;
+
+	Ok(deleted > 0)
+}
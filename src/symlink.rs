@@ -0,0 +1,109 @@
+//! Handling of config files which are symbolic links, such as those put in place by dotfile managers, given by
+//! [Abserde::symlinks](crate::Abserde::symlinks).
+
+use std::fs::{self, remove_file};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+// Most links followed to find the target of a config file, as on Linux.
+const MAX_LINKS: usize = 40;
+
+/// How config files which are symbolic links are saved and deleted. Loading always follows links.
+///
+/// ```no_run
+/// # use abserde::*;
+/// #
+/// let my_abserde = Abserde {
+/// 	app: "MyApp".to_string(),
+/// 	symlinks: SymlinkPolicy::Refuse,
+/// 	..Default::default()
+/// };
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub enum SymlinkPolicy {
+	/// Configs are saved to the file the link points to, keeping the link in place, such as one into a dotfile
+	/// repository. Deleting the config deletes that file, leaving the link to be written through again on the
+	/// next save. Backups are still kept next to the link.
+	#[default]
+	Follow,
+
+	/// Configs are saved to a regular file replacing the link, and deleting the config only removes the link,
+	/// leaving the file it points to as it was.
+	Replace,
+
+	/// Saving or deleting the config fails with an [Error::Symlink], leaving the link and the file it points to
+	/// as they were.
+	Refuse,
+}
+
+impl SymlinkPolicy {
+	// Path of the file to write a config file to, which differs from the config file when following a link.
+	// Links being replaced are removed first when writing in place, as writing to a link writes to its target.
+	pub(crate) fn write_path(&self, config_path: &Path, atomic: bool) -> Result<PathBuf> {
+		if !is_link(config_path)? {
+			return Ok(config_path.to_owned());
+		}
+
+		match self {
+			SymlinkPolicy::Follow => target(config_path).map_err(Error::file(config_path)),
+			SymlinkPolicy::Replace => {
+				if !atomic {
+					remove_file(config_path).map_err(Error::file(config_path))?;
+				}
+
+				Ok(config_path.to_owned())
+			}
+			SymlinkPolicy::Refuse => Err(Error::Symlink(config_path.to_owned())),
+		}
+	}
+
+	// Path of the file to delete in place of a config file, which differs from the config file when following
+	// a link.
+	pub(crate) fn delete_path(&self, config_path: &Path) -> Result<PathBuf> {
+		if !is_link(config_path)? {
+			return Ok(config_path.to_owned());
+		}
+
+		match self {
+			SymlinkPolicy::Follow => target(config_path).map_err(Error::file(config_path)),
+			SymlinkPolicy::Replace => Ok(config_path.to_owned()),
+			SymlinkPolicy::Refuse => Err(Error::Symlink(config_path.to_owned())),
+		}
+	}
+}
+
+// Whether a path is a symbolic link, which is false if there is nothing there.
+fn is_link(path: &Path) -> Result<bool> {
+	match fs::symlink_metadata(path) {
+		Ok(metadata) => Ok(metadata.file_type().is_symlink()),
+		Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+		Err(err) => Err(Error::file(path)(err)),
+	}
+}
+
+// Final target of a chain of links, which needn't exist, so that links left dangling by a delete are written
+// through again.
+fn target(link: &Path) -> io::Result<PathBuf> {
+	let mut path = link.to_owned();
+
+	for _ in 0..MAX_LINKS {
+		if !fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+			return Ok(path);
+		}
+
+		// Relative targets are relative to the directory holding the link.
+		path = match path.parent() {
+			Some(dir) => dir.join(fs::read_link(&path)?),
+			None => fs::read_link(&path)?,
+		};
+	}
+
+	Err(io::Error::other(format!(
+		"too many levels of symbolic links from {}",
+		link.display()
+	)))
+}
@@ -0,0 +1,91 @@
+//! Helpers for testing apps which use abserde, enabled with the testing feature.
+//!
+//! ```
+//! # use abserde::*;
+//! # use abserde::testing::TempAbserde;
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct MyConfig {
+//! 	window_width: usize,
+//! }
+//!
+//! let abserde = TempAbserde::new(Format::Json);
+//!
+//! abserde.seed_with(&MyConfig { window_width: 800 })?;
+//!
+//! assert_eq!(MyConfig::load_config(&abserde)?, MyConfig { window_width: 800 });
+//! assert!(String::from_utf8_lossy(&abserde.raw_contents()?).contains("800"));
+//! # Ok::<(), Error>(())
+//! ```
+
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+use serde::Serialize;
+use tempfile::TempDir;
+
+use crate::{Abserde, Format, Location, Operation, Result};
+
+/// [Abserde] keeping its config files in a temporary directory of its own, so tests don't touch the user's
+/// configs or each other's.
+///
+/// Dereferences to the [Abserde], which can be passed to anything expecting one, and whose options can be
+/// changed as usual. The directory and everything in it is removed when this is dropped.
+#[derive(Debug)]
+pub struct TempAbserde {
+	abserde: Abserde,
+	dir: TempDir,
+}
+
+impl TempAbserde {
+	/// Create an [Abserde] using the given format, with config files in a new temporary directory.
+	///
+	/// # Panics
+	///
+	/// Panics if the temporary directory can't be created, as tests can't run without it.
+	pub fn new(format: Format) -> Self {
+		let dir = TempDir::new().expect("temporary config directory");
+		let abserde = Abserde {
+			app: "abserde-test".to_string(),
+			location: Location::Dir(dir.path().into()),
+			format,
+			..Default::default()
+		};
+
+		Self { abserde, dir }
+	}
+
+	/// Path of the temporary directory holding the config files.
+	pub fn dir(&self) -> &Path {
+		self.dir.path()
+	}
+
+	/// Save the given value as the config, as if the app had saved it, so tests can start from a known config.
+	pub fn seed_with<T: Serialize>(&self, value: &T) -> Result<()> {
+		self.abserde
+			.with_context(Operation::Save, || self.abserde.write_config(value))
+	}
+
+	/// Read the config as it is stored, after serialisation and any compression and encryption, so tests can
+	/// assert on exactly what was saved.
+	pub fn raw_contents(&self) -> Result<Vec<u8>> {
+		self.abserde.with_context(Operation::Load, || {
+			self.abserde.read_data(&self.abserde.load_path()?)
+		})
+	}
+}
+
+impl Deref for TempAbserde {
+	type Target = Abserde;
+
+	fn deref(&self) -> &Abserde {
+		&self.abserde
+	}
+}
+
+impl DerefMut for TempAbserde {
+	fn deref_mut(&mut self) -> &mut Abserde {
+		&mut self.abserde
+	}
+}
@@ -0,0 +1,54 @@
+//! Spans and events describing operations on configs, enabled with the tracing feature.
+//!
+//! Each load, save and delete runs in an `abserde` span holding the app, operation, path and format of the
+//! config, along with the size of the config file in bytes once it has been read or written. An event is
+//! emitted when the operation finishes, holding how long it took, and its error if it failed.
+
+use std::time::Instant;
+
+use tracing::field::Empty;
+
+use crate::{Abserde, Operation, Result};
+
+// Run an operation on a config in a span describing it, emitting an event once it finishes.
+pub(crate) fn instrument<T, F>(abserde: &Abserde, operation: Operation, f: F) -> Result<T>
+where
+	F: FnOnce() -> Result<T>,
+{
+	let path = match operation {
+		Operation::Load => abserde.load_path(),
+		_ => abserde.config_path(),
+	};
+	let format = abserde
+		.file_format()
+		.unwrap_or_else(|_| abserde.format.clone());
+	let span = tracing::info_span!(
+		"abserde",
+		app = %abserde.app,
+		%operation,
+		path = Empty,
+		format = ?format,
+		bytes = Empty,
+	);
+
+	if let Ok(path) = path {
+		span.record("path", tracing::field::display(path.display()));
+	}
+
+	let _entered = span.enter();
+	let start = Instant::now();
+	let result = f();
+	let duration = start.elapsed();
+
+	match &result {
+		Ok(_) => tracing::debug!(?duration, "config {} finished", operation),
+		Err(error) => tracing::debug!(?duration, %error, "config {} failed", operation),
+	}
+
+	result
+}
+
+// Record the size of the config file read or written by the current operation.
+pub(crate) fn record_bytes(bytes: u64) {
+	tracing::Span::current().record("bytes", bytes);
+}
@@ -0,0 +1,26 @@
+//! Detection of keys in config files which the config type doesn't recognise.
+
+use std::result;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+
+// Config loaded along with the paths of any keys which were ignored while deserialising it, such as
+// `window.hieght`.
+pub(crate) struct WithUnknownKeys<T> {
+	pub(crate) config: T,
+	pub(crate) unknown_keys: Vec<String>,
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for WithUnknownKeys<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> result::Result<Self, D::Error> {
+		let mut unknown_keys = Vec::new();
+		let config =
+			serde_ignored::deserialize(deserializer, |path| unknown_keys.push(path.to_string()))?;
+
+		Ok(Self {
+			config,
+			unknown_keys,
+		})
+	}
+}
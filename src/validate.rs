@@ -0,0 +1,69 @@
+//! Validation of configs with the validator crate, enabled with the validator feature.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use validator::Validate;
+
+#[cfg(doc)]
+use crate::Error;
+use crate::{Abserde, Config, Operation, Result};
+
+/// [Config] methods which also check configs with [Validate], so constraints such as ranges and lengths can be
+/// declared on config types. Enabled with the validator feature.
+///
+/// Invalid configs fail with an [Error::Validation], holding the errors of each invalid field.
+///
+/// ```no_run
+/// # use abserde::*;
+/// # use serde::{Deserialize, Serialize};
+/// # use validator::{Validate, ValidationErrors};
+/// #
+/// #[derive(Serialize, Deserialize)]
+/// struct MyConfig {
+/// 	port: u16,
+/// }
+///
+/// impl Validate for MyConfig {
+/// 	fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+/// 		let mut errors = ValidationErrors::new();
+///
+/// 		if self.port < 1024 {
+/// 			errors.add("port", validator::ValidationError::new("range"));
+/// 		}
+///
+/// 		if errors.is_empty() { Ok(()) } else { Err(errors) }
+/// 	}
+/// }
+///
+/// let my_abserde = Abserde::default();
+/// let my_config = MyConfig::load_config_validated(&my_abserde)?;
+/// # Ok::<(), Error>(())
+/// ```
+pub trait ValidatedConfig: Config {
+	/// Load a config from disk, then validate it.
+	fn load_config_validated(abserde: &Abserde) -> Result<Self::T>;
+
+	/// Validate a config, then save it to disk, so invalid configs are never saved.
+	fn save_config_validated(&self, abserde: &Abserde) -> Result<()>;
+}
+
+impl<T> ValidatedConfig for T
+where
+	T: Serialize,
+	T: DeserializeOwned,
+	T: Validate,
+{
+	fn load_config_validated(abserde: &Abserde) -> Result<Self::T> {
+		let config = Self::load_config(abserde)?;
+
+		abserde.with_context(Operation::Load, || Ok(config.validate()?))?;
+
+		Ok(config)
+	}
+
+	fn save_config_validated(&self, abserde: &Abserde) -> Result<()> {
+		abserde.with_context(Operation::Save, || Ok(self.validate()?))?;
+
+		self.save_config(abserde)
+	}
+}
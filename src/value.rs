@@ -0,0 +1,109 @@
+//! Access to single keys of stored configs by dot-separated path, such as `window.width`, without knowing
+//! the config type.
+
+use serde_json::{Map, Value};
+
+use crate::{Abserde, Error, Operation, Result};
+
+impl Abserde {
+	/// Get the value of a single key of the stored config, given by its dot-separated path, such as
+	/// `window.width`.
+	///
+	/// Items of arrays are given by their index, such as `recent.0`, and an empty path gets the whole config.
+	/// Returns none if there is no such key. This lets small tools and scripts read settings without knowing
+	/// the config type.
+	pub fn get_value(&self, path: &str) -> Result<Option<Value>> {
+		let config: Value = self.with_context(Operation::Load, || {
+			self.read_config_file(&self.load_path()?)
+		})?;
+
+		Ok(get(&config, path).cloned())
+	}
+
+	/// Set the value of a single key of the stored config, given by its dot-separated path, such as `theme`,
+	/// saving the changed config.
+	///
+	/// Missing objects along the path are created, as is the config file if there isn't one. Items of arrays
+	/// are given by their index, and must already exist. This lets small tools and scripts change settings
+	/// without knowing the config type.
+	pub fn set_value(&self, path: &str, value: Value) -> Result<()> {
+		// The config is read and saved under the same lock, so changes saved by others in between aren't lost.
+		self.with_context(Operation::Save, || {
+			let mut config = match self.with_context(Operation::Load, || {
+				self.read_config_file(&self.load_path()?)
+			}) {
+				Ok(config) => config,
+				Err(err) if matches!(err.root(), Error::NotFound(_)) => Value::Object(Map::new()),
+				Err(err) => return Err(err),
+			};
+
+			// Invalid paths are returned as they are, rather than as errors saving the config.
+			if let Err(err) = set(&mut config, path, value) {
+				return Ok(Err(err));
+			}
+
+			self.write_config(&config).map(Ok)
+		})?
+	}
+}
+
+// Get the value at a dot-separated path within a config.
+pub(crate) fn get<'a>(config: &'a Value, path: &str) -> Option<&'a Value> {
+	if path.is_empty() {
+		return Some(config);
+	}
+
+	path.split('.').try_fold(config, |value, key| match value {
+		Value::Object(map) => map.get(key),
+		Value::Array(items) => key.parse().ok().and_then(|index: usize| items.get(index)),
+		_ => None,
+	})
+}
+
+// Set the value at a dot-separated path within a config, creating missing objects along the path.
+pub(crate) fn set(config: &mut Value, path: &str, value: Value) -> Result<()> {
+	if path.is_empty() {
+		*config = value;
+
+		return Ok(());
+	}
+
+	let invalid = |reason: String| Error::InvalidKeyPath {
+		path: path.to_string(),
+		reason,
+	};
+	let mut target = config;
+
+	for key in path.split('.') {
+		if target.is_null() {
+			*target = Value::Object(Map::new());
+		}
+
+		target = match target {
+			Value::Object(map) => map.entry(key).or_insert(Value::Null),
+			Value::Array(items) => {
+				let len = items.len();
+
+				key.parse()
+					.ok()
+					.and_then(|index: usize| items.get_mut(index))
+					.ok_or_else(|| {
+						invalid(format!(
+							"{} is not an index of an array of {} items",
+							key, len
+						))
+					})?
+			}
+			_ => {
+				return Err(invalid(format!(
+					"{} is not a key of an object or array",
+					key
+				)))
+			}
+		};
+	}
+
+	*target = value;
+
+	Ok(())
+}
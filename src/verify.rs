@@ -0,0 +1,86 @@
+//! Verification that saved configs load back as they were saved, enabled with
+//! [Abserde::verify_saves](crate::Abserde::verify_saves).
+//!
+//! The config file is read back and deserialised as soon as it is written, and the loaded config is compared to
+//! the saved one through [serde_json::Value], so lossy formats are caught when saving rather than on the next
+//! launch. Configs can't hold 128-bit integers outside the range of 64-bit integers.
+
+use std::collections::BTreeSet;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{Abserde, Error, Result};
+
+impl Abserde {
+	// Write a config, then read it back, failing if it doesn't load as it was saved.
+	pub(crate) fn write_verified<T: Serialize + DeserializeOwned>(&self, config: &T) -> Result<()> {
+		self.write_config(config)?;
+
+		// Captured configs aren't written, so there's nothing to read back.
+		if !self.verify_saves || self.capture.is_some() {
+			return Ok(());
+		}
+
+		#[cfg(feature = "encryption")]
+		if matches!(self.encryption, crate::Encryption::Fields(_)) {
+			return Err(Error::Unsupported(
+				"configs with encrypted fields can't be verified, as they're encrypted differently each time"
+					.to_string(),
+			));
+		}
+
+		// The config file is read back as written, without the policy, includes or environment variables which
+		// loading would bring in.
+		let abserde = Abserde {
+			policy: None,
+			include_key: None,
+			interpolate: false,
+			..self.clone()
+		};
+		let config_path = abserde.config_path()?;
+		let loaded = abserde.read_config_file::<T>(&config_path)?;
+		let _field_keys = self.field_keys()?;
+		let saved = serde_json::to_value(config).map_err(|err| Error::Serialize(err.into()))?;
+		let loaded = serde_json::to_value(loaded).map_err(|err| Error::Serialize(err.into()))?;
+		let mut keys = Vec::new();
+
+		differing_paths(&saved, &loaded, "", &mut keys);
+
+		if !keys.is_empty() {
+			return Err(Error::RoundTrip {
+				path: config_path,
+				keys,
+			});
+		}
+
+		Ok(())
+	}
+}
+
+// Collect the paths of the values which differ between the saved and loaded config, in alphabetical order, where
+// null values and missing keys are the same.
+fn differing_paths(saved: &Value, loaded: &Value, prefix: &str, paths: &mut Vec<String>) {
+	match (saved, loaded) {
+		(Value::Object(saved), Value::Object(loaded)) => {
+			let keys = saved.keys().chain(loaded.keys()).collect::<BTreeSet<_>>();
+
+			for key in keys {
+				let key_path = match prefix {
+					"" => key.to_string(),
+					prefix => format!("{}.{}", prefix, key),
+				};
+
+				differing_paths(
+					saved.get(key).unwrap_or(&Value::Null),
+					loaded.get(key).unwrap_or(&Value::Null),
+					&key_path,
+					paths,
+				);
+			}
+		}
+		(saved, loaded) if saved != loaded => paths.push(prefix.to_string()),
+		_ => {}
+	}
+}
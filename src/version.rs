@@ -0,0 +1,49 @@
+//! Detection of config files modified by other processes, for optimistic concurrency.
+
+use std::collections::hash_map::DefaultHasher;
+use std::error;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+/// Version of a config file, identifying its contents at the time it was loaded or saved.
+///
+/// Returned by [Config::load_config_versioned](crate::Config::load_config_versioned), and checked by
+/// [Config::save_config_if_unmodified](crate::Config::save_config_if_unmodified). The default version is that
+/// of a config file which doesn't exist yet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct ConfigVersion(Option<u64>);
+
+impl ConfigVersion {
+	// Version of the given config file contents, or of a missing config file.
+	pub(crate) fn of_data(data: Option<&[u8]>) -> Self {
+		Self(data.map(|data| {
+			let mut hasher = DefaultHasher::new();
+
+			data.hash(&mut hasher);
+
+			hasher.finish()
+		}))
+	}
+}
+
+/// Error returned when saving a config file which another process has modified since it was loaded.
+///
+/// Returned wrapped in [Error::Conflict](crate::Error::Conflict).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conflict {
+	/// Path of the modified config file.
+	pub path: PathBuf,
+}
+
+impl Display for Conflict {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"config file {} was modified since it was loaded",
+			self.path.display()
+		)
+	}
+}
+
+impl error::Error for Conflict {}
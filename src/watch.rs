@@ -0,0 +1,82 @@
+//! Watching of config files for changes made on disk.
+//!
+//! The config directory is watched rather than the config file itself, so that changes are still seen when
+//! the file is replaced, as happens with atomic saves and many editors.
+
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::de::DeserializeOwned;
+
+use crate::{reload_config, Abserde, Result};
+
+/// Time to wait for further changes after a change is seen, before reloading the config.
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Watches a config file for changes, until dropped.
+///
+/// Created by [Config::watch_config](crate::Config::watch_config).
+#[derive(Debug)]
+pub struct ConfigWatcher {
+	_watcher: RecommendedWatcher,
+}
+
+// Start watching a config file, calling back with the reloaded config on each change.
+pub(crate) fn watch<T, F>(abserde: &Abserde, callback: F) -> Result<ConfigWatcher>
+where
+	T: DeserializeOwned,
+	F: FnMut(Result<T>) + Send + 'static,
+{
+	let abserde = abserde.clone();
+	let config_path = abserde.load_path()?;
+	let config_dir = config_path.parent().unwrap_or(&config_path).to_path_buf();
+	let (sender, receiver) = channel();
+
+	std::fs::create_dir_all(&config_dir)?;
+
+	let mut watcher = notify::recommended_watcher(sender)?;
+
+	watcher.watch(&config_dir, RecursiveMode::NonRecursive)?;
+
+	thread::spawn(move || reload_on_change(&abserde, config_path, receiver, callback));
+
+	Ok(ConfigWatcher { _watcher: watcher })
+}
+
+// Reload the config whenever it changes, until the watcher is dropped.
+fn reload_on_change<T, F>(
+	abserde: &Abserde,
+	config_path: PathBuf,
+	receiver: Receiver<notify::Result<Event>>,
+	mut callback: F,
+) where
+	T: DeserializeOwned,
+	F: FnMut(Result<T>),
+{
+	let is_change = |event: &notify::Result<Event>| match event {
+		Ok(event) => {
+			!matches!(event.kind, EventKind::Access(_)) && event.paths.contains(&config_path)
+		}
+		Err(_) => false,
+	};
+
+	while let Ok(event) = receiver.recv() {
+		if !is_change(&event) {
+			continue;
+		}
+
+		// Editors often write a file in several steps, so wait until changes settle.
+		loop {
+			match receiver.recv_timeout(WATCH_DEBOUNCE) {
+				Ok(_) => continue,
+				Err(RecvTimeoutError::Timeout) => break,
+				Err(RecvTimeoutError::Disconnected) => return,
+			}
+		}
+
+		reload_config(abserde, &config_path, &mut callback);
+	}
+}